@@ -7,12 +7,6 @@ fn main() {
     println!("cargo:rustc-env=COMPILER={}", compiler());
     println!("cargo:rustc-env=TARGET={}", target());
     println!("cargo:rustc-env=PROFILE={}", profile());
-
-    #[cfg(feature = "syzygy")]
-    build_fathom();
-
-    #[cfg(feature = "syzygy")]
-    generate_fathom_bindings();
 }
 
 fn date() -> String {
@@ -48,46 +42,9 @@ fn profile() -> String {
         features.push("dev");
     }
 
-    if cfg!(feature = "syzygy") {
-        features.push("syzygy");
-    }
-
     if features.is_empty() {
         features.push("none");
     }
 
     format!("{} ({})", profile, features.join(", "))
 }
-
-#[cfg(feature = "syzygy")]
-fn build_fathom() {
-    let cc = &mut cc::Build::new();
-    cc.file("./deps/fathom/src/tbprobe.c");
-    cc.include("./deps/fathom/src/");
-    cc.define("_CRT_SECURE_NO_WARNINGS", None);
-
-    // MSVC doesn't support stdatomic.h, so use clang on Windows
-    if env::consts::OS == "windows" {
-        cc.compiler("clang");
-    }
-
-    cc.compile("fathom");
-}
-
-#[cfg(feature = "syzygy")]
-fn generate_fathom_bindings() {
-    let input = "./deps/fathom/src/tbprobe.h";
-    let output = "./src/tablebases/syzygy/bindings.rs";
-
-    bindgen::Builder::default()
-        .header(input)
-        .parse_callbacks(Box::new(bindgen::CargoCallbacks::new()))
-        .layout_tests(false)
-        .generate()
-        .unwrap()
-        .write_to_file(output)
-        .unwrap();
-
-    // Fix one-line files generated by bindgen
-    Command::new("cargo").args(["rustfmt", output]).current_dir(env!("CARGO_MANIFEST_DIR")).output().unwrap();
-}
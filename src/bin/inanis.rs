@@ -1,7 +1,9 @@
 use inanis::engine::see;
 use inanis::interface::terminal;
 use inanis::state::movegen;
+use inanis::tablebases::bitbase;
 use std::env;
+use std::thread;
 
 /// Entry point of the Inanis engine.
 pub fn main() {
@@ -10,6 +12,9 @@ pub fn main() {
 
     see::init();
     movegen::init();
+    bitbase::init();
+    thread::spawn(bitbase::init_kbnk);
+
     terminal::run(args, features);
 }
 
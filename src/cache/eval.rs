@@ -0,0 +1,107 @@
+use crate::utils::assert_fast;
+use crate::utils::percent;
+use std::mem;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+
+pub struct EHTable {
+    pub table: Vec<EHTableEntry>,
+}
+
+#[derive(Default)]
+pub struct EHTableEntry {
+    pub key_data: AtomicU64,
+}
+
+pub struct EHTableResult {
+    pub key: u64,
+    pub score: i16,
+}
+
+impl EHTable {
+    /// Constructs a new instance of [EHTable] by allocating `size` bytes of memory.
+    pub fn new(size: usize) -> Self {
+        const SIZE: usize = mem::size_of::<EHTableEntry>();
+        let mut hashtable = Self { table: Vec::with_capacity(size / SIZE) };
+
+        if size != 0 {
+            hashtable.table.resize_with(hashtable.table.capacity(), EHTableEntry::default);
+        }
+
+        hashtable
+    }
+
+    /// Adds a new entry (storing the key and `score`) using `hash` to calculate an index. The key and score are packed into a single atomic word,
+    /// so the table can be shared between threads without any locks and without risk of reading a score stored for another position.
+    pub fn add(&self, hash: u64, score: i16) {
+        let key = self.get_key(hash);
+        let index = self.get_index(hash);
+        assert_fast!(index < self.table.len());
+
+        self.table[index].set_data(key, score);
+    }
+
+    /// Gets a wanted entry using `hash` to calculate an index. Returns [None] if entry does not exists
+    /// or `hash` is incompatible with the stored key.
+    pub fn get(&self, hash: u64) -> Option<EHTableResult> {
+        let index = self.get_index(hash);
+        assert_fast!(index < self.table.len());
+
+        let entry = &self.table[index];
+        let entry_data = entry.get_data();
+
+        if entry_data.key == self.get_key(hash) {
+            return Some(entry_data);
+        }
+
+        None
+    }
+
+    /// Calculates an approximate percentage usage of the table, based on the first `resolution` entries.
+    pub fn get_usage(&self, resolution: usize) -> f32 {
+        let mut filled_entries = 0;
+        for entry in self.table.iter().take(resolution) {
+            let entry_data = entry.get_data();
+            if entry_data.key != 0 {
+                filled_entries += 1;
+            }
+        }
+
+        percent!(filled_entries, resolution)
+    }
+
+    /// Calculates a key for the `hash` by taking first 48 bits of it.
+    fn get_key(&self, hash: u64) -> u64 {
+        hash & 0xffffffffffff
+    }
+
+    /// Calculates an index for the `hash`.
+    fn get_index(&self, hash: u64) -> usize {
+        (((hash as u128).wrapping_mul(self.table.len() as u128)) >> 64) as usize
+    }
+}
+
+impl EHTableEntry {
+    /// Loads and parses atomic value into a [EHTableResult] struct.
+    pub fn get_data(&self) -> EHTableResult {
+        let key_data = self.key_data.load(Ordering::Relaxed);
+
+        let key = key_data >> 16;
+        let score = key_data as i16;
+
+        EHTableResult::new(key, score)
+    }
+
+    /// Converts `key` and `score` into an atomic word, and stores it.
+    pub fn set_data(&self, key: u64, score: i16) {
+        let key_data = (key << 16) | ((score as u16) as u64);
+        self.key_data.store(key_data, Ordering::Relaxed);
+    }
+}
+
+impl EHTableResult {
+    /// Constructs a new instance of [EHTableResult] with stored `key` and `score`.
+    pub fn new(key: u64, score: i16) -> Self {
+        Self { key, score }
+    }
+}
@@ -1,6 +1,8 @@
 pub mod counters;
+pub mod eval;
 pub mod history;
 pub mod killers;
+pub mod material;
 pub mod pawns;
 pub mod perft;
 pub mod search;
@@ -205,6 +205,15 @@ impl TTable {
         pv_line
     }
 
+    /// Removes all entries from the table, without reallocating its memory.
+    pub fn clear(&self) {
+        for bucket in &self.table {
+            for entry in &bucket.entries {
+                entry.key_data.store(0, Ordering::Relaxed);
+            }
+        }
+    }
+
     /// Calculates an approximate percentage usage of the table, based on the first `resolution` entries.
     pub fn get_usage(&self, resolution: usize) -> f32 {
         let buckets_count_to_check: usize = resolution / BUCKET_SLOTS;
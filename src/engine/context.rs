@@ -2,10 +2,13 @@ use self::params::SearchParams;
 use super::stats::SearchStats;
 use super::*;
 use crate::cache::counters::CMTable;
+use crate::cache::eval::EHTable;
 use crate::cache::history::HTable;
 use crate::cache::killers::KTable;
+use crate::cache::material::MHTable;
 use crate::cache::pawns::PHTable;
 use crate::cache::search::TTable;
+use crate::cache::search::TTableScoreType;
 use crate::engine::clock;
 use crate::state::movescan::Move;
 use crate::state::representation::Board;
@@ -32,18 +35,23 @@ pub struct SearchContext {
     pub moves_to_search: Vec<Move>,
     pub search_time_start: SystemTime,
     pub time_hard_bound: u32,
-    pub multipv: bool,
+    pub multipv: usize,
+    pub excluded_moves: Vec<Move>,
     pub lines: Vec<SearchResultLine>,
     pub search_done: bool,
     pub uci_debug: bool,
     pub ponder_mode: bool,
     pub soft_nodes: bool,
+    pub instant_move: bool,
     pub search_noise: bool,
     pub syzygy_enabled: bool,
     pub syzygy_probe_limit: u32,
     pub syzygy_probe_depth: i8,
+    pub syzygy_rule50: bool,
     pub ttable: Arc<TTable>,
+    pub ehtable: Arc<EHTable>,
     pub phtable: Arc<PHTable>,
+    pub mhtable: Arc<MHTable>,
     pub ktable: KTable,
     pub htable: HTable,
     pub cmtable: CMTable,
@@ -61,6 +69,9 @@ pub struct SearchResult {
 
 pub struct SearchResultLine {
     pub score: i16,
+    pub depth: i8,
+    pub seldepth: u16,
+    pub score_type: u8,
     pub pv_line: Vec<Move>,
 }
 
@@ -68,10 +79,20 @@ impl SearchContext {
     /// Constructs a new instance of [SearchContext] with parameters as follows:
     ///  - `board` - initial position of the board
     ///  - `ttable` - transposition table
+    ///  - `ehtable` - evaluation hash table
     ///  - `phtable` - pawn hash table
+    ///  - `mhtable` - material hash table
     ///  - `abort_flag` - flag used to abort search from the outside of the context
     ///  - `ponder_flag` - flag used to change a search mode from pondering to the regular one
-    pub fn new(board: Board, ttable: Arc<TTable>, phtable: Arc<PHTable>, abort_flag: Arc<AtomicBool>, ponder_flag: Arc<AtomicBool>) -> Self {
+    pub fn new(
+        board: Board,
+        ttable: Arc<TTable>,
+        ehtable: Arc<EHTable>,
+        phtable: Arc<PHTable>,
+        mhtable: Arc<MHTable>,
+        abort_flag: Arc<AtomicBool>,
+        ponder_flag: Arc<AtomicBool>,
+    ) -> Self {
         Self {
             board,
             params: SearchParams::default(),
@@ -87,18 +108,23 @@ impl SearchContext {
             moves_to_search: Vec::new(),
             search_time_start: SystemTime::now(),
             time_hard_bound: 0,
-            multipv: false,
+            multipv: 1,
+            excluded_moves: Vec::new(),
             lines: Vec::new(),
             search_done: false,
             uci_debug: false,
             ponder_mode: false,
             soft_nodes: false,
+            instant_move: true,
             search_noise: false,
             syzygy_enabled: false,
             syzygy_probe_limit: 0,
             syzygy_probe_depth: 0,
+            syzygy_rule50: true,
             ttable,
+            ehtable,
             phtable,
+            mhtable,
             ktable: KTable::default(),
             htable: HTable::default(),
             cmtable: CMTable::default(),
@@ -118,7 +144,7 @@ impl Iterator for SearchContext {
     ///  - the search has been done in the previous iteration or the current depth is about to exceed [MAX_DEPTH] value
     ///  - forced depth is not 0 and the current depth is about to exceed this value
     ///  - soft nodes are enabled and the nodes count exceeded the maximal value
-    ///  - instant move is possible
+    ///  - instant move is enabled and possible
     ///  - Syzygy tablebase move is possible
     ///  - time allocated for the current search has expired
     ///  - mate score has detected and was recognized as reliable
@@ -143,22 +169,25 @@ impl Iterator for SearchContext {
                 }
             }
 
-            // Check instant move and Syzygy tablebase move only if there's no forced depth to reach
-            if self.forced_depth == 0 && self.current_depth == 1 {
+            // Check instant move only if it's enabled and there's no forced depth to reach
+            if self.instant_move && self.forced_depth == 0 && self.current_depth == 1 {
                 if let Some(r#move) = self.board.get_instant_move() {
                     self.search_done = true;
-                    self.lines.push(SearchResultLine::new(0, vec![r#move]));
+                    self.lines.push(SearchResultLine::new(0, self.current_depth, 0, TTableScoreType::EXACT_SCORE, vec![r#move]));
 
                     return Some(SearchResult::new(0, self.current_depth));
                 }
+            }
 
-                if self.syzygy_enabled {
-                    if let Some((r#move, score)) = self.board.get_tablebase_move(self.syzygy_probe_limit) {
-                        self.search_done = true;
-                        self.stats.tb_hits = 1;
-                        self.lines.push(SearchResultLine::new(score, vec![r#move]));
+            // If the position is in Syzygy tablebases, keep searching but only among moves which preserve the tablebase result
+            if self.syzygy_enabled && self.current_depth == 1 {
+                if let Some((moves, _)) = self.board.get_tablebase_moves(self.syzygy_probe_limit, self.syzygy_rule50) {
+                    self.stats.tb_hits += 1;
 
-                        return Some(SearchResult::new(0, self.current_depth));
+                    if self.moves_to_search.is_empty() {
+                        self.moves_to_search = moves;
+                    } else if self.moves_to_search.iter().any(|r#move| moves.contains(r#move)) {
+                        self.moves_to_search.retain(|r#move| moves.contains(r#move));
                     }
                 }
             }
@@ -171,7 +200,6 @@ impl Iterator for SearchContext {
             let (time_soft_bound, time_hard_bound) = clock::get_time_bounds(self);
 
             self.time_hard_bound = time_hard_bound;
-            self.lines.clear();
 
             let helper_contexts_arc = self.helper_contexts.clone();
             let mut helper_contexts_lock = helper_contexts_arc.write().unwrap();
@@ -182,6 +210,8 @@ impl Iterator for SearchContext {
 
                 for helper_context in helper_contexts_lock.iter_mut() {
                     helper_context.forced_depth = depth;
+                    helper_context.moves_to_search = self.moves_to_search.clone();
+                    helper_context.stats = SearchStats::default();
                     threads.push(scope.spawn(move || {
                         search::run(helper_context, depth);
                     }));
@@ -212,6 +242,7 @@ impl Iterator for SearchContext {
                     self.forced_depth = 0;
                     self.search_time_start = SystemTime::now();
                     self.stats = SearchStats::default();
+                    self.lines.clear();
 
                     self.ponder_flag.store(false, Ordering::Relaxed);
                     self.abort_flag.store(false, Ordering::Relaxed);
@@ -237,7 +268,6 @@ impl Iterator for SearchContext {
 
             let search_time = self.search_time_start.elapsed().unwrap().as_millis() as u32;
 
-            self.lines.sort_by(|a, b| a.score.cmp(&b.score).reverse());
             self.current_depth += 1;
 
             if self.forced_depth == 0 && self.max_nodes_count == 0 {
@@ -264,8 +294,8 @@ impl SearchResult {
 }
 
 impl SearchResultLine {
-    /// Constructs a new instance of [SearchResultLine] with stored `score` and `pv_line`.
-    pub fn new(score: i16, pv_line: Vec<Move>) -> Self {
-        Self { score, pv_line }
+    /// Constructs a new instance of [SearchResultLine] with stored `score`, `depth`, `seldepth`, `score_type` and `pv_line`.
+    pub fn new(score: i16, depth: i8, seldepth: u16, score_type: u8, pv_line: Vec<Move>) -> Self {
+        Self { score, depth, seldepth, score_type, pv_line }
     }
 }
@@ -16,9 +16,15 @@ pub const INVALID_SCORE: i16 = -32700;
 pub const DRAW_SCORE: i16 = 0;
 pub const CHECKMATE_SCORE: i16 = 31900;
 pub const TBMATE_SCORE: i16 = 10000;
+pub const TBCURSED_SCORE: i16 = 2;
 
 /// Checks if `score` is within mate range (from -[CHECKMATE_SCORE] to -[CHECKMATE_SCORE] + [MAX_DEPTH] and
 /// from [CHECKMATE_SCORE] - [MAX_DEPTH] to [CHECKMATE_SCORE]).
 pub fn is_score_near_checkmate(score: i16) -> bool {
     score.abs() >= CHECKMATE_SCORE - (MAX_DEPTH as i16) && score.abs() <= CHECKMATE_SCORE + (MAX_DEPTH as i16)
 }
+
+/// Gets the number of moves to mate for `score` which is within mate range (see [is_score_near_checkmate]), negative if the side to move is getting mated.
+pub fn get_moves_to_mate(score: i16) -> i16 {
+    (score.abs() - CHECKMATE_SCORE).abs() / 2 * score.signum()
+}
@@ -163,3 +163,86 @@ impl Default for SearchParams {
         }
     }
 }
+
+#[cfg(feature = "dev")]
+macro_rules! search_params_fields {
+    ($($name:ident: $type:ident),*) => {
+        impl SearchParams {
+            /// List of names and types of all search parameters, in the declaration order.
+            pub const FIELDS: &'static [(&'static str, &'static str)] = &[$((stringify!($name), stringify!($type))),*];
+
+            /// Gets a value of the search parameter `name`, or [None] if there's no such parameter.
+            pub fn get(&self, name: &str) -> Option<i64> {
+                match name {
+                    $(stringify!($name) => Some(self.$name as i64),)*
+                    _ => None,
+                }
+            }
+
+            /// Sets the search parameter `name` to `value`. Returns [Err] with a proper error message if there's no such parameter or
+            /// the value is out of range of its type.
+            pub fn set(&mut self, name: &str, value: i64) -> Result<(), String> {
+                match name {
+                    $(stringify!($name) => self.$name = value.try_into().map_err(|_| format!("Invalid value: name={}, value={}", name, value))?,)*
+                    _ => return Err(format!("Invalid search parameter: name={}", name)),
+                }
+
+                Ok(())
+            }
+
+            /// Never called, fails to compile if the list of fields passed to the macro doesn't cover every field of [SearchParams] with
+            /// the same type, so [Self::FIELDS] can't go out of sync with the struct.
+            #[allow(dead_code)]
+            fn check_fields(&self) {
+                let Self { $($name),* } = self;
+                $(let _: &$type = $name;)*
+            }
+        }
+    };
+}
+
+#[cfg(feature = "dev")]
+search_params_fields!(
+    time_a: i8,
+    time_b: i8,
+    time_c: i8,
+    time_d: i8,
+    time_e: i8,
+    time_soft_bound: i16,
+    time_hard_bound: i16,
+    aspwin_delta: i16,
+    aspwin_multiplier: i16,
+    aspwin_min_depth: i8,
+    aspwin_max_delta: i16,
+    iir_min_depth: i8,
+    iir_reduction_base: i8,
+    iir_reduction_step: i8,
+    iir_max_reduction: i8,
+    razoring_min_depth: i8,
+    razoring_depth_margin_base: i16,
+    razoring_depth_margin_multiplier: i16,
+    snmp_min_depth: i8,
+    snmp_depth_margin_base: i16,
+    snmp_depth_margin_multiplier: i16,
+    nmp_min_depth: i8,
+    nmp_margin: i16,
+    nmp_depth_base: i8,
+    nmp_depth_divider: i8,
+    lmp_min_depth: i8,
+    lmp_max_depth: i8,
+    lmp_move_index_margin_base: usize,
+    lmp_move_index_margin_multiplier: usize,
+    lmp_max_score: i16,
+    lmr_min_depth: i8,
+    lmr_max_score: i16,
+    lmr_min_move_index: usize,
+    lmr_reduction_base: usize,
+    lmr_reduction_step: usize,
+    lmr_max_reduction: i8,
+    lmr_pv_min_move_index: usize,
+    lmr_pv_reduction_base: usize,
+    lmr_pv_reduction_step: usize,
+    lmr_pv_max_reduction: i8,
+    q_score_pruning_treshold: i16,
+    q_futility_pruning_margin: i16
+);
@@ -29,7 +29,7 @@ pub fn run(context: &mut SearchContext, ply: u16, mut alpha: i16, beta: i16) ->
         return -CHECKMATE_SCORE + (ply as i16);
     }
 
-    let stand_pat = context.board.evaluate(context.board.stm, &context.phtable, &mut context.stats);
+    let stand_pat = context.board.evaluate(context.board.stm, &context.ehtable, &context.phtable, &context.mhtable, &mut context.stats);
     if stand_pat >= beta {
         dev!(context.stats.q_leafs_count += 1);
         dev!(context.stats.q_beta_cutoffs += 1);
@@ -2,6 +2,8 @@ use crate::cache::search::TTableScoreType;
 use crate::engine::context::SearchContext;
 use crate::engine::*;
 use crate::state::movescan::Move;
+use crate::tablebases;
+use crate::tablebases::bitbase;
 use crate::tablebases::syzygy;
 use crate::tablebases::WdlResult;
 use crate::utils::assert_fast;
@@ -12,41 +14,102 @@ use search::movepick;
 use search::movepick::MoveGenStage;
 use search::movepick::MoveGenState;
 use std::cmp;
+use std::mem;
 use std::sync::atomic::Ordering;
 
-/// Aspiration window wrapper for the entry point of the regular search, look at `run_internal` for more information.
+/// Entry point of the regular search with MultiPV support, look at `run_internal` for more information. Every line is searched separately with its own aspiration
+/// window (centered at the score of the same line in the previous iteration), excluding root moves which have already started one of the better lines.
 pub fn run(context: &mut SearchContext, depth: i8) {
+    let previous_lines = mem::take(&mut context.lines);
+    let lines_count = cmp::min(context.multipv, context.board.get_legal_moves_count(&context.moves_to_search)).max(1);
+
+    context.excluded_moves.clear();
+
+    for line_index in 0..lines_count {
+        let expected_score = match previous_lines.get(line_index) {
+            Some(line) => line.score,
+            None => context.last_score,
+        };
+
+        let max_ply = context.stats.max_ply;
+        context.stats.max_ply = 0;
+
+        let completed = run_aspiration_window(context, depth, line_index, expected_score);
+        let seldepth = context.stats.max_ply;
+        context.stats.max_ply = cmp::max(max_ply, seldepth);
+
+        // Root node can return early without a line (e.g. stalemate), which is treated the same as an aborted search
+        if !completed || context.lines.len() <= line_index {
+            context.lines.truncate(line_index);
+            break;
+        }
+
+        context.lines[line_index].depth = depth;
+        context.lines[line_index].seldepth = seldepth;
+        if line_index == 0 {
+            context.last_score = context.lines[0].score;
+        }
+
+        match context.lines[line_index].pv_line.first() {
+            Some(r#move) => context.excluded_moves.push(*r#move),
+            None => break,
+        }
+    }
+
+    context.excluded_moves.clear();
+
+    // Aborted search leaves no reliable lines, so keep the ones from the previous iteration
+    if context.lines.is_empty() {
+        context.lines = previous_lines;
+        return;
+    }
+
+    // Lines with equal scores keep the order from the previous iteration, so GUIs don't flicker when displaying them
+    context.lines.sort_by_key(|line| {
+        let previous_index = previous_lines.iter().position(|previous_line| previous_line.pv_line.first() == line.pv_line.first());
+        (-line.score, previous_index.unwrap_or(usize::MAX))
+    });
+
+    // Lines other than the first one don't update the root entry, so ensure the best line is stored there for the next iteration
+    if lines_count > 1 {
+        let best_line = &context.lines[0];
+        context.ttable.add(context.board.state.hash, best_line.score, best_line.pv_line[0], depth, 0, best_line.score_type, context.search_id);
+    }
+}
+
+/// Aspiration window wrapper for the search of the line with `line_index`, centered at `expected_score`. Returns false if the search has been aborted.
+fn run_aspiration_window(context: &mut SearchContext, depth: i8, line_index: usize, expected_score: i16) -> bool {
     let king_checked = context.board.is_king_checked(context.board.stm);
     if depth < param!(context.params.aspwin_min_depth) {
-        context.last_score = run_internal::<true, true>(context, depth, 0, MIN_ALPHA, MIN_BETA, true, king_checked, Move::default());
-    } else {
-        let mut delta = param!(context.params.aspwin_delta);
-        let mut alpha = context.last_score - delta;
-        let mut beta = context.last_score + delta;
+        let score = run_internal::<true, true>(context, depth, 0, MIN_ALPHA, MIN_BETA, true, king_checked, Move::default());
+        return score.abs() != INVALID_SCORE.abs();
+    }
 
-        loop {
-            let score = run_internal::<true, true>(context, depth, 0, alpha, beta, true, king_checked, Move::default());
-            if score.abs() == INVALID_SCORE.abs() {
-                break;
-            }
+    let mut delta = param!(context.params.aspwin_delta);
+    let mut alpha = expected_score - delta;
+    let mut beta = expected_score + delta;
 
-            if score <= alpha {
-                alpha -= delta;
-            } else if score >= beta {
-                beta += delta;
-            } else {
-                context.last_score = score;
-                break;
-            }
+    loop {
+        let score = run_internal::<true, true>(context, depth, 0, alpha, beta, true, king_checked, Move::default());
+        if score.abs() == INVALID_SCORE.abs() {
+            return false;
+        }
 
-            delta = (delta as i32 * param!(context.params.aspwin_multiplier) as i32 / 100) as i16;
-            if delta >= param!(context.params.aspwin_max_delta) {
-                alpha = MIN_ALPHA;
-                beta = MIN_BETA;
-            }
+        if score <= alpha {
+            alpha -= delta;
+        } else if score >= beta {
+            beta += delta;
+        } else {
+            return true;
+        }
 
-            context.lines.clear();
+        delta = (delta as i32 * param!(context.params.aspwin_multiplier) as i32 / 100) as i16;
+        if delta >= param!(context.params.aspwin_max_delta) {
+            alpha = MIN_ALPHA;
+            beta = MIN_BETA;
         }
+
+        context.lines.truncate(line_index);
     }
 }
 
@@ -140,14 +203,17 @@ fn run_internal<const ROOT: bool, const PV: bool>(
         return DRAW_SCORE;
     }
 
-    if context.syzygy_enabled && depth >= context.syzygy_probe_depth && context.board.get_pieces_count() <= syzygy::probe::get_max_pieces_count() {
+    if syzygy_can_be_probed::<ROOT>(context, depth) {
         if let Some(wdl) = syzygy::probe::get_wdl(&context.board) {
             context.stats.tb_hits += 1;
-            return match wdl {
-                WdlResult::Win => TBMATE_SCORE - (ply as i16),
-                WdlResult::Loss => -TBMATE_SCORE + (ply as i16),
-                WdlResult::Draw => DRAW_SCORE,
-            };
+            return tablebases::get_wdl_score(&wdl, ply, context.syzygy_rule50);
+        }
+    }
+
+    if bitbase_can_be_probed::<ROOT>() {
+        if let Some(WdlResult::Draw) = bitbase::get_wdl(&context.board) {
+            dev!(context.stats.leafs_count += 1);
+            return DRAW_SCORE;
         }
     }
 
@@ -223,7 +289,7 @@ fn run_internal<const ROOT: bool, const PV: bool>(
         let margin = razoring_get_margin(context, depth);
         let static_eval_value = match static_eval {
             Some(value) => value,
-            None => context.board.evaluate_fast(context.board.stm, &context.phtable, &mut context.stats),
+            None => context.board.evaluate_fast(context.board.stm, &context.ehtable, &context.phtable, &context.mhtable, &mut context.stats),
         };
 
         dev!(context.stats.razoring_attempts += 1);
@@ -245,7 +311,7 @@ fn run_internal<const ROOT: bool, const PV: bool>(
         let margin = snmp_get_margin(context, depth);
         let static_eval_value = match static_eval {
             Some(value) => value,
-            None => context.board.evaluate_fast(context.board.stm, &context.phtable, &mut context.stats),
+            None => context.board.evaluate_fast(context.board.stm, &context.ehtable, &context.phtable, &context.mhtable, &mut context.stats),
         };
 
         dev!(context.stats.snmp_attempts += 1);
@@ -264,7 +330,7 @@ fn run_internal<const ROOT: bool, const PV: bool>(
         let margin = param!(context.params.nmp_margin);
         let static_eval_value = match static_eval {
             Some(value) => value,
-            None => context.board.evaluate_fast(context.board.stm, &context.phtable, &mut context.stats),
+            None => context.board.evaluate_fast(context.board.stm, &context.ehtable, &context.phtable, &context.mhtable, &mut context.stats),
         };
 
         dev!(context.stats.nmp_attempts += 1);
@@ -298,6 +364,10 @@ fn run_internal<const ROOT: bool, const PV: bool>(
             continue;
         }
 
+        if ROOT && context.excluded_moves.contains(&r#move) {
+            continue;
+        }
+
         if lmp_can_be_applied::<PV>(context, depth, state.move_number, score, friendly_king_checked) {
             dev!(context.stats.lmp_accepted += 1);
             break;
@@ -384,20 +454,6 @@ fn run_internal<const ROOT: bool, const PV: bool>(
                 break;
             }
         }
-
-        if ROOT && context.multipv {
-            context.board.make_move(best_move);
-            if !context.board.is_king_checked(context.board.stm ^ 1) {
-                let mut pv_line = context.ttable.get_pv_line(&mut context.board.clone(), 0);
-                pv_line.insert(0, best_move);
-
-                context.lines.push(SearchResultLine::new(best_score, pv_line));
-            }
-            context.board.undo_move(best_move);
-
-            alpha = original_alpha;
-            best_score = -CHECKMATE_SCORE;
-        }
     }
 
     // When no legal move is possible, but king is not checked, it's a stalemate
@@ -405,27 +461,65 @@ fn run_internal<const ROOT: bool, const PV: bool>(
         return DRAW_SCORE;
     }
 
-    if (!tt_entry_found || alpha != original_alpha) && !context.abort_flag.load(Ordering::Relaxed) {
-        let score_type = if alpha <= original_alpha {
-            TTableScoreType::UPPER_BOUND
-        } else if alpha >= beta {
-            TTableScoreType::LOWER_BOUND
-        } else {
-            TTableScoreType::EXACT_SCORE
-        };
+    let score_type = if alpha <= original_alpha {
+        TTableScoreType::UPPER_BOUND
+    } else if alpha >= beta {
+        TTableScoreType::LOWER_BOUND
+    } else {
+        TTableScoreType::EXACT_SCORE
+    };
 
+    // Root node with excluded moves (MultiPV) doesn't represent the real position score, so it's not stored in the transposition table
+    let excluded_root = ROOT && !context.excluded_moves.is_empty();
+    if (!tt_entry_found || alpha != original_alpha) && !excluded_root && !context.abort_flag.load(Ordering::Relaxed) {
         context.ttable.add(context.board.state.hash, best_score, best_move, depth, ply, score_type, context.search_id);
         dev!(context.stats.tt_added += 1);
     }
 
-    if ROOT && !context.multipv {
-        let pv_line = context.ttable.get_pv_line(&mut context.board.clone(), 0);
-        context.lines.push(SearchResultLine::new(best_score, pv_line));
+    if ROOT {
+        let mut pv_line = Vec::new();
+        if best_move.is_some() {
+            let mut board = context.board.clone();
+            board.make_move(best_move);
+
+            pv_line.push(best_move);
+            pv_line.append(&mut context.ttable.get_pv_line(&mut board, 1));
+        }
+
+        context.lines.push(SearchResultLine::new(best_score, depth, 0, score_type, pv_line));
     }
 
     best_score
 }
 
+/// Syzygy tablebases are probed to get the exact result of the position, instead of searching it further. Root node is never probed, as it's
+/// filtered earlier to keep only moves which preserve the tablebase result.
+///
+/// Conditions:
+///  - not a root node
+///  - Syzygy tablebases are enabled
+///  - depth >= `syzygy_probe_depth`
+///  - pieces count is within tablebases and probe limit
+///  - halfmove clock is equal to zero (only if the fifty-move rule is obeyed, as the probed WDL assumes that the move has just zeroed it)
+fn syzygy_can_be_probed<const ROOT: bool>(context: &mut SearchContext, depth: i8) -> bool {
+    let max_pieces_count = cmp::min(context.syzygy_probe_limit as u8, syzygy::probe::get_max_pieces_count());
+
+    !ROOT
+        && context.syzygy_enabled
+        && depth >= context.syzygy_probe_depth
+        && context.board.get_pieces_count() <= max_pieces_count
+        && (!context.syzygy_rule50 || context.board.state.halfmove_clock == 0)
+}
+
+/// Bitbases are probed to cut off branches which are known to be drawn, wins are searched further (with the bonus applied by the evaluation)
+/// so the engine can still find the shortest way to mate. Root node is never probed, as it has to return a move.
+///
+/// Conditions:
+///  - not a root node
+fn bitbase_can_be_probed<const ROOT: bool>() -> bool {
+    !ROOT
+}
+
 /// The main idea of the check extensions is to extend search when there's a check. Because it's a forced move, we assume that a lot is going on
 /// in that branch and it's a good idea to search deeper so we avoid horizon effects.
 ///
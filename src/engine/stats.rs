@@ -54,10 +54,18 @@ pub struct SearchStats {
     pub cmtable_legal_moves: u64,
     pub cmtable_illegal_moves: u64,
 
+    pub ehtable_added: u64,
+    pub ehtable_hits: u64,
+    pub ehtable_misses: u64,
+
     pub phtable_added: u64,
     pub phtable_hits: u64,
     pub phtable_misses: u64,
 
+    pub mhtable_added: u64,
+    pub mhtable_hits: u64,
+    pub mhtable_misses: u64,
+
     pub movegen_hash_move_stages: u64,
     pub movegen_captures_stages: u64,
     pub movegen_killers_stages: u64,
@@ -116,10 +124,18 @@ impl ops::AddAssign<&SearchStats> for SearchStats {
         self.tt_legal_hashmoves += rhs.tt_legal_hashmoves;
         self.tt_illegal_hashmoves += rhs.tt_illegal_hashmoves;
 
+        self.ehtable_added += rhs.ehtable_added;
+        self.ehtable_hits += rhs.ehtable_hits;
+        self.ehtable_misses += rhs.ehtable_misses;
+
         self.phtable_added += rhs.phtable_added;
         self.phtable_hits += rhs.phtable_hits;
         self.phtable_misses += rhs.phtable_misses;
 
+        self.mhtable_added += rhs.mhtable_added;
+        self.mhtable_hits += rhs.mhtable_hits;
+        self.mhtable_misses += rhs.mhtable_misses;
+
         self.movegen_hash_move_stages += rhs.movegen_hash_move_stages;
         self.movegen_captures_stages += rhs.movegen_captures_stages;
         self.movegen_quiets_stages += rhs.movegen_quiets_stages;
@@ -0,0 +1,246 @@
+use super::*;
+use crate::engine::DRAW_SCORE;
+use crate::state::representation::Board;
+use crate::tablebases::bitbase;
+use crate::tablebases::WdlResult;
+use crate::utils::assert_fast;
+use crate::utils::bithelpers::BitHelpers;
+use std::cmp;
+
+pub const KNOWN_WIN_SCORE: i16 = 2000;
+
+pub const SCALE_NORMAL: u8 = 64;
+pub const SCALE_OPPOSITE_BISHOPS: u8 = 32;
+pub const SCALE_OPPOSITE_BISHOPS_WITH_PIECES: u8 = 48;
+pub const SCALE_ROOK_VS_MINOR: u8 = 16;
+pub const SCALE_MINOR_ADVANTAGE_WITHOUT_PAWNS: u8 = 8;
+pub const SCALE_DRAW: u8 = 0;
+
+const KPK_KEY: [u64; 2] = [get_endgame_key(WHITE, &[PAWN]), get_endgame_key(BLACK, &[PAWN])];
+const KBNK_KEY: [u64; 2] = [get_endgame_key(WHITE, &[BISHOP, KNIGHT]), get_endgame_key(BLACK, &[BISHOP, KNIGHT])];
+
+/// Gets a value which is added to the material key when `piece` with `color` appears on the board, and subtracted when it's removed. Every piece type
+/// has its own 4-bit counter in the key, so it's unique for every material configuration and allows reading the count of each piece.
+pub const fn get_material_key_delta(color: usize, piece: usize) -> u64 {
+    1u64 << ((color * 6 + piece) * 4)
+}
+
+/// Gets count of `piece` with `color` stored in the material `key`.
+pub fn get_pieces_count(key: u64, color: usize, piece: usize) -> u8 {
+    assert_fast!(color < 2);
+    assert_fast!(piece < 6);
+
+    ((key >> ((color * 6 + piece) * 4)) & 0xf) as u8
+}
+
+/// Recalculates material key of the `board` entirely.
+pub fn recalculate_material_key(board: &mut Board) {
+    let mut key = 0;
+
+    for color in ALL_COLORS {
+        for piece in ALL_PIECES {
+            key += (board.pieces[color][piece].bit_count() as u64) * get_material_key_delta(color, piece);
+        }
+    }
+
+    board.state.material_key = key;
+}
+
+/// Runs a specialised evaluator if the material on the `board` is recognized (KPK, KBNK and KXK, where X is any set of pieces able to force
+/// mate), otherwise returns [None]. The result is from the white color perspective, known wins are scored with [KNOWN_WIN_SCORE] bonus, and
+/// known draws (based on bitbases) as [DRAW_SCORE]. Signatures where mate can't be forced against the lone king (like KNNK, or KBBK with bishops
+/// on the same color) are not recognized on purpose: the known win bonus and the king-driving terms would make the search play for a win that
+/// doesn't exist, so they fall through to the regular evaluation and the generic scaling in [get_scale_factor].
+pub fn evaluate(board: &Board) -> Option<i16> {
+    let strong = if board.occupancy[BLACK] == board.pieces[BLACK][KING] {
+        WHITE
+    } else if board.occupancy[WHITE] == board.pieces[WHITE][KING] {
+        BLACK
+    } else {
+        return None;
+    };
+
+    let key = board.state.material_key;
+    let score = if key == KPK_KEY[strong] {
+        evaluate_kpk(board, strong)?
+    } else if key == KBNK_KEY[strong] {
+        evaluate_kbnk(board, strong)
+    } else if has_mating_material(board, strong) {
+        evaluate_kxk(board, strong)
+    } else {
+        return None;
+    };
+
+    Some(if strong == WHITE { score } else { -score })
+}
+
+/// Gets scale factor (from [SCALE_DRAW] to [SCALE_NORMAL]) of the ending score in `eval`, based on the material on the `board`. The stronger side
+/// is the one which is favored by the ending score. Recognized drawish endings:
+///  - rook against a minor piece, without pawns
+///  - minor piece advantage without pawns of the stronger side
+///  - bishop and rook pawns, where the bishop doesn't control the promotion square and the enemy king is next to it
+///  - opposite-colored bishops, with and without other pieces
+pub fn get_scale_factor(board: &Board, eval: PackedEval) -> u8 {
+    let strong = if eval.get_ending() >= 0 { WHITE } else { BLACK };
+    let weak = strong ^ 1;
+    let key = board.state.material_key;
+
+    let count = |color: usize, piece: usize| get_pieces_count(key, color, piece);
+    let non_pawn_material = |color: usize| [KNIGHT, BISHOP, ROOK, QUEEN].iter().map(|&piece| count(color, piece) as i16 * PIECE_VALUES[piece]).sum::<i16>();
+
+    if count(strong, PAWN) == 0 {
+        let strong_minors = count(strong, KNIGHT) + count(strong, BISHOP);
+        let weak_minors = count(weak, KNIGHT) + count(weak, BISHOP);
+
+        if count(strong, ROOK) == 1
+            && non_pawn_material(strong) == PIECE_VALUES[ROOK]
+            && weak_minors == 1
+            && non_pawn_material(weak) <= PIECE_VALUES[BISHOP]
+            && count(weak, PAWN) == 0
+        {
+            return SCALE_ROOK_VS_MINOR;
+        }
+
+        if strong_minors > 0 && non_pawn_material(strong) - non_pawn_material(weak) <= PIECE_VALUES[BISHOP] {
+            return SCALE_MINOR_ADVANTAGE_WITHOUT_PAWNS;
+        }
+    }
+
+    if count(strong, BISHOP) == 1 && non_pawn_material(strong) == PIECE_VALUES[BISHOP] && non_pawn_material(weak) == 0 && is_wrong_bishop(board, strong) {
+        return SCALE_DRAW;
+    }
+
+    if count(WHITE, BISHOP) == 1 && count(BLACK, BISHOP) == 1 {
+        let white_bishop_light = (board.pieces[WHITE][BISHOP] & WHITE_SQUARES_BB) != 0;
+        let black_bishop_light = (board.pieces[BLACK][BISHOP] & WHITE_SQUARES_BB) != 0;
+
+        if white_bishop_light != black_bishop_light {
+            return if non_pawn_material(WHITE) == PIECE_VALUES[BISHOP] && non_pawn_material(BLACK) == PIECE_VALUES[BISHOP] {
+                SCALE_OPPOSITE_BISHOPS
+            } else {
+                SCALE_OPPOSITE_BISHOPS_WITH_PIECES
+            };
+        }
+    }
+
+    SCALE_NORMAL
+}
+
+/// Checks if `strong` side has only rook pawns on a single file, with a bishop which doesn't control the promotion square, and the enemy king
+/// is next to that square (so it can't be driven away).
+fn is_wrong_bishop(board: &Board, strong: usize) -> bool {
+    let pawns_bb = board.pieces[strong][PAWN];
+    if pawns_bb == 0 {
+        return false;
+    }
+
+    let file_bb = if (pawns_bb & !FILE_A_BB) == 0 {
+        FILE_A_BB
+    } else if (pawns_bb & !FILE_H_BB) == 0 {
+        FILE_H_BB
+    } else {
+        return false;
+    };
+
+    let promotion_square = (file_bb & if strong == WHITE { RANK_8_BB } else { RANK_1_BB }).bit_scan();
+    let promotion_square_light = ((1u64 << promotion_square) & WHITE_SQUARES_BB) != 0;
+    let bishop_light = (board.pieces[strong][BISHOP] & WHITE_SQUARES_BB) != 0;
+    let enemy_king_square = board.pieces[strong ^ 1][KING].bit_scan();
+
+    promotion_square_light != bishop_light && get_distance(enemy_king_square, promotion_square) <= 1
+}
+
+/// Checks if `strong` side has enough material to force mate against the lone king: a queen, a rook, two bishops on different colors, or a bishop
+/// and a knight.
+fn has_mating_material(board: &Board, strong: usize) -> bool {
+    let pieces = &board.pieces[strong];
+    let bishops_on_both_colors = (pieces[BISHOP] & WHITE_SQUARES_BB) != 0 && (pieces[BISHOP] & BLACK_SQUARES_BB) != 0;
+
+    pieces[QUEEN] != 0 || pieces[ROOK] != 0 || bishops_on_both_colors || (pieces[BISHOP] != 0 && pieces[KNIGHT] != 0)
+}
+
+/// Evaluates KPK ending using bitbase, from the `strong` side perspective. Returns [None] if the bitbase is not available.
+fn evaluate_kpk(board: &Board, strong: usize) -> Option<i16> {
+    let win = match bitbase::get_wdl(board)? {
+        WdlResult::Win => board.stm == strong,
+        WdlResult::Loss => board.stm != strong,
+        _ => false,
+    };
+
+    if !win {
+        return Some(DRAW_SCORE);
+    }
+
+    let pawn_square = board.pieces[strong][PAWN].bit_scan();
+    let pawn_rank = if strong == WHITE { pawn_square >> 3 } else { 7 - (pawn_square >> 3) };
+
+    Some(KNOWN_WIN_SCORE + PIECE_VALUES[PAWN] + params::KPK_PAWN_RANK_BONUS * pawn_rank as i16)
+}
+
+/// Evaluates KBNK ending from the `strong` side perspective, driving the enemy king into the corner with the same color as the bishop.
+fn evaluate_kbnk(board: &Board, strong: usize) -> i16 {
+    if let Some(WdlResult::Draw) = bitbase::get_wdl(board) {
+        return DRAW_SCORE;
+    }
+
+    let king_square = board.pieces[strong][KING].bit_scan();
+    let enemy_king_square = board.pieces[strong ^ 1][KING].bit_scan();
+    let corners = if (board.pieces[strong][BISHOP] & WHITE_SQUARES_BB) != 0 { [H1, A8] } else { [A1, H8] };
+    let corner_distance = cmp::min(get_distance(enemy_king_square, corners[0]), get_distance(enemy_king_square, corners[1]));
+
+    let mut score = KNOWN_WIN_SCORE + PIECE_VALUES[BISHOP] + PIECE_VALUES[KNIGHT];
+    score += params::KBNK_CORNER_DISTANCE_BONUS * (7 - corner_distance as i16);
+    score += params::KBNK_KINGS_DISTANCE_BONUS * (7 - get_distance(king_square, enemy_king_square) as i16);
+    score += params::KBNK_CENTER_DISTANCE_BONUS * get_center_distance(enemy_king_square) as i16;
+
+    score
+}
+
+/// Evaluates KXK ending from the `strong` side perspective, driving the enemy king to the edge of the board and bringing both kings closer.
+fn evaluate_kxk(board: &Board, strong: usize) -> i16 {
+    if let Some(WdlResult::Draw) = bitbase::get_wdl(board) {
+        return DRAW_SCORE;
+    }
+
+    let king_square = board.pieces[strong][KING].bit_scan();
+    let enemy_king_square = board.pieces[strong ^ 1][KING].bit_scan();
+
+    let mut score = KNOWN_WIN_SCORE;
+    for piece in [PAWN, KNIGHT, BISHOP, ROOK, QUEEN] {
+        score += board.pieces[strong][piece].bit_count() as i16 * PIECE_VALUES[piece];
+    }
+
+    score += params::KXK_CENTER_DISTANCE_BONUS * get_center_distance(enemy_king_square) as i16;
+    score += params::KXK_KINGS_DISTANCE_BONUS * (7 - get_distance(king_square, enemy_king_square) as i16);
+
+    score
+}
+
+/// Gets Chebyshev distance (number of king moves) between `from` and `to` squares.
+pub fn get_distance(from: usize, to: usize) -> u8 {
+    let file_distance = ((from & 7) as i8 - (to & 7) as i8).abs();
+    let rank_distance = ((from >> 3) as i8 - (to >> 3) as i8).abs();
+
+    cmp::max(file_distance, rank_distance) as u8
+}
+
+/// Gets distance of `square` from the center of the board (0 for the four central squares, 3 for edges).
+fn get_center_distance(square: usize) -> u8 {
+    let file = (square & 7) as i8;
+    let rank = (square >> 3) as i8;
+
+    cmp::max(cmp::max(3 - file, file - 4), cmp::max(3 - rank, rank - 4)) as u8
+}
+
+/// Gets material key of the position with both kings and `pieces` owned by `color`.
+const fn get_endgame_key(color: usize, pieces: &[usize]) -> u64 {
+    let mut key = get_material_key_delta(WHITE, KING) + get_material_key_delta(BLACK, KING);
+    let mut index = 0;
+
+    while index < pieces.len() {
+        key += get_material_key_delta(color, pieces[index]);
+        index += 1;
+    }
+
+    key
+}
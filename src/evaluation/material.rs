@@ -1,18 +1,35 @@
+use super::endgame;
+use super::params;
+use super::trace::EvalTrace;
 use super::PackedEval;
-use crate::evaluation::params;
+use crate::cache::material::MHTable;
+use crate::engine::stats::SearchStats;
 use crate::state::representation::Board;
 use crate::state::*;
 use crate::utils::assert_fast;
 use crate::utils::bithelpers::BitHelpers;
+use crate::utils::dev;
+use crate::utils::eval_param;
 
 #[cfg(feature = "dev")]
 use crate::tuning::tuner::TunerCoeff;
 
+const IMBALANCE_PIECE_NAMES: [&str; 5] = ["PAWN", "KNIGHT", "BISHOP", "ROOK", "QUEEN"];
+
+// Imbalance weights are not tuned yet, so the evaluation is skipped entirely (including the material hashtable lookup) while all of them are zeros.
+// Developer builds always evaluate it, as weights can be changed there at runtime.
+const IMBALANCE_ENABLED: bool = cfg!(feature = "dev") || !is_zero(&params::IMBALANCE_OURS) || !is_zero(&params::IMBALANCE_THEIRS);
+
 pub struct MaterialData {
     pub bishop_pair: i8,
     pub pawns_attacking_pieces: i8,
 }
 
+pub struct ImbalanceData {
+    pub ours: [i16; 15],
+    pub theirs: [i16; 10],
+}
+
 /// Evaluates material on the `board` and returns score from the white color perspective (more than 0 when advantage, less than 0 when disadvantage).
 /// The piece values themself are included in PST so it's no longer evaluated here, instead other features like bishop pair are processed.
 pub fn evaluate(board: &Board) -> PackedEval {
@@ -20,12 +37,98 @@ pub fn evaluate(board: &Board) -> PackedEval {
     let white_data = get_material_data(board, WHITE);
     let black_data = get_material_data(board, BLACK);
 
-    result += (white_data.bishop_pair - black_data.bishop_pair) * params::BISHOP_PAIR;
-    result += (white_data.pawns_attacking_pieces - black_data.pawns_attacking_pieces) * params::PAWNS_ATTACKING_PIECES;
+    result += (white_data.bishop_pair - black_data.bishop_pair) * eval_param!(BISHOP_PAIR);
+    result += (white_data.pawns_attacking_pieces - black_data.pawns_attacking_pieces) * eval_param!(PAWNS_ATTACKING_PIECES);
 
     result
 }
 
+/// Evaluates material imbalance on the `board` and returns score from the white color perspective (more than 0 when advantage, less than 0 when
+/// disadvantage). The imbalance is a quadratic function of piece counts, where every pair of our pieces ([params::IMBALANCE_OURS]) and every pair
+/// of our and enemy pieces ([params::IMBALANCE_THEIRS]) has its own weight - this allows to model interactions like knights gaining value with
+/// more pawns on the board, or redundancy of major pieces. Both tables store only pairs with the first piece not less valuable than the second one,
+/// since the rest of them are duplicates. Pairs of our and enemy pieces of the same type are skipped too, as they cancel out in the final score.
+///
+/// The result depends only on the material, so each evaluation is saved in the material hashtable (`mhtable`), and used again if possible. While
+/// imbalance weights are not tuned (all of them are zeros), the evaluation is skipped and zero is returned immediately.
+pub fn evaluate_imbalance(board: &Board, mhtable: &MHTable, stats: &mut SearchStats) -> PackedEval {
+    if !IMBALANCE_ENABLED {
+        return PackedEval::default();
+    }
+
+    let hash = get_material_hash(board.state.material_key);
+    match mhtable.get(hash) {
+        Some(entry) => {
+            dev!(stats.mhtable_hits += 1);
+            return PackedEval::new(entry.score_opening, entry.score_ending);
+        }
+        None => {
+            dev!(stats.mhtable_misses += 1);
+        }
+    }
+
+    let eval = evaluate_imbalance_without_cache(board);
+
+    mhtable.add(hash, eval.get_opening(), eval.get_ending());
+    dev!(stats.mhtable_added += 1);
+
+    eval
+}
+
+/// Does the same thing as [evaluate_imbalance], but without using material hashtable to save evaluations.
+pub fn evaluate_imbalance_without_cache(board: &Board) -> PackedEval {
+    if !IMBALANCE_ENABLED {
+        return PackedEval::default();
+    }
+
+    let mut result = PackedEval::default();
+    let white_data = get_imbalance_data(board, WHITE);
+    let black_data = get_imbalance_data(board, BLACK);
+
+    for index in 0..white_data.ours.len() {
+        result += (white_data.ours[index] - black_data.ours[index]) * eval_param!(IMBALANCE_OURS)[index];
+    }
+
+    for index in 0..white_data.theirs.len() {
+        result += (white_data.theirs[index] - black_data.theirs[index]) * eval_param!(IMBALANCE_THEIRS)[index];
+    }
+
+    result
+}
+
+/// Records all material features of the `board` in `trace`.
+pub fn trace(board: &Board, trace: &mut EvalTrace) {
+    let white_data = get_material_data(board, WHITE);
+    let black_data = get_material_data(board, BLACK);
+
+    trace.add("BISHOP_PAIR", white_data.bishop_pair as i16, black_data.bishop_pair as i16, eval_param!(BISHOP_PAIR));
+    trace.add(
+        "PAWNS_ATTACKING_PIECES",
+        white_data.pawns_attacking_pieces as i16,
+        black_data.pawns_attacking_pieces as i16,
+        eval_param!(PAWNS_ATTACKING_PIECES),
+    );
+}
+
+/// Records all material imbalance features of the `board` in `trace`, every pair of pieces as a separate entry.
+pub fn trace_imbalance(board: &Board, trace: &mut EvalTrace) {
+    let white_data = get_imbalance_data(board, WHITE);
+    let black_data = get_imbalance_data(board, BLACK);
+
+    for first in 0..5 {
+        for second in 0..=first {
+            let name = format!("[{}][{}]", IMBALANCE_PIECE_NAMES[first], IMBALANCE_PIECE_NAMES[second]);
+            let index = get_ours_index(first, second);
+            trace.add(&format!("IMBALANCE_OURS{}", name), white_data.ours[index], black_data.ours[index], eval_param!(IMBALANCE_OURS)[index]);
+
+            if second < first {
+                let index = get_theirs_index(first, second);
+                trace.add(&format!("IMBALANCE_THEIRS{}", name), white_data.theirs[index], black_data.theirs[index], eval_param!(IMBALANCE_THEIRS)[index]);
+            }
+        }
+    }
+}
+
 /// Gets material data for `board` and `color`.
 fn get_material_data(board: &Board, color: usize) -> MaterialData {
     assert_fast!(color < 2);
@@ -37,19 +140,85 @@ fn get_material_data(board: &Board, color: usize) -> MaterialData {
     MaterialData { bishop_pair, pawns_attacking_pieces }
 }
 
+/// Gets material imbalance data for `board` and `color`: products of piece counts for every pair of our pieces (in the order of [params::IMBALANCE_OURS]),
+/// and for every pair of our and enemy pieces (in the order of [params::IMBALANCE_THEIRS]).
+fn get_imbalance_data(board: &Board, color: usize) -> ImbalanceData {
+    assert_fast!(color < 2);
+
+    let our_counts = get_pieces_counts(board, color);
+    let their_counts = get_pieces_counts(board, color ^ 1);
+    let mut data = ImbalanceData { ours: [0; 15], theirs: [0; 10] };
+
+    for first in 0..5 {
+        for second in 0..=first {
+            data.ours[get_ours_index(first, second)] = our_counts[first] * our_counts[second];
+        }
+
+        for second in 0..first {
+            data.theirs[get_theirs_index(first, second)] = our_counts[first] * their_counts[second];
+        }
+    }
+
+    data
+}
+
+/// Gets counts of pawns, knights, bishops, rooks and queens with `color`, based on the material key of the `board`.
+fn get_pieces_counts(board: &Board, color: usize) -> [i16; 5] {
+    assert_fast!(color < 2);
+
+    let mut counts = [0; 5];
+    for (piece, count) in counts.iter_mut().enumerate() {
+        *count = endgame::get_pieces_count(board.state.material_key, color, piece) as i16;
+    }
+
+    counts
+}
+
+/// Gets index of the pair of `first` and `second` pieces (where `second` is not greater than `first`) in [params::IMBALANCE_OURS].
+fn get_ours_index(first: usize, second: usize) -> usize {
+    first * (first + 1) / 2 + second
+}
+
+/// Gets index of the pair of `first` and `second` pieces (where `second` is less than `first`) in [params::IMBALANCE_THEIRS].
+fn get_theirs_index(first: usize, second: usize) -> usize {
+    first * (first - 1) / 2 + second
+}
+
+/// Gets a well-distributed hash of the material `key` (which itself is just a set of piece counters), so it can be used as an index in the material hashtable.
+fn get_material_hash(key: u64) -> u64 {
+    let mut hash = key ^ (key >> 30);
+    hash = hash.wrapping_mul(0xbf58476d1ce4e5b9);
+    hash ^= hash >> 27;
+    hash = hash.wrapping_mul(0x94d049bb133111eb);
+    hash ^ (hash >> 31)
+}
+
 /// Gets coefficients of material for `board` and inserts them into `coeffs`. Similarly, their indices (starting from `index`) are inserted into `indices`.
 #[cfg(feature = "dev")]
 pub fn get_coeffs(board: &Board, index: &mut u16, coeffs: &mut Vec<TunerCoeff>, indices: &mut Vec<u16>) {
     let white_data = get_material_data(board, WHITE);
     let black_data = get_material_data(board, BLACK);
 
+    let pawns = board.pieces[WHITE][PAWN].bit_count() as i8 - board.pieces[BLACK][PAWN].bit_count() as i8;
+    let knights = board.pieces[WHITE][KNIGHT].bit_count() as i8 - board.pieces[BLACK][KNIGHT].bit_count() as i8;
+    let bishops = board.pieces[WHITE][BISHOP].bit_count() as i8 - board.pieces[BLACK][BISHOP].bit_count() as i8;
+    let rooks = board.pieces[WHITE][ROOK].bit_count() as i8 - board.pieces[BLACK][ROOK].bit_count() as i8;
+    let queens = board.pieces[WHITE][QUEEN].bit_count() as i8 - board.pieces[BLACK][QUEEN].bit_count() as i8;
+    let kings = board.pieces[WHITE][KING].bit_count() as i8 - board.pieces[BLACK][KING].bit_count() as i8;
+
     let mut data = [
-        TunerCoeff::new(board.pieces[WHITE][PAWN].bit_count() as i8 - board.pieces[BLACK][PAWN].bit_count() as i8, OPENING),
-        TunerCoeff::new(board.pieces[WHITE][KNIGHT].bit_count() as i8 - board.pieces[BLACK][KNIGHT].bit_count() as i8, OPENING),
-        TunerCoeff::new(board.pieces[WHITE][BISHOP].bit_count() as i8 - board.pieces[BLACK][BISHOP].bit_count() as i8, OPENING),
-        TunerCoeff::new(board.pieces[WHITE][ROOK].bit_count() as i8 - board.pieces[BLACK][ROOK].bit_count() as i8, OPENING),
-        TunerCoeff::new(board.pieces[WHITE][QUEEN].bit_count() as i8 - board.pieces[BLACK][QUEEN].bit_count() as i8, OPENING),
-        TunerCoeff::new(board.pieces[WHITE][KING].bit_count() as i8 - board.pieces[BLACK][KING].bit_count() as i8, OPENING),
+        TunerCoeff::new(pawns, OPENING),
+        TunerCoeff::new(pawns, ENDING),
+        TunerCoeff::new(knights, OPENING),
+        TunerCoeff::new(knights, ENDING),
+        TunerCoeff::new(bishops, OPENING),
+        TunerCoeff::new(bishops, ENDING),
+        TunerCoeff::new(rooks, OPENING),
+        TunerCoeff::new(rooks, ENDING),
+        TunerCoeff::new(queens, OPENING),
+        TunerCoeff::new(queens, ENDING),
+        TunerCoeff::new(kings, OPENING),
+        TunerCoeff::new(kings, ENDING),
         TunerCoeff::new(white_data.bishop_pair - black_data.bishop_pair, OPENING),
         TunerCoeff::new(white_data.bishop_pair - black_data.bishop_pair, ENDING),
         TunerCoeff::new(white_data.pawns_attacking_pieces - black_data.pawns_attacking_pieces, OPENING),
@@ -66,3 +235,40 @@ pub fn get_coeffs(board: &Board, index: &mut u16, coeffs: &mut Vec<TunerCoeff>,
         *index += 1;
     }
 }
+
+/// Gets coefficients of material imbalance for `board` and inserts them into `coeffs`. Similarly, their indices (starting from `index`) are inserted into `indices`.
+/// The order of coefficients is the same as in [params::IMBALANCE_OURS] and [params::IMBALANCE_THEIRS].
+#[cfg(feature = "dev")]
+pub fn get_imbalance_coeffs(board: &Board, index: &mut u16, coeffs: &mut Vec<TunerCoeff>, indices: &mut Vec<u16>) {
+    let white_data = get_imbalance_data(board, WHITE);
+    let black_data = get_imbalance_data(board, BLACK);
+
+    // Products never exceed 100 (at most 10 pieces of the same type in a legal position), so they always fit in the range of coefficients
+    let ours = white_data.ours.iter().zip(black_data.ours.iter()).map(|(white, black)| (white - black) as i8);
+    let theirs = white_data.theirs.iter().zip(black_data.theirs.iter()).map(|(white, black)| (white - black) as i8);
+    let data = ours.chain(theirs).flat_map(|value| [TunerCoeff::new(value, OPENING), TunerCoeff::new(value, ENDING)]);
+
+    for coeff in data {
+        let (value, _) = coeff.get_data();
+        if value != 0 {
+            coeffs.push(coeff);
+            indices.push(*index);
+        }
+
+        *index += 1;
+    }
+}
+
+/// Checks if all `values` are zeros.
+const fn is_zero(values: &[PackedEval]) -> bool {
+    let mut index = 0;
+    while index < values.len() {
+        if values[index].data != 0 {
+            return false;
+        }
+
+        index += 1;
+    }
+
+    true
+}
@@ -1,8 +1,10 @@
+use super::trace::EvalTrace;
 use super::*;
 use crate::state::movescan;
 use crate::state::representation::Board;
 use crate::utils::assert_fast;
 use crate::utils::bithelpers::BitHelpers;
+use crate::utils::eval_param;
 
 #[cfg(feature = "dev")]
 use crate::tuning::tuner::TunerCoeff;
@@ -16,6 +18,15 @@ pub struct MobilityData {
     queen_mobility: PieceMobility,
 }
 
+pub struct PlacementData {
+    knight_outposts: i8,
+    bishop_outposts: i8,
+    bad_bishop_pawns: i8,
+    rook_on_seventh: i8,
+    queen_on_seventh: i8,
+    connected_rooks: i8,
+}
+
 #[derive(Default)]
 pub struct EvalAux {
     pub king_area_threats: i8,
@@ -31,29 +42,82 @@ pub struct PieceMobility {
 }
 
 /// Evaluates mobility and part of the king safety on the `board` and returns score from the white color perspective (more than 0 when advantage,
-/// less than 0 when disadvantage). This evaluator does two things at once: first, counts all possible moves of knight, bishop, rook, queen
-/// (pawns and king are too slow and not very important), and second, fills `white_aux` and `black_aux` with additional data used in other evaluators.
+/// less than 0 when disadvantage). This evaluator does two things at once: first, counts all possible moves of knight, bishop, rook, queen (pawns
+/// and king are too slow and not very important) together with rooks on open files, and second, fills `white_aux` and `black_aux` with additional
+/// data used in other evaluators.
 pub fn evaluate(board: &Board, white_aux: &mut EvalAux, black_aux: &mut EvalAux) -> PackedEval {
     let mut result = PackedEval::default();
     let white_data = get_mobility_data(board, WHITE, white_aux);
     let black_data = get_mobility_data(board, BLACK, black_aux);
 
-    result += (white_data.rook_open_file - black_data.rook_open_file) * params::ROOK_OPEN_FILE;
-    result += (white_data.rook_semi_open_file - black_data.rook_semi_open_file) * params::ROOK_SEMI_OPEN_FILE;
+    result += (white_data.rook_open_file - black_data.rook_open_file) * eval_param!(ROOK_OPEN_FILE);
+    result += (white_data.rook_semi_open_file - black_data.rook_semi_open_file) * eval_param!(ROOK_SEMI_OPEN_FILE);
+
+    result += (white_data.knight_mobility.inner - black_data.knight_mobility.inner) * eval_param!(MOBILITY_INNER)[KNIGHT];
+    result += (white_data.bishop_mobility.inner - black_data.bishop_mobility.inner) * eval_param!(MOBILITY_INNER)[BISHOP];
+    result += (white_data.rook_mobility.inner - black_data.rook_mobility.inner) * eval_param!(MOBILITY_INNER)[ROOK];
+    result += (white_data.queen_mobility.inner - black_data.queen_mobility.inner) * eval_param!(MOBILITY_INNER)[QUEEN];
+
+    result += (white_data.knight_mobility.outer - black_data.knight_mobility.outer) * eval_param!(MOBILITY_OUTER)[KNIGHT];
+    result += (white_data.bishop_mobility.outer - black_data.bishop_mobility.outer) * eval_param!(MOBILITY_OUTER)[BISHOP];
+    result += (white_data.rook_mobility.outer - black_data.rook_mobility.outer) * eval_param!(MOBILITY_OUTER)[ROOK];
+    result += (white_data.queen_mobility.outer - black_data.queen_mobility.outer) * eval_param!(MOBILITY_OUTER)[QUEEN];
+
+    result
+}
 
-    result += (white_data.knight_mobility.inner - black_data.knight_mobility.inner) * params::MOBILITY_INNER[KNIGHT];
-    result += (white_data.bishop_mobility.inner - black_data.bishop_mobility.inner) * params::MOBILITY_INNER[BISHOP];
-    result += (white_data.rook_mobility.inner - black_data.rook_mobility.inner) * params::MOBILITY_INNER[ROOK];
-    result += (white_data.queen_mobility.inner - black_data.queen_mobility.inner) * params::MOBILITY_INNER[QUEEN];
+/// Evaluates piece placement (outposts, bad bishops, rooks and queens on the seventh rank, connected rooks) on the `board` and returns score from
+/// the white color perspective (more than 0 when advantage, less than 0 when disadvantage). Placement weights are not tuned yet, so this evaluator
+/// is not a part of [Board::evaluate] (features are only traced and passed to the tuner).
+pub fn evaluate_placement(board: &Board) -> PackedEval {
+    let mut result = PackedEval::default();
+    let white_data = get_placement_data(board, WHITE);
+    let black_data = get_placement_data(board, BLACK);
 
-    result += (white_data.knight_mobility.outer - black_data.knight_mobility.outer) * params::MOBILITY_OUTER[KNIGHT];
-    result += (white_data.bishop_mobility.outer - black_data.bishop_mobility.outer) * params::MOBILITY_OUTER[BISHOP];
-    result += (white_data.rook_mobility.outer - black_data.rook_mobility.outer) * params::MOBILITY_OUTER[ROOK];
-    result += (white_data.queen_mobility.outer - black_data.queen_mobility.outer) * params::MOBILITY_OUTER[QUEEN];
+    result += (white_data.knight_outposts - black_data.knight_outposts) * eval_param!(KNIGHT_OUTPOST);
+    result += (white_data.bishop_outposts - black_data.bishop_outposts) * eval_param!(BISHOP_OUTPOST);
+    result += (white_data.bad_bishop_pawns - black_data.bad_bishop_pawns) * eval_param!(BAD_BISHOP);
+    result += (white_data.rook_on_seventh - black_data.rook_on_seventh) * eval_param!(ROOK_ON_SEVENTH);
+    result += (white_data.queen_on_seventh - black_data.queen_on_seventh) * eval_param!(QUEEN_ON_SEVENTH);
+    result += (white_data.connected_rooks - black_data.connected_rooks) * eval_param!(CONNECTED_ROOKS);
 
     result
 }
 
+/// Records all mobility features of the `board` in `trace`, filling `white_aux` and `black_aux` in the same way as [evaluate].
+pub fn trace(board: &Board, white_aux: &mut EvalAux, black_aux: &mut EvalAux, trace: &mut EvalTrace) {
+    let white_data = get_mobility_data(board, WHITE, white_aux);
+    let black_data = get_mobility_data(board, BLACK, black_aux);
+
+    trace.add("ROOK_OPEN_FILE", white_data.rook_open_file as i16, black_data.rook_open_file as i16, eval_param!(ROOK_OPEN_FILE));
+    trace.add("ROOK_SEMI_OPEN_FILE", white_data.rook_semi_open_file as i16, black_data.rook_semi_open_file as i16, eval_param!(ROOK_SEMI_OPEN_FILE));
+
+    let pieces = [
+        ("KNIGHT", KNIGHT, &white_data.knight_mobility, &black_data.knight_mobility),
+        ("BISHOP", BISHOP, &white_data.bishop_mobility, &black_data.bishop_mobility),
+        ("ROOK", ROOK, &white_data.rook_mobility, &black_data.rook_mobility),
+        ("QUEEN", QUEEN, &white_data.queen_mobility, &black_data.queen_mobility),
+    ];
+
+    for (name, piece, white_mobility, black_mobility) in pieces {
+        trace.add(&format!("MOBILITY_INNER[{}]", name), white_mobility.inner as i16, black_mobility.inner as i16, eval_param!(MOBILITY_INNER)[piece]);
+        trace.add(&format!("MOBILITY_OUTER[{}]", name), white_mobility.outer as i16, black_mobility.outer as i16, eval_param!(MOBILITY_OUTER)[piece]);
+    }
+}
+
+/// Records all piece placement features of the `board` in `trace`.
+pub fn trace_placement(board: &Board, trace: &mut EvalTrace) {
+    let white_data = get_placement_data(board, WHITE);
+    let black_data = get_placement_data(board, BLACK);
+
+    trace.add("KNIGHT_OUTPOST", white_data.knight_outposts as i16, black_data.knight_outposts as i16, eval_param!(KNIGHT_OUTPOST));
+    trace.add("BISHOP_OUTPOST", white_data.bishop_outposts as i16, black_data.bishop_outposts as i16, eval_param!(BISHOP_OUTPOST));
+    trace.add("BAD_BISHOP", white_data.bad_bishop_pawns as i16, black_data.bad_bishop_pawns as i16, eval_param!(BAD_BISHOP));
+    trace.add("ROOK_ON_SEVENTH", white_data.rook_on_seventh as i16, black_data.rook_on_seventh as i16, eval_param!(ROOK_ON_SEVENTH));
+    trace.add("QUEEN_ON_SEVENTH", white_data.queen_on_seventh as i16, black_data.queen_on_seventh as i16, eval_param!(QUEEN_ON_SEVENTH));
+    trace.add("CONNECTED_ROOKS", white_data.connected_rooks as i16, black_data.connected_rooks as i16, eval_param!(CONNECTED_ROOKS));
+}
+
 /// Gets mobility data for `board`, `color` and fills `aux` with additional data used in other evaluators.
 fn get_mobility_data(board: &Board, color: usize, aux: &mut EvalAux) -> MobilityData {
     assert_fast!(color < 2);
@@ -88,12 +152,100 @@ fn get_mobility_data(board: &Board, color: usize, aux: &mut EvalAux) -> Mobility
     }
 }
 
+/// Gets piece placement data for `board` and `color`.
+fn get_placement_data(board: &Board, color: usize) -> PlacementData {
+    assert_fast!(color < 2);
+
+    let occupancy_bb = board.occupancy[WHITE] | board.occupancy[BLACK];
+    let (seventh_rank_bb, eighth_rank_bb) = match color {
+        WHITE => (RANK_7_BB, RANK_8_BB),
+        BLACK => (RANK_2_BB, RANK_1_BB),
+        _ => panic_fast!("Invalid value: color={}", color),
+    };
+
+    // Pieces on the seventh rank are dangerous only if there are enemy pawns to attack, or the enemy king is cut off on the last rank
+    let seventh_rank_active = (board.pieces[color ^ 1][PAWN] & seventh_rank_bb) != 0 || (board.pieces[color ^ 1][KING] & eighth_rank_bb) != 0;
+    let (rook_on_seventh, queen_on_seventh) = if seventh_rank_active {
+        ((board.pieces[color][ROOK] & seventh_rank_bb).bit_count() as i8, (board.pieces[color][QUEEN] & seventh_rank_bb).bit_count() as i8)
+    } else {
+        (0, 0)
+    };
+
+    let rooks_bb = board.pieces[color][ROOK];
+    let connected_rooks =
+        if rooks_bb.bit_count() >= 2 && (movegen::get_rook_moves(occupancy_bb, rooks_bb.bit_scan()) & rooks_bb.pop_lsb()) != 0 { 1 } else { 0 };
+
+    PlacementData {
+        knight_outposts: get_outposts_count(board, color, board.pieces[color][KNIGHT]),
+        bishop_outposts: get_outposts_count(board, color, board.pieces[color][BISHOP]),
+        bad_bishop_pawns: get_bad_bishop_pawns_count(board, color),
+        rook_on_seventh,
+        queen_on_seventh,
+        connected_rooks,
+    }
+}
+
+/// Gets a number of `pieces_bb` with `color` standing on outposts: squares on the 4th, 5th or 6th rank (from the `color` perspective), supported
+/// by own pawn and impossible to attack by enemy pawns, as there are none of them in front of the piece on the adjacent files.
+fn get_outposts_count(board: &Board, color: usize, pieces_bb: u64) -> i8 {
+    assert_fast!(color < 2);
+
+    let outpost_ranks_bb = match color {
+        WHITE => RANK_4_BB | RANK_5_BB | RANK_6_BB,
+        BLACK => RANK_3_BB | RANK_4_BB | RANK_5_BB,
+        _ => panic_fast!("Invalid value: color={}", color),
+    };
+
+    let mut outposts = 0;
+    let mut pieces_bb = pieces_bb & outpost_ranks_bb & board.pawn_attacks[color];
+
+    while pieces_bb != 0 {
+        let square = pieces_bb.bit_scan();
+        pieces_bb = pieces_bb.pop_lsb();
+
+        let attack_span_bb = patterns::get_front(color, square) & !patterns::get_file(square);
+        if (attack_span_bb & board.pieces[color ^ 1][PAWN]) == 0 {
+            outposts += 1;
+        }
+    }
+
+    outposts
+}
+
+/// Gets a number of own pawns with `color`, which are blocked and stand on squares with the same color as one of the bishops.
+fn get_bad_bishop_pawns_count(board: &Board, color: usize) -> i8 {
+    assert_fast!(color < 2);
+
+    let occupancy_bb = board.occupancy[WHITE] | board.occupancy[BLACK];
+    let pawns_bb = board.pieces[color][PAWN];
+    let blocked_pawns_bb = match color {
+        WHITE => pawns_bb & (occupancy_bb >> 8),
+        BLACK => pawns_bb & (occupancy_bb << 8),
+        _ => panic_fast!("Invalid value: color={}", color),
+    };
+
+    let mut bad_bishop_pawns = 0;
+    let mut bishops_bb = board.pieces[color][BISHOP];
+
+    while bishops_bb != 0 {
+        let square_bb = bishops_bb.get_lsb();
+        bishops_bb = bishops_bb.pop_lsb();
+
+        let squares_bb = if (square_bb & WHITE_SQUARES_BB) != 0 { WHITE_SQUARES_BB } else { BLACK_SQUARES_BB };
+        bad_bishop_pawns += (blocked_pawns_bb & squares_bb).bit_count() as i8;
+    }
+
+    bad_bishop_pawns
+}
+
 /// Gets coefficients of mobility for `board` and inserts them into `coeffs`. Similarly, their indices (starting from `index`) are inserted into `indices`.
 /// Some additional data is also saved in `white_aux` and `black_aux` for further processing.
 #[cfg(feature = "dev")]
 pub fn get_coeffs(board: &Board, white_aux: &mut EvalAux, black_aux: &mut EvalAux, index: &mut u16, coeffs: &mut Vec<TunerCoeff>, indices: &mut Vec<u16>) {
     let white_data = get_mobility_data(board, WHITE, white_aux);
     let black_data = get_mobility_data(board, BLACK, black_aux);
+    let white_placement = get_placement_data(board, WHITE);
+    let black_placement = get_placement_data(board, BLACK);
 
     let mut data = [
         TunerCoeff::new(white_data.rook_open_file - black_data.rook_open_file, OPENING),
@@ -126,6 +278,19 @@ pub fn get_coeffs(board: &Board, white_aux: &mut EvalAux, black_aux: &mut EvalAu
         TunerCoeff::new(white_data.queen_mobility.outer - black_data.queen_mobility.outer, ENDING),
         TunerCoeff::new(0, OPENING),
         TunerCoeff::new(0, ENDING),
+        //
+        TunerCoeff::new(white_placement.knight_outposts - black_placement.knight_outposts, OPENING),
+        TunerCoeff::new(white_placement.knight_outposts - black_placement.knight_outposts, ENDING),
+        TunerCoeff::new(white_placement.bishop_outposts - black_placement.bishop_outposts, OPENING),
+        TunerCoeff::new(white_placement.bishop_outposts - black_placement.bishop_outposts, ENDING),
+        TunerCoeff::new(white_placement.bad_bishop_pawns - black_placement.bad_bishop_pawns, OPENING),
+        TunerCoeff::new(white_placement.bad_bishop_pawns - black_placement.bad_bishop_pawns, ENDING),
+        TunerCoeff::new(white_placement.rook_on_seventh - black_placement.rook_on_seventh, OPENING),
+        TunerCoeff::new(white_placement.rook_on_seventh - black_placement.rook_on_seventh, ENDING),
+        TunerCoeff::new(white_placement.queen_on_seventh - black_placement.queen_on_seventh, OPENING),
+        TunerCoeff::new(white_placement.queen_on_seventh - black_placement.queen_on_seventh, ENDING),
+        TunerCoeff::new(white_placement.connected_rooks - black_placement.connected_rooks, OPENING),
+        TunerCoeff::new(white_placement.connected_rooks - black_placement.connected_rooks, ENDING),
     ];
 
     for coeff in &mut data {
@@ -6,12 +6,18 @@ use std::ops;
 #[cfg(feature = "dev")]
 use crate::tuning::tuner::TunerParameter;
 
+pub mod endgame;
 pub mod material;
 pub mod mobility;
 pub mod params;
+pub mod passers;
 pub mod pawns;
 pub mod pst;
+#[cfg(feature = "dev")]
+pub mod runtime;
 pub mod safety;
+pub mod threats;
+pub mod trace;
 
 pub const INITIAL_GAME_PHASE: u8 = 24;
 pub const PIECE_VALUES: [i16; 6] = [100, 337, 338, 521, 1050, 10000];
@@ -61,6 +67,16 @@ impl PackedEval {
         ((opening_score + ending_score) / (INITIAL_GAME_PHASE as i32)) as i16
     }
 
+    /// Does the same thing as [PackedEval::taper_score], but the ending score is additionally scaled by `scale` (where [endgame::SCALE_NORMAL]
+    /// means no change), which allows to make drawish endings less attractive.
+    pub fn taper_score_scaled(&self, game_phase: u8, scale: u8) -> i16 {
+        let ending = (self.get_ending() as i32) * (scale as i32) / (endgame::SCALE_NORMAL as i32);
+        let opening_score = (self.get_opening() as i32) * (game_phase as i32);
+        let ending_score = ending * ((INITIAL_GAME_PHASE as i32) - (game_phase as i32));
+
+        ((opening_score + ending_score) / (INITIAL_GAME_PHASE as i32)) as i16
+    }
+
     /// Gets tuner coefficients for opening and ending score, constrained by `min`, `min_init`, `max_init` and `max`. Additionally, `offset` is added to each score.
     #[cfg(feature = "dev")]
     pub fn to_tuner_params(&self, min: i16, min_init: i16, max_init: i16, max: i16, offset: i16) -> [TunerParameter; 2] {
@@ -5,12 +5,19 @@
 use super::*;
 
 pub const TEMPO: i16 = 15;
+pub const TAPERED_PIECE_VALUES: [PackedEval; 6] = [s!(100, 100), s!(337, 337), s!(338, 338), s!(521, 521), s!(1050, 1050), s!(0, 0)];
 pub const BISHOP_PAIR: PackedEval = s!(20, 55);
 pub const PAWNS_ATTACKING_PIECES: PackedEval = s!(42, 43);
 pub const ROOK_OPEN_FILE: PackedEval = s!(24, -4);
 pub const ROOK_SEMI_OPEN_FILE: PackedEval = s!(9, 15);
 pub const MOBILITY_INNER: [PackedEval; 6] = [s!(0, 0), s!(11, 2), s!(10, 12), s!(7, 2), s!(4, 5), s!(0, 0)];
 pub const MOBILITY_OUTER: [PackedEval; 6] = [s!(0, 0), s!(3, 0), s!(2, 0), s!(3, 1), s!(2, 0), s!(0, 0)];
+pub const KNIGHT_OUTPOST: PackedEval = s!(0, 0);
+pub const BISHOP_OUTPOST: PackedEval = s!(0, 0);
+pub const BAD_BISHOP: PackedEval = s!(0, 0);
+pub const ROOK_ON_SEVENTH: PackedEval = s!(0, 0);
+pub const QUEEN_ON_SEVENTH: PackedEval = s!(0, 0);
+pub const CONNECTED_ROOKS: PackedEval = s!(0, 0);
 pub const DOUBLED_PAWN: [PackedEval; 8] = [s!(-1, -8), s!(-15, -20), s!(-25, -41), s!(-54, -49), s!(-22, -39), s!(0, 0), s!(0, 0), s!(0, 0)];
 pub const ISOLATED_PAWN: [PackedEval; 8] = [s!(-1, -9), s!(-13, -20), s!(-24, -31), s!(-33, -39), s!(-37, -56), s!(0, 0), s!(0, 0), s!(0, 0)];
 pub const CHAINED_PAWN: [PackedEval; 8] = [s!(0, 1), s!(10, 8), s!(19, 18), s!(26, 32), s!(32, 49), s!(40, 62), s!(44, 42), s!(0, 0)];
@@ -24,3 +31,33 @@ pub const KNIGHT_SAFE_CHECKS: [PackedEval; 8] = [s!(-118, 50), s!(-75, 46), s!(-
 pub const BISHOP_SAFE_CHECKS: [PackedEval; 8] = [s!(-88, 37), s!(-85, 54), s!(-54, 46), s!(0, 0), s!(0, 0), s!(0, 0), s!(0, 0), s!(0, 0)];
 pub const ROOK_SAFE_CHECKS: [PackedEval; 8] = [s!(-118, 41), s!(-70, 32), s!(-48, 30), s!(-32, 57), s!(17, 34), s!(0, 0), s!(0, 0), s!(0, 0)];
 pub const QUEEN_SAFE_CHECKS: [PackedEval; 8] = [s!(-110, 29), s!(-104, 62), s!(-81, 60), s!(-39, 37), s!(34, 5), s!(82, -5), s!(131, -20), s!(202, -69)];
+pub const MINORS_ATTACKING_MAJORS: [PackedEval; 8] = [s!(0, 0), s!(0, 0), s!(0, 0), s!(0, 0), s!(0, 0), s!(0, 0), s!(0, 0), s!(0, 0)];
+pub const HANGING_PIECES: [PackedEval; 8] = [s!(0, 0), s!(0, 0), s!(0, 0), s!(0, 0), s!(0, 0), s!(0, 0), s!(0, 0), s!(0, 0)];
+pub const SAFE_PAWN_PUSH_THREATS: [PackedEval; 8] = [s!(0, 0), s!(0, 0), s!(0, 0), s!(0, 0), s!(0, 0), s!(0, 0), s!(0, 0), s!(0, 0)];
+pub const QUEEN_ATTACKS: [PackedEval; 8] = [s!(0, 0), s!(0, 0), s!(0, 0), s!(0, 0), s!(0, 0), s!(0, 0), s!(0, 0), s!(0, 0)];
+pub const PASSED_PAWN_OWN_KING_DISTANCE: [PackedEval; 8] = [s!(0, 0), s!(0, 0), s!(0, 0), s!(0, 0), s!(0, 0), s!(0, 0), s!(0, 0), s!(0, 0)];
+pub const PASSED_PAWN_ENEMY_KING_DISTANCE: [PackedEval; 8] = [s!(0, 0), s!(0, 0), s!(0, 0), s!(0, 0), s!(0, 0), s!(0, 0), s!(0, 0), s!(0, 0)];
+pub const PASSED_PAWN_BLOCKED: PackedEval = s!(0, 0);
+pub const PASSED_PAWN_FREE_PATH: PackedEval = s!(0, 0);
+pub const PASSED_PAWN_UNSTOPPABLE: PackedEval = s!(0, 0);
+#[rustfmt::skip]
+pub const IMBALANCE_OURS: [PackedEval; 15] = [
+    s!(0, 0),
+    s!(0, 0), s!(0, 0),
+    s!(0, 0), s!(0, 0), s!(0, 0),
+    s!(0, 0), s!(0, 0), s!(0, 0), s!(0, 0),
+    s!(0, 0), s!(0, 0), s!(0, 0), s!(0, 0), s!(0, 0),
+];
+#[rustfmt::skip]
+pub const IMBALANCE_THEIRS: [PackedEval; 10] = [
+    s!(0, 0),
+    s!(0, 0), s!(0, 0),
+    s!(0, 0), s!(0, 0), s!(0, 0),
+    s!(0, 0), s!(0, 0), s!(0, 0), s!(0, 0),
+];
+pub const KPK_PAWN_RANK_BONUS: i16 = 20;
+pub const KBNK_CORNER_DISTANCE_BONUS: i16 = 20;
+pub const KBNK_KINGS_DISTANCE_BONUS: i16 = 10;
+pub const KBNK_CENTER_DISTANCE_BONUS: i16 = 5;
+pub const KXK_CENTER_DISTANCE_BONUS: i16 = 20;
+pub const KXK_KINGS_DISTANCE_BONUS: i16 = 10;
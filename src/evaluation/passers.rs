@@ -0,0 +1,175 @@
+use super::*;
+use crate::state::representation::Board;
+use crate::utils::assert_fast;
+use crate::utils::bithelpers::BitHelpers;
+use crate::utils::eval_param;
+use mobility::EvalAux;
+use std::cmp;
+use trace::EvalTrace;
+
+#[cfg(feature = "dev")]
+use crate::tuning::tuner::TunerCoeff;
+
+pub struct PassersData {
+    pub own_king_distance: [u8; 8],
+    pub enemy_king_distance: [u8; 8],
+    pub blocked: u8,
+    pub free_path: u8,
+    pub unstoppable: u8,
+}
+
+/// Evaluates passed pawns on the `board` in relation to other pieces and returns score from the white color perspective (more than 0 when advantage,
+/// less than 0 when disadvantage). Unlike [pawns::evaluate], the result depends on the position of kings and pieces, so it can't be stored in
+/// the pawn hashtable. Both additional parameters, `white_aux` and `black_aux`, are calculated during mobility evaluation. This evaluator considers:
+///  - distance of own and enemy king to the stop square
+///  - stop square blocked by any piece
+///  - free path to the promotion square (no pieces and no enemy attacks)
+///  - unstoppable pawns in pawn endings (based on the rule of the square)
+///
+/// Passed pawn weights are not tuned yet, so this evaluator is not a part of [Board::evaluate] (features are only traced and passed to the tuner).
+pub fn evaluate(board: &Board, white_aux: &EvalAux, black_aux: &EvalAux) -> PackedEval {
+    evaluate_color(board, WHITE, white_aux, black_aux) - evaluate_color(board, BLACK, white_aux, black_aux)
+}
+
+/// Evaluates passed pawns on the `board` for the specified `color`, using `white_aux` and `black_aux`.
+pub fn evaluate_color(board: &Board, color: usize, white_aux: &EvalAux, black_aux: &EvalAux) -> PackedEval {
+    assert_fast!(color < 2);
+
+    let mut result = PackedEval::default();
+    let their_aux = match color {
+        WHITE => black_aux,
+        BLACK => white_aux,
+        _ => panic_fast!("Invalid value: color={}", color),
+    };
+    let data = get_passers_data(board, color, their_aux);
+
+    for distance in 0..8 {
+        result += data.own_king_distance[distance] as i16 * eval_param!(PASSED_PAWN_OWN_KING_DISTANCE)[distance];
+        result += data.enemy_king_distance[distance] as i16 * eval_param!(PASSED_PAWN_ENEMY_KING_DISTANCE)[distance];
+    }
+
+    result += data.blocked as i16 * eval_param!(PASSED_PAWN_BLOCKED);
+    result += data.free_path as i16 * eval_param!(PASSED_PAWN_FREE_PATH);
+    result += data.unstoppable as i16 * eval_param!(PASSED_PAWN_UNSTOPPABLE);
+
+    result
+}
+
+/// Records all passed pawn features of the `board` in `trace`, using `white_aux` and `black_aux` calculated during mobility evaluation.
+pub fn trace(board: &Board, white_aux: &EvalAux, black_aux: &EvalAux, trace: &mut EvalTrace) {
+    let white_data = get_passers_data(board, WHITE, black_aux);
+    let black_data = get_passers_data(board, BLACK, white_aux);
+
+    for distance in 0..8 {
+        trace.add(
+            &format!("PASSED_PAWN_OWN_KING_DISTANCE[{}]", distance),
+            white_data.own_king_distance[distance] as i16,
+            black_data.own_king_distance[distance] as i16,
+            eval_param!(PASSED_PAWN_OWN_KING_DISTANCE)[distance],
+        );
+        trace.add(
+            &format!("PASSED_PAWN_ENEMY_KING_DISTANCE[{}]", distance),
+            white_data.enemy_king_distance[distance] as i16,
+            black_data.enemy_king_distance[distance] as i16,
+            eval_param!(PASSED_PAWN_ENEMY_KING_DISTANCE)[distance],
+        );
+    }
+
+    trace.add("PASSED_PAWN_BLOCKED", white_data.blocked as i16, black_data.blocked as i16, eval_param!(PASSED_PAWN_BLOCKED));
+    trace.add("PASSED_PAWN_FREE_PATH", white_data.free_path as i16, black_data.free_path as i16, eval_param!(PASSED_PAWN_FREE_PATH));
+    trace.add("PASSED_PAWN_UNSTOPPABLE", white_data.unstoppable as i16, black_data.unstoppable as i16, eval_param!(PASSED_PAWN_UNSTOPPABLE));
+}
+
+/// Gets passed pawns data for `board`, `color` and `their_aux` (calculated for the enemy color during mobility evaluation).
+pub fn get_passers_data(board: &Board, color: usize, their_aux: &EvalAux) -> PassersData {
+    assert_fast!(color < 2);
+
+    let mut data = PassersData { own_king_distance: [0; 8], enemy_king_distance: [0; 8], blocked: 0, free_path: 0, unstoppable: 0 };
+    let occupancy_bb = board.occupancy[WHITE] | board.occupancy[BLACK];
+    let king_square = board.pieces[color][KING].bit_scan();
+    let enemy_king_square = board.pieces[color ^ 1][KING].bit_scan();
+
+    let their_threats = their_aux.knight_threats
+        | their_aux.bishop_threats
+        | their_aux.rook_threats
+        | their_aux.queen_threats
+        | board.pawn_attacks[color ^ 1]
+        | movegen::get_king_moves(enemy_king_square);
+    let pawn_ending = board.occupancy[color ^ 1] == (board.pieces[color ^ 1][PAWN] | board.pieces[color ^ 1][KING]);
+
+    let mut pawns_bb = board.pieces[color][PAWN];
+    while pawns_bb != 0 {
+        let square = pawns_bb.bit_scan();
+        pawns_bb = pawns_bb.pop_lsb();
+
+        if (patterns::get_front(color, square) & board.pieces[color ^ 1][PAWN]) != 0 {
+            continue;
+        }
+
+        let (stop_square, promotion_square, relative_rank) = match color {
+            WHITE => (square + 8, (square & 7) + 56, square >> 3),
+            BLACK => (square - 8, square & 7, 7 - (square >> 3)),
+            _ => panic_fast!("Invalid value: color={}", color),
+        };
+        let path_bb = patterns::get_front(color, square) & patterns::get_file(square);
+
+        data.own_king_distance[endgame::get_distance(king_square, stop_square) as usize] += 1;
+        data.enemy_king_distance[endgame::get_distance(enemy_king_square, stop_square) as usize] += 1;
+
+        if (occupancy_bb & (1u64 << stop_square)) != 0 {
+            data.blocked += 1;
+        } else if (path_bb & (occupancy_bb | their_threats)) == 0 {
+            data.free_path += 1;
+        }
+
+        // Rule of the square: the enemy king can't catch the pawn if it's outside of the square between the pawn and the promotion square
+        if pawn_ending && (path_bb & occupancy_bb) == 0 {
+            let pawn_distance = cmp::min(7 - relative_rank, 5) as u8;
+            let king_distance = endgame::get_distance(enemy_king_square, promotion_square).saturating_sub((board.stm != color) as u8);
+
+            if king_distance > pawn_distance {
+                data.unstoppable += 1;
+            }
+        }
+    }
+
+    data
+}
+
+/// Gets coefficients of passed pawns for `board` and inserts them into `coeffs`. Similarly, their indices (starting from `index`) are inserted into `indices`.
+/// Additionally, `white_aux` and `black_aux` calculated during mobility phase are also used here.
+#[cfg(feature = "dev")]
+pub fn get_coeffs(board: &Board, white_aux: &EvalAux, black_aux: &EvalAux, index: &mut u16, coeffs: &mut Vec<TunerCoeff>, indices: &mut Vec<u16>) {
+    let white_data = get_passers_data(board, WHITE, black_aux);
+    let black_data = get_passers_data(board, BLACK, white_aux);
+
+    let mut data = Vec::new();
+    for distance in 0..8 {
+        let value = white_data.own_king_distance[distance] as i8 - black_data.own_king_distance[distance] as i8;
+        data.push(TunerCoeff::new(value, OPENING));
+        data.push(TunerCoeff::new(value, ENDING));
+    }
+
+    for distance in 0..8 {
+        let value = white_data.enemy_king_distance[distance] as i8 - black_data.enemy_king_distance[distance] as i8;
+        data.push(TunerCoeff::new(value, OPENING));
+        data.push(TunerCoeff::new(value, ENDING));
+    }
+
+    for (white_value, black_value) in
+        [(white_data.blocked, black_data.blocked), (white_data.free_path, black_data.free_path), (white_data.unstoppable, black_data.unstoppable)]
+    {
+        data.push(TunerCoeff::new(white_value as i8 - black_value as i8, OPENING));
+        data.push(TunerCoeff::new(white_value as i8 - black_value as i8, ENDING));
+    }
+
+    for coeff in &mut data {
+        let (value, _) = coeff.get_data();
+        if value != 0 {
+            indices.push(*index);
+            coeffs.push(coeff.clone());
+        }
+
+        *index += 1;
+    }
+}
@@ -5,7 +5,9 @@ use crate::state::representation::Board;
 use crate::utils::assert_fast;
 use crate::utils::bithelpers::BitHelpers;
 use crate::utils::dev;
+use crate::utils::eval_param;
 use std::cmp;
+use trace::EvalTrace;
 
 #[cfg(feature = "dev")]
 use crate::tuning::tuner::TunerCoeff;
@@ -66,18 +68,43 @@ fn evaluate_color(board: &Board, color: usize) -> PackedEval {
     let mut result = PackedEval::default();
     let pawns_data = get_pawns_data(board, color);
 
-    result += params::DOUBLED_PAWN[pawns_data.doubled_pawns.min(7) as usize];
-    result += params::ISOLATED_PAWN[pawns_data.isolated_pawns.min(7) as usize];
-    result += params::CHAINED_PAWN[pawns_data.chained_pawns.min(7) as usize];
-    result += params::PASSED_PAWN[pawns_data.passed_pawns.min(7) as usize];
-    result += params::BACKWARD_PAWN_OPEN_FILE[pawns_data.backward_pawns_open_file.min(7) as usize];
-    result += params::BACKWARD_PAWN_CLOSED_FILE[pawns_data.backward_pawns_closed_file.min(7) as usize];
-    result += params::PAWN_SHIELD[pawns_data.pawn_shield.min(7) as usize];
-    result += params::PAWN_SHIELD_OPEN_FILE[pawns_data.opened_files.min(7) as usize];
+    result += eval_param!(DOUBLED_PAWN)[pawns_data.doubled_pawns.min(7) as usize];
+    result += eval_param!(ISOLATED_PAWN)[pawns_data.isolated_pawns.min(7) as usize];
+    result += eval_param!(CHAINED_PAWN)[pawns_data.chained_pawns.min(7) as usize];
+    result += eval_param!(PASSED_PAWN)[pawns_data.passed_pawns.min(7) as usize];
+    result += eval_param!(BACKWARD_PAWN_OPEN_FILE)[pawns_data.backward_pawns_open_file.min(7) as usize];
+    result += eval_param!(BACKWARD_PAWN_CLOSED_FILE)[pawns_data.backward_pawns_closed_file.min(7) as usize];
+    result += eval_param!(PAWN_SHIELD)[pawns_data.pawn_shield.min(7) as usize];
+    result += eval_param!(PAWN_SHIELD_OPEN_FILE)[pawns_data.opened_files.min(7) as usize];
 
     result
 }
 
+/// Records all pawn structure features of the `board` in `trace`.
+pub fn trace(board: &Board, trace: &mut EvalTrace) {
+    let white_data = get_pawns_data(board, WHITE);
+    let black_data = get_pawns_data(board, BLACK);
+
+    trace.add_array("DOUBLED_PAWN", white_data.doubled_pawns as usize, black_data.doubled_pawns as usize, &eval_param!(DOUBLED_PAWN));
+    trace.add_array("ISOLATED_PAWN", white_data.isolated_pawns as usize, black_data.isolated_pawns as usize, &eval_param!(ISOLATED_PAWN));
+    trace.add_array("CHAINED_PAWN", white_data.chained_pawns as usize, black_data.chained_pawns as usize, &eval_param!(CHAINED_PAWN));
+    trace.add_array("PASSED_PAWN", white_data.passed_pawns as usize, black_data.passed_pawns as usize, &eval_param!(PASSED_PAWN));
+    trace.add_array(
+        "BACKWARD_PAWN_OPEN_FILE",
+        white_data.backward_pawns_open_file as usize,
+        black_data.backward_pawns_open_file as usize,
+        &eval_param!(BACKWARD_PAWN_OPEN_FILE),
+    );
+    trace.add_array(
+        "BACKWARD_PAWN_CLOSED_FILE",
+        white_data.backward_pawns_closed_file as usize,
+        black_data.backward_pawns_closed_file as usize,
+        &eval_param!(BACKWARD_PAWN_CLOSED_FILE),
+    );
+    trace.add_array("PAWN_SHIELD", white_data.pawn_shield as usize, black_data.pawn_shield as usize, &eval_param!(PAWN_SHIELD));
+    trace.add_array("PAWN_SHIELD_OPEN_FILE", white_data.opened_files as usize, black_data.opened_files as usize, &eval_param!(PAWN_SHIELD_OPEN_FILE));
+}
+
 /// Gets all pawn features on `board` for `color`.
 fn get_pawns_data(board: &Board, color: usize) -> PawnsData {
     assert_fast!(color < 2);
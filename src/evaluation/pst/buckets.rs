@@ -0,0 +1,13 @@
+pub const KING_BUCKETS_COUNT: usize = 16;
+
+#[rustfmt::skip]
+pub const KING_BUCKETS: [usize; 64] = [
+    15, 14, 13, 12, 11, 10, 9,  8,
+    15, 14, 13, 12, 11, 10, 9,  8,
+    15, 14, 13, 12, 11, 10, 9,  8,
+    15, 14, 13, 12, 11, 10, 9,  8,
+    7,  6,  5,  4,  3,  2,  1,  0,
+    7,  6,  5,  4,  3,  2,  1,  0,
+    7,  6,  5,  4,  3,  2,  1,  0,
+    7,  6,  5,  4,  3,  2,  1,  0,
+];
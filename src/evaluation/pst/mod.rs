@@ -1,5 +1,6 @@
 use super::*;
 use crate::evaluation;
+use crate::evaluation::trace::EvalTrace;
 use crate::state::representation::Board;
 use crate::utils::assert_fast;
 use crate::utils::bithelpers::BitHelpers;
@@ -8,6 +9,7 @@ use crate::utils::bithelpers::BitHelpers;
 use crate::tuning::tuner::TunerCoeff;
 
 pub mod bishop;
+pub mod buckets;
 pub mod king;
 pub mod knight;
 pub mod pawn;
@@ -15,25 +17,18 @@ pub mod queen;
 pub mod rook;
 
 pub use bishop::BISHOP_PST_PATTERN;
+pub use buckets::KING_BUCKETS;
+pub use buckets::KING_BUCKETS_COUNT;
 pub use king::KING_PST_PATTERN;
 pub use knight::KNIGHT_PST_PATTERN;
 pub use pawn::PAWN_PST_PATTERN;
 pub use queen::QUEEN_PST_PATTERN;
 pub use rook::ROOK_PST_PATTERN;
 
-pub const KING_BUCKETS_COUNT: usize = 16;
-
-#[rustfmt::skip]
-pub const KING_BUCKETS: [usize; 64] = [
-    15, 14, 13, 12, 11, 10, 9,  8,
-    15, 14, 13, 12, 11, 10, 9,  8,
-    15, 14, 13, 12, 11, 10, 9,  8,
-    15, 14, 13, 12, 11, 10, 9,  8,
-    7,  6,  5,  4,  3,  2,  1,  0,
-    7,  6,  5,  4,  3,  2,  1,  0,
-    7,  6,  5,  4,  3,  2,  1,  0,
-    7,  6,  5,  4,  3,  2,  1,  0,
-];
+pub struct PstData {
+    pub counts: [i16; 6],
+    pub evals: [PackedEval; 6],
+}
 
 /// Evaluates piece-square table value on the `board` and returns score from the white color perspective (more than 0 when advantage, less than 0 when disadvantage).
 pub fn evaluate(board: &Board) -> PackedEval {
@@ -42,43 +37,53 @@ pub fn evaluate(board: &Board) -> PackedEval {
 
 /// Recalculates incremental counters on the `board`. This function should be called only if really necessary, as it's too slow in regular search.
 pub fn recalculate_incremental_values(board: &mut Board) {
-    let mut score = PackedEval::default();
-
-    for color in ALL_COLORS {
-        let sign = -(color as i16 * 2 - 1);
-
-        let king_bb = board.pieces[color][KING];
-        let king_square = match color == WHITE {
-            true => king_bb.bit_scan() % 64,
-            false => king_bb.swap_bytes().bit_scan() % 64,
-        };
-
-        let enemy_king_bb = board.pieces[color ^ 1][KING];
-        let enemy_king_square = match color == WHITE {
-            true => enemy_king_bb.swap_bytes().bit_scan() % 64,
-            false => enemy_king_bb.bit_scan() % 64,
-        };
-
-        for pov in ALL_POVS {
-            let king_square = if pov == US { king_square } else { enemy_king_square };
-            for piece_index in ALL_PIECES {
-                let mut pieces_bb = board.pieces[color][piece_index];
-                while pieces_bb != 0 {
-                    let square_bb = pieces_bb.get_lsb();
-                    let mut square = square_bb.bit_scan();
-                    pieces_bb = pieces_bb.pop_lsb();
-
-                    if color == BLACK {
-                        square = (1u64 << square).swap_bytes().bit_scan();
-                    }
+    let white_data = get_pst_data(board, WHITE);
+    let black_data = get_pst_data(board, BLACK);
 
-                    score += sign * evaluation::get_pst_value(piece_index, pov, king_square, square);
-                }
-            }
+    let white_score = white_data.evals.iter().fold(PackedEval::default(), |score, eval| score + *eval);
+    let black_score = black_data.evals.iter().fold(PackedEval::default(), |score, eval| score + *eval);
+
+    board.state.pst_score = white_score - black_score;
+}
+
+/// Records piece-square table values of the `board` in `trace`, one entry per piece type (including the material value, which is a part of PST).
+pub fn trace(board: &Board, trace: &mut EvalTrace) {
+    const NAMES: [&str; 6] = ["PAWN_PST", "KNIGHT_PST", "BISHOP_PST", "ROOK_PST", "QUEEN_PST", "KING_PST"];
+
+    let white_data = get_pst_data(board, WHITE);
+    let black_data = get_pst_data(board, BLACK);
+
+    for piece in ALL_PIECES {
+        trace.add_eval(NAMES[piece].to_string(), white_data.counts[piece], black_data.counts[piece], white_data.evals[piece], black_data.evals[piece]);
+    }
+}
+
+/// Gets piece-square table data for `board` and `color`: count of pieces and sum of their values (seen from both kings) for every piece type.
+fn get_pst_data(board: &Board, color: usize) -> PstData {
+    assert_fast!(color < 2);
+
+    let mut data = PstData { counts: [0; 6], evals: [PackedEval::default(); 6] };
+
+    // Squares are always relative to the side which owns the piece, except enemy king which is seen from the opposite perspective. Boards
+    // without kings are allowed here (for example during setup), so missing king square is wrapped to a valid one
+    let (king_square, enemy_king_square) = match color {
+        WHITE => (board.pieces[WHITE][KING].bit_scan() % 64, board.pieces[BLACK][KING].swap_bytes().bit_scan() % 64),
+        _ => (board.pieces[BLACK][KING].swap_bytes().bit_scan() % 64, board.pieces[WHITE][KING].bit_scan() % 64),
+    };
+
+    for piece in ALL_PIECES {
+        let mut pieces_bb = if color == WHITE { board.pieces[color][piece] } else { board.pieces[color][piece].swap_bytes() };
+        while pieces_bb != 0 {
+            let square = pieces_bb.bit_scan();
+            pieces_bb = pieces_bb.pop_lsb();
+
+            data.counts[piece] += 1;
+            data.evals[piece] += evaluation::get_pst_value(piece, US, king_square, square);
+            data.evals[piece] += evaluation::get_pst_value(piece, THEM, enemy_king_square, square);
         }
     }
 
-    board.state.pst_score = score;
+    data
 }
 
 /// Gets a PST value for the specified `piece`, `pov`, `king_square` and `square` (relative perspective).
@@ -88,6 +93,10 @@ pub fn get_pst_value(piece: usize, pov: usize, king_square: usize, square: usize
     assert_fast!(king_square < 64);
     assert_fast!(square < 64);
 
+    #[cfg(feature = "dev")]
+    let pst = &evaluation::runtime::get().PST[piece];
+
+    #[cfg(not(feature = "dev"))]
     let pst = match piece {
         PAWN => &pst::PAWN_PST_PATTERN,
         KNIGHT => &pst::KNIGHT_PST_PATTERN,
@@ -98,27 +107,94 @@ pub fn get_pst_value(piece: usize, pov: usize, king_square: usize, square: usize
         _ => panic_fast!("Invalid value: piece={}", piece),
     };
 
-    assert_fast!(KING_BUCKETS[63 - king_square] < KING_BUCKETS_COUNT);
-    pst[pov][KING_BUCKETS[63 - king_square]][63 - square]
+    pst[pov][get_king_bucket(king_square)][63 - square]
 }
 
-/// Gets coefficients of piece-square table for `piece` on `board` and inserts them into `coeffs`.
-/// Similarly, their indices (starting from `index`) are inserted into `indices`.
+/// Gets a king bucket for the `king_square` (relative perspective). In dev builds, the layout loaded at runtime is used instead of [KING_BUCKETS].
+pub fn get_king_bucket(king_square: usize) -> usize {
+    assert_fast!(king_square < 64);
+
+    #[cfg(feature = "dev")]
+    let bucket = evaluation::runtime::get().KING_BUCKETS[63 - king_square];
+
+    #[cfg(not(feature = "dev"))]
+    let bucket = KING_BUCKETS[63 - king_square];
+
+    assert_fast!(bucket < KING_BUCKETS_COUNT);
+    bucket
+}
+
+/// Parses king buckets layout stored in `content` as Rust source in the format of `pst/buckets.rs` (also generated by the tuner): `KING_BUCKETS_COUNT`
+/// constant followed by `KING_BUCKETS` array with 64 values, in this order. Empty, comment and attribute lines are ignored. Returns [Err] with a proper
+/// error message if any of the declarations is missing or invalid, or the layout doesn't fit into tables compiled into the engine (see [validate_king_buckets]).
+#[cfg(feature = "dev")]
+pub fn parse_king_buckets(content: &str) -> Result<[usize; 64], String> {
+    let lines =
+        content.lines().map(|line| line.trim()).filter(|line| !line.is_empty() && !line.starts_with("//") && !line.starts_with("#[")).collect::<Vec<_>>();
+
+    let count = match lines.first().and_then(|line| line.strip_prefix("pub const KING_BUCKETS_COUNT: usize = ")).and_then(|line| line.strip_suffix(';')) {
+        Some(value) => value.parse::<usize>().map_err(|error| format!("Invalid king buckets count: {}", error))?,
+        None => return Err("KING_BUCKETS_COUNT declaration not found".to_string()),
+    };
+
+    if lines.get(1) != Some(&"pub const KING_BUCKETS: [usize; 64] = [") {
+        return Err("KING_BUCKETS declaration not found".to_string());
+    }
+
+    let end = match lines.iter().position(|line| *line == "];") {
+        Some(value) => value,
+        None => return Err("Unterminated KING_BUCKETS array".to_string()),
+    };
+
+    if end + 1 != lines.len() {
+        return Err(format!("Invalid content after KING_BUCKETS array: line={}", lines[end + 1]));
+    }
+
+    let values = lines[2..end].iter().flat_map(|line| line.split(',')).map(|value| value.trim()).filter(|value| !value.is_empty());
+    let values = values.map(|value| value.parse::<usize>()).collect::<Result<Vec<usize>, _>>().map_err(|error| format!("Invalid king bucket: {}", error))?;
+
+    let king_buckets = validate_king_buckets(&values)?;
+    let used_count = king_buckets.iter().max().unwrap() + 1;
+
+    if count != used_count {
+        return Err(format!("Invalid king buckets count: value={}, used={}", count, used_count));
+    }
+
+    Ok(king_buckets)
+}
+
+/// Checks if `values` form a valid king buckets layout (64 buckets, each less than [KING_BUCKETS_COUNT] compiled into the engine) and returns it as an array.
+/// Returns [Err] with a proper error message otherwise.
+#[cfg(feature = "dev")]
+pub fn validate_king_buckets(values: &[usize]) -> Result<[usize; 64], String> {
+    if values.len() != 64 {
+        return Err(format!("Invalid king buckets layout: count={}", values.len()));
+    }
+
+    if let Some(bucket) = values.iter().find(|bucket| **bucket >= KING_BUCKETS_COUNT) {
+        return Err(format!("Invalid king bucket: value={}, max={}", bucket, KING_BUCKETS_COUNT - 1));
+    }
+
+    Ok(std::array::from_fn(|index| values[index]))
+}
+
+/// Gets coefficients of piece-square table for `piece` on `board` (with kings assigned to buckets using `king_buckets` layout) and inserts them
+/// into `coeffs`. Similarly, their indices (starting from `index`) are inserted into `indices`.
 #[cfg(feature = "dev")]
-pub fn get_coeffs(board: &Board, piece: usize, index: &mut u16, coeffs: &mut Vec<TunerCoeff>, indices: &mut Vec<u16>) {
+pub fn get_coeffs(board: &Board, piece: usize, king_buckets: &[usize; 64], index: &mut u16, coeffs: &mut Vec<TunerCoeff>, indices: &mut Vec<u16>) {
     assert_fast!(piece < 6);
 
     for pov in ALL_POVS {
         for bucket in 0..KING_BUCKETS_COUNT {
             let (valid_for_white, valid_for_black) = if pov == US {
                 (
-                    bucket == KING_BUCKETS[63 - board.pieces[WHITE][KING].bit_scan()],
-                    bucket == KING_BUCKETS[63 - board.pieces[BLACK][KING].swap_bytes().bit_scan()],
+                    bucket == king_buckets[63 - board.pieces[WHITE][KING].bit_scan()],
+                    bucket == king_buckets[63 - board.pieces[BLACK][KING].swap_bytes().bit_scan()],
                 )
             } else {
                 (
-                    bucket == KING_BUCKETS[63 - board.pieces[BLACK][KING].swap_bytes().bit_scan()],
-                    bucket == KING_BUCKETS[63 - board.pieces[WHITE][KING].bit_scan()],
+                    bucket == king_buckets[63 - board.pieces[BLACK][KING].swap_bytes().bit_scan()],
+                    bucket == king_buckets[63 - board.pieces[WHITE][KING].bit_scan()],
                 )
             };
 
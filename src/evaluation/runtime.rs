@@ -0,0 +1,333 @@
+use super::*;
+use std::fs;
+use std::iter;
+use std::path::Path;
+use std::ptr;
+use std::sync::atomic::AtomicPtr;
+use std::sync::atomic::Ordering;
+use std::sync::OnceLock;
+
+static DEFAULT_PARAMS: OnceLock<EvalParams> = OnceLock::new();
+static LOADED_PARAMS: AtomicPtr<EvalParams> = AtomicPtr::new(ptr::null_mut());
+
+const PST_FILES: [&str; 6] = ["pawn", "knight", "bishop", "rook", "queen", "king"];
+
+#[allow(non_snake_case)]
+#[derive(Clone)]
+pub struct EvalParams {
+    pub TEMPO: i16,
+    pub TAPERED_PIECE_VALUES: [PackedEval; 6],
+    pub BISHOP_PAIR: PackedEval,
+    pub PAWNS_ATTACKING_PIECES: PackedEval,
+    pub ROOK_OPEN_FILE: PackedEval,
+    pub ROOK_SEMI_OPEN_FILE: PackedEval,
+    pub MOBILITY_INNER: [PackedEval; 6],
+    pub MOBILITY_OUTER: [PackedEval; 6],
+    pub KNIGHT_OUTPOST: PackedEval,
+    pub BISHOP_OUTPOST: PackedEval,
+    pub BAD_BISHOP: PackedEval,
+    pub ROOK_ON_SEVENTH: PackedEval,
+    pub QUEEN_ON_SEVENTH: PackedEval,
+    pub CONNECTED_ROOKS: PackedEval,
+    pub DOUBLED_PAWN: [PackedEval; 8],
+    pub ISOLATED_PAWN: [PackedEval; 8],
+    pub CHAINED_PAWN: [PackedEval; 8],
+    pub PASSED_PAWN: [PackedEval; 8],
+    pub BACKWARD_PAWN_OPEN_FILE: [PackedEval; 8],
+    pub BACKWARD_PAWN_CLOSED_FILE: [PackedEval; 8],
+    pub PAWN_SHIELD: [PackedEval; 8],
+    pub PAWN_SHIELD_OPEN_FILE: [PackedEval; 8],
+    pub KING_AREA_THREATS: [PackedEval; 8],
+    pub KNIGHT_SAFE_CHECKS: [PackedEval; 8],
+    pub BISHOP_SAFE_CHECKS: [PackedEval; 8],
+    pub ROOK_SAFE_CHECKS: [PackedEval; 8],
+    pub QUEEN_SAFE_CHECKS: [PackedEval; 8],
+    pub MINORS_ATTACKING_MAJORS: [PackedEval; 8],
+    pub HANGING_PIECES: [PackedEval; 8],
+    pub SAFE_PAWN_PUSH_THREATS: [PackedEval; 8],
+    pub QUEEN_ATTACKS: [PackedEval; 8],
+    pub PASSED_PAWN_OWN_KING_DISTANCE: [PackedEval; 8],
+    pub PASSED_PAWN_ENEMY_KING_DISTANCE: [PackedEval; 8],
+    pub PASSED_PAWN_BLOCKED: PackedEval,
+    pub PASSED_PAWN_FREE_PATH: PackedEval,
+    pub PASSED_PAWN_UNSTOPPABLE: PackedEval,
+    pub IMBALANCE_OURS: [PackedEval; 15],
+    pub IMBALANCE_THEIRS: [PackedEval; 10],
+    pub PST: [[[[PackedEval; 64]; KING_BUCKETS_COUNT]; 2]; 6],
+    pub KING_BUCKETS: [usize; 64],
+}
+
+impl EvalParams {
+    /// Constructs a new instance of [EvalParams] with `tempo` and `values` stored in the same layout as the list of tuner parameters (tapered piece
+    /// values, material, mobility, pawns, safety, threats, passed pawns, imbalance and piece-square tables without piece values for our point of view,
+    /// which are added back here). Returns [Err] if the number of values is invalid.
+    pub fn new_from_values(tempo: i16, values: &[i16]) -> Result<Self, String> {
+        let mut iter = values.iter().copied();
+        let mut next = || iter.next().ok_or(format!("Invalid parameters count: values.len()={}", values.len()));
+
+        let mut params = Self { TEMPO: tempo, ..Default::default() };
+        read_array(&mut next, &mut params.TAPERED_PIECE_VALUES)?;
+        params.BISHOP_PAIR = read_eval(&mut next)?;
+        params.PAWNS_ATTACKING_PIECES = read_eval(&mut next)?;
+        params.ROOK_OPEN_FILE = read_eval(&mut next)?;
+        params.ROOK_SEMI_OPEN_FILE = read_eval(&mut next)?;
+        read_array(&mut next, &mut params.MOBILITY_INNER)?;
+        read_array(&mut next, &mut params.MOBILITY_OUTER)?;
+        params.KNIGHT_OUTPOST = read_eval(&mut next)?;
+        params.BISHOP_OUTPOST = read_eval(&mut next)?;
+        params.BAD_BISHOP = read_eval(&mut next)?;
+        params.ROOK_ON_SEVENTH = read_eval(&mut next)?;
+        params.QUEEN_ON_SEVENTH = read_eval(&mut next)?;
+        params.CONNECTED_ROOKS = read_eval(&mut next)?;
+        read_array(&mut next, &mut params.DOUBLED_PAWN)?;
+        read_array(&mut next, &mut params.ISOLATED_PAWN)?;
+        read_array(&mut next, &mut params.CHAINED_PAWN)?;
+        read_array(&mut next, &mut params.PASSED_PAWN)?;
+        read_array(&mut next, &mut params.BACKWARD_PAWN_OPEN_FILE)?;
+        read_array(&mut next, &mut params.BACKWARD_PAWN_CLOSED_FILE)?;
+        read_array(&mut next, &mut params.PAWN_SHIELD)?;
+        read_array(&mut next, &mut params.PAWN_SHIELD_OPEN_FILE)?;
+        read_array(&mut next, &mut params.KING_AREA_THREATS)?;
+        read_array(&mut next, &mut params.KNIGHT_SAFE_CHECKS)?;
+        read_array(&mut next, &mut params.BISHOP_SAFE_CHECKS)?;
+        read_array(&mut next, &mut params.ROOK_SAFE_CHECKS)?;
+        read_array(&mut next, &mut params.QUEEN_SAFE_CHECKS)?;
+        read_array(&mut next, &mut params.MINORS_ATTACKING_MAJORS)?;
+        read_array(&mut next, &mut params.HANGING_PIECES)?;
+        read_array(&mut next, &mut params.SAFE_PAWN_PUSH_THREATS)?;
+        read_array(&mut next, &mut params.QUEEN_ATTACKS)?;
+        read_array(&mut next, &mut params.PASSED_PAWN_OWN_KING_DISTANCE)?;
+        read_array(&mut next, &mut params.PASSED_PAWN_ENEMY_KING_DISTANCE)?;
+        params.PASSED_PAWN_BLOCKED = read_eval(&mut next)?;
+        params.PASSED_PAWN_FREE_PATH = read_eval(&mut next)?;
+        params.PASSED_PAWN_UNSTOPPABLE = read_eval(&mut next)?;
+
+        read_array(&mut next, &mut params.IMBALANCE_OURS)?;
+        read_array(&mut next, &mut params.IMBALANCE_THEIRS)?;
+
+        for piece in ALL_PIECES {
+            let piece_value = params.TAPERED_PIECE_VALUES[piece];
+            for pov in ALL_POVS {
+                for bucket in &mut params.PST[piece][pov] {
+                    read_array(&mut next, bucket)?;
+
+                    if pov == US {
+                        for value in bucket.iter_mut() {
+                            *value += piece_value;
+                        }
+                    }
+                }
+            }
+        }
+
+        if iter.next().is_some() {
+            return Err(format!("Invalid parameters count: values.len()={}", values.len()));
+        }
+
+        Ok(params)
+    }
+
+    /// Loads a new instance of [EvalParams] from `path`, which can be either a tuner output directory (with `params.rs` and `pst/*.rs` files
+    /// generated by the tuner) or a JSON file with `tempo` and `values` keys (the latter in the layout described in [EvalParams::new_from_values]).
+    /// King buckets layout is loaded from `pst/buckets.rs` file or `buckets` key if available, otherwise [KING_BUCKETS] is used.
+    /// Returns [Err] with the proper message if the parameters couldn't be loaded.
+    pub fn load(path: &str) -> Result<Self, String> {
+        let path = Path::new(path);
+        if path.is_dir() {
+            load_from_directory(path)
+        } else {
+            load_from_json(path)
+        }
+    }
+}
+
+impl Default for EvalParams {
+    /// Constructs a default instance of [EvalParams] with values compiled into the engine.
+    fn default() -> Self {
+        Self {
+            TEMPO: params::TEMPO,
+            TAPERED_PIECE_VALUES: params::TAPERED_PIECE_VALUES,
+            BISHOP_PAIR: params::BISHOP_PAIR,
+            PAWNS_ATTACKING_PIECES: params::PAWNS_ATTACKING_PIECES,
+            ROOK_OPEN_FILE: params::ROOK_OPEN_FILE,
+            ROOK_SEMI_OPEN_FILE: params::ROOK_SEMI_OPEN_FILE,
+            MOBILITY_INNER: params::MOBILITY_INNER,
+            MOBILITY_OUTER: params::MOBILITY_OUTER,
+            KNIGHT_OUTPOST: params::KNIGHT_OUTPOST,
+            BISHOP_OUTPOST: params::BISHOP_OUTPOST,
+            BAD_BISHOP: params::BAD_BISHOP,
+            ROOK_ON_SEVENTH: params::ROOK_ON_SEVENTH,
+            QUEEN_ON_SEVENTH: params::QUEEN_ON_SEVENTH,
+            CONNECTED_ROOKS: params::CONNECTED_ROOKS,
+            DOUBLED_PAWN: params::DOUBLED_PAWN,
+            ISOLATED_PAWN: params::ISOLATED_PAWN,
+            CHAINED_PAWN: params::CHAINED_PAWN,
+            PASSED_PAWN: params::PASSED_PAWN,
+            BACKWARD_PAWN_OPEN_FILE: params::BACKWARD_PAWN_OPEN_FILE,
+            BACKWARD_PAWN_CLOSED_FILE: params::BACKWARD_PAWN_CLOSED_FILE,
+            PAWN_SHIELD: params::PAWN_SHIELD,
+            PAWN_SHIELD_OPEN_FILE: params::PAWN_SHIELD_OPEN_FILE,
+            KING_AREA_THREATS: params::KING_AREA_THREATS,
+            KNIGHT_SAFE_CHECKS: params::KNIGHT_SAFE_CHECKS,
+            BISHOP_SAFE_CHECKS: params::BISHOP_SAFE_CHECKS,
+            ROOK_SAFE_CHECKS: params::ROOK_SAFE_CHECKS,
+            QUEEN_SAFE_CHECKS: params::QUEEN_SAFE_CHECKS,
+            MINORS_ATTACKING_MAJORS: params::MINORS_ATTACKING_MAJORS,
+            HANGING_PIECES: params::HANGING_PIECES,
+            SAFE_PAWN_PUSH_THREATS: params::SAFE_PAWN_PUSH_THREATS,
+            QUEEN_ATTACKS: params::QUEEN_ATTACKS,
+            PASSED_PAWN_OWN_KING_DISTANCE: params::PASSED_PAWN_OWN_KING_DISTANCE,
+            PASSED_PAWN_ENEMY_KING_DISTANCE: params::PASSED_PAWN_ENEMY_KING_DISTANCE,
+            PASSED_PAWN_BLOCKED: params::PASSED_PAWN_BLOCKED,
+            PASSED_PAWN_FREE_PATH: params::PASSED_PAWN_FREE_PATH,
+            PASSED_PAWN_UNSTOPPABLE: params::PASSED_PAWN_UNSTOPPABLE,
+            IMBALANCE_OURS: params::IMBALANCE_OURS,
+            IMBALANCE_THEIRS: params::IMBALANCE_THEIRS,
+            PST: [PAWN_PST_PATTERN, KNIGHT_PST_PATTERN, BISHOP_PST_PATTERN, ROOK_PST_PATTERN, QUEEN_PST_PATTERN, KING_PST_PATTERN],
+            KING_BUCKETS,
+        }
+    }
+}
+
+/// Gets the evaluation parameters currently used by the engine: the ones loaded by [set], or the compiled ones if nothing was loaded.
+pub fn get() -> &'static EvalParams {
+    let params = LOADED_PARAMS.load(Ordering::Acquire);
+    if params.is_null() {
+        DEFAULT_PARAMS.get_or_init(EvalParams::default)
+    } else {
+        unsafe { &*params }
+    }
+}
+
+/// Sets `params` as the evaluation parameters used by the engine. The previous set is never released, since it still can be read by other threads
+/// (it's fine as parameters are replaced only on the user request).
+pub fn set(params: EvalParams) {
+    LOADED_PARAMS.store(Box::into_raw(Box::new(params)), Ordering::Release);
+}
+
+/// Restores the evaluation parameters compiled into the engine.
+pub fn reset() {
+    LOADED_PARAMS.store(ptr::null_mut(), Ordering::Release);
+}
+
+/// Loads evaluation parameters from the tuner output directory `path`, reading `TEMPO` and all `s!(opening, ending)` values in order of their appearance.
+fn load_from_directory(path: &Path) -> Result<EvalParams, String> {
+    let params = read_file(&path.join("params.rs"))?;
+    let tempo = match params.lines().find_map(|line| line.trim().strip_prefix("pub const TEMPO: i16 = ")) {
+        Some(value) => value.trim_end_matches(';').parse::<i16>().map_err(|error| format!("Invalid TEMPO value: {}", error))?,
+        None => return Err("TEMPO value not found".to_string()),
+    };
+
+    // Tapered piece values are the first ones in params.rs, they have to be subtracted from piece-square tables for our point of view
+    let mut values = get_packed_values(&params)?;
+    let piece_values = values.iter().take(PIECE_VALUES.len() * 2).copied().collect::<Vec<i16>>();
+
+    if piece_values.len() != PIECE_VALUES.len() * 2 {
+        return Err(format!("Invalid parameters count: values.len()={}", values.len()));
+    }
+
+    let buckets_path = path.join("pst").join("buckets.rs");
+    let king_buckets = if buckets_path.exists() { pst::parse_king_buckets(&read_file(&buckets_path)?)? } else { KING_BUCKETS };
+    let buckets_count = king_buckets.iter().max().unwrap() + 1;
+
+    for (piece, name) in PST_FILES.iter().enumerate() {
+        let pst = get_packed_values(&read_file(&path.join("pst").join(format!("{}.rs", name)))?)?;
+        if pst.len() != 2 * buckets_count * 64 * 2 {
+            return Err(format!("Invalid piece-square table size: name={}, values.len()={}, buckets={}", name, pst.len(), buckets_count));
+        }
+
+        for (pov, pov_values) in pst.chunks(pst.len() / 2).enumerate() {
+            for (index, value) in pov_values.iter().enumerate() {
+                values.push(if pov == US { value - piece_values[piece * 2 + index % 2] } else { *value });
+            }
+
+            // Tables of buckets not used by the layout are not saved by the tuner, so they are filled with zeros up to the compiled buckets count
+            values.extend(iter::repeat(0).take((KING_BUCKETS_COUNT - buckets_count) * 64 * 2));
+        }
+    }
+
+    let mut params = EvalParams::new_from_values(tempo, &values)?;
+    params.KING_BUCKETS = king_buckets;
+
+    Ok(params)
+}
+
+/// Loads evaluation parameters from the JSON file `path`, which is expected to contain `tempo` number and `values` array.
+fn load_from_json(path: &Path) -> Result<EvalParams, String> {
+    let json = read_file(path)?;
+
+    let tempo = match get_json_field(&json, "tempo") {
+        Some(value) => value.trim_end_matches(|c| c == '}' || char::is_whitespace(c)).parse::<i16>().map_err(|error| format!("Invalid tempo: {}", error))?,
+        None => params::TEMPO,
+    };
+
+    let values = match get_json_field(&json, "values") {
+        Some(values) => values.strip_prefix('[').and_then(|v| v.split(']').next()).ok_or("Invalid values array".to_string())?,
+        None => return Err("Values array not found".to_string()),
+    };
+
+    let values = values.split(',').map(|v| v.trim()).filter(|v| !v.is_empty()).map(|v| v.parse::<i16>()).collect::<Result<Vec<i16>, _>>();
+    let values = values.map_err(|error| format!("Invalid value: {}", error))?;
+    let mut params = EvalParams::new_from_values(tempo, &values)?;
+
+    if let Some(buckets) = get_json_field(&json, "buckets") {
+        let buckets = buckets.strip_prefix('[').and_then(|v| v.split(']').next()).ok_or("Invalid buckets array".to_string())?;
+        let buckets = buckets.split(',').map(|v| v.trim()).filter(|v| !v.is_empty()).map(|v| v.parse::<usize>()).collect::<Result<Vec<usize>, _>>();
+        params.KING_BUCKETS = pst::validate_king_buckets(&buckets.map_err(|error| format!("Invalid king bucket: {}", error))?)?;
+    }
+
+    Ok(params)
+}
+
+/// Reads the whole file stored in `path`. Returns [Err] with the proper message if the file couldn't be read.
+fn read_file(path: &Path) -> Result<String, String> {
+    fs::read_to_string(path).map_err(|error| format!("Invalid file {}: {}", path.display(), error))
+}
+
+/// Gets the value of the JSON field `name` (everything after the colon, up to the next comma for simple values) from `json`.
+fn get_json_field<'a>(json: &'a str, name: &str) -> Option<&'a str> {
+    let key = format!("\"{}\"", name);
+    let value = json[json.find(&key)? + key.len()..].trim_start().strip_prefix(':')?.trim_start();
+
+    if value.starts_with('[') {
+        Some(value)
+    } else {
+        Some(value.split(',').next()?.trim())
+    }
+}
+
+/// Gets a list of all opening and ending scores stored as `s!(opening, ending)` in the Rust source `content`.
+fn get_packed_values(content: &str) -> Result<Vec<i16>, String> {
+    let mut values = Vec::new();
+    for chunk in content.split("s!(").skip(1) {
+        let chunk = chunk.split(')').next().unwrap_or_default();
+        for value in chunk.split(',') {
+            values.push(value.trim().parse::<i16>().map_err(|error| format!("Invalid value {}: {}", value.trim(), error))?);
+        }
+    }
+
+    Ok(values)
+}
+
+/// Reads a single [PackedEval] (opening and ending score) using `next`.
+fn read_eval<F>(next: &mut F) -> Result<PackedEval, String>
+where
+    F: FnMut() -> Result<i16, String>,
+{
+    let opening = next()?;
+    let ending = next()?;
+
+    Ok(s!(opening, ending))
+}
+
+/// Reads all [PackedEval] values of `array` using `next`.
+fn read_array<F>(next: &mut F, array: &mut [PackedEval]) -> Result<(), String>
+where
+    F: FnMut() -> Result<i16, String>,
+{
+    for value in array {
+        *value = read_eval(next)?;
+    }
+
+    Ok(())
+}
@@ -2,7 +2,9 @@ use super::*;
 use crate::state::representation::Board;
 use crate::utils::assert_fast;
 use crate::utils::bithelpers::BitHelpers;
+use crate::utils::eval_param;
 use mobility::EvalAux;
+use trace::EvalTrace;
 
 #[cfg(feature = "dev")]
 use crate::tuning::tuner::TunerCoeff;
@@ -33,15 +35,27 @@ pub fn evaluate_color(board: &Board, color: usize, white_aux: &EvalAux, black_au
     };
     let data = get_safety_data(board, color, stm_aux, nstm_aux);
 
-    result += params::KING_AREA_THREATS[((stm_aux.king_area_threats) as usize).min(7)];
-    result += params::KNIGHT_SAFE_CHECKS[((data.knight_safe_checks) as usize).min(7)];
-    result += params::BISHOP_SAFE_CHECKS[((data.bishop_safe_checks) as usize).min(7)];
-    result += params::ROOK_SAFE_CHECKS[((data.rook_safe_checks) as usize).min(7)];
-    result += params::QUEEN_SAFE_CHECKS[((data.queen_safe_checks) as usize).min(7)];
+    result += eval_param!(KING_AREA_THREATS)[((stm_aux.king_area_threats) as usize).min(7)];
+    result += eval_param!(KNIGHT_SAFE_CHECKS)[((data.knight_safe_checks) as usize).min(7)];
+    result += eval_param!(BISHOP_SAFE_CHECKS)[((data.bishop_safe_checks) as usize).min(7)];
+    result += eval_param!(ROOK_SAFE_CHECKS)[((data.rook_safe_checks) as usize).min(7)];
+    result += eval_param!(QUEEN_SAFE_CHECKS)[((data.queen_safe_checks) as usize).min(7)];
 
     result
 }
 
+/// Records all king safety features of the `board` in `trace`, using `white_aux` and `black_aux` calculated during mobility evaluation.
+pub fn trace(board: &Board, white_aux: &EvalAux, black_aux: &EvalAux, trace: &mut EvalTrace) {
+    let white_data = get_safety_data(board, WHITE, white_aux, black_aux);
+    let black_data = get_safety_data(board, BLACK, black_aux, white_aux);
+
+    trace.add_array("KING_AREA_THREATS", white_aux.king_area_threats as usize, black_aux.king_area_threats as usize, &eval_param!(KING_AREA_THREATS));
+    trace.add_array("KNIGHT_SAFE_CHECKS", white_data.knight_safe_checks as usize, black_data.knight_safe_checks as usize, &eval_param!(KNIGHT_SAFE_CHECKS));
+    trace.add_array("BISHOP_SAFE_CHECKS", white_data.bishop_safe_checks as usize, black_data.bishop_safe_checks as usize, &eval_param!(BISHOP_SAFE_CHECKS));
+    trace.add_array("ROOK_SAFE_CHECKS", white_data.rook_safe_checks as usize, black_data.rook_safe_checks as usize, &eval_param!(ROOK_SAFE_CHECKS));
+    trace.add_array("QUEEN_SAFE_CHECKS", white_data.queen_safe_checks as usize, black_data.queen_safe_checks as usize, &eval_param!(QUEEN_SAFE_CHECKS));
+}
+
 /// Gets safety data for `board`, `color`, `our_aux` and `their_aux`.
 pub fn get_safety_data(board: &Board, color: usize, our_aux: &EvalAux, their_aux: &EvalAux) -> SafetyData {
     assert_fast!(color < 2);
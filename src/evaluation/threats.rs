@@ -0,0 +1,147 @@
+use super::*;
+use crate::state::representation::Board;
+use crate::utils::assert_fast;
+use crate::utils::bithelpers::BitHelpers;
+use crate::utils::eval_param;
+use mobility::EvalAux;
+use trace::EvalTrace;
+
+#[cfg(feature = "dev")]
+use crate::tuning::tuner::TunerCoeff;
+
+pub struct ThreatsData {
+    pub minors_attacking_majors: u8,
+    pub hanging_pieces: u8,
+    pub safe_pawn_push_threats: u8,
+    pub queen_attacks: u8,
+}
+
+/// Evaluates threats on the `board` and returns score from the white color perspective (more than 0 when advantage, less than 0 when disadvantage).
+/// Both additional parameters, `white_aux` and `black_aux`, are calculated during mobility evaluation and are used here to get the final score.
+/// Threat weights are not tuned yet, so this evaluator is not a part of [Board::evaluate] (features are only traced and passed to the tuner).
+pub fn evaluate(board: &Board, white_aux: &EvalAux, black_aux: &EvalAux) -> PackedEval {
+    evaluate_color(board, WHITE, white_aux, black_aux) - evaluate_color(board, BLACK, white_aux, black_aux)
+}
+
+/// Evaluates threats on the `board` for the specified `color`, using `white_aux` and `black_aux`.
+pub fn evaluate_color(board: &Board, color: usize, white_aux: &EvalAux, black_aux: &EvalAux) -> PackedEval {
+    assert_fast!(color < 2);
+
+    let mut result = PackedEval::default();
+    let (stm_aux, nstm_aux) = match color {
+        WHITE => (white_aux, black_aux),
+        BLACK => (black_aux, white_aux),
+        _ => panic_fast!("Invalid value: color={}", color),
+    };
+    let data = get_threats_data(board, color, stm_aux, nstm_aux);
+
+    result += eval_param!(MINORS_ATTACKING_MAJORS)[(data.minors_attacking_majors as usize).min(7)];
+    result += eval_param!(HANGING_PIECES)[(data.hanging_pieces as usize).min(7)];
+    result += eval_param!(SAFE_PAWN_PUSH_THREATS)[(data.safe_pawn_push_threats as usize).min(7)];
+    result += eval_param!(QUEEN_ATTACKS)[(data.queen_attacks as usize).min(7)];
+
+    result
+}
+
+/// Records all threat features of the `board` in `trace`, using `white_aux` and `black_aux` calculated during mobility evaluation.
+pub fn trace(board: &Board, white_aux: &EvalAux, black_aux: &EvalAux, trace: &mut EvalTrace) {
+    let white_data = get_threats_data(board, WHITE, white_aux, black_aux);
+    let black_data = get_threats_data(board, BLACK, black_aux, white_aux);
+
+    trace.add_array(
+        "MINORS_ATTACKING_MAJORS",
+        white_data.minors_attacking_majors as usize,
+        black_data.minors_attacking_majors as usize,
+        &eval_param!(MINORS_ATTACKING_MAJORS),
+    );
+    trace.add_array("HANGING_PIECES", white_data.hanging_pieces as usize, black_data.hanging_pieces as usize, &eval_param!(HANGING_PIECES));
+    trace.add_array(
+        "SAFE_PAWN_PUSH_THREATS",
+        white_data.safe_pawn_push_threats as usize,
+        black_data.safe_pawn_push_threats as usize,
+        &eval_param!(SAFE_PAWN_PUSH_THREATS),
+    );
+    trace.add_array("QUEEN_ATTACKS", white_data.queen_attacks as usize, black_data.queen_attacks as usize, &eval_param!(QUEEN_ATTACKS));
+}
+
+/// Gets threats data for `board`, `color`, `our_aux` and `their_aux`. Collected features:
+///  - number of enemy rooks and queens attacked by our knights and bishops
+///  - number of enemy pieces (except pawns and king) attacked by us and not defended at all
+///  - number of enemy pieces (except pawns and king) which can be attacked by a safe pawn push
+///  - number of safe squares, from which our knights, bishops and rooks can attack the enemy queen in the next move
+pub fn get_threats_data(board: &Board, color: usize, our_aux: &EvalAux, their_aux: &EvalAux) -> ThreatsData {
+    assert_fast!(color < 2);
+
+    let occupancy_bb = board.occupancy[WHITE] | board.occupancy[BLACK];
+    let king_square = board.pieces[color][KING].bit_scan();
+    let enemy_king_square = board.pieces[color ^ 1][KING].bit_scan();
+
+    let our_threats = our_aux.knight_threats
+        | our_aux.bishop_threats
+        | our_aux.rook_threats
+        | our_aux.queen_threats
+        | board.pawn_attacks[color]
+        | movegen::get_king_moves(king_square);
+    let their_threats = their_aux.knight_threats
+        | their_aux.bishop_threats
+        | their_aux.rook_threats
+        | their_aux.queen_threats
+        | board.pawn_attacks[color ^ 1]
+        | movegen::get_king_moves(enemy_king_square);
+
+    let enemy_majors_bb = board.pieces[color ^ 1][ROOK] | board.pieces[color ^ 1][QUEEN];
+    let enemy_pieces_bb = board.occupancy[color ^ 1] & !board.pieces[color ^ 1][PAWN] & !board.pieces[color ^ 1][KING];
+
+    let minors_attacking_majors = ((our_aux.knight_threats | our_aux.bishop_threats) & enemy_majors_bb).bit_count() as u8;
+    let hanging_pieces = (our_threats & !their_threats & enemy_pieces_bb).bit_count() as u8;
+
+    let safe_push_squares_bb = !board.pawn_attacks[color ^ 1] & (our_threats | !their_threats);
+    let pawns_bb = board.pieces[color][PAWN];
+    let pushes_bb = match color {
+        WHITE => {
+            let single_pushes_bb = (pawns_bb << 8) & !occupancy_bb;
+            (single_pushes_bb | (((single_pushes_bb & RANK_3_BB) << 8) & !occupancy_bb)) & safe_push_squares_bb
+        }
+        BLACK => {
+            let single_pushes_bb = (pawns_bb >> 8) & !occupancy_bb;
+            (single_pushes_bb | (((single_pushes_bb & RANK_6_BB) >> 8) & !occupancy_bb)) & safe_push_squares_bb
+        }
+        _ => panic_fast!("Invalid value: color={}", color),
+    };
+    let push_attacks_bb = match color {
+        WHITE => ((pushes_bb & !FILE_A_BB) << 9) | ((pushes_bb & !FILE_H_BB) << 7),
+        BLACK => ((pushes_bb & !FILE_A_BB) >> 7) | ((pushes_bb & !FILE_H_BB) >> 9),
+        _ => panic_fast!("Invalid value: color={}", color),
+    };
+    let safe_pawn_push_threats = (push_attacks_bb & enemy_pieces_bb & !board.pawn_attacks[color]).bit_count() as u8;
+
+    let mut queen_attacks = 0;
+    let mut enemy_queens_bb = board.pieces[color ^ 1][QUEEN];
+    let safe_squares_bb = !board.occupancy[color] & !board.pawn_attacks[color ^ 1] & (our_threats | !their_threats);
+
+    while enemy_queens_bb != 0 {
+        let square = enemy_queens_bb.bit_scan();
+        enemy_queens_bb = enemy_queens_bb.pop_lsb();
+
+        let knight_squares_bb = movegen::get_knight_moves(square) & our_aux.knight_threats;
+        let bishop_squares_bb = movegen::get_bishop_moves(occupancy_bb, square) & our_aux.bishop_threats;
+        let rook_squares_bb = movegen::get_rook_moves(occupancy_bb, square) & our_aux.rook_threats;
+
+        queen_attacks += ((knight_squares_bb | bishop_squares_bb | rook_squares_bb) & safe_squares_bb).bit_count() as u8;
+    }
+
+    ThreatsData { minors_attacking_majors, hanging_pieces, safe_pawn_push_threats, queen_attacks }
+}
+
+/// Gets coefficients of threats for `board` and inserts them into `coeffs`. Similarly, their indices (starting from `index`) are inserted into `indices`.
+/// Additionally, `white_aux` and `black_aux` calculated during mobility phase are also used here.
+#[cfg(feature = "dev")]
+pub fn get_coeffs(board: &Board, white_aux: &EvalAux, black_aux: &EvalAux, index: &mut u16, coeffs: &mut Vec<TunerCoeff>, indices: &mut Vec<u16>) {
+    let white_data = get_threats_data(board, WHITE, white_aux, black_aux);
+    let black_data = get_threats_data(board, BLACK, black_aux, white_aux);
+
+    get_array_coeffs(white_data.minors_attacking_majors, black_data.minors_attacking_majors, 8, index, coeffs, indices);
+    get_array_coeffs(white_data.hanging_pieces, black_data.hanging_pieces, 8, index, coeffs, indices);
+    get_array_coeffs(white_data.safe_pawn_push_threats, black_data.safe_pawn_push_threats, 8, index, coeffs, indices);
+    get_array_coeffs(white_data.queen_attacks, black_data.queen_attacks, 8, index, coeffs, indices);
+}
@@ -0,0 +1,223 @@
+use super::*;
+use crate::state::representation::Board;
+use crate::utils::eval_param;
+use mobility::EvalAux;
+
+pub struct EvalTrace {
+    pub game_phase: u8,
+    pub stm: usize,
+    pub scale: u8,
+    pub endgame: Option<i16>,
+    pub entries: Vec<EvalTraceEntry>,
+    group: &'static str,
+}
+
+pub struct EvalTraceEntry {
+    pub group: &'static str,
+    pub name: String,
+    pub white_count: i16,
+    pub black_count: i16,
+    pub white_eval: PackedEval,
+    pub black_eval: PackedEval,
+}
+
+impl EvalTrace {
+    /// Constructs a new instance of [EvalTrace] by running all evaluators on the `board` and recording every feature hit.
+    pub fn new(board: &Board) -> Self {
+        let mut trace = Self { game_phase: board.game_phase, stm: board.stm, scale: endgame::SCALE_NORMAL, endgame: None, entries: Vec::new(), group: "" };
+        let mut white_aux = EvalAux::default();
+        let mut black_aux = EvalAux::default();
+
+        trace.group = "Material";
+        material::trace(board, &mut trace);
+
+        trace.group = "Imbalance";
+        material::trace_imbalance(board, &mut trace);
+
+        trace.group = "Piece-square tables";
+        pst::trace(board, &mut trace);
+
+        trace.group = "Mobility";
+        mobility::trace(board, &mut white_aux, &mut black_aux, &mut trace);
+
+        trace.group = "Placement";
+        mobility::trace_placement(board, &mut trace);
+
+        trace.group = "Safety";
+        safety::trace(board, &white_aux, &black_aux, &mut trace);
+
+        trace.group = "Pawns";
+        pawns::trace(board, &mut trace);
+
+        trace.group = "Passed pawns";
+        passers::trace(board, &white_aux, &black_aux, &mut trace);
+
+        trace.group = "Threats";
+        threats::trace(board, &white_aux, &black_aux, &mut trace);
+
+        trace.scale = endgame::get_scale_factor(board, trace.get_eval(None));
+        trace.endgame = endgame::evaluate(board);
+
+        trace
+    }
+
+    /// Records a feature `name` hit `white_count` times by white and `black_count` times by black, each hit worth `value`. Features which are
+    /// not present for both colors are skipped.
+    pub fn add(&mut self, name: &str, white_count: i16, black_count: i16, value: PackedEval) {
+        if white_count != 0 || black_count != 0 {
+            self.add_eval(name.to_string(), white_count, black_count, white_count * value, black_count * value);
+        }
+    }
+
+    /// Records a feature from the array `values` (named `name`), where `white_index` and `black_index` (clamped to the array length) are
+    /// values selected for both colors. Every index is reported as a separate entry, like `PASSED_PAWN[2]`.
+    pub fn add_array(&mut self, name: &str, white_index: usize, black_index: usize, values: &[PackedEval]) {
+        let white_index = white_index.min(values.len() - 1);
+        let black_index = black_index.min(values.len() - 1);
+
+        self.add_eval(format!("{}[{}]", name, white_index), 1, 0, values[white_index], PackedEval::default());
+        self.add_eval(format!("{}[{}]", name, black_index), 0, 1, PackedEval::default(), values[black_index]);
+    }
+
+    /// Records a feature `name` with `white_count`/`black_count` hits and already calculated `white_eval`/`black_eval` contributions. If the entry
+    /// with the same name already exists, values are added to it.
+    pub fn add_eval(&mut self, name: String, white_count: i16, black_count: i16, white_eval: PackedEval, black_eval: PackedEval) {
+        match self.entries.iter_mut().find(|entry| entry.name == name) {
+            Some(entry) => {
+                entry.white_count += white_count;
+                entry.black_count += black_count;
+                entry.white_eval += white_eval;
+                entry.black_eval += black_eval;
+            }
+            None => {
+                self.entries.push(EvalTraceEntry { group: self.group, name, white_count, black_count, white_eval, black_eval });
+            }
+        }
+    }
+
+    /// Gets a sum of all entries in the `group` (or all of them if [None]), from the white color perspective.
+    pub fn get_eval(&self, group: Option<&str>) -> PackedEval {
+        let entries = self.entries.iter().filter(|entry| group.is_none() || Some(entry.group) == group);
+        entries.fold(PackedEval::default(), |eval, entry| eval + entry.get_eval())
+    }
+
+    /// Gets tempo bonus from the white color perspective.
+    pub fn get_tempo(&self) -> i16 {
+        if self.stm == WHITE {
+            eval_param!(TEMPO)
+        } else {
+            -eval_param!(TEMPO)
+        }
+    }
+
+    /// Gets the final score from the white color perspective, which is the same as the result of [Board::evaluate_without_cache] for the side to move.
+    /// If the position is handled by a specialised endgame evaluator, its result is returned instead of the sum of all entries.
+    pub fn get_score(&self) -> i16 {
+        match self.endgame {
+            Some(score) => score,
+            None => self.get_eval(None).taper_score_scaled(self.game_phase, self.scale) + self.get_tempo(),
+        }
+    }
+
+    /// Gets the trace formatted as a table, with one row per feature and subtotals for every group.
+    pub fn to_table(&self) -> String {
+        let mut output = String::new();
+        let header = format!("{:<32} {:>5} {:>5} {:>13} {:>13} {:>13} {:>7}", "Term", "White", "Black", "White (O/E)", "Black (O/E)", "Total (O/E)", "Score");
+
+        output.push_str(&format!("{}\n", header));
+        output.push_str(&format!("{}\n", "-".repeat(header.len())));
+
+        let mut groups = Vec::new();
+        for entry in &self.entries {
+            if !groups.contains(&entry.group) {
+                groups.push(entry.group);
+            }
+        }
+
+        for group in groups {
+            for entry in self.entries.iter().filter(|entry| entry.group == group) {
+                output.push_str(&format!(
+                    "{:<32} {:>5} {:>5} {:>13} {:>13} {:>13} {:>7}\n",
+                    entry.name,
+                    entry.white_count,
+                    entry.black_count,
+                    format_eval(entry.white_eval),
+                    format_eval(entry.black_eval),
+                    format_eval(entry.get_eval()),
+                    entry.get_eval().taper_score(self.game_phase)
+                ));
+            }
+
+            let eval = self.get_eval(Some(group));
+            output.push_str(&format!(
+                "{:<32} {:>5} {:>5} {:>13} {:>13} {:>13} {:>7}\n",
+                format!("= {}", group),
+                "",
+                "",
+                "",
+                "",
+                format_eval(eval),
+                eval.taper_score(self.game_phase)
+            ));
+            output.push('\n');
+        }
+
+        output.push_str(&format!("Tempo: {}\n", self.get_tempo()));
+        output.push_str(&format!("Game phase: {}/{}\n", self.game_phase, INITIAL_GAME_PHASE));
+        output.push_str(&format!("Scale factor: {}/{}\n", self.scale, endgame::SCALE_NORMAL));
+
+        if let Some(score) = self.endgame {
+            output.push_str(&format!("Endgame evaluator: {}\n", score));
+        }
+
+        output.push_str(&format!(" --- Total: {} --- \n", self.get_score()));
+        output
+    }
+
+    /// Gets the trace formatted as JSON, with all entries and the final score.
+    pub fn to_json(&self) -> String {
+        let mut output = String::new();
+
+        output.push_str("{\n");
+        output.push_str(&format!("  \"game_phase\": {},\n", self.game_phase));
+        output.push_str(&format!("  \"tempo\": {},\n", self.get_tempo()));
+        output.push_str(&format!("  \"scale\": {},\n", self.scale));
+        output.push_str(&format!("  \"endgame\": {},\n", self.endgame.map(|score| score.to_string()).unwrap_or("null".to_string())));
+        output.push_str(&format!("  \"score\": {},\n", self.get_score()));
+        output.push_str("  \"terms\": [\n");
+
+        for (index, entry) in self.entries.iter().enumerate() {
+            output.push_str(&format!(
+                "    {{ \"group\": \"{}\", \"name\": \"{}\", \"white_count\": {}, \"black_count\": {}, \"white_opening\": {}, \"white_ending\": {}, \"black_opening\": {}, \"black_ending\": {}, \"opening\": {}, \"ending\": {}, \"score\": {} }}{}\n",
+                entry.group,
+                entry.name,
+                entry.white_count,
+                entry.black_count,
+                entry.white_eval.get_opening(),
+                entry.white_eval.get_ending(),
+                entry.black_eval.get_opening(),
+                entry.black_eval.get_ending(),
+                entry.get_eval().get_opening(),
+                entry.get_eval().get_ending(),
+                entry.get_eval().taper_score(self.game_phase),
+                if index + 1 < self.entries.len() { "," } else { "" }
+            ));
+        }
+
+        output.push_str("  ]\n");
+        output.push_str("}\n");
+        output
+    }
+}
+
+impl EvalTraceEntry {
+    /// Gets a contribution of the entry from the white color perspective.
+    pub fn get_eval(&self) -> PackedEval {
+        self.white_eval - self.black_eval
+    }
+}
+
+/// Formats `eval` as `opening/ending` pair.
+fn format_eval(eval: PackedEval) -> String {
+    format!("{}/{}", eval.get_opening(), eval.get_ending())
+}
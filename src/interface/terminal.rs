@@ -1,10 +1,5 @@
 use super::uci;
-use crate::evaluation::material;
-use crate::evaluation::mobility;
-use crate::evaluation::mobility::EvalAux;
-use crate::evaluation::pawns;
-use crate::evaluation::pst;
-use crate::evaluation::safety;
+use crate::evaluation::trace::EvalTrace;
 use crate::perft;
 use crate::state::representation::Board;
 use crate::testing::benchmark;
@@ -59,6 +54,7 @@ pub fn run(args: Vec<OsString>, features: Vec<&'static str>) {
 
         match tokens[0] {
             "help" => handle_help(),
+            "analyze" => handle_analyze(tokens),
             "benchmark" => handle_benchmark(),
 
             #[cfg(feature = "dev")]
@@ -66,19 +62,32 @@ pub fn run(args: Vec<OsString>, features: Vec<&'static str>) {
 
             "evaluate" => handle_evaluate(tokens),
 
+            #[cfg(feature = "dev")]
+            "evalparams" => handle_evalparams(tokens),
+
             #[cfg(feature = "dev")]
             "magic" => handle_magic(),
+            #[cfg(feature = "dev")]
+            "pack" => handle_pack(tokens),
+
+            #[cfg(feature = "dev")]
+            "pgnsearch" => handle_pgnsearch(tokens),
+            #[cfg(feature = "dev")]
+            "pgnstats" => handle_pgnstats(tokens),
 
             "perft" => handle_perft(tokens),
             "dperft" => handle_dperft(tokens),
             "qperft" => handle_qperft(tokens),
 
+            #[cfg(feature = "dev")]
+            "spsa" => handle_spsa(tokens),
             #[cfg(feature = "dev")]
             "testset" => handle_testset(tokens),
             #[cfg(feature = "dev")]
             "tuner" => handle_tuner(tokens),
 
             "uci" => handle_uci(),
+            "verify-eval" => handle_verify_eval(tokens),
             "wah" => handle_wah(),
             "quit" => handle_quit(),
             _ => handle_unknown_command(),
@@ -93,8 +102,10 @@ pub fn run(args: Vec<OsString>, features: Vec<&'static str>) {
 /// Handles `help` command by printing all available ones.
 fn handle_help() {
     println!("=== General ===");
+    println!(" analyze [pgn] [output] [depth|movetime|nodes] [limit] [options] - analyze games and save them as annotated PGN");
     println!(" benchmark - run test for a set of positions");
-    println!(" evaluate [fen] - show score for the position");
+    println!(" evaluate [fen] - show score for the position, with all evaluation terms");
+    println!(" evaluate json [fen] - show score for the position, with all evaluation terms in the JSON format");
     println!(" uci - run Universal Chess Interface");
     println!(" quit - close the application");
     println!();
@@ -102,10 +113,16 @@ fn handle_help() {
     #[cfg(feature = "dev")]
     {
         println!("=== Development ===");
-        println!(" dataset [pgn] [output] [min_ply] [max_score] [max_diff] [density] - dataset generator");
+        println!(" dataset [pgn] [output] [min_ply] [max_score] [max_diff] [density] [options] - dataset generator");
+        println!(" evalparams load [path] - load evaluation parameters from the tuner output directory or JSON file");
+        println!(" evalparams reset - restore evaluation parameters compiled into the engine");
         println!(" magic - generate magic numbers");
-        println!(" testset [epd] [depth] [ttable_size] [threads_count] - run test of positions");
-        println!(" tuner [epd] [output] [randomize] [k] [wdl_ratio] [threads_count] - run tuning");
+        println!(" pack [epd] [output] [buckets] - convert tuner dataset into the packed binary format");
+        println!(" pgnsearch [pgn] [output] [fen|material|pawns] [value] - save games or positions from the PGN file which match the query");
+        println!(" pgnstats [pgn] [max_ply] [options] - show statistics and opening tree of the PGN file");
+        println!(" spsa [config] [openings] [output] [iterations] [nodes] [threads_count] - run SPSA tuning of search parameters");
+        println!(" testset [epd] [depth|movetime|nodes] [limit] [ttable_size] [threads_count] [summary] - run test of positions");
+        println!(" tuner [epd] [output] [randomize] [k] [wdl_ratio] [threads_count] [options] - run tuning");
         println!();
     }
 
@@ -123,6 +140,82 @@ fn handle_help() {
     println!(" qperft [depth] [threads_count] [hashtable_size_mb]");
     println!(" qperft [depth] [threads_count] [hashtable_size_mb] fen [fen]");
     println!(" qperft [depth] [threads_count] [hashtable_size_mb] moves [moves]");
+    println!();
+    println!("=== Evaluation Verifier ===");
+    println!(" verify-eval [depth]");
+    println!(" verify-eval [depth] fen [fen]");
+    println!(" verify-eval [depth] moves [moves]");
+    println!(" verify-eval [depth] epd [epd]");
+}
+
+/// Handles `analyze [pgn] [output] [depth|movetime|nodes] [limit] [options]` command by searching every position of the games stored in `pgn`
+/// with the specified limit, and saving them into `output` with scores, annotations of weak moves and variations with better ones.
+/// Optional `name=value` pairs in `options` can change the transposition table size, threads count and annotation thresholds.
+fn handle_analyze(input: Vec<&str>) {
+    use crate::testing::analyze;
+    use crate::testing::analyze::AnalysisLimit;
+    use crate::testing::analyze::AnalysisOptions;
+
+    if input.len() < 2 {
+        println!("PGN filename parameter not found");
+        return;
+    }
+
+    if input.len() < 3 {
+        println!("Output filename parameter not found");
+        return;
+    }
+
+    if input.len() < 4 {
+        println!("Limit type parameter not found");
+        return;
+    }
+
+    if input.len() < 5 {
+        println!("Limit parameter not found");
+        return;
+    }
+
+    let limit = match input[3] {
+        "depth" => input[4].parse().map(AnalysisLimit::Depth).map_err(|error| error.to_string()),
+        "movetime" => input[4].parse().map(AnalysisLimit::MoveTime).map_err(|error| error.to_string()),
+        "nodes" => input[4].parse().map(AnalysisLimit::Nodes).map_err(|error| error.to_string()),
+        _ => {
+            println!("Invalid limit type parameter: {}", input[3]);
+            return;
+        }
+    };
+
+    // Zero depth means no depth limit in the search context, so it has to be rejected together with other limits which wouldn't let the search run
+    let limit = match limit {
+        Ok(AnalysisLimit::Depth(..=0)) | Ok(AnalysisLimit::MoveTime(0)) | Ok(AnalysisLimit::Nodes(0)) => {
+            println!("Invalid limit parameter: {}", input[4]);
+            return;
+        }
+        Ok(value) => value,
+        Err(error) => {
+            println!("Invalid limit parameter: {}", error);
+            return;
+        }
+    };
+
+    let mut options = AnalysisOptions::default();
+    for option in &input[5..] {
+        let (name, value) = match option.split_once('=') {
+            Some(value) => value,
+            None => {
+                println!("Invalid analysis option: {}", option);
+                return;
+            }
+        };
+
+        if let Err(error) = options.set(name, value) {
+            println!("{}", error);
+            return;
+        }
+    }
+
+    analyze::run(input[1], input[2], limit, options);
 }
 
 /// Handles `benchmark` command by running a fixed-depth search for a set of static positions and printing diagnostic data.
@@ -236,6 +329,19 @@ fn handle_benchmark() {
             V = VALUE_INDENT
         );
 
+        let ehtable_attempts = result.ehtable_hits + result.ehtable_misses;
+        let ehtable_hits_percent = percent!(result.ehtable_hits, ehtable_attempts);
+        let ehtable_misses_percent = percent!(result.ehtable_misses, ehtable_attempts);
+        println!(
+            "{: <H$} {: <V$} {: <V$} {: <V$}",
+            "Evaluation hashtable",
+            format!("{}", result.ehtable_added),
+            format!("{} ({:.2}%)", result.ehtable_hits, ehtable_hits_percent),
+            format!("{} ({:.2}%)", result.ehtable_misses, ehtable_misses_percent),
+            H = HEADER_INDENT,
+            V = VALUE_INDENT
+        );
+
         let phtable_attempts = result.phtable_hits + result.phtable_misses;
         let phtable_hits_percent = percent!(result.phtable_hits, phtable_attempts);
         let phtable_misses_percent = percent!(result.phtable_misses, phtable_attempts);
@@ -249,6 +355,19 @@ fn handle_benchmark() {
             V = VALUE_INDENT
         );
 
+        let mhtable_attempts = result.mhtable_hits + result.mhtable_misses;
+        let mhtable_hits_percent = percent!(result.mhtable_hits, mhtable_attempts);
+        let mhtable_misses_percent = percent!(result.mhtable_misses, mhtable_attempts);
+        println!(
+            "{: <H$} {: <V$} {: <V$} {: <V$}",
+            "Material hashtable",
+            format!("{}", result.mhtable_added),
+            format!("{} ({:.2}%)", result.mhtable_hits, mhtable_hits_percent),
+            format!("{} ({:.2}%)", result.mhtable_misses, mhtable_misses_percent),
+            H = HEADER_INDENT,
+            V = VALUE_INDENT
+        );
+
         println!();
         println!("{: <H$} {: <V$} {: <V$} {: <V$}", "", "Attempts", "Accepted", "Rejected", H = HEADER_INDENT, V = VALUE_INDENT);
 
@@ -354,14 +473,18 @@ fn handle_benchmark() {
     println!();
 }
 
-/// Handles `evaluate [fen]` command by printing evaluation for the position specified by FEN.
+/// Handles `evaluate [fen]` and `evaluate json [fen]` commands by printing a detailed evaluation trace for the position specified by FEN, as a table
+/// (every feature hit with white/black counts, opening/ending contributions and subtotals of the evaluators) or JSON.
 fn handle_evaluate(input: Vec<&str>) {
-    if input.len() < 2 {
+    let json = input.get(1) == Some(&"json");
+    let fen_start = if json { 2 } else { 1 };
+
+    if input.len() <= fen_start {
         println!("FEN parameter not found");
         return;
     }
 
-    let fen = input[1..].join(" ");
+    let fen = input[fen_start..].join(" ");
     let board = match Board::new_from_fen(fen.as_str()) {
         Ok(board) => board,
         Err(error) => {
@@ -370,23 +493,48 @@ fn handle_evaluate(input: Vec<&str>) {
         }
     };
 
-    let mut white_aux = EvalAux::default();
-    let mut black_aux = EvalAux::default();
+    let trace = EvalTrace::new(&board);
+    if json {
+        print!("{}", trace.to_json());
+    } else {
+        print!("{}", trace.to_table());
+    }
+}
+
+/// Handles `evalparams load [path]` and `evalparams reset` commands by loading evaluation parameters used by the engine from `path` (which can be
+/// the tuner output directory or JSON file), or restoring the ones compiled into the engine.
+#[cfg(feature = "dev")]
+fn handle_evalparams(input: Vec<&str>) {
+    use crate::evaluation::runtime;
+    use crate::evaluation::runtime::EvalParams;
 
-    let material_eval = material::evaluate(&board);
-    let pst_eval = pst::evaluate(&board);
-    let mobility_eval = mobility::evaluate(&board, &mut white_aux, &mut black_aux);
-    let safety_eval = safety::evaluate(&board, &white_aux, &black_aux);
-    let pawns_eval = pawns::evaluate_without_cache(&board);
+    if input.len() < 2 {
+        println!("Mode parameter not found");
+        return;
+    }
 
-    println!("Material: {}", material_eval.taper_score(board.game_phase));
-    println!("Piece-square tables: {}", pst_eval.taper_score(board.game_phase));
-    println!("Mobility: {}", mobility_eval.taper_score(board.game_phase));
-    println!("Safety: {}", safety_eval.taper_score(board.game_phase));
-    println!("Pawns: {}", pawns_eval.taper_score(board.game_phase));
+    match input[1] {
+        "load" => {
+            if input.len() < 3 {
+                println!("Path parameter not found");
+                return;
+            }
 
-    let sum = material_eval + pst_eval + mobility_eval + safety_eval + pawns_eval;
-    println!(" --- Total: {} --- ", sum.taper_score(board.game_phase));
+            let path = input[2..].join(" ");
+            match EvalParams::load(&path) {
+                Ok(params) => {
+                    runtime::set(params);
+                    println!("Evaluation parameters loaded from {}", path);
+                }
+                Err(error) => println!("Invalid path parameter: {}", error),
+            }
+        }
+        "reset" => {
+            runtime::reset();
+            println!("Evaluation parameters restored");
+        }
+        _ => println!("Invalid mode parameter: {}", input[1]),
+    }
 }
 
 /// Handles `magic` command by printing a fresh set of magic numbers.
@@ -567,11 +715,13 @@ fn handle_qperft(input: Vec<&str>) {
     println!("Perft done!");
 }
 
-/// Handles `testset [epd] [depth] [ttable_size] [threads_count]` command by running a fixed-`depth` search of positions stored in the `epd` file,
-/// using hashtable with size specified in `ttable_size`. To classify the test as successful, the last iteration has to return the correct best move.
+/// Handles `testset [epd] [depth|movetime|nodes] [limit] [ttable_size] [threads_count] [summary]` command by running a search of positions stored in the `epd` file,
+/// limited by fixed depth, move time (in milliseconds) or nodes count, and using hashtable with size specified in `ttable_size`. To classify the test as successful,
+/// the last iteration has to satisfy `bm`, `am` and `dm` opcodes of the position. Optional `summary` file is used to save results as CSV or JSON (`.json` extension).
 #[cfg(feature = "dev")]
 fn handle_testset(input: Vec<&str>) {
     use crate::testing::testset;
+    use crate::testing::testset::TestLimit;
 
     if input.len() < 2 {
         println!("EPD filename parameter not found");
@@ -579,29 +729,44 @@ fn handle_testset(input: Vec<&str>) {
     }
 
     if input.len() < 3 {
-        println!("Depth parameter not found");
+        println!("Limit type parameter not found");
         return;
     }
 
     if input.len() < 4 {
-        println!("Transposition table size parameter not found");
+        println!("Limit parameter not found");
         return;
     }
 
     if input.len() < 5 {
+        println!("Transposition table size parameter not found");
+        return;
+    }
+
+    if input.len() < 6 {
         println!("Threads count parameter not found");
         return;
     }
 
-    let depth = match input[2].parse() {
+    let limit = match input[2] {
+        "depth" => input[3].parse().map(TestLimit::Depth).map_err(|error| error.to_string()),
+        "movetime" => input[3].parse().map(TestLimit::MoveTime).map_err(|error| error.to_string()),
+        "nodes" => input[3].parse().map(TestLimit::Nodes).map_err(|error| error.to_string()),
+        _ => {
+            println!("Invalid limit type parameter: {}", input[2]);
+            return;
+        }
+    };
+
+    let limit = match limit {
         Ok(value) => value,
         Err(error) => {
-            println!("Invalid depth parameter: {}", error);
+            println!("Invalid limit parameter: {}", error);
             return;
         }
     };
 
-    let ttable_size: usize = match input[3].parse() {
+    let ttable_size: usize = match input[4].parse() {
         Ok(value) => value,
         Err(error) => {
             println!("Invalid transposition table size parameter: {}", error);
@@ -614,7 +779,7 @@ fn handle_testset(input: Vec<&str>) {
         return;
     }
 
-    let threads_count = match input[4].parse() {
+    let threads_count = match input[5].parse() {
         Ok(value) => value,
         Err(error) => {
             println!("Invalid threads count parameter: {}", error);
@@ -622,16 +787,17 @@ fn handle_testset(input: Vec<&str>) {
         }
     };
 
-    testset::run(input[1], depth, ttable_size * 1024 * 1024, threads_count);
+    testset::run(input[1], limit, ttable_size * 1024 * 1024, threads_count, input.get(6).copied());
 }
 
-/// Handles `tuner [epd] [output] [randomize] [k] [wdl_ratio] [threads_count]` command by running the evaluation parameters tuner. The input file is specified by `epd`
-/// file with a list of positions and their expected results, and the `output` directory is used to store generated Rust sources with the optimized values.
-/// Use `randomize` to initialize evaluation parameters with random values, `k` to set scaling constant and `wdl_ratio` to set the ratio between WDL and eval.
-/// Multithreading is supported by `threads_count`.
+/// Handles `tuner [epd] [output] [randomize] [k] [wdl_ratio] [threads_count] [options]` command by running the evaluation parameters tuner. The input file is
+/// specified by `epd` file with a list of positions and their expected results, and the `output` directory is used to store generated Rust sources with the optimized
+/// values. Use `randomize` to initialize evaluation parameters with random values, `k` to set scaling constant and `wdl_ratio` to set the ratio between WDL and eval.
+/// Multithreading is supported by `threads_count`. Optional `options` are passed as `name=value` pairs (see [crate::tuning::tuner::TunerOptions::set]).
 #[cfg(feature = "dev")]
 fn handle_tuner(input: Vec<&str>) {
     use crate::tuning::tuner;
+    use crate::tuning::tuner::TunerOptions;
 
     if input.len() < 2 {
         println!("EPD filename parameter not found");
@@ -699,15 +865,136 @@ fn handle_tuner(input: Vec<&str>) {
         }
     };
 
-    tuner::run(input[1], input[2], random_values, k, wdl_ratio, threads_count);
+    let mut options = TunerOptions::default();
+    for option in &input[7..] {
+        let (name, value) = match option.split_once('=') {
+            Some(value) => value,
+            None => {
+                println!("Invalid tuner option: {}", option);
+                return;
+            }
+        };
+
+        if let Err(error) = options.set(name, value) {
+            println!("{}", error);
+            return;
+        }
+    }
+
+    tuner::run(input[1], input[2], random_values, k, wdl_ratio, threads_count, options);
+}
+
+/// Handles `spsa [config] [openings] [output] [iterations] [nodes] [threads_count]` command by running SPSA tuner of search parameters. The list of tuned
+/// parameters (with their ranges and steps) is loaded from `config`, and games are played from positions stored in `openings`, with `nodes` limit per move.
+/// The tuned parameters and progress log are saved into the `output` directory. Multithreading is supported by `threads_count`.
+#[cfg(feature = "dev")]
+fn handle_spsa(input: Vec<&str>) {
+    use crate::tuning::spsa;
+
+    if input.len() < 2 {
+        println!("Configuration filename parameter not found");
+        return;
+    }
+
+    if input.len() < 3 {
+        println!("Openings filename parameter not found");
+        return;
+    }
+
+    if input.len() < 4 {
+        println!("Output directory parameter not found");
+        return;
+    }
+
+    if input.len() < 5 {
+        println!("Iterations count parameter not found");
+        return;
+    }
+
+    if input.len() < 6 {
+        println!("Nodes count parameter not found");
+        return;
+    }
+
+    if input.len() < 7 {
+        println!("Threads count parameter not found");
+        return;
+    }
+
+    let iterations_count = match input[4].parse() {
+        Ok(value) => value,
+        Err(error) => {
+            println!("Invalid iterations count parameter: {}", error);
+            return;
+        }
+    };
+
+    let nodes = match input[5].parse() {
+        Ok(value) => value,
+        Err(error) => {
+            println!("Invalid nodes count parameter: {}", error);
+            return;
+        }
+    };
+
+    let threads_count = match input[6].parse() {
+        Ok(value) => value,
+        Err(error) => {
+            println!("Invalid threads count parameter: {}", error);
+            return;
+        }
+    };
+
+    spsa::run(input[1], input[2], input[3], iterations_count, nodes, threads_count);
 }
 
-/// Handles `dataset [pgn] [output] [min_ply] [max_score] [max_diff] [density]` command by running generator of the dataset for the tuner.
+/// Handles `pack [epd] [output] [buckets]` command by converting `epd` file with positions for the tuner into the packed binary dataset saved as `output`,
+/// which contains precomputed coefficients and can be loaded by the tuner much faster. Optional `buckets` file contains king buckets layout used by the tuner.
+#[cfg(feature = "dev")]
+fn handle_pack(input: Vec<&str>) {
+    use crate::evaluation::pst;
+    use crate::tuning::packed;
+    use std::fs;
+
+    if input.len() < 2 {
+        println!("EPD filename parameter not found");
+        return;
+    }
+
+    if input.len() < 3 {
+        println!("Output filename parameter not found");
+        return;
+    }
+
+    let king_buckets = match input.get(3) {
+        Some(filename) => match fs::read_to_string(filename) {
+            Ok(content) => match pst::parse_king_buckets(&content) {
+                Ok(value) => value,
+                Err(error) => {
+                    println!("Invalid king buckets parameter: {}", error);
+                    return;
+                }
+            },
+            Err(error) => {
+                println!("Invalid king buckets parameter: {}", error);
+                return;
+            }
+        },
+        None => pst::KING_BUCKETS,
+    };
+
+    if let Err(error) = packed::convert(input[1], input[2], &king_buckets) {
+        println!("Conversion failed: {}", error);
+    }
+}
+
+/// Handles `dataset [pgn] [output] [min_ply] [max_score] [max_diff] [density] [options]` command by running generator of the dataset for the tuner.
 /// It works by parsing `pgn_filename`, and then picking random positions based on the provided restrictions like `min_ply`, `max_score`,
-/// `max_differ` and `density`. Output positions are then stored in the `output_file`.
+/// `max_differ` and `density`. Output positions are then stored in the `output_file`. Optional `options` are passed as `name=value` pairs.
 #[cfg(feature = "dev")]
 fn handle_dataset(input: Vec<&str>) {
     use crate::tuning::dataset;
+    use crate::tuning::dataset::DatasetOptions;
 
     if input.len() < 2 {
         println!("PGN filename parameter not found");
@@ -771,7 +1058,105 @@ fn handle_dataset(input: Vec<&str>) {
         }
     };
 
-    dataset::run(input[1], input[2], min_ply, max_score, max_diff, density);
+    let mut options = DatasetOptions::default();
+    for option in &input[7..] {
+        let (name, value) = match option.split_once('=') {
+            Some(value) => value,
+            None => {
+                println!("Invalid dataset option: {}", option);
+                return;
+            }
+        };
+
+        if let Err(error) = options.set(name, value) {
+            println!("{}", error);
+            return;
+        }
+    }
+
+    dataset::run(input[1], input[2], min_ply, max_score, max_diff, density, options);
+}
+
+/// Handles `pgnstats [pgn] [max_ply] [options]` command by printing statistics of the `pgn` file (games, results, length and evaluation coverage)
+/// and its opening tree up to `max_ply`. Optional `name=value` pairs in `options` can filter rare moves and export the tree to the book file.
+#[cfg(feature = "dev")]
+fn handle_pgnstats(input: Vec<&str>) {
+    use crate::tuning::pgnstats;
+    use crate::tuning::pgnstats::PGNStatsOptions;
+
+    if input.len() < 2 {
+        println!("PGN filename parameter not found");
+        return;
+    }
+
+    if input.len() < 3 {
+        println!("Maximal ply parameter not found");
+        return;
+    }
+
+    let max_ply = match input[2].parse() {
+        Ok(value) => value,
+        Err(error) => {
+            println!("Invalid maximal ply parameter: {}", error);
+            return;
+        }
+    };
+
+    let mut options = PGNStatsOptions::default();
+    for option in &input[3..] {
+        let (name, value) = match option.split_once('=') {
+            Some(value) => value,
+            None => {
+                println!("Invalid statistics option: {}", option);
+                return;
+            }
+        };
+
+        if let Err(error) = options.set(name, value) {
+            println!("{}", error);
+            return;
+        }
+    }
+
+    pgnstats::run(input[1], max_ply, options);
+}
+
+/// Handles `pgnsearch [pgn] [output] [fen|material|pawns] [value]` command by searching `pgn` file for games reaching the position specified
+/// by FEN, material signature (like `KRPvKR`) or pawn structure (taken from FEN), and saving them into `output` (positions if it's EPD file, games otherwise).
+#[cfg(feature = "dev")]
+fn handle_pgnsearch(input: Vec<&str>) {
+    use crate::tuning::pgnsearch;
+    use crate::tuning::pgnsearch::SearchQuery;
+
+    if input.len() < 2 {
+        println!("PGN filename parameter not found");
+        return;
+    }
+
+    if input.len() < 3 {
+        println!("Output filename parameter not found");
+        return;
+    }
+
+    if input.len() < 4 {
+        println!("Query type parameter not found");
+        return;
+    }
+
+    if input.len() < 5 {
+        println!("Query value parameter not found");
+        return;
+    }
+
+    let query = match SearchQuery::new(input[3], &input[4..].join(" ")) {
+        Ok(value) => value,
+        Err(error) => {
+            println!("Invalid query parameter: {}", error);
+            return;
+        }
+    };
+
+    pgnsearch::run(input[1], input[2], query);
 }
 
 /// Handles `uci` command by entering into the UCI (Universal Chess Interface) mode.
@@ -779,6 +1164,58 @@ fn handle_uci() {
     uci::run();
 }
 
+/// Handles `verify-eval [depth]`, `verify-eval [depth] fen [fen]`, `verify-eval [depth] moves [moves]` and `verify-eval [depth] epd [epd]` commands
+/// by verifying evaluation symmetry and consistency of incremental values for every position reached during perft to the depth specified by `depth`
+/// parameter. The initial position can be specified by FEN, a list of moves, EPD file, or just omitted (so the default start position will be taken).
+fn handle_verify_eval(input: Vec<&str>) {
+    use crate::testing::verify;
+
+    if input.len() < 2 {
+        println!("Depth parameter not found");
+        return;
+    }
+
+    let depth: i32 = match input[1].parse() {
+        Ok(result) => result,
+        Err(error) => {
+            println!("Invalid depth parameter: {}", error);
+            return;
+        }
+    };
+
+    let now = SystemTime::now();
+    let result = if input.get(2) == Some(&"epd") {
+        if input.len() < 4 {
+            println!("EPD filename parameter not found");
+            return;
+        }
+
+        match verify::run_epd(input[3], depth) {
+            Ok(result) => result,
+            Err(error) => {
+                println!("Invalid EPD parameter: {}", error);
+                return;
+            }
+        }
+    } else {
+        let mut board = match prepare_board(&input[2..]) {
+            Ok(board) => board,
+            Err(error) => {
+                println!("Invalid FEN parameter: {}", error);
+                return;
+            }
+        };
+
+        verify::run(&mut board, depth)
+    };
+
+    let diff = (now.elapsed().unwrap().as_millis() as f64) / 1000.0;
+    match result.error {
+        Some(error) => println!("Verification failed after {} positions: {}", result.positions_count, error),
+        None => println!("Verification done! {} positions checked in {:.2} s", result.positions_count, diff),
+    }
+}
+
 /// Handles `wah` command by printing WAH.                  
 fn handle_wah() {
     println!("WAH");
@@ -1,8 +1,11 @@
 use crate::cache::counters::CMTable;
+use crate::cache::eval::EHTable;
 use crate::cache::history::HTable;
 use crate::cache::killers::KTable;
+use crate::cache::material::MHTable;
 use crate::cache::pawns::PHTable;
 use crate::cache::search::TTable;
+use crate::cache::search::TTableScoreType;
 use crate::engine;
 use crate::engine::context::SearchContext;
 use crate::engine::params::SearchParams;
@@ -29,6 +32,7 @@ use std::time::SystemTime;
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 const AUTHOR: &str = env!("CARGO_PKG_AUTHORS");
 const PAWN_HASHTABLE_SIZE: usize = 1 * 1024 * 1024;
+const MATERIAL_HASHTABLE_SIZE: usize = 256 * 1024;
 
 pub struct UciState {
     context: Arc<RwLock<SearchContext>>,
@@ -83,7 +87,9 @@ impl Default for UciState {
             context: Arc::new(RwLock::new(SearchContext::new(
                 Board::new_initial_position(),
                 Arc::new(TTable::new(1 * 1024 * 1024)),
+                Arc::new(EHTable::new(1 * 1024 * 1024)),
                 Arc::new(PHTable::new(1 * 1024 * 1024)),
+                Arc::new(MHTable::new(256 * 1024)),
                 abort_flag.clone(),
                 ponder_flag.clone(),
             ))),
@@ -105,20 +111,25 @@ pub fn run() {
     println!("id author {}", AUTHOR);
 
     options_lock.insert("Hash".to_string(), UciOption::new(0, "spin", 1, 1048576, 2));
-    options_lock.insert("Move Overhead".to_string(), UciOption::new(1, "spin", 0, 3600000, 100));
-    options_lock.insert("MultiPV".to_string(), UciOption::new(2, "spin", 1, 256, 1));
-    options_lock.insert("Threads".to_string(), UciOption::new(3, "spin", 1, 1024, 1));
-    options_lock.insert("SyzygyPath".to_string(), UciOption::new(4, "string", "", "", "<empty>"));
-    options_lock.insert("SyzygyProbeLimit".to_string(), UciOption::new(5, "spin", 1, 9, 8));
-    options_lock.insert("SyzygyProbeDepth".to_string(), UciOption::new(6, "spin", 1, 32, 6));
-    options_lock.insert("Ponder".to_string(), UciOption::new(7, "check", false, false, false));
+    options_lock.insert("EvalHash".to_string(), UciOption::new(1, "spin", 1, 1048576, 1));
+    options_lock.insert("Move Overhead".to_string(), UciOption::new(2, "spin", 0, 3600000, 100));
+    options_lock.insert("MultiPV".to_string(), UciOption::new(3, "spin", 1, 256, 1));
+    options_lock.insert("Threads".to_string(), UciOption::new(4, "spin", 1, 1024, 1));
+    options_lock.insert("SyzygyPath".to_string(), UciOption::new(5, "string", "", "", "<empty>"));
+    options_lock.insert("SyzygyProbeLimit".to_string(), UciOption::new(6, "spin", 1, 9, 8));
+    options_lock.insert("SyzygyProbeDepth".to_string(), UciOption::new(7, "spin", 1, 32, 6));
+    options_lock.insert("Syzygy50MoveRule".to_string(), UciOption::new(8, "check", true, true, true));
+    options_lock.insert("Ponder".to_string(), UciOption::new(9, "check", false, false, false));
     options_lock.insert("Soft Nodes".to_string(), UciOption::new(50, "check", false, false, false));
     options_lock.insert("Search Noise".to_string(), UciOption::new(50, "check", false, false, false));
-    options_lock.insert("Clear Hash".to_string(), UciOption::new(8, "button", "", "", ""));
+    options_lock.insert("Clear Hash".to_string(), UciOption::new(10, "button", "", "", ""));
 
     #[cfg(feature = "dev")]
     options_lock.insert("Crash Files".to_string(), UciOption::new(50, "check", false, false, false));
 
+    #[cfg(feature = "dev")]
+    options_lock.insert("EvalParamsFile".to_string(), UciOption::new(50, "string", "", "", "<empty>"));
+
     #[cfg(feature = "dev")]
     {
         let params = SearchParams::default();
@@ -392,6 +403,7 @@ fn handle_go(params: &[String], state: &UciState) {
         let syzygy_enabled = !syzygy_path.is_empty() && syzygy_path != "<empty>";
         let syzygy_probe_limit = options_lock["SyzygyProbeLimit"].value.parse::<u32>().unwrap();
         let syzygy_probe_depth = options_lock["SyzygyProbeDepth"].value.parse::<i8>().unwrap();
+        let syzygy_rule50 = options_lock["Syzygy50MoveRule"].value.parse::<bool>().unwrap();
         let soft_nodes = options_lock["Soft Nodes"].value.parse::<bool>().unwrap();
         let search_noise = options_lock["Search Noise"].value.parse::<bool>().unwrap();
 
@@ -464,7 +476,7 @@ fn handle_go(params: &[String], state: &UciState) {
         context_lock.moves_to_go = moves_to_go;
         context_lock.moves_to_search = moves_to_search.clone();
         context_lock.search_time_start = SystemTime::now();
-        context_lock.multipv = multipv > 1;
+        context_lock.multipv = multipv as usize;
         context_lock.search_done = false;
         context_lock.uci_debug = debug_mode;
         context_lock.ponder_mode = ponder_mode;
@@ -473,6 +485,7 @@ fn handle_go(params: &[String], state: &UciState) {
         context_lock.syzygy_enabled = syzygy_enabled;
         context_lock.syzygy_probe_limit = syzygy_probe_limit;
         context_lock.syzygy_probe_depth = syzygy_probe_depth;
+        context_lock.syzygy_rule50 = syzygy_rule50;
         context_lock.stats = SearchStats::default();
 
         if soft_nodes {
@@ -486,11 +499,17 @@ fn handle_go(params: &[String], state: &UciState) {
             let mut helper_context = SearchContext::new(
                 context_lock.board.clone(),
                 context_lock.ttable.clone(),
+                context_lock.ehtable.clone(),
                 context_lock.phtable.clone(),
+                context_lock.mhtable.clone(),
                 context_lock.abort_flag.clone(),
                 context_lock.ponder_flag.clone(),
             );
             helper_context.params = search_params.clone();
+            helper_context.syzygy_enabled = syzygy_enabled;
+            helper_context.syzygy_probe_limit = syzygy_probe_limit;
+            helper_context.syzygy_probe_depth = syzygy_probe_depth;
+            helper_context.syzygy_rule50 = syzygy_rule50;
             context_lock.helper_contexts.write().unwrap().push(helper_context);
         }
 
@@ -501,20 +520,23 @@ fn handle_go(params: &[String], state: &UciState) {
             for (line_index, line) in context_lock.lines.iter().take(multipv as usize).enumerate() {
                 let pv_line: Vec<String> = line.pv_line.iter().map(|v| v.to_long_notation()).collect();
                 let formatted_score = if engine::is_score_near_checkmate(line.score) {
-                    let mut moves_to_mate = (line.score.abs() - engine::CHECKMATE_SCORE).abs() / 2;
-                    moves_to_mate *= line.score.signum();
-
-                    format!("score mate {}", moves_to_mate).to_string()
+                    format!("score mate {}", engine::get_moves_to_mate(line.score)).to_string()
                 } else {
                     format!("score cp {}", line.score).to_string()
                 };
+                let formatted_bound = match line.score_type {
+                    TTableScoreType::LOWER_BOUND => " lowerbound",
+                    TTableScoreType::UPPER_BOUND => " upperbound",
+                    _ => "",
+                };
 
                 println!(
-                    "info time {} {} depth {} seldepth {} multipv {} nodes {} hashfull {} tbhits {} pv {}",
+                    "info time {} {}{} depth {} seldepth {} multipv {} nodes {} hashfull {} tbhits {} pv {}",
                     depth_result.time,
                     formatted_score,
-                    depth_result.depth,
-                    context_lock.stats.max_ply,
+                    formatted_bound,
+                    line.depth,
+                    line.seldepth,
                     line_index + 1,
                     context_lock.stats.nodes_count + context_lock.stats.q_nodes_count,
                     (context_lock.ttable.get_usage(1000) * 10.0) as u32,
@@ -621,7 +643,7 @@ fn handle_position(params: &[String], state: &UciState) {
 }
 
 /// Handles `setoption [name] value [value]` command by creating or overwriting a `name` option with the specified `value`.
-/// Recreates tables if `Hash` or `Clear Hash` options are modified.
+/// Recreates tables if `Hash`, `EvalHash` or `Clear Hash` options are modified.
 fn handle_setoption(params: &[String], state: &mut UciState) {
     let options_arc = state.options.clone();
     let mut options_lock = options_arc.write().unwrap();
@@ -666,16 +688,11 @@ fn handle_setoption(params: &[String], state: &mut UciState) {
     drop(options_lock);
 
     match name.as_str() {
-        "Hash" => {
+        "Hash" | "EvalHash" => {
             recreate_state_tables(state);
         }
         "SyzygyPath" => {
-            if !value.is_empty() && value != "<empty>" {
-                syzygy::probe::init(&value);
-            }
-
-            #[cfg(not(feature = "syzygy"))]
-            println!("info string Syzygy tablebases not supported in this build");
+            syzygy::probe::init(&value);
         }
         "Clear Hash" => {
             recreate_state_tables(state);
@@ -685,6 +702,10 @@ fn handle_setoption(params: &[String], state: &mut UciState) {
             true => enable_crash_files(),
             false => disable_crash_files(),
         },
+        #[cfg(feature = "dev")]
+        "EvalParamsFile" => {
+            load_eval_params(state, &value);
+        }
         _ => {}
     }
 }
@@ -710,19 +731,45 @@ fn handle_quit() {
     process::exit(0);
 }
 
-/// Recreates transposition table, pawn hashtable, killers table and history table.
+/// Recreates transposition table, evaluation hashtable, pawn hashtable, material hashtable, killers table and history table.
 fn recreate_state_tables(state: &mut UciState) {
     let mut context_lock = state.context.write().unwrap();
     let options_lock = state.options.read().unwrap();
     let ttable_size = options_lock["Hash"].value.parse::<usize>().unwrap();
+    let ehtable_size = options_lock["EvalHash"].value.parse::<usize>().unwrap();
 
     context_lock.ttable = Arc::new(TTable::new(ttable_size * 1024 * 1024));
+    context_lock.ehtable = Arc::new(EHTable::new(ehtable_size * 1024 * 1024));
     context_lock.phtable = Arc::new(PHTable::new(PAWN_HASHTABLE_SIZE));
+    context_lock.mhtable = Arc::new(MHTable::new(MATERIAL_HASHTABLE_SIZE));
     context_lock.ktable = KTable::default();
     context_lock.htable = HTable::default();
     context_lock.cmtable = CMTable::default();
 }
 
+/// Loads evaluation parameters from `path` (tuner output directory or JSON file), or restores the compiled ones if `path` is empty. Tables and
+/// incremental values of the board are recreated, as they were calculated using the previous parameters.
+#[cfg(feature = "dev")]
+fn load_eval_params(state: &mut UciState, path: &str) {
+    use crate::evaluation::runtime;
+    use crate::evaluation::runtime::EvalParams;
+
+    if path.is_empty() || path == "<empty>" {
+        runtime::reset();
+    } else {
+        match EvalParams::load(path) {
+            Ok(params) => runtime::set(params),
+            Err(error) => {
+                println!("info string Error: {}", error);
+                return;
+            }
+        }
+    }
+
+    state.context.write().unwrap().board.recalculate_incremental_values();
+    recreate_state_tables(state);
+}
+
 /// Enables saving of crash files by setting a custom panic hook.
 #[cfg(feature = "dev")]
 fn enable_crash_files() {
@@ -14,6 +14,7 @@ pub fn run_internal(context: &mut PerftContext, depth: i32) -> u64 {
     if context.check_integrity {
         let original_hash = context.board.state.hash;
         let original_pawn_hash = context.board.state.pawn_hash;
+        let original_material_key = context.board.state.material_key;
         let original_eval = context.board.evaluate_without_cache(WHITE);
 
         context.board.recalculate_hashes();
@@ -37,6 +38,15 @@ pub fn run_internal(context: &mut PerftContext, depth: i32) -> u64 {
             );
         }
 
+        if original_material_key != context.board.state.material_key {
+            panic_fast!(
+                "Integrity check failed, invalid material key: fen={}, original_material_key={}, context.board.state.material_key={}",
+                context.board,
+                original_material_key,
+                context.board.state.material_key
+            );
+        }
+
         let eval = context.board.evaluate_without_cache(WHITE);
         if original_eval != eval {
             panic_fast!("Integrity check failed, invalid evaluation: fen={}, original_eval={}, eval={}", context.board, original_eval, eval)
@@ -3,21 +3,25 @@ use super::movescan::Move;
 use super::movescan::MoveFlags;
 use super::text::fen;
 use super::*;
+use crate::cache::eval::EHTable;
+use crate::cache::material::MHTable;
 use crate::cache::pawns::PHTable;
 use crate::engine;
 use crate::engine::stats::SearchStats;
+use crate::evaluation::endgame;
 use crate::evaluation::material;
 use crate::evaluation::mobility;
 use crate::evaluation::mobility::EvalAux;
 use crate::evaluation::pawns;
 use crate::evaluation::pst;
-use crate::evaluation::pst::*;
 use crate::evaluation::safety;
 use crate::evaluation::*;
 use crate::tablebases;
 use crate::utils::assert_fast;
 use crate::utils::bitflags::BitFlags;
 use crate::utils::bithelpers::BitHelpers;
+use crate::utils::dev;
+use crate::utils::eval_param;
 use crate::utils::panic_fast;
 use crate::Moves;
 use std::fmt::Display;
@@ -58,6 +62,7 @@ pub struct BoardState {
     pub en_passant: u64,
     pub hash: u64,
     pub pawn_hash: u64,
+    pub material_key: u64,
     pub captured_piece: u8,
     pub pst_score: PackedEval,
 }
@@ -275,7 +280,7 @@ impl Board {
             let from = if stm == WHITE { from } else { (1u64 << from).swap_bytes().bit_scan() };
             let to = if stm == WHITE { to } else { (1u64 << to).swap_bytes().bit_scan() };
 
-            if KING_BUCKETS[from] != KING_BUCKETS[to] {
+            if pst::get_king_bucket(from) != pst::get_king_bucket(to) {
                 pst::recalculate_incremental_values(self);
             }
         } else if piece == ROOK {
@@ -649,6 +654,7 @@ impl Board {
             let sign = -(color as i16 * 2 - 1);
             let score = pst::get_pst_value(piece, US, king_square, square) + pst::get_pst_value(piece, THEM, enemy_king_square, square);
             self.state.pst_score += sign * score;
+            self.state.material_key += endgame::get_material_key_delta(color, piece);
         }
     }
 
@@ -677,6 +683,7 @@ impl Board {
             let sign = -(color as i16 * 2 - 1);
             let score = pst::get_pst_value(piece, US, king_square, square) + pst::get_pst_value(piece, THEM, enemy_king_square, square);
             self.state.pst_score -= sign * score;
+            self.state.material_key -= endgame::get_material_key_delta(color, piece);
         }
     }
 
@@ -730,64 +737,121 @@ impl Board {
         };
     }
 
-    /// Runs full evaluation (material, piece-square tables, mobility, pawn structure and safety) of the current position, using `phtable` to store pawn
-    /// evaluations and `stats` to gather diagnostic data. Returns score from the `color` perspective (more than 0 when advantage, less than 0 when disadvantage).
-    pub fn evaluate(&self, color: usize, phtable: &PHTable, stats: &mut SearchStats) -> i16 {
+    /// Runs full evaluation (material, imbalance, piece-square tables, mobility, pawn structure and safety) of the current position,
+    /// using `ehtable` to store final scores, `phtable` to store pawn evaluations, `mhtable` to store material imbalance evaluations and `stats` to gather
+    /// diagnostic data. Known endgames are evaluated by specialised evaluators, and the ending score of drawish ones is scaled down. Returns score from
+    /// the `color` perspective (more than 0 when advantage, less than 0 when disadvantage).
+    pub fn evaluate(&self, color: usize, ehtable: &EHTable, phtable: &PHTable, mhtable: &MHTable, stats: &mut SearchStats) -> i16 {
         assert_fast!(color < 2);
 
+        let sign = -((color as i16) * 2 - 1);
+        if let Some(score) = endgame::evaluate(self) {
+            return sign * score;
+        }
+
+        match ehtable.get(self.state.hash) {
+            Some(entry) => {
+                dev!(stats.ehtable_hits += 1);
+                return sign * entry.score + eval_param!(TEMPO);
+            }
+            None => {
+                dev!(stats.ehtable_misses += 1);
+            }
+        }
+
         let mut white_aux = EvalAux::default();
         let mut black_aux = EvalAux::default();
 
         let material_eval = material::evaluate(self);
+        let imbalance_eval = material::evaluate_imbalance(self, mhtable, stats);
         let pst_eval = pst::evaluate(self);
         let mobility_eval = mobility::evaluate(self, &mut white_aux, &mut black_aux);
         let safety_eval = safety::evaluate(self, &white_aux, &black_aux);
         let pawns_eval = pawns::evaluate(self, phtable, stats);
 
-        let eval = material_eval + pst_eval + mobility_eval + safety_eval + pawns_eval;
-        let sign = -((color as i16) * 2 - 1);
+        let eval = material_eval + imbalance_eval + pst_eval + mobility_eval + safety_eval + pawns_eval;
+        let scale = endgame::get_scale_factor(self, eval);
+        let score = eval.taper_score_scaled(self.game_phase, scale);
 
-        sign * eval.taper_score(self.game_phase) + params::TEMPO
+        ehtable.add(self.state.hash, score);
+        dev!(stats.ehtable_added += 1);
+
+        sign * score + eval_param!(TEMPO)
     }
 
-    /// Runs full evaluation (material, piece-square tables, mobility, pawn structure and safety) of the current position.
-    /// Returns score from the `color` perspective (more than 0 when advantage, less than 0 when disadvantage).
+    /// Runs full evaluation (material, imbalance, piece-square tables, mobility, pawn structure and safety) of the current position,
+    /// including known endgames and scaling. Returns score from the `color` perspective (more than 0 when advantage, less than 0 when disadvantage).
     pub fn evaluate_without_cache(&self, color: usize) -> i16 {
+        self.evaluate_without_cache_internal::<true>(color)
+    }
+
+    /// Runs full evaluation like [Board::evaluate_without_cache], but skips piece-square tables (which depend on the king bucket and because of
+    /// that aren't invariant to the horizontal flip). Returns score from the `color` perspective.
+    pub fn evaluate_without_cache_and_pst(&self, color: usize) -> i16 {
+        self.evaluate_without_cache_internal::<false>(color)
+    }
+
+    /// Internal part of [Board::evaluate_without_cache] and [Board::evaluate_without_cache_and_pst], `PST` indicates if piece-square tables are included.
+    fn evaluate_without_cache_internal<const PST: bool>(&self, color: usize) -> i16 {
         assert_fast!(color < 2);
 
+        let sign = -((color as i16) * 2 - 1);
+        if let Some(score) = endgame::evaluate(self) {
+            return sign * score;
+        }
+
         let mut white_aux = EvalAux::default();
         let mut black_aux = EvalAux::default();
 
         let material_eval = material::evaluate(self);
-        let pst_eval = pst::evaluate(self);
+        let imbalance_eval = material::evaluate_imbalance_without_cache(self);
+        let pst_eval = if PST { pst::evaluate(self) } else { PackedEval::default() };
         let mobility_eval = mobility::evaluate(self, &mut white_aux, &mut black_aux);
         let safety_eval = safety::evaluate(self, &white_aux, &black_aux);
         let pawns_eval = pawns::evaluate_without_cache(self);
 
-        let eval = material_eval + pst_eval + mobility_eval + safety_eval + pawns_eval;
-        let sign = -((color as i16) * 2 - 1);
+        let eval = material_eval + imbalance_eval + pst_eval + mobility_eval + safety_eval + pawns_eval;
+        let scale = endgame::get_scale_factor(self, eval);
 
-        sign * eval.taper_score(self.game_phase) + params::TEMPO
+        sign * eval.taper_score_scaled(self.game_phase, scale) + eval_param!(TEMPO)
     }
 
-    /// Runs fast evaluations, considering only material, piece-square tables and pawn structure. Returns score from the `color` perspective (more than 0 when
-    /// advantage, less than 0 when disadvantage).
-    pub fn evaluate_fast(&self, color: usize, phtable: &PHTable, stats: &mut SearchStats) -> i16 {
+    /// Runs fast evaluations, considering only material, imbalance, piece-square tables and pawn structure (together with known endgames and scaling).
+    /// If the full evaluation of the position is already stored in `ehtable`, it's returned instead (but fast evaluations are never stored there).
+    /// Returns score from the `color` perspective (more than 0 when advantage, less than 0 when disadvantage).
+    pub fn evaluate_fast(&self, color: usize, ehtable: &EHTable, phtable: &PHTable, mhtable: &MHTable, stats: &mut SearchStats) -> i16 {
         assert_fast!(color < 2);
 
+        let sign = -((color as i16) * 2 - 1);
+        if let Some(score) = endgame::evaluate(self) {
+            return sign * score;
+        }
+
+        match ehtable.get(self.state.hash) {
+            Some(entry) => {
+                dev!(stats.ehtable_hits += 1);
+                return sign * entry.score + eval_param!(TEMPO);
+            }
+            None => {
+                dev!(stats.ehtable_misses += 1);
+            }
+        }
+
         let material_eval = material::evaluate(self);
+        let imbalance_eval = material::evaluate_imbalance(self, mhtable, stats);
         let pst_eval = pst::evaluate(self);
         let pawns_eval = pawns::evaluate(self, phtable, stats);
 
-        let eval = material_eval + pst_eval + pawns_eval;
-        let sign = -((color as i16) * 2 - 1);
+        let eval = material_eval + imbalance_eval + pst_eval + pawns_eval;
+        let scale = endgame::get_scale_factor(self, eval);
 
-        sign * eval.taper_score(self.game_phase) + params::TEMPO
+        sign * eval.taper_score_scaled(self.game_phase, scale) + eval_param!(TEMPO)
     }
 
-    /// Recalculates incremental values entirely.
+    /// Recalculates incremental values (piece-square tables score and material key) entirely.
     pub fn recalculate_incremental_values(&mut self) {
         pst::recalculate_incremental_values(self);
+        endgame::recalculate_material_key(self);
     }
 
     /// Checks if there's repetition draw with the specified `threshold` (should be 3 in the most cases) at the current position.
@@ -906,9 +970,79 @@ impl Board {
         None
     }
 
-    /// Checks if there's a tablebase move (only Syzygy supported for now) and returns it as [Some], otherwise [None].
-    pub fn get_tablebase_move(&self, probe_limit: u32) -> Option<(Move, i16)> {
-        tablebases::get_tablebase_move(self, probe_limit)
+    /// Gets the number of legal moves in the current position, restricted to `moves_to_search` if the list is not empty.
+    pub fn get_legal_moves_count(&mut self, moves_to_search: &[Move]) -> usize {
+        let mut moves = [MaybeUninit::uninit(); engine::MAX_MOVES_COUNT];
+        let moves_count = self.get_all_moves(&mut moves, u64::MAX);
+        let mut legal_moves_count = 0;
+
+        for r#move in &moves[0..moves_count] {
+            let r#move = unsafe { r#move.assume_init() };
+            if !moves_to_search.is_empty() && !moves_to_search.contains(&r#move) {
+                continue;
+            }
+
+            self.make_move(r#move);
+            if !self.is_king_checked(self.stm ^ 1) {
+                legal_moves_count += 1;
+            }
+            self.undo_move(r#move);
+        }
+
+        legal_moves_count
+    }
+
+    /// Gets a list of root moves which preserve the tablebase result (only Syzygy supported for now) together with the expected score, or [None] if the position
+    /// is not covered by tablebases. Use `rule50` to take the fifty-move rule into consideration.
+    pub fn get_tablebase_moves(&self, probe_limit: u32, rule50: bool) -> Option<(Vec<Move>, i16)> {
+        tablebases::get_root_moves(self, probe_limit, rule50)
+    }
+
+    /// Gets a copy of the board mirrored vertically with swapped colors (white pieces on the first rank become black pieces on the eighth rank), so
+    /// the position is the same from the perspective of the side to move. Castling rights and en passant square are mirrored too, history is not kept.
+    pub fn mirror(&self) -> Board {
+        let mut castling_rights = (self.state.castling_rights & CastlingRights::WHITE_CASTLING) << 2;
+        castling_rights |= (self.state.castling_rights & CastlingRights::BLACK_CASTLING) >> 2;
+
+        self.transform(true, |square| square ^ 56, castling_rights, self.state.en_passant.swap_bytes())
+    }
+
+    /// Gets a copy of the board flipped horizontally (pieces on the A file go to the H file and vice versa), with colors unchanged. Castling rights
+    /// can't be preserved after such operation, so they are always removed. En passant square is flipped, history is not kept.
+    pub fn flip(&self) -> Board {
+        self.transform(false, |square| square ^ 7, CastlingRights::NONE, self.state.en_passant.reverse_bits().swap_bytes())
+    }
+
+    /// Gets a copy of the board with every piece moved to the square returned by `square_fn` (and with swapped colors if `swap_colors` is set),
+    /// `castling_rights` and `en_passant`. Hashes and incremental values are recalculated from scratch.
+    fn transform(&self, swap_colors: bool, square_fn: fn(usize) -> usize, castling_rights: u8, en_passant: u64) -> Board {
+        let mut board = Board::default();
+        let color_mask = swap_colors as usize;
+
+        for color in ALL_COLORS {
+            for piece in ALL_PIECES {
+                let mut pieces_bb = self.pieces[color][piece];
+                while pieces_bb != 0 {
+                    let square = pieces_bb.bit_scan();
+                    pieces_bb = pieces_bb.pop_lsb();
+
+                    board.add_piece::<true>(color ^ color_mask, piece, square_fn(square));
+                }
+            }
+        }
+
+        board.stm = self.stm ^ color_mask;
+        board.fullmove_number = self.fullmove_number;
+        board.state.halfmove_clock = self.state.halfmove_clock;
+        board.state.castling_rights = castling_rights;
+        board.state.en_passant = en_passant;
+
+        board.recalculate_hashes();
+        board.recalculate_incremental_values();
+        board.recalculate_pawn_attacks(WHITE);
+        board.recalculate_pawn_attacks(BLACK);
+
+        board
     }
 
     /// Converts the board's state into FEN.
@@ -938,6 +1072,7 @@ impl Default for Board {
                 en_passant: 0,
                 hash: 0,
                 pawn_hash: 0,
+                material_key: 0,
                 captured_piece: 0,
                 pst_score: PackedEval::default(),
             },
@@ -954,8 +1089,18 @@ impl Display for Board {
 }
 
 impl BoardState {
-    /// Constructs a new instance of [BoardState] with stored `halfmove_clock`, `castling_rights`, `en_passant`, `hash`, `pawn_hash`, `captured_piece` and `pst_score`.
-    pub fn new(halfmove_clock: u16, castling_rights: u8, en_passant: u64, hash: u64, pawn_hash: u64, captured_piece: u8, pst_score: PackedEval) -> BoardState {
-        BoardState { halfmove_clock, castling_rights, en_passant, hash, pawn_hash, captured_piece, pst_score }
+    /// Constructs a new instance of [BoardState] with stored `halfmove_clock`, `castling_rights`, `en_passant`, `hash`, `pawn_hash`, `material_key`,
+    /// `captured_piece` and `pst_score`.
+    pub fn new(
+        halfmove_clock: u16,
+        castling_rights: u8,
+        en_passant: u64,
+        hash: u64,
+        pawn_hash: u64,
+        material_key: u64,
+        captured_piece: u8,
+        pst_score: PackedEval,
+    ) -> BoardState {
+        BoardState { halfmove_clock, castling_rights, en_passant, hash, pawn_hash, material_key, captured_piece, pst_score }
     }
 }
@@ -9,13 +9,16 @@ pub struct ParsedEPD {
     pub board: Board,
     pub id: Option<String>,
     pub best_move: Option<String>,
-    pub comment: Option<String>,
+    pub avoid_move: Option<String>,
+    pub direct_mate: Option<String>,
+    pub pv: Option<String>,
+    pub comments: [Option<String>; 10],
 }
 
 impl ParsedEPD {
     /// Constructs a new instance of [ParsedEPD] with the `board` and rest of the squares zeroed.
     pub fn new(board: Board) -> Self {
-        Self { board, id: None, best_move: None, comment: None }
+        Self { board, id: None, best_move: None, avoid_move: None, direct_mate: None, pv: None, comments: Default::default() }
     }
 }
 
@@ -24,9 +27,10 @@ pub fn fen_to_board(fen: &str) -> Result<Board, String> {
     Ok(epd_to_board(fen)?.board)
 }
 
-/// Converts `epd` into the [Board]. Returns [Err] with proper error message if `epd` couldn't be parsed correctly.
+/// Converts `epd` into the [Board]. Halfmove clock and fullmove number are optional, and all recognized operations (`id`, `bm`, `am`, `dm`, `pv`, `c0`..`c9`)
+/// are stored in the [ParsedEPD]. Returns [Err] with proper error message if `epd` couldn't be parsed correctly.
 pub fn epd_to_board(epd: &str) -> Result<ParsedEPD, String> {
-    let tokens: Vec<&str> = epd.split(' ').map(|v| v.trim()).collect();
+    let tokens: Vec<&str> = epd.split_whitespace().collect();
     if tokens.len() < 4 {
         return Err(format!("Invalid FEN, input too short: epd={}", epd));
     }
@@ -42,19 +46,32 @@ pub fn epd_to_board(epd: &str) -> Result<ParsedEPD, String> {
     board.recalculate_pawn_attacks(WHITE);
     board.recalculate_pawn_attacks(BLACK);
 
-    if tokens.len() > 4 {
-        let _ = fen_to_halfmove_clock(&mut board, tokens[4]);
-        let _ = fen_to_fullmove_number(&mut board, tokens[5]);
-
-        let mut parsed_epd = ParsedEPD::new(board);
-        parsed_epd.id = get_epd_parameter(epd, &["id"]);
-        parsed_epd.best_move = get_epd_parameter(epd, &["bm"]);
-        parsed_epd.comment = get_epd_parameter(epd, &["c0", "c9"]);
+    let mut fields_count = 4;
+    if tokens.len() >= 6 && tokens[4].parse::<u16>().is_ok() && tokens[5].parse::<u16>().is_ok() {
+        fen_to_halfmove_clock(&mut board, tokens[4])?;
+        fen_to_fullmove_number(&mut board, tokens[5])?;
+        fields_count = 6;
+    }
 
-        return Ok(parsed_epd);
+    let mut parsed_epd = ParsedEPD::new(board);
+    for (opcode, operand) in get_epd_operations(skip_epd_fields(epd, fields_count)) {
+        match opcode.as_str() {
+            "id" => parsed_epd.id = Some(operand),
+            "bm" => parsed_epd.best_move = Some(operand),
+            "am" => parsed_epd.avoid_move = Some(operand),
+            "dm" => parsed_epd.direct_mate = Some(operand),
+            "pv" => parsed_epd.pv = Some(operand),
+            _ => {
+                if let Some(index) = opcode.strip_prefix('c').and_then(|v| v.parse::<usize>().ok()) {
+                    if index < parsed_epd.comments.len() {
+                        parsed_epd.comments[index] = Some(operand);
+                    }
+                }
+            }
+        }
     }
 
-    Ok(ParsedEPD::new(board))
+    Ok(parsed_epd)
 }
 
 /// Converts [Board] into the FEN.
@@ -79,20 +96,55 @@ pub fn board_to_epd(board: &Board) -> String {
     format!("{} {} {} {}", pieces, stm, castling, en_passant)
 }
 
-/// Gets a value of the `name` parameters from the specified `epd`. Returns [None] if the parameter was not found.
-fn get_epd_parameter(mut epd: &str, name: &[&str]) -> Option<String> {
-    let parameter_index = name.iter().find_map(|p| epd.find(p))?;
-    epd = &epd[parameter_index..];
+/// Skips the first `fields_count` whitespace-separated fields of the `epd`, and returns the rest of it.
+fn skip_epd_fields(mut epd: &str, fields_count: usize) -> &str {
+    for _ in 0..fields_count {
+        epd = epd.trim_start();
+        epd = match epd.find(char::is_whitespace) {
+            Some(index) => &epd[index..],
+            None => "",
+        };
+    }
 
-    let value_index = epd.find(' ')?;
-    epd = &epd[value_index..];
+    epd
+}
 
-    let separator_index = epd.find(';')?;
-    let mut value = &epd[0..separator_index];
-    value = value.trim_matches(' ');
-    value = value.trim_matches('\"');
+/// Splits `operations` into a list of opcodes and their operands (with quotes removed). Semicolons inside quoted strings are not treated as separators.
+fn get_epd_operations(operations: &str) -> Vec<(String, String)> {
+    let mut result = Vec::new();
+    let mut quoted = false;
+    let mut operation_start = 0;
+
+    for (index, char) in operations.char_indices() {
+        match char {
+            '"' => quoted = !quoted,
+            ';' if !quoted => {
+                if let Some(operation) = get_epd_operation(&operations[operation_start..index]) {
+                    result.push(operation);
+                }
+
+                operation_start = index + 1;
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(operation) = get_epd_operation(&operations[operation_start..]) {
+        result.push(operation);
+    }
+
+    result
+}
+
+/// Splits a single `operation` into an opcode and its operand. Returns [None] if the operation is empty.
+fn get_epd_operation(operation: &str) -> Option<(String, String)> {
+    let operation = operation.trim();
+    if operation.is_empty() {
+        return None;
+    }
 
-    Some(value.to_string())
+    let (opcode, operand) = operation.split_once(char::is_whitespace).unwrap_or((operation, ""));
+    Some((opcode.to_string(), operand.trim().trim_matches('"').to_string()))
 }
 
 /// Parses FEN's pieces and stores them into the `board`. Returns [Err] with the proper error message if `pieces` couldn't be parsed.
@@ -23,11 +23,7 @@ impl Move {
         let mut desired_promotion: Option<usize> = None;
 
         let original_text = text;
-        text = text.trim_matches('#');
-        text = text.trim_matches('+');
-        text = text.trim_matches('=');
-        text = text.trim_matches('?');
-        text = text.trim_matches('!');
+        text = text.trim_matches(|c| "#+=?!".contains(c));
 
         if text.contains('=') {
             let promotion = &text[text.len() - 2..];
@@ -302,4 +298,95 @@ impl Move {
 
         result.into_iter().collect()
     }
+
+    /// Converts move into the short notation (e4, Rc8, Qxb6, exd8=Q+), using the `board` (before the move is made) as context.
+    pub fn to_short_notation(self, board: &mut Board) -> String {
+        let from = self.get_from();
+        let to = self.get_to();
+        let piece = board.get_piece(from);
+        let flags = self.get_flags();
+
+        let mut result = String::new();
+        if flags == MoveFlags::SHORT_CASTLING {
+            result.push_str("O-O");
+        } else if flags == MoveFlags::LONG_CASTLING {
+            result.push_str("O-O-O");
+        } else {
+            let to_file = char::from(b'a' + (7 - to % 8) as u8);
+            let to_rank = char::from(b'1' + (to / 8) as u8);
+            let from_file = char::from(b'a' + (7 - from % 8) as u8);
+            let from_rank = char::from(b'1' + (from / 8) as u8);
+
+            if piece == PAWN {
+                if self.is_capture() || self.is_en_passant() {
+                    result.push(from_file);
+                }
+            } else {
+                result.push(text::piece_to_symbol(piece).unwrap());
+
+                let mut moves = [MaybeUninit::uninit(); engine::MAX_MOVES_COUNT];
+                let moves_count = board.get_all_moves(&mut moves, u64::MAX);
+
+                // Disambiguation is needed only when another legal move of the same piece type has the same destination
+                let mut ambiguous = false;
+                let mut same_file = false;
+                let mut same_rank = false;
+
+                for move_index in 0..moves_count {
+                    let r#move = unsafe { moves[move_index].assume_init() };
+                    if r#move == self || r#move.get_to() != to || board.get_piece(r#move.get_from()) != piece || !is_move_legal(r#move, board) {
+                        continue;
+                    }
+
+                    ambiguous = true;
+                    same_file |= r#move.get_from() % 8 == from % 8;
+                    same_rank |= r#move.get_from() / 8 == from / 8;
+                }
+
+                if ambiguous {
+                    if !same_file {
+                        result.push(from_file);
+                    } else if !same_rank {
+                        result.push(from_rank);
+                    } else {
+                        result.push(from_file);
+                        result.push(from_rank);
+                    }
+                }
+            }
+
+            if self.is_capture() || self.is_en_passant() {
+                result.push('x');
+            }
+
+            result.push(to_file);
+            result.push(to_rank);
+
+            if self.is_promotion() {
+                result.push('=');
+                result.push(text::piece_to_symbol(self.get_promotion_piece()).unwrap());
+            }
+        }
+
+        board.make_move(self);
+        if board.is_king_checked(board.stm) {
+            let mut moves = [MaybeUninit::uninit(); engine::MAX_MOVES_COUNT];
+            let moves_count = board.get_all_moves(&mut moves, u64::MAX);
+            let checkmate = (0..moves_count).all(|move_index| !is_move_legal(unsafe { moves[move_index].assume_init() }, board));
+
+            result.push(if checkmate { '#' } else { '+' });
+        }
+        board.undo_move(self);
+
+        result
+    }
+}
+
+/// Checks if pseudo-legal `r#move` doesn't leave the king in check, using the `board` as context.
+fn is_move_legal(r#move: Move, board: &mut Board) -> bool {
+    board.make_move(r#move);
+    let legal = !board.is_king_checked(board.stm ^ 1);
+    board.undo_move(r#move);
+
+    legal
 }
@@ -1,134 +1,174 @@
 use super::*;
 use crate::state::movescan::Move;
 use crate::state::representation::Board;
-use std::fs::File;
-use std::io::BufReader;
-use std::io::Lines;
+use std::io::BufRead;
 
-pub struct PGNLoader {
-    pub file_iterator: Lines<BufReader<File>>,
+const RESULTS: [&str; 4] = ["1-0", "0-1", "1/2-1/2", "*"];
+const MAX_LINE_LENGTH: usize = 80;
+const SUFFIX_ANNOTATIONS: [&str; 6] = ["!", "?", "!!", "??", "!?", "?!"];
+
+pub struct PGNLoader<R: BufRead> {
+    pub reader: R,
+    pub games_count: usize,
+    pending_line: Option<String>,
 }
 
 pub struct ParsedPGN {
     pub result: String,
     pub fen: Option<String>,
+    pub headers: Vec<(String, String)>,
     pub data: Vec<ParsedPGNMove>,
 }
 
 pub struct ParsedPGNMove {
     pub r#move: Move,
-    pub eval: f32,
+    pub eval: Option<f32>,
+    pub mate: Option<i16>,
+    pub nag: Option<u8>,
+    pub variation: Vec<Move>,
 }
 
-impl PGNLoader {
-    /// Constructs a new instance of [PGNLoader] with the specified `file_iterator`, which will be used to read input PGN file.
-    pub fn new(file_iterator: Lines<BufReader<File>>) -> PGNLoader {
-        PGNLoader { file_iterator }
+impl<R: BufRead> PGNLoader<R> {
+    /// Constructs a new instance of [PGNLoader] with the specified `reader`, which will be used to read input PGN file. Lines which aren't
+    /// valid UTF-8 (like names encoded in Latin-1) are decoded lossily, so they never stop the loader.
+    pub fn new(reader: R) -> PGNLoader<R> {
+        PGNLoader { reader, games_count: 0, pending_line: None }
     }
 
-    /// Parses a single `pgn` and returns [Some] if it has been done with success, otherwise [Err].
+    /// Reads the next line from the input file, without a trailing newline and a byte order mark. Returns [None] if there are no more lines.
+    fn read_line(&mut self) -> Option<String> {
+        if let Some(line) = self.pending_line.take() {
+            return Some(line);
+        }
+
+        let mut buffer = Vec::new();
+        match self.reader.read_until(b'\n', &mut buffer) {
+            Ok(0) | Err(_) => None,
+            Ok(_) => {
+                let line = String::from_utf8_lossy(&buffer);
+                Some(line.trim_start_matches('\u{feff}').trim_end().to_string())
+            }
+        }
+    }
+
+    /// Parses a single `pgn` (tag pairs separated by newlines, followed by movetext) and returns [Some] if it has been done with success, otherwise [Err].
     fn parse(&self, pgn: String) -> Result<ParsedPGN, String> {
         let mut result = None;
         let mut fen = None;
-        let mut moves = Vec::new();
+        let mut headers = Vec::new();
+        let mut movetext = String::new();
 
         for line in pgn.lines() {
-            if line.is_empty() {
-                continue;
-            } else if line.starts_with('[') {
-                let name_start_index = match line.find(char::is_alphabetic) {
-                    Some(value) => value,
-                    None => return Err(format!("Invalid property: line={}", line)),
-                };
-
-                let name_end_index = match line[name_start_index..].find(' ') {
-                    Some(value) => name_start_index + value,
-                    None => return Err(format!("Invalid property: line={}", line)),
-                };
-
-                let value_start_index = match line.find('\"') {
-                    Some(value) => value + 1,
-                    None => return Err(format!("Invalid property: line={}", line)),
-                };
-
-                let value_end_index = match line[value_start_index..].find('\"') {
-                    Some(value) => value_start_index + value,
-                    None => return Err(format!("Invalid property: line={}", line)),
-                };
-
-                let name = line[name_start_index..name_end_index].to_string();
-                let value = line[value_start_index..value_end_index].to_string();
-
+            if is_property(line) && movetext.is_empty() {
+                let (name, value) = parse_property(line)?;
                 match name.as_str() {
-                    "Result" => result = Some(value),
-                    "FEN" => fen = Some(value),
+                    "Result" => result = Some(value.clone()),
+                    "FEN" => fen = Some(value.clone()),
                     _ => {}
                 }
-            } else if line.starts_with('1') {
-                let mut board = match fen.clone() {
-                    Some(value) => {
-                        let fen_result = Board::new_from_fen(&value);
-
-                        match fen_result {
-                            Ok(board) => board,
-                            Err(error) => return Err(format!("Invalid initial FEN position: {}", error)),
-                        }
-                    }
-                    None => Board::new_initial_position(),
-                };
 
-                let mut comment = false;
-                let mut pgn_move = None;
-                let mut pgn_eval: Option<&str> = None;
+                headers.push((name, value));
+            } else {
+                movetext.push_str(line);
+                movetext.push('\n');
+            }
+        }
 
-                for token in line.split_ascii_whitespace() {
-                    if token.ends_with('}') {
-                        comment = false;
-                        continue;
-                    }
+        let mut board = match &fen {
+            Some(value) => match Board::new_from_fen(value) {
+                Ok(board) => board,
+                Err(error) => return Err(format!("Invalid initial FEN position: {}", error)),
+            },
+            None => Board::new_initial_position(),
+        };
 
-                    if token.as_bytes()[0].is_ascii_digit() {
-                        continue;
-                    }
+        let mut moves: Vec<ParsedPGNMove> = Vec::new();
+        let mut termination = None;
+        let mut variation_depth = 0;
+        let mut chars = movetext.char_indices().peekable();
+
+        while let Some((index, char)) = chars.next() {
+            match char {
+                '{' => {
+                    let length = match movetext[index + 1..].find('}') {
+                        Some(value) => value,
+                        None => return Err("Unterminated comment".to_string()),
+                    };
 
-                    if let Some(r#move) = pgn_move {
-                        if let Some(eval) = pgn_eval {
-                            let mut eval = if eval.starts_with("+M") {
-                                100.0
-                            } else if eval.starts_with("-M") {
-                                -100.0
-                            } else {
-                                eval.parse::<f32>().unwrap()
-                            };
-
-                            if board.stm == BLACK {
-                                eval = -eval;
+                    // Only the first score after the move played in the game is taken, the ones in variations are ignored
+                    if variation_depth == 0 {
+                        if let Some(last_move) = moves.last_mut() {
+                            if last_move.eval.is_none() {
+                                if let Some((eval, mate)) = parse_eval(&movetext[index + 1..index + 1 + length], board.stm) {
+                                    last_move.eval = Some(eval);
+                                    last_move.mate = mate;
+                                }
                             }
+                        }
+                    }
 
-                            moves.push(ParsedPGNMove::new(r#move, eval));
-                            board.make_move(r#move);
+                    while chars.next_if(|(next_index, _)| *next_index <= index + 1 + length).is_some() {}
+                }
+                ';' => while chars.next_if(|(_, next_char)| *next_char != '\n').is_some() {},
+                '(' => variation_depth += 1,
+                ')' => {
+                    if variation_depth == 0 {
+                        return Err("Unbalanced variation".to_string());
+                    }
 
-                            pgn_move = None;
-                            pgn_eval = None;
-                        }
+                    variation_depth -= 1;
+                }
+                '$' => {
+                    let mut nag = String::new();
+                    while let Some((_, next_char)) = chars.next_if(|(_, next_char)| next_char.is_ascii_digit()) {
+                        nag.push(next_char);
                     }
 
-                    if let Some(token) = token.strip_prefix('{') {
-                        pgn_eval = Some(token.split('/').collect::<Vec<&str>>()[0]);
-                        comment = true;
-                        continue;
+                    if variation_depth == 0 {
+                        if let Some(last_move) = moves.last_mut() {
+                            last_move.nag = nag.parse().ok();
+                        }
+                    }
+                }
+                _ if char.is_whitespace() => {}
+                _ => {
+                    let mut end_index = index + char.len_utf8();
+                    while let Some((next_index, _)) = chars.next_if(|(_, next_char)| !next_char.is_whitespace() && !"{};()$".contains(*next_char)) {
+                        end_index = next_index + 1;
                     }
 
-                    if comment {
+                    if variation_depth > 0 {
                         continue;
                     }
 
-                    if token == "*" || token == "h1h1" {
+                    let token = &movetext[index..end_index];
+                    if RESULTS.contains(&token) || token == "h1h1" {
+                        termination = Some(token.to_string());
                         break;
                     }
 
-                    pgn_move = match Move::from_short_notation(token, &mut board) {
-                        Ok(r#move) => Some(r#move),
+                    // Move numbers can be glued with the move itself (1.e4, 12...Nf6)
+                    let token = match token.rfind('.') {
+                        Some(index) => &token[index + 1..],
+                        None => token,
+                    };
+
+                    if token.is_empty() {
+                        continue;
+                    }
+
+                    // Suffix annotations (like e4!?) are equivalent to the first six NAGs
+                    let suffix = token.trim_start_matches(|c| c != '!' && c != '?');
+                    let nag = SUFFIX_ANNOTATIONS.iter().position(|annotation| *annotation == suffix).map(|index| index as u8 + 1);
+
+                    match Move::from_short_notation(token, &mut board) {
+                        Ok(r#move) => {
+                            let mut parsed_move = ParsedPGNMove::new(r#move, None);
+                            parsed_move.nag = nag;
+
+                            moves.push(parsed_move);
+                            board.make_move(r#move);
+                        }
                         Err(error) => return Err(format!("Invalid move: {}", error)),
                     };
                 }
@@ -137,23 +177,32 @@ impl PGNLoader {
 
         let result = match result {
             Some(value) => value,
-            None => return Err("No Result property".to_string()),
+            None => match termination {
+                Some(value) => {
+                    headers.push(("Result".to_string(), value.clone()));
+                    value
+                }
+                None => return Err("No Result property".to_string()),
+            },
         };
 
-        Ok(ParsedPGN::new(result, fen, moves))
+        Ok(ParsedPGN::new(result, fen, headers, moves))
     }
 }
 
-impl Iterator for PGNLoader {
+impl<R: BufRead> Iterator for PGNLoader<R> {
     type Item = Result<ParsedPGN, String>;
 
-    /// Performs the next iteration by parsing the following PGN from the input file. If there are none left, returns [None].
+    /// Performs the next iteration by parsing the following PGN from the input file. If there are none left, returns [None]. Games are separated
+    /// by the first tag pair after the movetext, and every error is reported with the game number, so it can be skipped without aborting the whole file.
     fn next(&mut self) -> Option<Self::Item> {
         let mut pgn = String::new();
+        let mut movetext_started = false;
 
-        while let Some(Ok(line)) = self.file_iterator.next() {
-            if line.starts_with("[Event") && !pgn.is_empty() {
-                break;
+        while let Some(line) = self.read_line() {
+            // Lines starting with % are escaped and have to be ignored
+            if line.starts_with('%') {
+                continue;
             }
 
             let trimmed_line = line.trim();
@@ -161,17 +210,23 @@ impl Iterator for PGNLoader {
                 continue;
             }
 
-            pgn.push_str(trimmed_line);
-
-            if line.starts_with('[') {
-                pgn.push('\n');
+            // Tag pair after the movetext starts the next game, commands in comments spanning multiple lines (like [%eval 0.35]) don't
+            if is_property(trimmed_line) {
+                if movetext_started {
+                    self.pending_line = Some(line);
+                    break;
+                }
             } else {
-                pgn.push(' ');
+                movetext_started = true;
             }
+
+            pgn.push_str(trimmed_line);
+            pgn.push('\n');
         }
 
         if !pgn.is_empty() {
-            return Some(self.parse(pgn));
+            self.games_count += 1;
+            return Some(self.parse(pgn).map_err(|error| format!("Invalid game {}: {}", self.games_count, error)));
         }
 
         None
@@ -179,14 +234,167 @@ impl Iterator for PGNLoader {
 }
 
 impl ParsedPGN {
-    /// Constructs a new instance of [ParsedPGN] with stored `result`, `fen` and `moves`.
-    pub fn new(result: String, fen: Option<String>, moves: Vec<ParsedPGNMove>) -> ParsedPGN {
-        ParsedPGN { result, fen, data: moves }
+    /// Constructs a new instance of [ParsedPGN] with stored `result`, `fen`, `headers` and `moves`.
+    pub fn new(result: String, fen: Option<String>, headers: Vec<(String, String)>, moves: Vec<ParsedPGNMove>) -> ParsedPGN {
+        ParsedPGN { result, fen, headers, data: moves }
+    }
+
+    /// Converts the game into PGN: all tag pairs in the original order, followed by the movetext in the short notation, with annotations, scores
+    /// saved as `[%eval]` comments (from white's point of view, `#N` if moves to mate are known) and variations. Returns [Err] with a proper error
+    /// message if the initial FEN position is invalid.
+    pub fn to_pgn(&self) -> Result<String, String> {
+        let mut board = match &self.fen {
+            Some(value) => Board::new_from_fen(value)?,
+            None => Board::new_initial_position(),
+        };
+
+        let mut tokens = Vec::new();
+        let mut interrupted = true;
+
+        for data in &self.data {
+            // Black move needs its number too if it's not directly after white one (at the beginning, or after comment or variation)
+            let mut token = if board.stm == WHITE {
+                format!("{}. ", board.fullmove_number)
+            } else if interrupted {
+                format!("{}... ", board.fullmove_number)
+            } else {
+                String::new()
+            };
+            interrupted = data.eval.is_some() || data.mate.is_some() || !data.variation.is_empty();
+
+            token.push_str(&data.r#move.to_short_notation(&mut board));
+            match data.nag {
+                Some(nag @ 1..=6) => token.push_str(SUFFIX_ANNOTATIONS[nag as usize - 1]),
+                Some(nag) => token.push_str(&format!(" ${}", nag)),
+                None => {}
+            }
+            tokens.push(token);
+
+            if let Some(mate) = data.mate {
+                tokens.push(format!("{{[%eval #{}]}}", mate));
+            } else if let Some(eval) = data.eval {
+                // Adding zero turns negative zero into the positive one, so it is not printed with a minus sign
+                tokens.push(format!("{{[%eval {:.2}]}}", eval + 0.0));
+            }
+
+            if !data.variation.is_empty() {
+                let mut variation_board = board.clone();
+                let mut variation_tokens = Vec::new();
+
+                for (variation_index, r#move) in data.variation.iter().enumerate() {
+                    let mut variation_token = if variation_board.stm == WHITE {
+                        format!("{}. ", variation_board.fullmove_number)
+                    } else if variation_index == 0 {
+                        format!("{}... ", variation_board.fullmove_number)
+                    } else {
+                        String::new()
+                    };
+
+                    variation_token.push_str(&r#move.to_short_notation(&mut variation_board));
+                    variation_tokens.push(variation_token);
+                    variation_board.make_move(*r#move);
+                }
+
+                variation_tokens[0].insert(0, '(');
+                variation_tokens.last_mut().unwrap().push(')');
+                tokens.extend(variation_tokens);
+            }
+
+            board.make_move(data.r#move);
+        }
+        tokens.push(self.result.clone());
+
+        let mut pgn = String::new();
+        for (name, value) in &self.headers {
+            pgn.push_str(&format!("[{} \"{}\"]\n", name, value.replace('\\', "\\\\").replace('\"', "\\\"")));
+        }
+        pgn.push('\n');
+
+        let mut line_length = 0;
+        for token in tokens {
+            if line_length > 0 && line_length + token.len() + 1 > MAX_LINE_LENGTH {
+                pgn.push('\n');
+                line_length = 0;
+            } else if line_length > 0 {
+                pgn.push(' ');
+                line_length += 1;
+            }
+
+            pgn.push_str(&token);
+            line_length += token.len();
+        }
+        pgn.push_str("\n\n");
+
+        Ok(pgn)
     }
 }
 
 impl ParsedPGNMove {
-    pub fn new(r#move: Move, eval: f32) -> Self {
-        Self { r#move, eval }
+    /// Constructs a new instance of [ParsedPGNMove] with stored `r#move` and `eval`, without moves to mate, annotation and variation.
+    pub fn new(r#move: Move, eval: Option<f32>) -> Self {
+        Self { r#move, eval, mate: None, nag: None, variation: Vec::new() }
+    }
+}
+
+/// Checks if `line` looks like a tag pair (like `[Result "1-0"]`).
+fn is_property(line: &str) -> bool {
+    line.starts_with('[') && line.ends_with(']') && line[1..].starts_with(char::is_alphanumeric)
+}
+
+/// Parses tag pair in `line` (like `[Result "1-0"]`) into name and value, with escaped quotes and backslashes restored.
+/// Returns [Err] with the proper error message if `line` couldn't be parsed correctly.
+fn parse_property(line: &str) -> Result<(String, String), String> {
+    let name_start_index = match line.find(char::is_alphanumeric) {
+        Some(value) => value,
+        None => return Err(format!("Invalid property: line={}", line)),
+    };
+
+    let name_end_index = match line[name_start_index..].find(char::is_whitespace) {
+        Some(value) => name_start_index + value,
+        None => return Err(format!("Invalid property: line={}", line)),
+    };
+
+    let value_start_index = match line.find('\"') {
+        Some(value) => value + 1,
+        None => return Err(format!("Invalid property: line={}", line)),
+    };
+
+    let value_end_index = match line.rfind('\"') {
+        Some(value) if value >= value_start_index => value,
+        _ => return Err(format!("Invalid property: line={}", line)),
+    };
+
+    let name = line[name_start_index..name_end_index].to_string();
+    let value = line[value_start_index..value_end_index].replace("\\\"", "\"").replace("\\\\", "\\");
+
+    Ok((name, value))
+}
+
+/// Parses score stored in `comment`, either as `[%eval 0.35]`/`[%eval #-3]` (from white's point of view) or as `+0.35/12 0.5s`/`-M5/20` (from the point of
+/// view of the side which made the move, so `stm` after it is needed). Mates are returned as 100.0/-100.0 together with the number of moves to mate.
+/// Returns [None] if there is no score in the comment.
+fn parse_eval(comment: &str, stm: usize) -> Option<(f32, Option<i16>)> {
+    if let Some(index) = comment.find("[%eval ") {
+        let value = comment[index + 7..].split([' ', ']']).next()?;
+        return match value.strip_prefix('#') {
+            Some(mate) => {
+                let mate = mate.parse::<i16>().ok()?;
+                Some((if value.starts_with("#-") { -100.0 } else { 100.0 }, Some(mate)))
+            }
+            None => Some((value.parse::<f32>().ok()?, None)),
+        };
     }
+
+    let token = comment.split_ascii_whitespace().next()?;
+    let (value, _) = token.split_once('/')?;
+
+    let (eval, mate) = if let Some(mate) = value.strip_prefix("+M").or_else(|| value.strip_prefix('M')) {
+        (100.0, mate.parse::<i16>().ok())
+    } else if let Some(mate) = value.strip_prefix("-M") {
+        (-100.0, mate.parse::<i16>().ok().map(|mate| -mate))
+    } else {
+        (value.parse::<f32>().ok()?, None)
+    };
+
+    Some(if stm == WHITE { (-eval, mate.map(|mate| -mate)) } else { (eval, mate) })
 }
@@ -0,0 +1,350 @@
+use super::WdlResult;
+use crate::state::representation::Board;
+use crate::state::*;
+use crate::utils::bithelpers::BitHelpers;
+use std::sync::OnceLock;
+
+const STRONG_TO_MOVE: usize = 0;
+const WEAK_TO_MOVE: usize = 1;
+
+const UNKNOWN: u8 = 0;
+const INVALID: u8 = 0xfd;
+const DRAW: u8 = 0xfe;
+const WIN: u8 = 0xff;
+
+const KPK_PIECES: [usize; 1] = [PAWN];
+const KRK_PIECES: [usize; 1] = [ROOK];
+const KQK_PIECES: [usize; 1] = [QUEEN];
+const KBNK_PIECES: [usize; 2] = [BISHOP, KNIGHT];
+
+static KPK: OnceLock<Bitbase> = OnceLock::new();
+static KRK: OnceLock<Bitbase> = OnceLock::new();
+static KQK: OnceLock<Bitbase> = OnceLock::new();
+static KBNK: OnceLock<Bitbase> = OnceLock::new();
+
+pub struct Bitbase {
+    pub data: Vec<u64>,
+}
+
+#[derive(Clone, Copy)]
+struct Position {
+    stm: usize,
+    strong_king: usize,
+    weak_king: usize,
+    pieces: [usize; 2],
+}
+
+/// Generates KQK, KRK and KPK bitbases (in this order, as promotions in KPK are resolved using the first two). KBNK, which is much bigger, is generated
+/// separately by [init_kbnk].
+pub fn init() {
+    KQK.get_or_init(|| generate(&KQK_PIECES));
+    KRK.get_or_init(|| generate(&KRK_PIECES));
+    KPK.get_or_init(|| generate(&KPK_PIECES));
+}
+
+/// Generates KBNK bitbase. It takes a few seconds, so it's not a part of [init] and can be called in a separate thread - until it's finished, positions
+/// with this material are not covered.
+pub fn init_kbnk() {
+    KBNK.get_or_init(|| generate(&KBNK_PIECES));
+}
+
+/// Gets WDL result of the position stored in `board` (from the side to move perspective), or [None] if the material is not covered by bitbases or
+/// the bitbase is not ready yet. The fifty-move rule is not considered, castling rights and en passant are irrelevant for the supported material.
+pub fn get_wdl(board: &Board) -> Option<WdlResult> {
+    let pieces_count = board.get_pieces_count();
+    if !(3..=4).contains(&pieces_count) {
+        return None;
+    }
+
+    let strong = if board.occupancy[BLACK] == board.pieces[BLACK][KING] {
+        WHITE
+    } else if board.occupancy[WHITE] == board.pieces[WHITE][KING] {
+        BLACK
+    } else {
+        return None;
+    };
+
+    let pieces = &board.pieces[strong];
+    let (bitbase, squares) = if pieces_count == 3 {
+        let (bitbase, bb) = if pieces[PAWN] != 0 {
+            (KPK.get()?, pieces[PAWN])
+        } else if pieces[ROOK] != 0 {
+            (KRK.get()?, pieces[ROOK])
+        } else if pieces[QUEEN] != 0 {
+            (KQK.get()?, pieces[QUEEN])
+        } else {
+            return None;
+        };
+
+        (bitbase, [bb.bit_scan(), 0])
+    } else if pieces[BISHOP] != 0 && pieces[KNIGHT] != 0 {
+        (KBNK.get()?, [pieces[BISHOP].bit_scan(), pieces[KNIGHT].bit_scan()])
+    } else {
+        return None;
+    };
+
+    // Bitbases are generated with the strong side as white, so the board has to be flipped vertically if it's the other way
+    let flip = if strong == WHITE { 0 } else { 56 };
+    let position = Position {
+        stm: if board.stm == strong { STRONG_TO_MOVE } else { WEAK_TO_MOVE },
+        strong_king: board.pieces[strong][KING].bit_scan() ^ flip,
+        weak_king: board.pieces[strong ^ 1][KING].bit_scan() ^ flip,
+        pieces: [squares[0] ^ flip, if pieces_count == 4 { squares[1] ^ flip } else { 0 }],
+    };
+
+    let win = bitbase.get(position.encode(pieces_count as usize - 2));
+    Some(match (win, position.stm) {
+        (false, _) => WdlResult::Draw,
+        (true, STRONG_TO_MOVE) => WdlResult::Win,
+        (true, _) => WdlResult::Loss,
+    })
+}
+
+impl Bitbase {
+    /// Checks if the position with the specified `index` is won by the strong side.
+    pub fn get(&self, index: usize) -> bool {
+        (self.data[index / 64] & (1 << (index % 64))) != 0
+    }
+}
+
+impl Position {
+    /// Decodes a position from `index`, using `pieces_count` strong side pieces (not counting the king).
+    fn decode(mut index: usize, pieces_count: usize) -> Self {
+        let mut pieces = [0; 2];
+        for square in pieces.iter_mut().take(pieces_count).rev() {
+            *square = index & 63;
+            index >>= 6;
+        }
+
+        Self { stm: index >> 12, strong_king: (index >> 6) & 63, weak_king: index & 63, pieces }
+    }
+
+    /// Encodes the position into an index, using `pieces_count` strong side pieces (not counting the king).
+    fn encode(&self, pieces_count: usize) -> usize {
+        let mut index = (self.stm << 12) | (self.strong_king << 6) | self.weak_king;
+        for square in self.pieces.iter().take(pieces_count) {
+            index = (index << 6) | square;
+        }
+
+        index
+    }
+
+    /// Gets occupancy of the strong side pieces (excluding the king).
+    fn get_pieces_bb(&self, pieces: &[usize]) -> u64 {
+        self.pieces.iter().take(pieces.len()).fold(0, |bb, square| bb | (1 << square))
+    }
+
+    /// Gets all squares attacked by the strong side (including the king), considering `occupancy_bb`.
+    fn get_strong_attacks(&self, pieces: &[usize], occupancy_bb: u64) -> u64 {
+        let mut attacks = movegen::get_king_moves(self.strong_king);
+        for (piece, &square) in pieces.iter().zip(self.pieces.iter()) {
+            attacks |= match *piece {
+                PAWN => {
+                    let pawn_bb = 1u64 << square;
+                    ((pawn_bb & !FILE_A_BB) << 9) | ((pawn_bb & !FILE_H_BB) << 7)
+                }
+                KNIGHT => movegen::get_knight_moves(square),
+                BISHOP => movegen::get_bishop_moves(occupancy_bb, square),
+                ROOK => movegen::get_rook_moves(occupancy_bb, square),
+                QUEEN => movegen::get_queen_moves(occupancy_bb, square),
+                _ => 0,
+            };
+        }
+
+        attacks
+    }
+
+    /// Checks if the position is legal: all squares are different, kings are not adjacent, pawns are not on the first and last rank,
+    /// and the weak king is not in check when the strong side is to move.
+    fn is_valid(&self, pieces: &[usize]) -> bool {
+        let pieces_bb = self.get_pieces_bb(pieces);
+        let weak_king_bb = 1u64 << self.weak_king;
+        let strong_king_bb = 1u64 << self.strong_king;
+
+        if pieces_bb.bit_count() != pieces.len() || (pieces_bb & (weak_king_bb | strong_king_bb)) != 0 || self.weak_king == self.strong_king {
+            return false;
+        }
+
+        if (movegen::get_king_moves(self.strong_king) & weak_king_bb) != 0 {
+            return false;
+        }
+
+        for (piece, &square) in pieces.iter().zip(self.pieces.iter()) {
+            if *piece == PAWN && ((1u64 << square) & (RANK_1_BB | RANK_8_BB)) != 0 {
+                return false;
+            }
+        }
+
+        if self.stm == STRONG_TO_MOVE {
+            let occupancy_bb = pieces_bb | weak_king_bb | strong_king_bb;
+            if (self.get_strong_attacks(pieces, occupancy_bb) & weak_king_bb) != 0 {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// Gets initial state of the position with the weak side to move: [DRAW] if any capture is possible or the weak king is stalemated,
+    /// [WIN] if it's mated, otherwise number of the legal moves.
+    fn get_weak_state(&self, pieces: &[usize]) -> u8 {
+        let pieces_bb = self.get_pieces_bb(pieces);
+        let occupancy_bb = pieces_bb | (1u64 << self.strong_king);
+        let attacks = self.get_strong_attacks(pieces, occupancy_bb);
+        let moves = movegen::get_king_moves(self.weak_king) & !attacks;
+
+        if (moves & pieces_bb) != 0 {
+            DRAW
+        } else if moves == 0 {
+            if (attacks & (1u64 << self.weak_king)) != 0 {
+                WIN
+            } else {
+                DRAW
+            }
+        } else {
+            moves.bit_count() as u8
+        }
+    }
+
+    /// Checks if the position with the strong side to move is won by an immediate promotion to a queen or a rook.
+    fn is_promotion_win(&self, pieces: &[usize]) -> bool {
+        if pieces[0] != PAWN || (self.pieces[0] >> 3) != 6 {
+            return false;
+        }
+
+        let target = self.pieces[0] + 8;
+        if target == self.strong_king || target == self.weak_king {
+            return false;
+        }
+
+        let promoted = Position { stm: WEAK_TO_MOVE, pieces: [target, 0], ..*self };
+        let index = promoted.encode(1);
+
+        [&KQK, &KRK].iter().any(|bitbase| bitbase.get().map(|bitbase| bitbase.get(index)).unwrap_or(false))
+    }
+
+    /// Gets all positions with the strong side to move, from which the strong side could reach this one (it's not checked if they are legal).
+    fn get_strong_unmoves(&self, pieces: &[usize], result: &mut Vec<Position>) {
+        let occupancy_bb = self.get_pieces_bb(pieces) | (1u64 << self.strong_king) | (1u64 << self.weak_king);
+
+        let mut king_bb = movegen::get_king_moves(self.strong_king) & !occupancy_bb;
+        while king_bb != 0 {
+            let square = king_bb.bit_scan();
+            king_bb = king_bb.pop_lsb();
+
+            result.push(Position { stm: STRONG_TO_MOVE, strong_king: square, ..*self });
+        }
+
+        for (index, piece) in pieces.iter().enumerate() {
+            let from = self.pieces[index];
+            let mut targets_bb = match *piece {
+                PAWN => {
+                    let mut targets_bb = 0;
+                    if (from >> 3) > 1 && ((1u64 << (from - 8)) & occupancy_bb) == 0 {
+                        targets_bb |= 1u64 << (from - 8);
+
+                        if (from >> 3) == 3 && ((1u64 << (from - 16)) & occupancy_bb) == 0 {
+                            targets_bb |= 1u64 << (from - 16);
+                        }
+                    }
+
+                    targets_bb
+                }
+                KNIGHT => movegen::get_knight_moves(from),
+                BISHOP => movegen::get_bishop_moves(occupancy_bb, from),
+                ROOK => movegen::get_rook_moves(occupancy_bb, from),
+                QUEEN => movegen::get_queen_moves(occupancy_bb, from),
+                _ => 0,
+            } & !occupancy_bb;
+
+            while targets_bb != 0 {
+                let square = targets_bb.bit_scan();
+                targets_bb = targets_bb.pop_lsb();
+
+                let mut position = Position { stm: STRONG_TO_MOVE, ..*self };
+                position.pieces[index] = square;
+                result.push(position);
+            }
+        }
+    }
+
+    /// Gets all positions with the weak side to move, from which the weak king could reach this one (it's not checked if they are legal).
+    fn get_weak_unmoves(&self, pieces: &[usize], result: &mut Vec<Position>) {
+        let occupancy_bb = self.get_pieces_bb(pieces) | (1u64 << self.strong_king);
+        let mut king_bb = movegen::get_king_moves(self.weak_king) & !occupancy_bb;
+
+        while king_bb != 0 {
+            let square = king_bb.bit_scan();
+            king_bb = king_bb.pop_lsb();
+
+            result.push(Position { stm: WEAK_TO_MOVE, weak_king: square, ..*self });
+        }
+    }
+}
+
+/// Generates a bitbase for the strong side (always white) with king and `pieces` against the lone king, using retrograde analysis. Every position where
+/// the weak side is mated (or the strong side can promote into a won position) is a starting point, then the moves are unmade: a position with the strong
+/// side to move is won if any move leads to a won position, a position with the weak side to move is won if all moves lead to won positions (which is
+/// tracked by counting down the number of the legal moves). Everything which is never reached this way is a draw.
+fn generate(pieces: &[usize]) -> Bitbase {
+    let size = 1 << (13 + 6 * pieces.len());
+    let mut states = vec![INVALID; size];
+    let mut queue = Vec::new();
+
+    for (index, state) in states.iter_mut().enumerate() {
+        let position = Position::decode(index, pieces.len());
+        if !position.is_valid(pieces) {
+            continue;
+        }
+
+        *state = match position.stm {
+            STRONG_TO_MOVE if position.is_promotion_win(pieces) => WIN,
+            STRONG_TO_MOVE => UNKNOWN,
+            _ => position.get_weak_state(pieces),
+        };
+
+        if *state == WIN {
+            queue.push(index as u32);
+        }
+    }
+
+    let mut unmoves = Vec::new();
+    while let Some(index) = queue.pop() {
+        let position = Position::decode(index as usize, pieces.len());
+
+        unmoves.clear();
+        match position.stm {
+            STRONG_TO_MOVE => position.get_weak_unmoves(pieces, &mut unmoves),
+            _ => position.get_strong_unmoves(pieces, &mut unmoves),
+        };
+
+        for unmove in &unmoves {
+            let unmove_index = unmove.encode(pieces.len());
+            let state = &mut states[unmove_index];
+
+            match position.stm {
+                STRONG_TO_MOVE if (1..=8).contains(state) => {
+                    *state -= 1;
+                    if *state == 0 {
+                        *state = WIN;
+                        queue.push(unmove_index as u32);
+                    }
+                }
+                WEAK_TO_MOVE if *state == UNKNOWN => {
+                    *state = WIN;
+                    queue.push(unmove_index as u32);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let mut data = vec![0u64; size / 64];
+    for (index, state) in states.iter().enumerate() {
+        if *state == WIN {
+            data[index / 64] |= 1 << (index % 64);
+        }
+    }
+
+    Bitbase { data }
+}
@@ -3,12 +3,15 @@ use crate::state::movescan::Move;
 use crate::state::representation::Board;
 use std::cmp;
 
+pub mod bitbase;
 pub mod syzygy;
 
 #[derive(PartialEq, Eq, Debug)]
 pub enum WdlResult {
     Win,
+    CursedWin,
     Draw,
+    BlessedLoss,
     Loss,
 }
 
@@ -25,21 +28,64 @@ impl WdlDtzResult {
     }
 }
 
-/// Checks if there's a tablebase move (only Syzygy supported for now) and returns it as [Some], otherwise [None].
-pub fn get_tablebase_move(board: &Board, probe_limit: u32) -> Option<(Move, i16)> {
+/// Gets a list of root moves which preserve the tablebase result (only Syzygy supported for now) together with the expected score, or [None] if the position
+/// is not covered by tablebases. If `rule50` is set, cursed wins and blessed losses are ranked between draws and real wins/losses, otherwise they are treated
+/// as regular ones. Winning moves are additionally restricted to the ones with the smallest DTZ, and losing moves to the ones with the highest DTZ, so
+/// the search makes progress while still being able to choose between equivalent moves.
+pub fn get_root_moves(board: &Board, probe_limit: u32, rule50: bool) -> Option<(Vec<Move>, i16)> {
     if board.get_pieces_count() > cmp::min(probe_limit as u8, syzygy::probe::get_max_pieces_count()) {
         return None;
     }
 
-    if let Some(result) = syzygy::probe::get_root_wdl_dtz(board) {
-        let score = match result.wdl {
-            WdlResult::Win => TBMATE_SCORE,
-            WdlResult::Draw => 0,
-            WdlResult::Loss => -TBMATE_SCORE,
-        };
+    let root_moves = syzygy::probe::get_root_moves(board)?;
+    let best_rank = root_moves.iter().map(|result| get_wdl_rank(&result.wdl, rule50)).max()?;
+    let best_results = root_moves.iter().filter(|result| get_wdl_rank(&result.wdl, rule50) == best_rank).collect::<Vec<&WdlDtzResult>>();
 
-        return Some((result.r#move, score));
+    let best_dtz = match best_rank.cmp(&0) {
+        cmp::Ordering::Greater => best_results.iter().map(|result| result.dtz).min(),
+        cmp::Ordering::Less => best_results.iter().map(|result| result.dtz).max(),
+        cmp::Ordering::Equal => None,
+    };
+
+    let moves = best_results.iter().filter(|result| best_dtz.is_none() || Some(result.dtz) == best_dtz).map(|result| result.r#move).collect();
+    let score = get_wdl_score(&best_results[0].wdl, 0, rule50);
+
+    Some((moves, score))
+}
+
+/// Gets score of the `wdl` result found at `ply`. If `rule50` is set, cursed wins and blessed losses are scored only slightly differently than draws,
+/// otherwise they are treated as regular wins and losses.
+pub fn get_wdl_score(wdl: &WdlResult, ply: u16, rule50: bool) -> i16 {
+    match wdl {
+        WdlResult::Win => TBMATE_SCORE - (ply as i16),
+        WdlResult::CursedWin if !rule50 => TBMATE_SCORE - (ply as i16),
+        WdlResult::CursedWin => DRAW_SCORE + TBCURSED_SCORE,
+        WdlResult::Draw => DRAW_SCORE,
+        WdlResult::BlessedLoss if !rule50 => -TBMATE_SCORE + (ply as i16),
+        WdlResult::BlessedLoss => DRAW_SCORE - TBCURSED_SCORE,
+        WdlResult::Loss => -TBMATE_SCORE + (ply as i16),
     }
+}
 
-    None
+/// Gets rank of the `wdl` result, used to compare root moves (the higher, the better).
+fn get_wdl_rank(wdl: &WdlResult, rule50: bool) -> i8 {
+    match wdl {
+        WdlResult::Win => 2,
+        WdlResult::CursedWin => {
+            if rule50 {
+                1
+            } else {
+                2
+            }
+        }
+        WdlResult::Draw => 0,
+        WdlResult::BlessedLoss => {
+            if rule50 {
+                -1
+            } else {
+                -2
+            }
+        }
+        WdlResult::Loss => -2,
+    }
 }
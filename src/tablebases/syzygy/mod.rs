@@ -1,3 +1,2 @@
-#![allow(warnings)]
-pub mod bindings;
 pub mod probe;
+pub mod table;
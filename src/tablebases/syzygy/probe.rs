@@ -1,123 +1,448 @@
-use super::bindings::*;
+use super::table::*;
 use crate::engine;
 use crate::state::movescan::Move;
-use crate::state::movescan::MoveFlags;
 use crate::state::representation::Board;
 use crate::state::*;
 use crate::tablebases::WdlDtzResult;
 use crate::tablebases::WdlResult;
-use std::ffi::CString;
+use std::cmp;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
 use std::mem::MaybeUninit;
-use std::ptr;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::RwLock;
 
-/// Loads Syzygy tablebases stored in `syzygy_path` location.
+const WDL_TO_DTZ: [i32; 5] = [-1, -101, 0, 101, 1];
+
+static TABLEBASES: RwLock<Option<Arc<Tablebases>>> = RwLock::new(None);
+
+struct Tablebases {
+    entries: HashMap<u64, Arc<TableEntry>>,
+    max_pieces_count: u8,
+}
+
+struct TableEntry {
+    info: TableInfo,
+    wdl: Table,
+    dtz: Option<Table>,
+}
+
+impl TableEntry {
+    /// Gets a table of the specified `kind`. Returns [None] if the table is not available.
+    fn get_table(&self, kind: TableKind) -> Option<&Table> {
+        match kind {
+            TableKind::Wdl => Some(&self.wdl),
+            TableKind::Dtz => self.dtz.as_ref(),
+        }
+    }
+}
+
+/// Loads Syzygy tablebases stored in `syzygy_path` location (multiple directories are separated by the platform-specific separator, `;` on Windows
+/// and `:` elsewhere). Files are memory-mapped and their headers are decoded here, so the data is read by the operating system on demand, without
+/// loading whole tables into memory or stalling the first probe.
 pub fn init(syzygy_path: &str) {
-    #[cfg(feature = "syzygy")]
-    unsafe {
-        tb_init(CString::new(syzygy_path).unwrap().as_ptr());
+    let mut entries = HashMap::new();
+    let mut max_pieces_count = 0;
+
+    if !syzygy_path.is_empty() && syzygy_path != "<empty>" {
+        let directories = env::split_paths(syzygy_path).collect::<Vec<PathBuf>>();
+
+        for directory in &directories {
+            let files = match fs::read_dir(directory) {
+                Ok(value) => value,
+                Err(_) => continue,
+            };
+
+            for file in files.flatten() {
+                let path = file.path();
+                if path.extension().map_or(true, |extension| extension != "rtbw") || !is_table_complete(&path) {
+                    continue;
+                }
+
+                let name = match path.file_stem().and_then(|stem| stem.to_str()) {
+                    Some(value) => value.to_string(),
+                    None => continue,
+                };
+
+                let info = match TableInfo::new(&name) {
+                    Some(value) => value,
+                    None => continue,
+                };
+
+                if entries.contains_key(&info.key) {
+                    continue;
+                }
+
+                let dtz_path = directories.iter().map(|directory| directory.join(format!("{}.rtbz", name))).find(|path| is_table_complete(path));
+                let wdl = match Table::load(&path, TableKind::Wdl, &info) {
+                    Some(value) => value,
+                    None => continue,
+                };
+
+                let keys = [info.key, info.mirrored_key];
+                max_pieces_count = cmp::max(max_pieces_count, info.pieces_count as u8);
+
+                let dtz = dtz_path.and_then(|path| Table::load(&path, TableKind::Dtz, &info));
+                let entry = Arc::new(TableEntry { info, wdl, dtz });
+                for key in keys {
+                    entries.insert(key, entry.clone());
+                }
+            }
+        }
     }
+
+    *TABLEBASES.write().unwrap() = Some(Arc::new(Tablebases { entries, max_pieces_count }));
 }
 
-/// Gets maximal pieces count supported by loaded Syzygy tablebases. Returns 0 if the feature is disabled.
+/// Gets maximal pieces count supported by loaded Syzygy tablebases. Returns 0 if there are no tablebases loaded.
 pub fn get_max_pieces_count() -> u8 {
-    #[cfg(feature = "syzygy")]
-    unsafe {
-        return TB_LARGEST as u8;
+    match TABLEBASES.read().unwrap().as_ref() {
+        Some(tablebases) => tablebases.max_pieces_count,
+        None => 0,
     }
-
-    0
 }
 
-/// Gets WDL (Win-Draw-Loss) for the position specified in `board`. Returns [None] if data couldn't be obtained or the feature is disabled.
+/// Gets WDL (Win-Draw-Loss) for the position specified in `board`. Returns [None] if data couldn't be obtained.
 pub fn get_wdl(board: &Board) -> Option<WdlResult> {
-    #[cfg(feature = "syzygy")]
-    unsafe {
-        let wdl = tb_probe_wdl(
-            board.occupancy[WHITE],
-            board.occupancy[BLACK],
-            board.pieces[WHITE][KING] | board.pieces[BLACK][KING],
-            board.pieces[WHITE][QUEEN] | board.pieces[BLACK][QUEEN],
-            board.pieces[WHITE][ROOK] | board.pieces[BLACK][ROOK],
-            board.pieces[WHITE][BISHOP] | board.pieces[BLACK][BISHOP],
-            board.pieces[WHITE][KNIGHT] | board.pieces[BLACK][KNIGHT],
-            board.pieces[WHITE][PAWN] | board.pieces[BLACK][PAWN],
-            0,
-            0,
-            0,
-            board.stm == WHITE,
-        );
-
-        return match wdl {
-            TB_WIN => Some(WdlResult::Win),
-            TB_LOSS => Some(WdlResult::Loss),
-            TB_DRAW | TB_CURSED_WIN | TB_BLESSED_LOSS => Some(WdlResult::Draw),
-            _ => None,
-        };
-    }
+    let tablebases = get_tablebases(board)?;
+    let mut board = board.clone();
+    let (wdl, _) = probe_wdl(&tablebases, &mut board).ok()?;
 
-    None
+    Some(get_wdl_result(wdl + 2))
 }
 
 /// Gets WDL (Win-Draw-Loss), DTZ (Distance To Zeroing) and the best move for the position specified in `board`.
-/// Returns [None] if data couldn't be obtained or the feature is disabled.
+/// Returns [None] if data couldn't be obtained.
 pub fn get_root_wdl_dtz(board: &Board) -> Option<WdlDtzResult> {
-    #[cfg(feature = "syzygy")]
-    unsafe {
-        let result = tb_probe_root(
-            board.occupancy[WHITE],
-            board.occupancy[BLACK],
-            board.pieces[WHITE][KING] | board.pieces[BLACK][KING],
-            board.pieces[WHITE][QUEEN] | board.pieces[BLACK][QUEEN],
-            board.pieces[WHITE][ROOK] | board.pieces[BLACK][ROOK],
-            board.pieces[WHITE][BISHOP] | board.pieces[BLACK][BISHOP],
-            board.pieces[WHITE][KNIGHT] | board.pieces[BLACK][KNIGHT],
-            board.pieces[WHITE][PAWN] | board.pieces[BLACK][PAWN],
-            board.state.halfmove_clock as u32,
-            0,
-            0,
-            board.stm == WHITE,
-            ptr::null_mut(),
-        );
-
-        let wdl = ((result & TB_RESULT_WDL_MASK) >> TB_RESULT_WDL_SHIFT);
-        let wdl = match wdl {
-            TB_WIN => WdlResult::Win,
-            TB_LOSS => WdlResult::Loss,
-            _ => WdlResult::Draw,
+    let (dtz, scores) = probe_root(board)?;
+    let best = match dtz.cmp(&0) {
+        cmp::Ordering::Greater => scores.iter().filter(|(_, score)| *score > 0).min_by_key(|(_, score)| *score),
+        cmp::Ordering::Less => scores.iter().filter(|(_, score)| *score < 0).min_by_key(|(_, score)| *score),
+        cmp::Ordering::Equal => scores.iter().find(|(_, score)| *score == 0),
+    };
+
+    let (r#move, _) = best?;
+    Some(WdlDtzResult::new(get_wdl_result(get_dtz_wdl(board.state.halfmove_clock as i32, dtz)), dtz.unsigned_abs(), *r#move))
+}
+
+/// Gets WDL (Win-Draw-Loss) and DTZ (Distance To Zeroing) for every legal move in the position specified in `board`, taking the halfmove clock into
+/// consideration (so wins and losses exceeding the fifty-move rule are reported as cursed wins and blessed losses). Returns [None] if data couldn't be
+/// obtained.
+pub fn get_root_moves(board: &Board) -> Option<Vec<WdlDtzResult>> {
+    let (_, scores) = probe_root(board)?;
+    let halfmove_clock = board.state.halfmove_clock as i32;
+
+    Some(scores.iter().map(|(r#move, score)| WdlDtzResult::new(get_wdl_result(get_dtz_wdl(halfmove_clock, *score)), score.unsigned_abs(), *r#move)).collect())
+}
+
+/// Gets loaded tablebases if the position specified in `board` can be probed (no castling rights and pieces count not exceeding the largest table).
+fn get_tablebases(board: &Board) -> Option<Arc<Tablebases>> {
+    let tablebases = TABLEBASES.read().unwrap().clone()?;
+    if board.state.castling_rights != 0 || board.get_pieces_count() > tablebases.max_pieces_count {
+        return None;
+    }
+
+    Some(tablebases)
+}
+
+/// Probes DTZ (Distance To Zeroing) of the position specified in `board` and scores all legal moves with DTZ counted from the root position
+/// (positive for wins, negative for losses and 0 for draws). Returns [None] if data couldn't be obtained or there are no legal moves.
+fn probe_root(board: &Board) -> Option<(i32, Vec<(Move, i32)>)> {
+    let tablebases = get_tablebases(board)?;
+    let mut board = board.clone();
+    let dtz = probe_dtz(&tablebases, &mut board).ok()?;
+
+    let mut moves = [MaybeUninit::uninit(); engine::MAX_MOVES_COUNT];
+    let moves_count = board.get_all_moves(&mut moves, u64::MAX);
+    let mut scores = Vec::new();
+
+    for r#move in &moves[0..moves_count] {
+        let r#move = unsafe { r#move.assume_init() };
+        if !make_legal_move(&mut board, r#move) {
+            continue;
+        }
+
+        let score = if dtz > 0 && is_mate(&mut board) {
+            Ok(1)
+        } else if board.state.halfmove_clock != 0 {
+            probe_dtz(&tablebases, &mut board).map(|dtz| match -dtz {
+                dtz if dtz > 0 => dtz + 1,
+                dtz if dtz < 0 => dtz - 1,
+                _ => 0,
+            })
+        } else {
+            probe_wdl(&tablebases, &mut board).map(|(wdl, _)| WDL_TO_DTZ[(-wdl + 2) as usize])
         };
-        let dtz = ((result & TB_RESULT_DTZ_MASK) >> TB_RESULT_DTZ_SHIFT);
 
-        if result == TB_RESULT_FAILED {
-            return None;
+        board.undo_move(r#move);
+        scores.push((r#move, score.ok()?));
+    }
+
+    if scores.is_empty() {
+        return None;
+    }
+
+    Some((dtz, scores))
+}
+
+/// Probes WDL of the position specified in `board` with all captures resolved (which is required, since tables don't store positions where
+/// the best move is a capture). The returned value is from the perspective of the side to move: -2 (loss), -1 (blessed loss), 0 (draw),
+/// 1 (cursed win) or 2 (win). The second value is set when the best move is a zeroing capture (or the position is winning by capture),
+/// which is used by [probe_dtz] to skip probing DTZ tables.
+fn probe_wdl(tablebases: &Tablebases, board: &mut Board) -> Result<(i32, bool), TableError> {
+    let mut moves = [MaybeUninit::uninit(); engine::MAX_MOVES_COUNT];
+    let moves_count = board.get_moves::<true>(&mut moves, 0, u64::MAX);
+    let mut best_capture = -3;
+    let mut best_en_passant = -3;
+
+    for r#move in &moves[0..moves_count] {
+        let r#move = unsafe { r#move.assume_init() };
+        if !r#move.is_capture() || !make_legal_move(board, r#move) {
+            continue;
+        }
+
+        let score = probe_ab(tablebases, board, -2, -best_capture);
+        board.undo_move(r#move);
+        let score = -score?;
+
+        if score > best_capture {
+            if score == 2 {
+                return Ok((2, true));
+            }
+
+            if !r#move.is_en_passant() {
+                best_capture = score;
+            } else if score > best_en_passant {
+                best_en_passant = score;
+            }
+        }
+    }
+
+    let score = probe_table(tablebases, board, TableKind::Wdl, 0)?;
+
+    // The table stores the score of the position without en passant rights, so it has to be compared with the best en passant capture
+    if best_en_passant > best_capture {
+        if best_en_passant > score {
+            return Ok((best_en_passant, true));
         }
 
-        let mut moves = [MaybeUninit::uninit(); engine::MAX_MOVES_COUNT];
+        best_capture = best_en_passant;
+    }
+
+    if best_capture >= score {
+        return Ok((best_capture, best_capture > 0));
+    }
+
+    // If the position without en passant rights is a stalemate, then the en passant capture is the only move
+    if best_en_passant > -3 && score == 0 {
         let moves_count = board.get_all_moves(&mut moves, u64::MAX);
+        let mut stalemate = true;
 
-        let from = ((result & TB_RESULT_FROM_MASK) >> TB_RESULT_FROM_SHIFT) as usize;
-        let to = ((result & TB_RESULT_TO_MASK) >> TB_RESULT_TO_SHIFT) as usize;
-        let promotion = ((result & TB_RESULT_PROMOTES_MASK) >> TB_RESULT_PROMOTES_SHIFT);
+        for r#move in &moves[0..moves_count] {
+            let r#move = unsafe { r#move.assume_init() };
+            if !r#move.is_en_passant() && make_legal_move(board, r#move) {
+                board.undo_move(r#move);
+                stalemate = false;
+                break;
+            }
+        }
 
-        let promotion_flags = match promotion {
-            TB_PROMOTES_QUEEN => MoveFlags::QUEEN_PROMOTION,
-            TB_PROMOTES_ROOK => MoveFlags::ROOK_PROMOTION,
-            TB_PROMOTES_BISHOP => MoveFlags::BISHOP_PROMOTION,
-            TB_PROMOTES_KNIGHT => MoveFlags::KNIGHT_PROMOTION,
-            _ => MoveFlags::SINGLE_PUSH,
-        };
+        if stalemate && !board.is_king_checked(board.stm) {
+            return Ok((best_en_passant, true));
+        }
+    }
+
+    Ok((score, false))
+}
+
+/// Performs alpha-beta search over captures in the position specified in `board`, using WDL tables to score quiet positions.
+fn probe_ab(tablebases: &Tablebases, board: &mut Board, mut alpha: i32, beta: i32) -> Result<i32, TableError> {
+    let mut moves = [MaybeUninit::uninit(); engine::MAX_MOVES_COUNT];
+    let moves_count = board.get_moves::<true>(&mut moves, 0, u64::MAX);
+
+    for r#move in &moves[0..moves_count] {
+        let r#move = unsafe { r#move.assume_init() };
+        if !r#move.is_capture() || !make_legal_move(board, r#move) {
+            continue;
+        }
+
+        let score = probe_ab(tablebases, board, -beta, -alpha);
+        board.undo_move(r#move);
+        let score = -score?;
+
+        if score > alpha {
+            if score >= beta {
+                return Ok(score);
+            }
+
+            alpha = score;
+        }
+    }
+
+    let score = probe_table(tablebases, board, TableKind::Wdl, 0)?;
+    Ok(cmp::max(alpha, score))
+}
+
+/// Probes DTZ (Distance To Zeroing) of the position specified in `board`. The returned value is positive for wins (cursed ones are
+/// greater than 100) and negative for losses, with 0 meaning a draw. It may be one ply off the exact value, as the tables store
+/// only positions with the side to move for which they are smaller.
+fn probe_dtz(tablebases: &Tablebases, board: &mut Board) -> Result<i32, TableError> {
+    let (wdl, zeroing_best) = probe_wdl(tablebases, board)?;
 
+    if wdl == 0 {
+        return Ok(0);
+    }
+
+    if zeroing_best {
+        return Ok(WDL_TO_DTZ[(wdl + 2) as usize]);
+    }
+
+    let mut moves = [MaybeUninit::uninit(); engine::MAX_MOVES_COUNT];
+    let moves_count = board.get_all_moves(&mut moves, u64::MAX);
+
+    // If winning, check for a winning pawn move
+    if wdl > 0 {
         for r#move in &moves[0..moves_count] {
             let r#move = unsafe { r#move.assume_init() };
-            if r#move.get_from() == from && r#move.get_to() == to {
-                let flags = r#move.get_flags();
-                if promotion == 0 || (flags & promotion_flags) == flags {
-                    return Some(WdlDtzResult::new(wdl, dtz, r#move));
-                }
+            if r#move.is_capture() || board.get_piece(r#move.get_from()) != PAWN || !make_legal_move(board, r#move) {
+                continue;
+            }
+
+            let score = probe_wdl(tablebases, board);
+            board.undo_move(r#move);
+
+            if -score?.0 == wdl {
+                return Ok(WDL_TO_DTZ[(wdl + 2) as usize]);
             }
         }
+    }
 
-        return None;
+    match probe_table(tablebases, board, TableKind::Dtz, wdl) {
+        Ok(dtz) => return Ok(WDL_TO_DTZ[(wdl + 2) as usize] + if wdl > 0 { dtz } else { -dtz }),
+        Err(TableError::Failed) => return Err(TableError::Failed),
+        Err(TableError::WrongSide) => {}
+    }
+
+    // The table stores only the other side to move, so DTZ has to be obtained by probing all positions after non-zeroing moves
+    let mut best = if wdl > 0 { i32::MAX } else { WDL_TO_DTZ[(wdl + 2) as usize] };
+
+    for r#move in &moves[0..moves_count] {
+        let r#move = unsafe { r#move.assume_init() };
+        if r#move.is_capture() || board.get_piece(r#move.get_from()) == PAWN || !make_legal_move(board, r#move) {
+            continue;
+        }
+
+        let score = probe_dtz(tablebases, board).map(|dtz| -dtz);
+        let mate = score == Ok(1) && is_mate(board);
+        board.undo_move(r#move);
+        let score = score?;
+
+        if mate {
+            best = 1;
+        } else if wdl > 0 {
+            if score > 0 && score + 1 < best {
+                best = score + 1;
+            }
+        } else if score - 1 < best {
+            best = score - 1;
+        }
+    }
+
+    Ok(best)
+}
+
+/// Probes a table of the specified `kind` for the position specified in `board`, with `wdl` required to decode DTZ values.
+fn probe_table(tablebases: &Tablebases, board: &Board, kind: TableKind, wdl: i32) -> Result<i32, TableError> {
+    let key = get_board_key(board, false);
+
+    // KvK is not stored in tables
+    if kind == TableKind::Wdl && key == 0 {
+        return Ok(0);
+    }
+
+    let entry = tablebases.entries.get(&key).ok_or(TableError::Failed)?;
+    let table = entry.get_table(kind).ok_or(TableError::Failed)?;
+
+    let (flip, bside) = if !entry.info.symmetric {
+        let flip = key != entry.info.key;
+        (flip, (board.stm == WHITE) == flip)
+    } else {
+        (board.stm != WHITE, false)
+    };
+
+    table.probe(&entry.info, board, flip, bside, wdl)
+}
+
+/// Makes `r#move` in the position specified in `board` and returns true if it's legal, otherwise undoes it and returns false.
+fn make_legal_move(board: &mut Board, r#move: Move) -> bool {
+    board.make_move(r#move);
+
+    if board.is_king_checked(board.stm ^ 1) {
+        board.undo_move(r#move);
+        return false;
+    }
+
+    true
+}
+
+/// Checks if the side to move in the position specified in `board` is checkmated.
+fn is_mate(board: &mut Board) -> bool {
+    if !board.is_king_checked(board.stm) {
+        return false;
+    }
+
+    let mut moves = [MaybeUninit::uninit(); engine::MAX_MOVES_COUNT];
+    let moves_count = board.get_all_moves(&mut moves, u64::MAX);
+
+    for r#move in &moves[0..moves_count] {
+        let r#move = unsafe { r#move.assume_init() };
+        if make_legal_move(board, r#move) {
+            board.undo_move(r#move);
+            return false;
+        }
     }
 
-    None
+    true
+}
+
+/// Checks if the tablebase file at `path` exists and has a valid size.
+fn is_table_complete(path: &Path) -> bool {
+    fs::metadata(path).map_or(false, |metadata| (metadata.len() & 63) == 16)
+}
+
+/// Converts `dtz` into WDL (in the range from 0 for a loss to 4 for a win), using `halfmove_clock` to detect cursed wins and blessed losses.
+fn get_dtz_wdl(halfmove_clock: i32, dtz: i32) -> i32 {
+    let wdl = match dtz.cmp(&0) {
+        cmp::Ordering::Greater => {
+            if dtz + halfmove_clock <= 100 {
+                2
+            } else {
+                1
+            }
+        }
+        cmp::Ordering::Less => {
+            if -dtz + halfmove_clock <= 100 {
+                -2
+            } else {
+                -1
+            }
+        }
+        cmp::Ordering::Equal => 0,
+    };
+
+    wdl + 2
+}
+
+/// Converts `wdl` (in the range from 0 for a loss to 4 for a win) into [WdlResult].
+fn get_wdl_result(wdl: i32) -> WdlResult {
+    match wdl {
+        4 => WdlResult::Win,
+        3 => WdlResult::CursedWin,
+        1 => WdlResult::BlessedLoss,
+        0 => WdlResult::Loss,
+        _ => WdlResult::Draw,
+    }
 }
@@ -0,0 +1,902 @@
+// Port of the Syzygy probing code from Fathom (https://github.com/jdart1/Fathom), originally written by Ronald de Man and
+// modified by Basil Falcinelli and Jon Dart, distributed under the MIT license.
+
+use crate::state::representation::Board;
+use crate::state::*;
+use crate::utils::bithelpers::BitHelpers;
+use memmap2::Mmap;
+use std::fs::File;
+use std::path::Path;
+
+pub const MAX_PIECES: usize = 7;
+
+const WDL_MAGIC: u32 = 0x5d23e871;
+const DTZ_MAGIC: u32 = 0xa50c66d7;
+
+const PIECE_KEYS: [[u64; 6]; 2] = [
+    [17008651141875982339, 15202887380319082783, 12311744257139811149, 10979190538029446137, 11811845319353239651, 0],
+    [11695583624105689831, 13469005675588064321, 15394650811035483107, 18264461213049635989, 15484752644942473553, 0],
+];
+
+#[rustfmt::skip]
+const OFF_DIAG: [i8; 64] = [
+    0, -1, -1, -1, -1, -1, -1, -1,
+    1,  0, -1, -1, -1, -1, -1, -1,
+    1,  1,  0, -1, -1, -1, -1, -1,
+    1,  1,  1,  0, -1, -1, -1, -1,
+    1,  1,  1,  1,  0, -1, -1, -1,
+    1,  1,  1,  1,  1,  0, -1, -1,
+    1,  1,  1,  1,  1,  1,  0, -1,
+    1,  1,  1,  1,  1,  1,  1,  0,
+];
+
+#[rustfmt::skip]
+const TRIANGLE: [usize; 64] = [
+    6, 0, 1, 2, 2, 1, 0, 6,
+    0, 7, 3, 4, 4, 3, 7, 0,
+    1, 3, 8, 5, 5, 8, 3, 1,
+    2, 4, 5, 9, 9, 5, 4, 2,
+    2, 4, 5, 9, 9, 5, 4, 2,
+    1, 3, 8, 5, 5, 8, 3, 1,
+    0, 7, 3, 4, 4, 3, 7, 0,
+    6, 0, 1, 2, 2, 1, 0, 6,
+];
+
+#[rustfmt::skip]
+const FLIP_DIAG: [usize; 64] = [
+    0,  8, 16, 24, 32, 40, 48, 56,
+    1,  9, 17, 25, 33, 41, 49, 57,
+    2, 10, 18, 26, 34, 42, 50, 58,
+    3, 11, 19, 27, 35, 43, 51, 59,
+    4, 12, 20, 28, 36, 44, 52, 60,
+    5, 13, 21, 29, 37, 45, 53, 61,
+    6, 14, 22, 30, 38, 46, 54, 62,
+    7, 15, 23, 31, 39, 47, 55, 63,
+];
+
+#[rustfmt::skip]
+const LOWER: [usize; 64] = [
+    28,  0,  1,  2,  3,  4,  5,  6,
+     0, 29,  7,  8,  9, 10, 11, 12,
+     1,  7, 30, 13, 14, 15, 16, 17,
+     2,  8, 13, 31, 18, 19, 20, 21,
+     3,  9, 14, 18, 32, 22, 23, 24,
+     4, 10, 15, 19, 22, 33, 25, 26,
+     5, 11, 16, 20, 23, 25, 34, 27,
+     6, 12, 17, 21, 24, 26, 27, 35,
+];
+
+#[rustfmt::skip]
+const DIAG: [usize; 64] = [
+     0,  0,  0,  0,  0,  0,  0,  8,
+     0,  1,  0,  0,  0,  0,  9,  0,
+     0,  0,  2,  0,  0, 10,  0,  0,
+     0,  0,  0,  3, 11,  0,  0,  0,
+     0,  0,  0, 12,  4,  0,  0,  0,
+     0,  0, 13,  0,  0,  5,  0,  0,
+     0, 14,  0,  0,  0,  0,  6,  0,
+    15,  0,  0,  0,  0,  0,  0,  7,
+];
+
+#[rustfmt::skip]
+const FLAP: [usize; 64] = [
+    0,  0,  0,  0,  0,  0,  0, 0,
+    0,  6, 12, 18, 18, 12,  6, 0,
+    1,  7, 13, 19, 19, 13,  7, 1,
+    2,  8, 14, 20, 20, 14,  8, 2,
+    3,  9, 15, 21, 21, 15,  9, 3,
+    4, 10, 16, 22, 22, 16, 10, 4,
+    5, 11, 17, 23, 23, 17, 11, 5,
+    0,  0,  0,  0,  0,  0,  0, 0,
+];
+
+#[rustfmt::skip]
+const PAWN_TWIST: [usize; 64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+    47, 35, 23, 11, 10, 22, 34, 46,
+    45, 33, 21,  9,  8, 20, 32, 44,
+    43, 31, 19,  7,  6, 18, 30, 42,
+    41, 29, 17,  5,  4, 16, 28, 40,
+    39, 27, 15,  3,  2, 14, 26, 38,
+    37, 25, 13,  1,  0, 12, 24, 36,
+     0,  0,  0,  0,  0,  0,  0,  0,
+];
+
+#[rustfmt::skip]
+const KK_IDX: [[i16; 64]; 10] = [
+    [
+         -1,  -1,  -1,   0,   1,   2,   3,   4,  -1,  -1,  -1,   5,   6,   7,   8,   9,  10,  11,  12,  13,  14,  15,  16,  17,  18,  19,  20,  21,  22,  23,  24,  25,
+         26,  27,  28,  29,  30,  31,  32,  33,  34,  35,  36,  37,  38,  39,  40,  41,  42,  43,  44,  45,  46,  47,  48,  49,  50,  51,  52,  53,  54,  55,  56,  57,
+    ],
+    [
+         58,  -1,  -1,  -1,  59,  60,  61,  62,  63,  -1,  -1,  -1,  64,  65,  66,  67,  68,  69,  70,  71,  72,  73,  74,  75,  76,  77,  78,  79,  80,  81,  82,  83,
+         84,  85,  86,  87,  88,  89,  90,  91,  92,  93,  94,  95,  96,  97,  98,  99, 100, 101, 102, 103, 104, 105, 106, 107, 108, 109, 110, 111, 112, 113, 114, 115,
+    ],
+    [
+        116, 117,  -1,  -1,  -1, 118, 119, 120, 121, 122,  -1,  -1,  -1, 123, 124, 125, 126, 127, 128, 129, 130, 131, 132, 133, 134, 135, 136, 137, 138, 139, 140, 141,
+        142, 143, 144, 145, 146, 147, 148, 149, 150, 151, 152, 153, 154, 155, 156, 157, 158, 159, 160, 161, 162, 163, 164, 165, 166, 167, 168, 169, 170, 171, 172, 173,
+    ],
+    [
+        174,  -1,  -1,  -1, 175, 176, 177, 178, 179,  -1,  -1,  -1, 180, 181, 182, 183, 184,  -1,  -1,  -1, 185, 186, 187, 188, 189, 190, 191, 192, 193, 194, 195, 196,
+        197, 198, 199, 200, 201, 202, 203, 204, 205, 206, 207, 208, 209, 210, 211, 212, 213, 214, 215, 216, 217, 218, 219, 220, 221, 222, 223, 224, 225, 226, 227, 228,
+    ],
+    [
+        229, 230,  -1,  -1,  -1, 231, 232, 233, 234, 235,  -1,  -1,  -1, 236, 237, 238, 239, 240,  -1,  -1,  -1, 241, 242, 243, 244, 245, 246, 247, 248, 249, 250, 251,
+        252, 253, 254, 255, 256, 257, 258, 259, 260, 261, 262, 263, 264, 265, 266, 267, 268, 269, 270, 271, 272, 273, 274, 275, 276, 277, 278, 279, 280, 281, 282, 283,
+    ],
+    [
+        284, 285, 286, 287, 288, 289, 290, 291, 292, 293,  -1,  -1,  -1, 294, 295, 296, 297, 298,  -1,  -1,  -1, 299, 300, 301, 302, 303,  -1,  -1,  -1, 304, 305, 306,
+        307, 308, 309, 310, 311, 312, 313, 314, 315, 316, 317, 318, 319, 320, 321, 322, 323, 324, 325, 326, 327, 328, 329, 330, 331, 332, 333, 334, 335, 336, 337, 338,
+    ],
+    [
+         -1,  -1, 339, 340, 341, 342, 343, 344,  -1,  -1, 345, 346, 347, 348, 349, 350,  -1,  -1, 441, 351, 352, 353, 354, 355,  -1,  -1,  -1, 442, 356, 357, 358, 359,
+         -1,  -1,  -1,  -1, 443, 360, 361, 362,  -1,  -1,  -1,  -1,  -1, 444, 363, 364,  -1,  -1,  -1,  -1,  -1,  -1, 445, 365,  -1,  -1,  -1,  -1,  -1,  -1,  -1, 446,
+    ],
+    [
+         -1,  -1,  -1, 366, 367, 368, 369, 370,  -1,  -1,  -1, 371, 372, 373, 374, 375,  -1,  -1,  -1, 376, 377, 378, 379, 380,  -1,  -1,  -1, 447, 381, 382, 383, 384,
+         -1,  -1,  -1,  -1, 448, 385, 386, 387,  -1,  -1,  -1,  -1,  -1, 449, 388, 389,  -1,  -1,  -1,  -1,  -1,  -1, 450, 390,  -1,  -1,  -1,  -1,  -1,  -1,  -1, 451,
+    ],
+    [
+        452, 391, 392, 393, 394, 395, 396, 397,  -1,  -1,  -1,  -1, 398, 399, 400, 401,  -1,  -1,  -1,  -1, 402, 403, 404, 405,  -1,  -1,  -1,  -1, 406, 407, 408, 409,
+         -1,  -1,  -1,  -1, 453, 410, 411, 412,  -1,  -1,  -1,  -1,  -1, 454, 413, 414,  -1,  -1,  -1,  -1,  -1,  -1, 455, 415,  -1,  -1,  -1,  -1,  -1,  -1,  -1, 456,
+    ],
+    [
+        457, 416, 417, 418, 419, 420, 421, 422,  -1, 458, 423, 424, 425, 426, 427, 428,  -1,  -1,  -1,  -1,  -1, 429, 430, 431,  -1,  -1,  -1,  -1,  -1, 432, 433, 434,
+         -1,  -1,  -1,  -1,  -1, 435, 436, 437,  -1,  -1,  -1,  -1,  -1, 459, 438, 439,  -1,  -1,  -1,  -1,  -1,  -1, 460, 440,  -1,  -1,  -1,  -1,  -1,  -1,  -1, 461,
+    ],
+];
+
+const FILE_TO_FILE: [usize; 8] = [0, 1, 2, 3, 3, 2, 1, 0];
+const WDL_TO_MAP: [usize; 5] = [1, 3, 0, 2, 0];
+const PA_FLAGS: [u8; 5] = [8, 0, 0, 0, 4];
+
+const BINOMIAL: [[usize; 64]; 7] = get_binomial();
+const PAWN_IDX: [[usize; 24]; 6] = get_pawn_indices().0;
+const PAWN_FACTOR_FILE: [[usize; 4]; 6] = get_pawn_indices().1;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum TableKind {
+    Wdl,
+    Dtz,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum TableError {
+    Failed,
+    WrongSide,
+}
+
+pub struct TableInfo {
+    pub key: u64,
+    pub mirrored_key: u64,
+    pub pieces_count: usize,
+    pub symmetric: bool,
+    pub has_pawns: bool,
+    pub kk_enc: bool,
+    pub pawns: [usize; 2],
+}
+
+pub struct Table {
+    kind: TableKind,
+    data: Mmap,
+    enc_info: Vec<EncInfo>,
+    dtz_flags: [u8; 4],
+    dtz_maps: [[usize; 4]; 4],
+}
+
+#[derive(Clone, Default)]
+struct EncInfo {
+    precomp: Option<PairsData>,
+    factor: [usize; MAX_PIECES],
+    pieces: [u8; MAX_PIECES],
+    norm: [usize; MAX_PIECES],
+}
+
+#[derive(Clone, Default)]
+struct PairsData {
+    index_table: usize,
+    size_table: usize,
+    data: usize,
+    offset: usize,
+    sym_pat: usize,
+    sym_len: Vec<u8>,
+    base: Vec<u64>,
+    block_size: u8,
+    idx_bits: u8,
+    min_len: u8,
+    const_value: [u8; 2],
+}
+
+impl TableInfo {
+    /// Constructs a new instance of [TableInfo] by parsing `name` of the tablebase file (like `KRPvKR`), or returns [None] if it's not a valid name.
+    pub fn new(name: &str) -> Option<Self> {
+        let (white, black) = name.split_once('v')?;
+        let mut counts = [[0; 6]; 2];
+
+        for (color, side) in [white, black].iter().enumerate() {
+            if !side.starts_with('K') || side[1..].contains('K') {
+                return None;
+            }
+
+            for symbol in side.chars() {
+                let piece = match symbol {
+                    'P' => PAWN,
+                    'N' => KNIGHT,
+                    'B' => BISHOP,
+                    'R' => ROOK,
+                    'Q' => QUEEN,
+                    'K' => KING,
+                    _ => return None,
+                };
+
+                counts[color][piece] += 1;
+            }
+        }
+
+        let pieces_count = counts.iter().flatten().sum::<usize>();
+        if pieces_count > MAX_PIECES {
+            return None;
+        }
+
+        let key = get_material_key(&counts, false);
+        let mirrored_key = get_material_key(&counts, true);
+        let has_pawns = counts[WHITE][PAWN] > 0 || counts[BLACK][PAWN] > 0;
+        let kk_enc = !has_pawns && counts.iter().flatten().filter(|count| **count == 1).count() == 2;
+        let mut pawns = [counts[WHITE][PAWN], counts[BLACK][PAWN]];
+
+        if pawns[1] > 0 && (pawns[0] == 0 || pawns[0] > pawns[1]) {
+            pawns.swap(0, 1);
+        }
+
+        Some(Self { key, mirrored_key, pieces_count, symmetric: key == mirrored_key, has_pawns, kk_enc, pawns })
+    }
+}
+
+impl Table {
+    /// Loads a table of the specified `kind` from `path`, using `info` to decode its header. The file is memory-mapped, so only its header
+    /// is read here. Returns [None] if the file couldn't be mapped or is corrupted.
+    pub fn load(path: &Path, kind: TableKind, info: &TableInfo) -> Option<Self> {
+        let file = File::open(path).ok()?;
+
+        // Tablebase files are read-only and never modified while the engine is running
+        let data = unsafe { Mmap::map(&file).ok()? };
+        let magic = match kind {
+            TableKind::Wdl => WDL_MAGIC,
+            TableKind::Dtz => DTZ_MAGIC,
+        };
+
+        if data.len() < 5 || read_le_u32(&data, 0) != magic {
+            return None;
+        }
+
+        let split = kind == TableKind::Wdl && (data[4] & 0x01) != 0;
+        let tables_count = if info.has_pawns { 4 } else { 1 };
+        let mut enc_info = vec![EncInfo::default(); if kind == TableKind::Wdl { 2 * tables_count } else { tables_count }];
+        let mut tb_sizes = [[0; 2]; 4];
+        let mut pointer = 5;
+
+        for t in 0..tables_count {
+            let header = data.get(pointer..pointer + info.pieces_count + 2)?;
+            tb_sizes[t][0] = enc_info[t].init(info, header, 0, t);
+
+            if split {
+                tb_sizes[t][1] = enc_info[tables_count + t].init(info, header, 4, t);
+            }
+
+            pointer += info.pieces_count + 1 + (info.has_pawns && info.pawns[1] > 0) as usize;
+        }
+        pointer += pointer & 0x01;
+
+        let mut sizes = [[[0; 3]; 2]; 4];
+        let mut dtz_flags = [0; 4];
+
+        for t in 0..tables_count {
+            let (precomp, flags, size) = PairsData::new(&data, &mut pointer, tb_sizes[t][0], kind)?;
+            enc_info[t].precomp = Some(precomp);
+            sizes[t][0] = size;
+
+            if kind == TableKind::Dtz {
+                dtz_flags[t] = flags;
+            }
+
+            if split {
+                let (precomp, _, size) = PairsData::new(&data, &mut pointer, tb_sizes[t][1], kind)?;
+                enc_info[tables_count + t].precomp = Some(precomp);
+                sizes[t][1] = size;
+            }
+        }
+
+        let mut dtz_maps = [[0; 4]; 4];
+        if kind == TableKind::Dtz {
+            for t in 0..tables_count {
+                if (dtz_flags[t] & 2) == 0 {
+                    continue;
+                }
+
+                if (dtz_flags[t] & 16) == 0 {
+                    for map in &mut dtz_maps[t] {
+                        *map = pointer + 1;
+                        pointer += 1 + *data.get(pointer)? as usize;
+                    }
+                } else {
+                    pointer += pointer & 0x01;
+                    for map in &mut dtz_maps[t] {
+                        *map = pointer + 2;
+                        pointer += 2 + 2 * read_le_u16(&data, pointer) as usize;
+                    }
+                }
+            }
+            pointer += pointer & 0x01;
+        }
+
+        let sides_count = if split { 2 } else { 1 };
+        for (field, alignment) in [(0, 1), (1, 1), (2, 64)] {
+            for t in 0..tables_count {
+                for side in 0..sides_count {
+                    let size = sizes[t][side][field];
+                    pointer = (pointer + alignment - 1) & !(alignment - 1);
+
+                    if size > 0 && pointer + size > data.len() {
+                        return None;
+                    }
+
+                    let precomp = enc_info[side * tables_count + t].precomp.as_mut()?;
+                    match field {
+                        0 => precomp.index_table = pointer,
+                        1 => precomp.size_table = pointer,
+                        _ => precomp.data = pointer,
+                    }
+
+                    pointer += size;
+                }
+            }
+        }
+
+        Some(Self { kind, data, enc_info, dtz_flags, dtz_maps })
+    }
+
+    /// Reads a value for the position specified in `board`. Use `flip` to swap colors of the pieces (when the material is stored in the table
+    /// from the opposite perspective) and `bside` to select the part of the table for black to move. For DTZ tables, `wdl` of the position is
+    /// required to decode the value, and [TableError::WrongSide] is returned if the table stores only the other side to move.
+    pub fn probe(&self, info: &TableInfo, board: &Board, flip: bool, bside: bool, wdl: i32) -> Result<i32, TableError> {
+        let mut squares = [0; MAX_PIECES];
+        let tables_count = if info.has_pawns { 4 } else { 1 };
+        let mut table_index = 0;
+
+        let enc_info = if !info.has_pawns {
+            if self.kind == TableKind::Dtz && (self.dtz_flags[0] & 1 != 0) != bside && !info.symmetric {
+                return Err(TableError::WrongSide);
+            }
+
+            let enc_info = &self.enc_info[if self.kind == TableKind::Wdl { bside as usize } else { 0 }];
+            let mut index = 0;
+
+            while index < info.pieces_count {
+                index = fill_squares(board, &enc_info.pieces, flip, 0, &mut squares, index);
+            }
+
+            enc_info
+        } else {
+            let mirror = if flip { 0x38 } else { 0 };
+            let mut index = fill_squares(board, &self.enc_info[0].pieces, flip, mirror, &mut squares, 0);
+            table_index = get_leading_pawn(&mut squares, info);
+
+            if self.kind == TableKind::Dtz && (self.dtz_flags[table_index] & 1 != 0) != bside && !info.symmetric {
+                return Err(TableError::WrongSide);
+            }
+
+            let side = if self.kind == TableKind::Wdl { bside as usize } else { 0 };
+            let enc_info = &self.enc_info[side * tables_count + table_index];
+
+            while index < info.pieces_count {
+                index = fill_squares(board, &enc_info.pieces, flip, mirror, &mut squares, index);
+            }
+
+            enc_info
+        };
+
+        let precomp = enc_info.precomp.as_ref().ok_or(TableError::Failed)?;
+        let index = encode(&mut squares, enc_info, info);
+        let value = precomp.decompress(&self.data, index);
+
+        if self.kind == TableKind::Wdl {
+            return Ok(value[0] as i32 - 2);
+        }
+
+        let flags = self.dtz_flags[table_index];
+        let mut dtz = value[0] as usize + ((value[1] as usize & 0x0f) << 8);
+
+        if (flags & 2) != 0 {
+            let map = self.dtz_maps[table_index][WDL_TO_MAP[(wdl + 2) as usize]];
+            dtz =
+                if (flags & 16) == 0 { *self.data.get(map + dtz).ok_or(TableError::Failed)? as usize } else { read_le_u16(&self.data, map + 2 * dtz) as usize };
+        }
+
+        if (flags & PA_FLAGS[(wdl + 2) as usize]) == 0 || (wdl & 1) != 0 {
+            dtz *= 2;
+        }
+
+        Ok(dtz as i32)
+    }
+}
+
+impl EncInfo {
+    /// Initializes encoding information of the table `t` using `header` bytes (lower or upper nibbles, depending on `shift`). Returns the table size.
+    fn init(&mut self, info: &TableInfo, header: &[u8], shift: u8, t: usize) -> usize {
+        let more_pawns = info.has_pawns && info.pawns[1] > 0;
+
+        for i in 0..info.pieces_count {
+            self.pieces[i] = (header[i + 1 + more_pawns as usize] >> shift) & 0x0f;
+            self.norm[i] = 0;
+        }
+
+        let order = ((header[0] >> shift) & 0x0f) as usize;
+        let order2 = if more_pawns { ((header[1] >> shift) & 0x0f) as usize } else { 0x0f };
+
+        let mut k = if info.has_pawns {
+            info.pawns[0]
+        } else if info.kk_enc {
+            2
+        } else {
+            3
+        };
+        self.norm[0] = k;
+
+        if more_pawns {
+            self.norm[k] = info.pawns[1];
+            k += self.norm[k];
+        }
+
+        let mut i = k;
+        while i < info.pieces_count {
+            let mut j = i;
+            while j < info.pieces_count && self.pieces[j] == self.pieces[i] {
+                self.norm[i] += 1;
+                j += 1;
+            }
+
+            i += self.norm[i];
+        }
+
+        let mut free_squares = 64 - k;
+        let mut size = 1;
+        let mut i = 0;
+
+        while k < info.pieces_count || i == order || i == order2 {
+            if i == order {
+                self.factor[0] = size;
+                size *= if info.has_pawns {
+                    PAWN_FACTOR_FILE[self.norm[0] - 1][t]
+                } else if info.kk_enc {
+                    462
+                } else {
+                    31332
+                };
+            } else if i == order2 {
+                self.factor[self.norm[0]] = size;
+                size *= get_subfactor(self.norm[self.norm[0]], 48 - self.norm[0]);
+            } else {
+                self.factor[k] = size;
+                size *= get_subfactor(self.norm[k], free_squares);
+                free_squares -= self.norm[k];
+                k += self.norm[k];
+            }
+
+            i += 1;
+        }
+
+        size
+    }
+}
+
+impl PairsData {
+    /// Constructs a new instance of [PairsData] by reading its header from `data` at `pointer` (which is moved past it), for the table
+    /// with `tb_size` entries. Returns the structure, flags and sizes of the index table, the size table and the compressed data.
+    fn new(data: &[u8], pointer: &mut usize, tb_size: usize, kind: TableKind) -> Option<(Self, u8, [usize; 3])> {
+        let flags = *data.get(*pointer)?;
+        if (flags & 0x80) != 0 {
+            let const_value = if kind == TableKind::Wdl { *data.get(*pointer + 1)? } else { 0 };
+            *pointer += 2;
+
+            return Some((Self { const_value: [const_value, 0], ..Default::default() }, flags, [0; 3]));
+        }
+
+        let header = data.get(*pointer..*pointer + 12)?;
+
+        let block_size = header[1];
+        let idx_bits = header[2];
+        let real_blocks_count = read_le_u32(header, 4) as usize;
+        let blocks_count = real_blocks_count + header[3] as usize;
+        let max_len = header[8] as usize;
+        let min_len = header[9] as usize;
+
+        if min_len == 0 || max_len < min_len || max_len >= 64 || idx_bits == 0 {
+            return None;
+        }
+
+        let h = max_len - min_len + 1;
+        let offset = *pointer + 10;
+        let symbols_count = read_le_u16(data, offset + 2 * h) as usize;
+        let sym_pat = offset + 2 * h + 2;
+
+        *pointer = sym_pat + 3 * symbols_count + (symbols_count & 1);
+        if *pointer > data.len() {
+            return None;
+        }
+
+        let indices_count = (tb_size + (1 << idx_bits) - 1) >> idx_bits;
+        let sizes = [6 * indices_count, 2 * blocks_count, real_blocks_count << block_size];
+
+        let mut sym_len = vec![0; symbols_count];
+        let mut visited = vec![false; symbols_count];
+
+        for symbol in 0..symbols_count {
+            if !visited[symbol] {
+                calculate_sym_len(data, sym_pat, symbol, &mut sym_len, &mut visited)?;
+            }
+        }
+
+        let mut base = vec![0u64; h];
+        for i in (0..h - 1).rev() {
+            let current_offset = read_le_u16(data, offset + 2 * i) as u64;
+            let next_offset = read_le_u16(data, offset + 2 * (i + 1)) as u64;
+            base[i] = base[i + 1].wrapping_add(current_offset).wrapping_sub(next_offset) / 2;
+        }
+
+        for (i, value) in base.iter_mut().enumerate() {
+            *value = value.checked_shl((64 - (min_len + i)) as u32).unwrap_or(0);
+        }
+
+        let precomp =
+            Self { index_table: 0, size_table: 0, data: 0, offset, sym_pat, sym_len, base, block_size, idx_bits, min_len: min_len as u8, const_value: [0; 2] };
+        Some((precomp, flags, sizes))
+    }
+
+    /// Decompresses the value stored at `index`, using `data` of the table file. Returns the first two bytes of the decoded symbol.
+    fn decompress(&self, data: &[u8], index: usize) -> [u8; 2] {
+        if self.idx_bits == 0 {
+            return self.const_value;
+        }
+
+        let main_index = index >> self.idx_bits;
+        let mut lit_index = (index & ((1 << self.idx_bits) - 1)) as i64 - (1 << (self.idx_bits - 1));
+        let mut block = read_le_u32(data, self.index_table + 6 * main_index) as usize;
+        lit_index += read_le_u16(data, self.index_table + 6 * main_index + 4) as i64;
+
+        if lit_index < 0 {
+            while lit_index < 0 && block > 0 {
+                block -= 1;
+                lit_index += read_le_u16(data, self.size_table + 2 * block) as i64 + 1;
+            }
+        } else {
+            while lit_index > read_le_u16(data, self.size_table + 2 * block) as i64 {
+                lit_index -= read_le_u16(data, self.size_table + 2 * block) as i64 + 1;
+                block += 1;
+            }
+        }
+
+        let min_len = self.min_len as usize;
+        let mut pointer = self.data + (block << self.block_size);
+        let mut code = read_be_u64(data, pointer);
+        let mut bits_count = 0;
+        let mut symbol;
+
+        pointer += 8;
+
+        loop {
+            let mut length = min_len;
+            while code < self.base[length - min_len] {
+                length += 1;
+            }
+
+            symbol = read_le_u16(data, self.offset + 2 * (length - min_len)) as usize;
+            symbol += ((code - self.base[length - min_len]) >> (64 - length)) as usize;
+
+            let symbol_length = *self.sym_len.get(symbol).unwrap_or(&0) as i64;
+            if lit_index < symbol_length + 1 {
+                break;
+            }
+
+            lit_index -= symbol_length + 1;
+            code <<= length;
+            bits_count += length;
+
+            if bits_count >= 32 {
+                bits_count -= 32;
+                code |= (read_be_u32(data, pointer) as u64) << bits_count;
+                pointer += 4;
+            }
+        }
+
+        while *self.sym_len.get(symbol).unwrap_or(&0) != 0 {
+            let pattern = self.sym_pat + 3 * symbol;
+            let left = ((get_byte(data, pattern + 1) as usize & 0x0f) << 8) | get_byte(data, pattern) as usize;
+
+            if lit_index < self.sym_len[left] as i64 + 1 {
+                symbol = left;
+            } else {
+                lit_index -= self.sym_len[left] as i64 + 1;
+                symbol = ((get_byte(data, pattern + 2) as usize) << 4) | (get_byte(data, pattern + 1) as usize >> 4);
+            }
+        }
+
+        [get_byte(data, self.sym_pat + 3 * symbol), get_byte(data, self.sym_pat + 3 * symbol + 1)]
+    }
+}
+
+/// Gets a material key of the position specified in `board`, which is the same for all positions with equal material. If `mirror` is set,
+/// colors are swapped before calculating the key.
+pub fn get_board_key(board: &Board, mirror: bool) -> u64 {
+    let mut counts = [[0; 6]; 2];
+    for (color, color_counts) in counts.iter_mut().enumerate() {
+        for (piece, count) in color_counts.iter_mut().enumerate() {
+            *count = board.pieces[color][piece].bit_count();
+        }
+    }
+
+    get_material_key(&counts, mirror)
+}
+
+/// Gets a material key for pieces `counts` indexed by color and piece. If `mirror` is set, colors are swapped before calculating the key.
+fn get_material_key(counts: &[[usize; 6]; 2], mirror: bool) -> u64 {
+    let mut key = 0u64;
+    for color in [WHITE, BLACK] {
+        let key_color = if mirror { color ^ 1 } else { color };
+        for piece in ALL_PIECES {
+            key = key.wrapping_add((counts[color][piece] as u64).wrapping_mul(PIECE_KEYS[key_color][piece]));
+        }
+    }
+
+    key
+}
+
+/// Stores squares of all pieces described by `pieces[index]` (lower three bits as a piece, the fourth one as a color, optionally swapped by `flip`)
+/// into `squares`, starting from `index`, and returns the index of the first free slot. Squares are mirrored vertically when `mirror` is 0x38.
+fn fill_squares(board: &Board, pieces: &[u8; MAX_PIECES], flip: bool, mirror: usize, squares: &mut [usize; MAX_PIECES], mut index: usize) -> usize {
+    let color = ((pieces[index] >> 3) as usize & 1) ^ (flip as usize);
+    let piece = ((pieces[index] & 0x07) as usize).saturating_sub(1);
+    let mut bitboard = board.pieces[color][piece];
+
+    if bitboard == 0 {
+        return MAX_PIECES;
+    }
+
+    while bitboard != 0 && index < MAX_PIECES {
+        squares[index] = bitboard.bit_scan() ^ mirror;
+        bitboard = bitboard.pop_lsb();
+        index += 1;
+    }
+
+    index
+}
+
+/// Moves the leading pawn (the one closest to the edge, according to the `FLAP` table) to the first slot of `squares` and returns
+/// the index of the subtable it belongs to.
+fn get_leading_pawn(squares: &mut [usize; MAX_PIECES], info: &TableInfo) -> usize {
+    for i in 1..info.pawns[0] {
+        if FLAP[squares[0]] > FLAP[squares[i]] {
+            squares.swap(0, i);
+        }
+    }
+
+    FILE_TO_FILE[squares[0] & 7]
+}
+
+/// Encodes pieces stored in `squares` into an index of the table described by `enc_info` and `info`.
+fn encode(squares: &mut [usize; MAX_PIECES], enc_info: &EncInfo, info: &TableInfo) -> usize {
+    let pieces_count = info.pieces_count;
+    let mut index;
+    let mut k;
+
+    if (squares[0] & 0x04) != 0 {
+        squares[..pieces_count].iter_mut().for_each(|square| *square ^= 0x07);
+    }
+
+    if !info.has_pawns {
+        if (squares[0] & 0x20) != 0 {
+            squares[..pieces_count].iter_mut().for_each(|square| *square ^= 0x38);
+        }
+
+        for i in 0..pieces_count {
+            let off_diag = OFF_DIAG[squares[i]];
+            if off_diag != 0 {
+                if off_diag > 0 && i < (if info.kk_enc { 2 } else { 3 }) {
+                    squares[..pieces_count].iter_mut().for_each(|square| *square = FLIP_DIAG[*square]);
+                }
+
+                break;
+            }
+        }
+
+        let (p0, p1, p2) = (squares[0], squares[1], squares[2]);
+        if info.kk_enc {
+            index = KK_IDX[TRIANGLE[p0]][p1] as usize;
+            k = 2;
+        } else {
+            let s1 = (p1 > p0) as usize;
+            let s2 = (p2 > p0) as usize + (p2 > p1) as usize;
+
+            index = if OFF_DIAG[p0] != 0 {
+                TRIANGLE[p0] * 63 * 62 + (p1 - s1) * 62 + (p2 - s2)
+            } else if OFF_DIAG[p1] != 0 {
+                6 * 63 * 62 + DIAG[p0] * 28 * 62 + LOWER[p1] * 62 + p2 - s2
+            } else if OFF_DIAG[p2] != 0 {
+                6 * 63 * 62 + 4 * 28 * 62 + DIAG[p0] * 7 * 28 + (DIAG[p1] - s1) * 28 + LOWER[p2]
+            } else {
+                6 * 63 * 62 + 4 * 28 * 62 + 4 * 7 * 28 + DIAG[p0] * 7 * 6 + (DIAG[p1] - s1) * 6 + (DIAG[p2] - s2)
+            };
+            k = 3;
+        }
+
+        index *= enc_info.factor[0];
+    } else {
+        let leading_pawns = info.pawns[0];
+        for i in 1..leading_pawns {
+            for j in (i + 1)..leading_pawns {
+                if PAWN_TWIST[squares[i]] < PAWN_TWIST[squares[j]] {
+                    squares.swap(i, j);
+                }
+            }
+        }
+
+        k = leading_pawns;
+        index = PAWN_IDX[k - 1][FLAP[squares[0]]];
+
+        for i in 1..k {
+            index += BINOMIAL[k - i][PAWN_TWIST[squares[i]]];
+        }
+        index *= enc_info.factor[0];
+
+        if info.pawns[1] > 0 {
+            let t = k + info.pawns[1];
+            squares[k..t].sort_unstable();
+
+            let mut subindex = 0;
+            for i in k..t {
+                let skips = squares[..k].iter().filter(|square| squares[i] > **square).count();
+                subindex += BINOMIAL[i - k + 1][squares[i] - skips - 8];
+            }
+
+            index += subindex * enc_info.factor[k];
+            k = t;
+        }
+    }
+
+    while k < pieces_count {
+        let t = k + enc_info.norm[k].max(1);
+        squares[k..t].sort_unstable();
+
+        let mut subindex = 0;
+        for i in k..t {
+            let skips = squares[..k].iter().filter(|square| squares[i] > **square).count();
+            subindex += BINOMIAL[i - k + 1][squares[i] - skips];
+        }
+
+        index += subindex * enc_info.factor[k];
+        k = t;
+    }
+
+    index
+}
+
+/// Calculates lengths of the `symbol` and (recursively) all symbols it consists of, using patterns stored in `data` at `sym_pat`.
+/// Returns [None] if the pattern refers to the non-existing symbol.
+fn calculate_sym_len(data: &[u8], sym_pat: usize, symbol: usize, sym_len: &mut [u8], visited: &mut [bool]) -> Option<()> {
+    let pattern = data.get(sym_pat + 3 * symbol..sym_pat + 3 * symbol + 3)?;
+    let right = ((pattern[2] as usize) << 4) | (pattern[1] as usize >> 4);
+
+    if right == 0x0fff {
+        sym_len[symbol] = 0;
+    } else {
+        let left = ((pattern[1] as usize & 0x0f) << 8) | pattern[0] as usize;
+        if left >= sym_len.len() || right >= sym_len.len() {
+            return None;
+        }
+
+        if !visited[left] {
+            calculate_sym_len(data, sym_pat, left, sym_len, visited)?;
+        }
+
+        if !visited[right] {
+            calculate_sym_len(data, sym_pat, right, sym_len, visited)?;
+        }
+
+        sym_len[symbol] = sym_len[left].wrapping_add(sym_len[right]).wrapping_add(1);
+    }
+
+    visited[symbol] = true;
+    Some(())
+}
+
+/// Gets the number of placements of `k` identical pieces on `n` squares.
+fn get_subfactor(k: usize, n: usize) -> usize {
+    let mut numerator = n;
+    let mut denominator = 1;
+
+    for i in 1..k {
+        numerator *= n - i;
+        denominator *= i + 1;
+    }
+
+    numerator / denominator
+}
+
+/// Gets binomial coefficients, where `result[k][n]` is equal to `n` choose `k`.
+const fn get_binomial() -> [[usize; 64]; 7] {
+    let mut result = [[0; 64]; 7];
+    let mut i = 0;
+
+    while i < 7 {
+        let mut j: usize = 0;
+        while j < 64 {
+            let mut numerator = 1;
+            let mut denominator = 1;
+            let mut k = 0;
+
+            while k < i {
+                numerator *= j.saturating_sub(k);
+                denominator *= k + 1;
+                k += 1;
+            }
+
+            result[i][j] = numerator / denominator;
+            j += 1;
+        }
+        i += 1;
+    }
+
+    result
+}
+
+/// Gets indices of the leading pawns and factors of the file-encoded pawn tables.
+const fn get_pawn_indices() -> ([[usize; 24]; 6], [[usize; 4]; 6]) {
+    let binomial = get_binomial();
+    let mut indices = [[0; 24]; 6];
+    let mut factors = [[0; 4]; 6];
+    let mut i = 0;
+
+    while i < 6 {
+        let mut sum = 0;
+        let mut j = 0;
+
+        while j < 24 {
+            indices[i][j] = sum;
+            sum += binomial[i][PAWN_TWIST[(1 + (j % 6)) * 8 + (j / 6)]];
+
+            if (j + 1) % 6 == 0 {
+                factors[i][j / 6] = sum;
+                sum = 0;
+            }
+
+            j += 1;
+        }
+        i += 1;
+    }
+
+    (indices, factors)
+}
+
+/// Gets byte of `data` at `index`, or 0 if it's out of range.
+fn get_byte(data: &[u8], index: usize) -> u8 {
+    *data.get(index).unwrap_or(&0)
+}
+
+/// Reads little-endian 16-bit value from `data` at `index`.
+fn read_le_u16(data: &[u8], index: usize) -> u16 {
+    u16::from_le_bytes([get_byte(data, index), get_byte(data, index + 1)])
+}
+
+/// Reads little-endian 32-bit value from `data` at `index`.
+fn read_le_u32(data: &[u8], index: usize) -> u32 {
+    u32::from_le_bytes([get_byte(data, index), get_byte(data, index + 1), get_byte(data, index + 2), get_byte(data, index + 3)])
+}
+
+/// Reads big-endian 32-bit value from `data` at `index`.
+fn read_be_u32(data: &[u8], index: usize) -> u32 {
+    u32::from_be_bytes([get_byte(data, index), get_byte(data, index + 1), get_byte(data, index + 2), get_byte(data, index + 3)])
+}
+
+/// Reads big-endian 64-bit value from `data` at `index`.
+fn read_be_u64(data: &[u8], index: usize) -> u64 {
+    ((read_be_u32(data, index) as u64) << 32) | (read_be_u32(data, index + 4) as u64)
+}
@@ -0,0 +1,301 @@
+use crate::cache::eval::EHTable;
+use crate::cache::material::MHTable;
+use crate::cache::pawns::PHTable;
+use crate::cache::search::TTable;
+use crate::engine;
+use crate::engine::context::SearchContext;
+use crate::state::movescan::Move;
+use crate::state::representation::Board;
+use crate::state::text::pgn::PGNLoader;
+use crate::state::text::pgn::ParsedPGN;
+use crate::state::*;
+use std::fs::File;
+use std::io::BufReader;
+use std::io::BufWriter;
+use std::io::Write;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+const MAX_SCORE: i16 = 1000;
+const NAG_MISTAKE: u8 = 2;
+const NAG_BLUNDER: u8 = 4;
+const NAG_INACCURACY: u8 = 6;
+
+#[derive(Clone, Copy)]
+pub enum AnalysisLimit {
+    Depth(i8),
+    MoveTime(u32),
+    Nodes(u64),
+}
+
+pub struct AnalysisOptions {
+    pub ttable_size: usize,
+    pub threads_count: usize,
+    pub inaccuracy_threshold: i16,
+    pub mistake_threshold: i16,
+    pub blunder_threshold: i16,
+}
+
+pub struct PositionAnalysis {
+    pub score: i16,
+    pub pv_line: Vec<Move>,
+}
+
+impl AnalysisOptions {
+    /// Sets analysis option `name` to `value`. Supported options:
+    ///  - `hash` - size of the transposition table in megabytes
+    ///  - `threads` - number of threads used by the search
+    ///  - `inaccuracy`, `mistake`, `blunder` - minimal score drop (in centipawns) for the move to be annotated as `?!`, `?` and `??`
+    ///
+    /// Returns [Err] with a proper error message if the option is unknown or its value is invalid.
+    pub fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+        match name {
+            "hash" => {
+                let ttable_size = value.parse::<usize>().map_err(|error| format!("Invalid transposition table size: {}", error))?;
+                if ttable_size == 0 {
+                    return Err(format!("Invalid transposition table size: value={}", value));
+                }
+
+                self.ttable_size = ttable_size * 1024 * 1024;
+            }
+            "threads" => {
+                self.threads_count = value.parse().map_err(|error| format!("Invalid threads count: {}", error))?;
+                if self.threads_count == 0 {
+                    return Err(format!("Invalid threads count: value={}", value));
+                }
+            }
+            "inaccuracy" => self.inaccuracy_threshold = value.parse().map_err(|error| format!("Invalid inaccuracy threshold: {}", error))?,
+            "mistake" => self.mistake_threshold = value.parse().map_err(|error| format!("Invalid mistake threshold: {}", error))?,
+            "blunder" => self.blunder_threshold = value.parse().map_err(|error| format!("Invalid blunder threshold: {}", error))?,
+            _ => return Err(format!("Invalid analysis option: name={}", name)),
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for AnalysisOptions {
+    /// Constructs a default instance of [AnalysisOptions] with 16 MB transposition table, a single thread, and thresholds of 50 (inaccuracy),
+    /// 100 (mistake) and 300 (blunder) centipawns.
+    fn default() -> Self {
+        Self { ttable_size: 16 * 1024 * 1024, threads_count: 1, inaccuracy_threshold: 50, mistake_threshold: 100, blunder_threshold: 300 }
+    }
+}
+
+impl PositionAnalysis {
+    /// Constructs a new instance of [PositionAnalysis] with stored `score` and `pv_line`.
+    pub fn new(score: i16, pv_line: Vec<Move>) -> Self {
+        Self { score, pv_line }
+    }
+}
+
+/// Runs analysis of all games stored in `pgn_filename`, and saves them annotated into `output_filename`. Every position is searched with `limit`,
+/// then every move gets `[%eval]` comment with the score after it (from white's point of view, `#N` for mates, replacing the one stored in PGN).
+/// If the score drop compared to the best move is big enough (based on thresholds in `options`), the move is annotated with `?!`, `?` or `??`,
+/// and the principal variation of the best move is added as an alternative.
+pub fn run(pgn_filename: &str, output_filename: &str, limit: AnalysisLimit, options: AnalysisOptions) {
+    println!("Loading PGN file...");
+
+    let start_time = SystemTime::now();
+    let file = match File::open(pgn_filename) {
+        Ok(value) => value,
+        Err(error) => {
+            println!("Invalid PGN file: {}", error);
+            return;
+        }
+    };
+
+    let output_file = match File::create(output_filename) {
+        Ok(value) => value,
+        Err(error) => {
+            println!("Invalid output file: {}", error);
+            return;
+        }
+    };
+
+    let mut writer = BufWriter::new(output_file);
+    let ttable = Arc::new(TTable::new(options.ttable_size));
+    let ehtable = Arc::new(EHTable::new(1 * 1024 * 1024));
+    let phtable = Arc::new(PHTable::new(1 * 1024 * 1024));
+    let mhtable = Arc::new(MHTable::new(256 * 1024));
+    let abort_flag = Arc::new(AtomicBool::new(false));
+    let ponder_flag = Arc::new(AtomicBool::new(false));
+
+    let mut analyzed_games = 0;
+    let mut skipped_games = 0;
+
+    for pgn in PGNLoader::new(BufReader::new(file)) {
+        let mut pgn = match pgn {
+            Ok(value) => value,
+            Err(error) => {
+                println!("{}, skipping", error);
+                skipped_games += 1;
+                continue;
+            }
+        };
+
+        let mut board = match &pgn.fen {
+            Some(fen) => match Board::new_from_fen(fen) {
+                Ok(value) => value,
+                Err(error) => {
+                    println!("Invalid initial FEN position: {}, skipping", error);
+                    skipped_games += 1;
+                    continue;
+                }
+            },
+            None => Board::new_initial_position(),
+        };
+
+        // Every position is searched once, including the last one, so the score of the move is known from the search of the position after it
+        let mut positions = Vec::new();
+        for data in &pgn.data {
+            positions.push(analyze_position(&board, limit, &options, &ttable, &ehtable, &phtable, &mhtable, &abort_flag, &ponder_flag));
+            board.make_move(data.r#move);
+        }
+        positions.push(analyze_position(&board, limit, &options, &ttable, &ehtable, &phtable, &mhtable, &abort_flag, &ponder_flag));
+
+        let annotations_count = annotate(&mut pgn, &positions, &options);
+        analyzed_games += 1;
+
+        println!(
+            "Game {}: {} moves analyzed, {} inaccuracies, {} mistakes, {} blunders",
+            analyzed_games,
+            pgn.data.len(),
+            annotations_count[0],
+            annotations_count[1],
+            annotations_count[2]
+        );
+
+        if !pgn.headers.iter().any(|(name, _)| name == "Annotator") {
+            pgn.headers.push(("Annotator".to_string(), format!("Inanis {}", env!("CARGO_PKG_VERSION"))));
+        }
+
+        let output = match pgn.to_pgn() {
+            Ok(value) => value,
+            Err(error) => {
+                println!("Invalid PGN: {}", error);
+                continue;
+            }
+        };
+
+        if let Err(error) = writer.write_all(output.as_bytes()) {
+            println!("Error while saving output: {}", error);
+            return;
+        }
+    }
+
+    if let Err(error) = writer.flush() {
+        println!("Error while saving output: {}", error);
+        return;
+    }
+
+    println!("-----------------------------------------------------------------------------");
+    println!(
+        "Analysis done in {:.2} s, {} games analyzed ({} skipped)",
+        (start_time.elapsed().unwrap().as_millis() as f32) / 1000.0,
+        analyzed_games,
+        skipped_games
+    );
+}
+
+/// Searches the position on the `board` with `limit`, using hashtables shared between all positions of the game and the number of threads
+/// specified in `options`. Returns the score (from the side to move perspective) and the principal variation, which is empty if the game is over.
+/// Positions with a single legal move are searched too (instead of returning the instant move without score), so the previous move gets a real score.
+pub fn analyze_position(
+    board: &Board,
+    limit: AnalysisLimit,
+    options: &AnalysisOptions,
+    ttable: &Arc<TTable>,
+    ehtable: &Arc<EHTable>,
+    phtable: &Arc<PHTable>,
+    mhtable: &Arc<MHTable>,
+    abort_flag: &Arc<AtomicBool>,
+    ponder_flag: &Arc<AtomicBool>,
+) -> PositionAnalysis {
+    let mut board = board.clone();
+    if board.get_legal_moves_count(&[]) == 0 {
+        let score = if board.is_king_checked(board.stm) { -engine::CHECKMATE_SCORE } else { 0 };
+        return PositionAnalysis::new(score, Vec::new());
+    }
+
+    let create_context =
+        || SearchContext::new(board.clone(), ttable.clone(), ehtable.clone(), phtable.clone(), mhtable.clone(), abort_flag.clone(), ponder_flag.clone());
+
+    let mut context = create_context();
+    context.instant_move = false;
+
+    match limit {
+        AnalysisLimit::Depth(depth) => context.forced_depth = depth,
+        AnalysisLimit::MoveTime(move_time) => context.max_move_time = move_time,
+        AnalysisLimit::Nodes(nodes) => context.max_nodes_count = nodes,
+    }
+
+    for _ in 0..options.threads_count - 1 {
+        context.helper_contexts.write().unwrap().push(create_context());
+    }
+
+    let mut result = PositionAnalysis::new(0, Vec::new());
+    while context.next().is_some() {
+        result = PositionAnalysis::new(context.lines[0].score, context.lines[0].pv_line.clone());
+    }
+
+    abort_flag.store(false, Ordering::Relaxed);
+    result
+}
+
+/// Annotates moves of `pgn` based on `positions` (analysis of every position in the game, including the last one): sets `[%eval]` scores and,
+/// if the score drop is big enough, NAGs and variations with the best moves. Returns the number of inaccuracies, mistakes and blunders.
+pub fn annotate(pgn: &mut ParsedPGN, positions: &[PositionAnalysis], options: &AnalysisOptions) -> [usize; 3] {
+    let mut annotations_count = [0; 3];
+    let mut stm = match &pgn.fen {
+        Some(fen) if fen.split_whitespace().nth(1) == Some("b") => BLACK,
+        _ => WHITE,
+    };
+
+    for (index, data) in pgn.data.iter_mut().enumerate() {
+        let best_score = positions[index].score;
+        let move_score = -positions[index + 1].score;
+
+        let white_score = if stm == WHITE { move_score } else { -move_score };
+        if engine::is_score_near_checkmate(white_score) {
+            data.eval = Some(100.0 * white_score.signum() as f32);
+            data.mate = Some(engine::get_moves_to_mate(white_score));
+        } else {
+            data.eval = Some(white_score as f32 / 100.0);
+            data.mate = None;
+        }
+
+        data.nag = None;
+        data.variation.clear();
+
+        // Mate scores are clamped, so choosing a slower mate is not considered as a mistake
+        let best_pv = &positions[index].pv_line;
+        let drop = best_score.clamp(-MAX_SCORE, MAX_SCORE) - move_score.clamp(-MAX_SCORE, MAX_SCORE);
+
+        if !best_pv.is_empty() && best_pv[0] != data.r#move {
+            let nag = if drop >= options.blunder_threshold {
+                annotations_count[2] += 1;
+                Some(NAG_BLUNDER)
+            } else if drop >= options.mistake_threshold {
+                annotations_count[1] += 1;
+                Some(NAG_MISTAKE)
+            } else if drop >= options.inaccuracy_threshold {
+                annotations_count[0] += 1;
+                Some(NAG_INACCURACY)
+            } else {
+                None
+            };
+
+            if nag.is_some() {
+                data.nag = nag;
+                data.variation = best_pv.clone();
+            }
+        }
+
+        stm ^= 1;
+    }
+
+    annotations_count
+}
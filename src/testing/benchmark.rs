@@ -1,3 +1,5 @@
+use crate::cache::eval::EHTable;
+use crate::cache::material::MHTable;
 use crate::cache::pawns::PHTable;
 use crate::cache::search::TTable;
 use crate::engine::context::SearchContext;
@@ -60,10 +62,18 @@ pub struct BenchmarkResult {
     pub cmtable_legal_moves: u64,
     pub cmtable_illegal_moves: u64,
 
+    pub ehtable_added: u64,
+    pub ehtable_hits: u64,
+    pub ehtable_misses: u64,
+
     pub phtable_added: u64,
     pub phtable_hits: u64,
     pub phtable_misses: u64,
 
+    pub mhtable_added: u64,
+    pub mhtable_hits: u64,
+    pub mhtable_misses: u64,
+
     pub movegen_hash_move_stages: u64,
     pub movegen_captures_stages: u64,
     pub movegen_killers_stages: u64,
@@ -118,12 +128,14 @@ pub fn run() -> BenchmarkResult {
         println!("{}/{}. {}", current_position_index + 1, BENCHMARK_POSITIONS.len(), fen);
 
         let ttable = Arc::new(TTable::new(64 * 1024 * 1024));
+        let ehtable = Arc::new(EHTable::new(2 * 1024 * 1024));
         let phtable = Arc::new(PHTable::new(2 * 1024 * 1024));
+        let mhtable = Arc::new(MHTable::new(256 * 1024));
         let abort_flag = Arc::new(AtomicBool::new(false));
         let ponder_flag = Arc::new(AtomicBool::new(false));
 
         let board = Board::new_from_fen(fen).unwrap();
-        let mut context = SearchContext::new(board, ttable.clone(), phtable.clone(), abort_flag.clone(), ponder_flag.clone());
+        let mut context = SearchContext::new(board, ttable.clone(), ehtable.clone(), phtable.clone(), mhtable.clone(), abort_flag.clone(), ponder_flag.clone());
 
         context.forced_depth = 16;
         context.by_ref().last().unwrap();
@@ -179,10 +191,18 @@ pub fn run() -> BenchmarkResult {
             benchmark_result.cmtable_legal_moves += context.stats.cmtable_legal_moves;
             benchmark_result.cmtable_illegal_moves += context.stats.cmtable_illegal_moves;
 
+            benchmark_result.ehtable_added += context.stats.ehtable_added;
+            benchmark_result.ehtable_hits += context.stats.ehtable_hits;
+            benchmark_result.ehtable_misses += context.stats.ehtable_misses;
+
             benchmark_result.phtable_added += context.stats.phtable_added;
             benchmark_result.phtable_hits += context.stats.phtable_hits;
             benchmark_result.phtable_misses += context.stats.phtable_misses;
 
+            benchmark_result.mhtable_added += context.stats.mhtable_added;
+            benchmark_result.mhtable_hits += context.stats.mhtable_hits;
+            benchmark_result.mhtable_misses += context.stats.mhtable_misses;
+
             benchmark_result.movegen_hash_move_stages += context.stats.movegen_hash_move_stages;
             benchmark_result.movegen_captures_stages += context.stats.movegen_captures_stages;
             benchmark_result.movegen_killers_stages += context.stats.movegen_killers_stages;
@@ -1,4 +1,6 @@
+pub mod analyze;
 pub mod benchmark;
 
 #[cfg(feature = "dev")]
 pub mod testset;
+pub mod verify;
@@ -1,5 +1,8 @@
+use crate::cache::eval::EHTable;
+use crate::cache::material::MHTable;
 use crate::cache::pawns::PHTable;
 use crate::cache::search::TTable;
+use crate::engine;
 use crate::engine::context::SearchContext;
 use crate::state::movescan::Move;
 use crate::state::representation::Board;
@@ -7,115 +10,245 @@ use crate::state::text::fen;
 use std::fs::File;
 use std::io::BufRead;
 use std::io::BufReader;
+use std::io::Write;
 use std::sync::atomic::AtomicBool;
 use std::sync::atomic::AtomicU32;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
+use std::sync::Mutex;
 use std::thread;
 use std::time::SystemTime;
 
+#[derive(Clone, Copy)]
+pub enum TestLimit {
+    Depth(i8),
+    MoveTime(u32),
+    Nodes(u64),
+}
+
 pub struct TestPosition {
+    index: usize,
     id: String,
     board: Board,
-    best_move: Move,
+    best_moves: Vec<Move>,
+    avoid_moves: Vec<Move>,
+    direct_mate: Option<i16>,
+}
+
+pub struct TestResult {
+    index: usize,
+    id: String,
+    expected: String,
+    found_move: Move,
+    score: i16,
+    depth: i8,
+    nodes: u64,
+    passed: bool,
+    solution_depth: i8,
+    solution_time: u32,
 }
 
 impl TestPosition {
-    /// Constructs a new instance of [TestPosition] with stored `id`, `board` and `best_move`.
-    pub fn new(id: String, board: Board, best_move: Move) -> Self {
-        Self { id, board, best_move }
+    /// Constructs a new instance of [TestPosition] with stored `index`, `id`, `board`, `best_moves`, `avoid_moves` and `direct_mate`.
+    pub fn new(index: usize, id: String, board: Board, best_moves: Vec<Move>, avoid_moves: Vec<Move>, direct_mate: Option<i16>) -> Self {
+        Self { index, id, board, best_moves, avoid_moves, direct_mate }
+    }
+
+    /// Checks if `r#move` with `score` solves the position, which means that all conditions have to be met:
+    ///  - `r#move` is one of the best moves (if `bm` was specified)
+    ///  - `r#move` is not one of the avoid moves (if `am` was specified)
+    ///  - `score` indicates a mate in the number of moves not greater than expected (if `dm` was specified)
+    pub fn is_solved(&self, r#move: Move, score: i16) -> bool {
+        if !self.best_moves.is_empty() && !self.best_moves.contains(&r#move) {
+            return false;
+        }
+
+        if !self.avoid_moves.is_empty() && self.avoid_moves.contains(&r#move) {
+            return false;
+        }
+
+        if let Some(direct_mate) = self.direct_mate {
+            if score <= 0 || !engine::is_score_near_checkmate(score) {
+                return false;
+            }
+
+            let moves_to_mate = (engine::CHECKMATE_SCORE - score + 1) / 2;
+            if moves_to_mate > direct_mate {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// Gets a human-readable description of the conditions required to solve the position.
+    pub fn get_expected(&self) -> String {
+        let mut expected = Vec::new();
+
+        if !self.best_moves.is_empty() {
+            expected.push(format!("bm {}", self.best_moves.iter().map(|v| v.to_string()).collect::<Vec<String>>().join(" ")));
+        }
+
+        if !self.avoid_moves.is_empty() {
+            expected.push(format!("am {}", self.avoid_moves.iter().map(|v| v.to_string()).collect::<Vec<String>>().join(" ")));
+        }
+
+        if let Some(direct_mate) = self.direct_mate {
+            expected.push(format!("dm {}", direct_mate));
+        }
+
+        expected.join(", ")
     }
 }
 
-/// Runs a test by performing a fixed-`depth` search for the positions loaded from the `epd_filename` file, using hashtable with
-/// size specified in `ttable_size`. To classify the test as successful, the last iteration has to return the correct best move.
-/// Multithreading is supported by `threads_count`.
-pub fn run(epd_filename: &str, depth: i8, ttable_size: usize, threads_count: usize) {
+/// Runs a test by performing a search for the positions loaded from the `epd_filename` file, limited by `limit` (fixed depth, move time or nodes count)
+/// and using hashtable with size specified in `ttable_size`. To classify the test as successful, the last iteration has to meet all conditions stored
+/// in `bm`, `am` and `dm` opcodes. Solution depth and time are measured from the iteration since which the result stayed correct. Multithreading
+/// is supported by `threads_count`. If `summary_filename` is specified, results are also saved there as JSON (if the extension is `.json`) or CSV.
+pub fn run(epd_filename: &str, limit: TestLimit, ttable_size: usize, threads_count: usize, summary_filename: Option<&str>) {
     println!("Loading EPD file...");
     let mut positions = match load_positions(epd_filename) {
         Ok(value) => value,
         Err(error) => {
-            println!("Invalid PGN: {}", error);
+            println!("Invalid EPD: {}", error);
             return;
         }
     };
     println!("Loaded {} positions, starting test", positions.len());
 
+    if positions.is_empty() {
+        return;
+    }
+
     let index = Arc::new(AtomicU32::new(0));
-    let passed_tests = Arc::new(AtomicU32::new(0));
-    let failed_tests = Arc::new(AtomicU32::new(0));
-    let recognition_depths_sum = Arc::new(AtomicU32::new(0));
+    let results = Arc::new(Mutex::new(Vec::new()));
     let start_time = SystemTime::now();
     let positions_count = positions.len();
+    let chunk_size = positions_count.div_ceil(threads_count.max(1));
 
     thread::scope(|scope| {
-        for chunk in positions.chunks_mut(positions_count / threads_count) {
+        for chunk in positions.chunks_mut(chunk_size) {
             let index_arc = index.clone();
-            let passed_tests_arc = passed_tests.clone();
-            let failed_tests_arc = failed_tests.clone();
-            let recognition_depths_sum_arc = recognition_depths_sum.clone();
+            let results_arc = results.clone();
 
             scope.spawn(move || {
                 for position in chunk {
-                    let ttable = Arc::new(TTable::new(ttable_size));
-                    let phtable = Arc::new(PHTable::new(1 * 1024 * 1024));
-                    let abort_flag = Arc::new(AtomicBool::new(false));
-                    let ponder_flag = Arc::new(AtomicBool::new(false));
-
-                    let board_clone = position.board.clone();
-                    let mut context = SearchContext::new(board_clone, ttable, phtable, abort_flag, ponder_flag);
-                    context.forced_depth = depth;
-
-                    let mut last_best_move = Move::default();
-                    let mut best_moves_count = 0;
-                    let mut recognition_depth = 0;
-
-                    while let Some(result) = context.next() {
-                        last_best_move = context.lines[0].pv_line[0];
-                        if last_best_move == position.best_move {
-                            if best_moves_count == 0 {
-                                recognition_depth = result.depth;
-                            }
-
-                            best_moves_count += 1;
-                        } else {
-                            best_moves_count = 0;
-                        }
-                    }
-
+                    let result = run_position(position, limit, ttable_size);
                     let index_to_display = index_arc.fetch_add(1, Ordering::Relaxed);
-                    if last_best_move == position.best_move {
-                        println!("{}/{}. Test {} PASSED (depth: {})", index_to_display + 1, positions_count, position.id, recognition_depth);
-                        recognition_depths_sum_arc.fetch_add(recognition_depth as u32, Ordering::Relaxed);
-                        passed_tests_arc.fetch_add(1, Ordering::Relaxed);
+
+                    if result.passed {
+                        println!(
+                            "{}/{}. Test {} PASSED (depth: {}, time: {} ms)",
+                            index_to_display + 1,
+                            positions_count,
+                            result.id,
+                            result.solution_depth,
+                            result.solution_time
+                        );
                     } else {
                         println!(
-                            "{}/{}. Test {} FAILED (expected {}, got {})",
+                            "{}/{}. Test {} FAILED (expected {}, got {} with score {})",
                             index_to_display + 1,
                             positions_count,
-                            position.id,
-                            position.best_move,
-                            last_best_move
+                            result.id,
+                            result.expected,
+                            result.found_move,
+                            result.score
                         );
-                        failed_tests_arc.fetch_add(1, Ordering::Relaxed);
                     }
+
+                    results_arc.lock().unwrap().push(result);
                 }
             });
         }
     });
 
+    let mut results = results.lock().unwrap();
+    results.sort_by_key(|result| result.index);
+
+    let passed_results = results.iter().filter(|result| result.passed).collect::<Vec<&TestResult>>();
+    let passed_tests = passed_results.len();
+    let failed_tests = results.len() - passed_tests;
+    let solution_depths_sum = passed_results.iter().map(|result| result.solution_depth as u32).sum::<u32>();
+    let solution_times_sum = passed_results.iter().map(|result| result.solution_time as u64).sum::<u64>();
+
     println!("-----------------------------------------------------------------------------");
     println!(
-        "Tests done in {:.2} s: {} passed ({:.2}% with average depth {:.2}), {} failed",
+        "Tests done in {:.2} s: {} passed ({:.2}% with average depth {:.2} and time {:.2} ms), {} failed",
         (start_time.elapsed().unwrap().as_millis() as f32) / 1000.0,
-        passed_tests.load(Ordering::Relaxed),
-        (passed_tests.load(Ordering::Relaxed) as f32) / (positions_count as f32) * 100.0,
-        (recognition_depths_sum.load(Ordering::Relaxed) as f32) / (passed_tests.load(Ordering::Relaxed) as f32),
-        failed_tests.load(Ordering::Relaxed)
+        passed_tests,
+        (passed_tests as f32) / (positions_count as f32) * 100.0,
+        (solution_depths_sum as f32) / (passed_tests as f32),
+        (solution_times_sum as f32) / (passed_tests as f32),
+        failed_tests
     );
+
+    if let Some(summary_filename) = summary_filename {
+        let summary = if summary_filename.ends_with(".json") { get_json_summary(&results) } else { get_csv_summary(&results) };
+        match File::create(summary_filename).and_then(|mut file| file.write_all(summary.as_bytes())) {
+            Ok(_) => println!("Summary saved to {}", summary_filename),
+            Err(error) => println!("Can't save summary: {}", error),
+        }
+    }
+}
+
+/// Performs a search of the single `position`, limited by `limit` and using hashtable with size specified in `ttable_size`.
+fn run_position(position: &TestPosition, limit: TestLimit, ttable_size: usize) -> TestResult {
+    let ttable = Arc::new(TTable::new(ttable_size));
+    let ehtable = Arc::new(EHTable::new(1 * 1024 * 1024));
+    let phtable = Arc::new(PHTable::new(1 * 1024 * 1024));
+    let mhtable = Arc::new(MHTable::new(256 * 1024));
+    let abort_flag = Arc::new(AtomicBool::new(false));
+    let ponder_flag = Arc::new(AtomicBool::new(false));
+
+    let board_clone = position.board.clone();
+    let mut context = SearchContext::new(board_clone, ttable, ehtable, phtable, mhtable, abort_flag, ponder_flag);
+
+    match limit {
+        TestLimit::Depth(depth) => context.forced_depth = depth,
+        TestLimit::MoveTime(move_time) => context.max_move_time = move_time,
+        TestLimit::Nodes(nodes) => context.max_nodes_count = nodes,
+    }
+
+    let mut last_best_move = Move::default();
+    let mut last_score = 0;
+    let mut last_depth = 0;
+    let mut solved = false;
+    let mut solution_depth = 0;
+    let mut solution_time = 0;
+
+    while let Some(result) = context.next() {
+        last_best_move = context.lines[0].pv_line[0];
+        last_score = context.lines[0].score;
+        last_depth = result.depth;
+
+        if position.is_solved(last_best_move, last_score) {
+            if !solved {
+                solution_depth = result.depth;
+                solution_time = result.time;
+            }
+
+            solved = true;
+        } else {
+            solved = false;
+        }
+    }
+
+    TestResult {
+        index: position.index,
+        id: position.id.clone(),
+        expected: position.get_expected(),
+        found_move: last_best_move,
+        score: last_score,
+        depth: last_depth,
+        nodes: context.stats.nodes_count + context.stats.q_nodes_count,
+        passed: solved,
+        solution_depth: if solved { solution_depth } else { 0 },
+        solution_time: if solved { solution_time } else { 0 },
+    }
 }
 
-/// Loads positions from the `epd_filename` and parses them into a list of [TestPosition].
+/// Loads positions from the `epd_filename` and parses them into a list of [TestPosition]. Every position must contain at least one of `bm`, `am` or `dm` opcodes.
 /// Returns [Err] with a proper error message if the file couldn't be parsed.
 fn load_positions(epd_filename: &str) -> Result<Vec<TestPosition>, String> {
     let mut positions = Vec::new();
@@ -126,14 +259,95 @@ fn load_positions(epd_filename: &str) -> Result<Vec<TestPosition>, String> {
 
     for line in BufReader::new(file).lines() {
         let position = line.unwrap();
-        if position.is_empty() {
+        if position.trim().is_empty() {
             continue;
         }
 
         let mut parsed_epd = fen::epd_to_board(position.as_str())?;
-        let parsed_best_move = Move::from_short_notation(&parsed_epd.best_move.unwrap(), &mut parsed_epd.board)?;
-        positions.push(TestPosition::new(parsed_epd.id.unwrap(), parsed_epd.board, parsed_best_move));
+        let best_moves = parse_moves(&parsed_epd.best_move, &mut parsed_epd.board)?;
+        let avoid_moves = parse_moves(&parsed_epd.avoid_move, &mut parsed_epd.board)?;
+        let direct_mate = match parsed_epd.direct_mate {
+            Some(value) => match value.parse::<i16>() {
+                Ok(value) => Some(value),
+                Err(error) => return Err(format!("Invalid direct mate: {}, epd={}", error, position)),
+            },
+            None => None,
+        };
+
+        if best_moves.is_empty() && avoid_moves.is_empty() && direct_mate.is_none() {
+            return Err(format!("No bm, am or dm opcodes found: epd={}", position));
+        }
+
+        let id = parsed_epd.id.unwrap_or_else(|| (positions.len() + 1).to_string());
+        positions.push(TestPosition::new(positions.len(), id, parsed_epd.board, best_moves, avoid_moves, direct_mate));
     }
 
     Ok(positions)
 }
+
+/// Parses space-separated list of moves in short notation stored in `moves`, using `board` to resolve them.
+fn parse_moves(moves: &Option<String>, board: &mut Board) -> Result<Vec<Move>, String> {
+    let mut result = Vec::new();
+    if let Some(moves) = moves {
+        for r#move in moves.split_whitespace() {
+            result.push(Move::from_short_notation(r#move, board)?);
+        }
+    }
+
+    Ok(result)
+}
+
+/// Gets summary of all `results` in the CSV format.
+fn get_csv_summary(results: &[TestResult]) -> String {
+    let mut output = String::new();
+    output.push_str("id,expected,move,score,depth,nodes,passed,solution_depth,solution_time\n");
+
+    for result in results {
+        output.push_str(&format!(
+            "\"{}\",\"{}\",{},{},{},{},{},{},{}\n",
+            result.id.replace('"', "\"\""),
+            result.expected,
+            result.found_move,
+            result.score,
+            result.depth,
+            result.nodes,
+            result.passed,
+            result.solution_depth,
+            result.solution_time
+        ));
+    }
+
+    output
+}
+
+/// Gets summary of all `results` in the JSON format.
+fn get_json_summary(results: &[TestResult]) -> String {
+    let passed_tests = results.iter().filter(|result| result.passed).count();
+    let mut output = String::new();
+
+    output.push_str("{\n");
+    output.push_str(&format!("  \"total\": {},\n", results.len()));
+    output.push_str(&format!("  \"passed\": {},\n", passed_tests));
+    output.push_str(&format!("  \"failed\": {},\n", results.len() - passed_tests));
+    output.push_str("  \"positions\": [\n");
+
+    for (index, result) in results.iter().enumerate() {
+        output.push_str(&format!(
+            "    {{ \"id\": \"{}\", \"expected\": \"{}\", \"move\": \"{}\", \"score\": {}, \"depth\": {}, \"nodes\": {}, \"passed\": {}, \"solution_depth\": {}, \"solution_time\": {} }}{}\n",
+            result.id.replace('\\', "\\\\").replace('"', "\\\""),
+            result.expected,
+            result.found_move,
+            result.score,
+            result.depth,
+            result.nodes,
+            result.passed,
+            result.solution_depth,
+            result.solution_time,
+            if index + 1 < results.len() { "," } else { "" }
+        ));
+    }
+
+    output.push_str("  ]\n");
+    output.push_str("}\n");
+    output
+}
@@ -0,0 +1,156 @@
+use crate::engine;
+use crate::evaluation::trace::EvalTrace;
+use crate::state::representation::Board;
+use crate::state::text::fen;
+use std::fs::File;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::mem::MaybeUninit;
+
+pub struct VerifyResult {
+    pub positions_count: u64,
+    pub error: Option<String>,
+}
+
+impl VerifyResult {
+    /// Constructs a new instance of [VerifyResult] with stored `positions_count` and `error`.
+    pub fn new(positions_count: u64, error: Option<String>) -> Self {
+        Self { positions_count, error }
+    }
+}
+
+/// Entry point of the evaluation verifier, which checks every position reached from the `board` during perft to the specified `depth`:
+///  - evaluation of the position mirrored vertically (with swapped colors) has to be the same from the side to move perspective
+///  - evaluation of the position flipped horizontally has to be the same, excluding piece-square tables (king buckets are not symmetric)
+///  - hashes, material key and piece-square tables score have to be equal to the ones recalculated from scratch
+///  - hashes, material key and piece-square tables score have to be restored after undoing every move
+///
+/// Verification stops at the first failure, and the FEN of the invalid position is included in the error message.
+pub fn run(board: &mut Board, depth: i32) -> VerifyResult {
+    let mut positions_count = 0;
+    let error = verify_internal(board, depth, &mut positions_count).err();
+
+    VerifyResult::new(positions_count, error)
+}
+
+/// Runs the same verification as [run] for every position stored in the `epd_filename` file (up to the specified `depth`). Returns [Err] if the file
+/// couldn't be read or parsed correctly.
+pub fn run_epd(epd_filename: &str, depth: i32) -> Result<VerifyResult, String> {
+    let file = match File::open(epd_filename) {
+        Ok(value) => value,
+        Err(error) => return Err(format!("Invalid EPD file: {}", error)),
+    };
+
+    let mut positions_count = 0;
+    for line in BufReader::new(file).lines() {
+        let line = line.map_err(|error| format!("Invalid EPD file: {}", error))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let mut board = fen::epd_to_board(&line)?.board;
+        if let Err(error) = verify_internal(&mut board, depth, &mut positions_count) {
+            return Ok(VerifyResult::new(positions_count, Some(error)));
+        }
+    }
+
+    Ok(VerifyResult::new(positions_count, None))
+}
+
+/// Internal verifier function, checks the `board` and recursively all positions reachable in `depth` moves, incrementing `positions_count`.
+fn verify_internal(board: &mut Board, depth: i32, positions_count: &mut u64) -> Result<(), String> {
+    verify_incremental_values(board)?;
+    verify_symmetry(board)?;
+    *positions_count += 1;
+
+    if depth <= 0 {
+        return Ok(());
+    }
+
+    let mut moves = [MaybeUninit::uninit(); engine::MAX_MOVES_COUNT];
+    let moves_count = board.get_all_moves(&mut moves, u64::MAX);
+
+    for r#move in &moves[0..moves_count] {
+        let r#move = unsafe { r#move.assume_init() };
+        let original = board.clone();
+
+        board.make_move(r#move);
+        if !board.is_king_checked(board.stm ^ 1) {
+            verify_internal(board, depth - 1, positions_count)?;
+        }
+        board.undo_move(r#move);
+
+        if board.state.hash != original.state.hash
+            || board.state.pawn_hash != original.state.pawn_hash
+            || board.state.material_key != original.state.material_key
+            || board.state.pst_score.data != original.state.pst_score.data
+        {
+            return Err(format!("Invalid state after undoing move: fen={}, r#move={}", original, r#move.to_long_notation()));
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks if hashes, material key and piece-square tables score of the `board` are equal to the ones recalculated from scratch.
+fn verify_incremental_values(board: &Board) -> Result<(), String> {
+    let mut recalculated = board.clone();
+    recalculated.recalculate_hashes();
+    recalculated.recalculate_incremental_values();
+
+    if board.state.hash != recalculated.state.hash {
+        return Err(format!("Invalid hash: fen={}, hash={}, recalculated={}", board, board.state.hash, recalculated.state.hash));
+    }
+
+    if board.state.pawn_hash != recalculated.state.pawn_hash {
+        return Err(format!("Invalid pawn hash: fen={}, pawn_hash={}, recalculated={}", board, board.state.pawn_hash, recalculated.state.pawn_hash));
+    }
+
+    if board.state.material_key != recalculated.state.material_key {
+        return Err(format!(
+            "Invalid material key: fen={}, material_key={}, recalculated={}",
+            board, board.state.material_key, recalculated.state.material_key
+        ));
+    }
+
+    if board.state.pst_score.data != recalculated.state.pst_score.data {
+        return Err(format!(
+            "Invalid piece-square tables score: fen={}, pst_score={}, recalculated={}",
+            board, board.state.pst_score.data, recalculated.state.pst_score.data
+        ));
+    }
+
+    Ok(())
+}
+
+/// Checks if evaluation of the `board` is invariant to the vertical mirror with swapped colors, and to the horizontal flip (excluding
+/// piece-square tables, which depend on the king bucket). Additionally, checks if the evaluation trace gives the same score as the evaluator.
+fn verify_symmetry(board: &Board) -> Result<(), String> {
+    let mirrored = board.mirror();
+    let eval = board.evaluate_without_cache(board.stm);
+    let mirrored_eval = mirrored.evaluate_without_cache(mirrored.stm);
+
+    if eval != mirrored_eval {
+        return Err(format!("Invalid mirrored evaluation: fen={}, mirrored_fen={}, eval={}, mirrored_eval={}", board, mirrored, eval, mirrored_eval));
+    }
+
+    let flipped = board.flip();
+    let eval_without_pst = board.evaluate_without_cache_and_pst(board.stm);
+    let flipped_eval_without_pst = flipped.evaluate_without_cache_and_pst(flipped.stm);
+
+    if eval_without_pst != flipped_eval_without_pst {
+        return Err(format!(
+            "Invalid flipped evaluation: fen={}, flipped_fen={}, eval={}, flipped_eval={}",
+            board, flipped, eval_without_pst, flipped_eval_without_pst
+        ));
+    }
+
+    let sign = -((board.stm as i16) * 2 - 1);
+    let trace_eval = EvalTrace::new(board).get_score();
+
+    if sign * eval != trace_eval {
+        return Err(format!("Invalid traced evaluation: fen={}, eval={}, trace_eval={}", board, sign * eval, trace_eval));
+    }
+
+    Ok(())
+}
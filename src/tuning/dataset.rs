@@ -1,3 +1,5 @@
+use crate::cache::eval::EHTable;
+use crate::cache::material::MHTable;
 use crate::cache::pawns::PHTable;
 use crate::cache::search::TTable;
 use crate::engine::context::SearchContext;
@@ -6,21 +8,91 @@ use crate::engine::*;
 use crate::evaluation::material;
 use crate::evaluation::*;
 use crate::state::representation::Board;
+use crate::state::text::fen;
 use crate::state::text::pgn::PGNLoader;
+use crate::state::text::pgn::ParsedPGN;
+use crate::state::*;
 use crate::utils::rand;
 use std::collections::HashSet;
 use std::fs::File;
-use std::io::BufRead;
 use std::io::BufReader;
 use std::io::LineWriter;
 use std::io::Write;
 use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
+use std::thread;
 use std::time::SystemTime;
 
+const BATCH_SIZE: usize = 1000;
+const RESULTS: [&str; 3] = ["1-0", "1/2-1/2", "0-1"];
+
+pub struct DatasetOptions {
+    pub threads_count: usize,
+    pub rescore_depth: Option<i8>,
+    pub phase_targets: Option<Vec<f32>>,
+    pub wdl_targets: Option<Vec<f32>>,
+}
+
+#[derive(Clone)]
+pub struct DatasetPosition {
+    pub epd: String,
+    pub hash: u64,
+    pub eval: f32,
+    pub result: usize,
+    pub game_phase: f32,
+}
+
+impl DatasetOptions {
+    /// Sets dataset option `name` to `value`. Supported options:
+    ///  - `threads` - number of threads used to process games
+    ///  - `rescore` - depth of the search used to score every output position instead of `[%eval]` stored in PGN (0 = disabled)
+    ///  - `phases` - comma-separated target weights of game phase buckets (equal ranges from ending to opening)
+    ///  - `wdl` - comma-separated target weights of white wins, draws and black wins
+    ///
+    /// Returns [Err] with a proper error message if the option is unknown or its value is invalid.
+    pub fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+        match name {
+            "threads" => {
+                self.threads_count = value.parse().map_err(|error| format!("Invalid threads count: {}", error))?;
+                if self.threads_count == 0 {
+                    return Err(format!("Invalid threads count: value={}", value));
+                }
+            }
+            "rescore" => {
+                let depth = value.parse().map_err(|error| format!("Invalid rescoring depth: {}", error))?;
+                self.rescore_depth = if depth > 0 { Some(depth) } else { None };
+            }
+            "phases" => self.phase_targets = Some(parse_targets(value)?),
+            "wdl" => {
+                let targets = parse_targets(value)?;
+                if targets.len() != RESULTS.len() {
+                    return Err(format!("Invalid WDL targets: value={}", value));
+                }
+
+                self.wdl_targets = Some(targets);
+            }
+            _ => return Err(format!("Invalid dataset option: name={}", name)),
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for DatasetOptions {
+    /// Constructs a default instance of [DatasetOptions] with a single thread, scores taken from PGN and no balancing.
+    fn default() -> Self {
+        Self { threads_count: 1, rescore_depth: None, phase_targets: None, wdl_targets: None }
+    }
+}
+
 /// Runs generator of the dataset for the tuner. It works by parsing `pgn_filename`, and then picking random positions based on the
 /// provided restrictions like `min_ply`, `max_score`, `max_diff` and `density`. Output positions are then stored in the `output_file`.
-pub fn run(pgn_filename: &str, output_file: &str, min_ply: usize, max_score: i16, max_diff: u16, density: usize) {
+///
+/// Games are read in batches and processed by the number of threads specified in `options`, positions are deduplicated using their Zobrist hashes.
+/// Optionally, every picked position can be scored again by the fixed-depth search, and the output can be balanced to match the target distribution
+/// of game phases and results (by dropping positions from the overrepresented buckets).
+pub fn run(pgn_filename: &str, output_file: &str, min_ply: usize, max_score: i16, max_diff: u16, density: usize, options: DatasetOptions) {
     println!("Loading PGN file...");
 
     let start_time = SystemTime::now();
@@ -32,124 +104,98 @@ pub fn run(pgn_filename: &str, output_file: &str, min_ply: usize, max_score: i16
         }
     };
 
-    let pgn_loader = PGNLoader::new(BufReader::new(file).lines());
-    let mut output_positions = HashSet::new();
+    let mut pgn_loader = PGNLoader::new(BufReader::new(file));
+    let mut output_positions = Vec::new();
+    let mut output_hashes = HashSet::new();
     let mut parsed_pgns = 0;
-
-    let ttable = Arc::new(TTable::new(1 * 1024 * 1024));
-    let phtable = Arc::new(PHTable::new(1 * 1024 * 1024));
-    let abort_flag = Arc::new(AtomicBool::new(false));
-    let ponder_flag = Arc::new(AtomicBool::new(false));
+    let mut skipped_pgns = 0;
 
     let mut total_viable_positions = 0;
     let mut ignored_positions = 0;
     let mut duplicates = 0;
-    let mut sum_of_game_phases = 0.0;
 
-    for pgn in pgn_loader {
-        let pgn = match pgn {
-            Ok(value) => value,
-            Err(error) => {
-                println!("Invalid PGN file: {}", error);
-                return;
+    loop {
+        let mut batch = Vec::new();
+        while batch.len() < BATCH_SIZE {
+            match pgn_loader.next() {
+                Some(Ok(pgn)) => batch.push(pgn),
+                Some(Err(error)) => {
+                    println!("{}, skipping", error);
+                    skipped_pgns += 1;
+                }
+                None => break,
             }
-        };
+        }
 
-        if pgn.result == "*" {
-            continue;
+        if batch.is_empty() {
+            break;
         }
 
-        let board = match pgn.fen {
-            Some(fen) => {
-                let fen_result = Board::new_from_fen(&fen);
-                match fen_result {
-                    Ok(board) => board,
-                    Err(error) => {
-                        println!("Invalid PGN file: {}", error);
-                        return;
-                    }
+        let rescored = options.rescore_depth.is_some();
+        let games =
+            match process_in_parallel(&batch, options.threads_count, |pgn, tables| get_viable_positions(pgn, min_ply, max_score, max_diff, rescored, tables)) {
+                Ok(value) => value,
+                Err(error) => {
+                    println!("Invalid PGN file: {}", error);
+                    return;
                 }
-            }
-            None => Board::new_initial_position(),
-        };
-
-        let mut context = SearchContext::new(board, ttable.clone(), phtable.clone(), abort_flag.clone(), ponder_flag.clone());
-        let mut viable_positions = Vec::new();
-
-        for (index, data) in pgn.data.iter().enumerate() {
-            context.board.make_move(data.r#move);
-
-            if index < min_ply {
-                ignored_positions += 1;
-                continue;
-            }
-
-            if data.r#move.is_capture() || data.r#move.is_castling() || data.r#move.is_promotion() {
-                ignored_positions += 1;
-                continue;
-            }
+            };
 
-            if context.board.is_king_checked(context.board.stm) {
-                ignored_positions += 1;
-                continue;
-            }
+        let mut picked_positions = Vec::new();
+        for (mut viable_positions, ignored) in games {
+            total_viable_positions += viable_positions.len();
+            ignored_positions += ignored;
 
-            let material_evaluation = material::evaluate(&context.board);
-            if material_evaluation.taper_score(context.board.game_phase).abs() > max_score {
-                ignored_positions += 1;
-                continue;
-            }
+            let mut picked_in_game = 0;
+            while picked_in_game < density && !viable_positions.is_empty() {
+                let position = viable_positions.swap_remove(rand::usize(0..viable_positions.len()));
 
-            let score = context.board.evaluate_without_cache(context.board.stm);
-            let q_score = qsearch::run(&mut context, 0, MIN_ALPHA, MIN_BETA);
+                if !output_hashes.insert(position.hash) {
+                    duplicates += 1;
+                    continue;
+                }
 
-            if score.abs_diff(q_score) > max_diff {
-                ignored_positions += 1;
-                continue;
+                picked_positions.push(position);
+                picked_in_game += 1;
             }
-
-            let epd = format!("{} c9 \"{:.2}|{}\";", context.board.to_epd(), data.eval, pgn.result);
-            let game_phase = (context.board.game_phase as f32) / (INITIAL_GAME_PHASE as f32);
-
-            viable_positions.push((epd, game_phase));
-            total_viable_positions += 1;
         }
 
-        let mut picked_positions = 0;
-
-        while picked_positions < density {
-            if viable_positions.is_empty() {
-                break;
-            }
-
-            let index = rand::usize(0..viable_positions.len());
-            let (position, game_phase) = viable_positions[index].to_owned();
+        if let Some(depth) = options.rescore_depth {
+            let rescored = process_in_parallel(&picked_positions, options.threads_count, |position, tables| rescore(position, depth, tables));
+            let rescored = match rescored {
+                Ok(value) => value,
+                Err(error) => {
+                    println!("Invalid position: {}", error);
+                    return;
+                }
+            };
 
-            if output_positions.contains(&position) {
-                viable_positions.remove(index);
-                duplicates += 1;
+            let picked_count = picked_positions.len();
+            picked_positions = rescored.into_iter().flatten().collect();
+            ignored_positions += picked_count - picked_positions.len();
+        }
 
-                continue;
-            }
+        output_positions.extend(picked_positions);
+        parsed_pgns += batch.len();
 
-            output_positions.insert(position);
-            viable_positions.remove(index);
-            picked_positions += 1;
-            sum_of_game_phases += game_phase;
-        }
+        println!(
+            "Parsed PGNs: {} ({} skipped, {} viable positions, {} ignored positions, {} output positions, {} duplicates)",
+            parsed_pgns,
+            skipped_pgns,
+            total_viable_positions,
+            ignored_positions,
+            output_positions.len(),
+            duplicates
+        );
+    }
 
-        parsed_pgns += 1;
+    if options.phase_targets.is_some() || options.wdl_targets.is_some() {
+        println!("-----------------------------------------------------------------------------");
+        println!("Balancing output...");
 
-        if parsed_pgns % 1000 == 0 {
-            println!(
-                "Parsed PGNs: {} ({} viable positions, {} ignored positions, {} output positions, {} duplicates)",
-                parsed_pgns,
-                total_viable_positions,
-                ignored_positions,
-                output_positions.len(),
-                duplicates
-            );
-        }
+        let phase_targets = options.phase_targets.unwrap_or(vec![1.0]);
+        let wdl_targets = options.wdl_targets.unwrap_or(vec![1.0]);
+        output_positions = balance(output_positions, &phase_targets, &wdl_targets);
     }
 
     println!("-----------------------------------------------------------------------------");
@@ -163,15 +209,264 @@ pub fn run(pgn_filename: &str, output_file: &str, min_ply: usize, max_score: i16
         }
     };
     let mut output_file_line_writer = LineWriter::new(output_file);
-    let positions_count = output_positions.len();
+    let mut results_count = [0; 3];
+    let mut sum_of_game_phases = 0.0;
 
-    for fen in output_positions {
-        output_file_line_writer.write_all((fen + "\n").as_bytes()).unwrap();
+    for position in &output_positions {
+        let line = format!("{} c9 \"{:.2}|{}\";\n", position.epd, position.eval, RESULTS[position.result]);
+        output_file_line_writer.write_all(line.as_bytes()).unwrap();
+
+        results_count[position.result] += 1;
+        sum_of_game_phases += position.game_phase;
     }
 
     println!(
-        "Tuner dataset generation done in {:.2} s, average game phase: {:.2}",
+        "Tuner dataset generation done in {:.2} s, {} positions (white wins: {}, draws: {}, black wins: {}), average game phase: {:.2}",
         (start_time.elapsed().unwrap().as_millis() as f32) / 1000.0,
-        sum_of_game_phases / (positions_count as f32)
+        output_positions.len(),
+        results_count[0],
+        results_count[1],
+        results_count[2],
+        sum_of_game_phases / (output_positions.len() as f32)
     );
 }
+
+/// Parses comma-separated list of target weights stored in `value`. Returns [Err] with a proper error message if any weight is invalid or all of them are zero.
+fn parse_targets(value: &str) -> Result<Vec<f32>, String> {
+    let mut targets = Vec::new();
+    for target in value.split(',') {
+        let target = target.parse::<f32>().map_err(|error| format!("Invalid target weight: {}", error))?;
+        if target < 0.0 {
+            return Err(format!("Invalid target weight: value={}", target));
+        }
+
+        targets.push(target);
+    }
+
+    if targets.iter().sum::<f32>() <= 0.0 {
+        return Err(format!("Invalid target weights: value={}", value));
+    }
+
+    Ok(targets)
+}
+
+/// Splits `items` into `threads_count` chunks and processes them in parallel using `process`, every thread with its own search tables.
+/// Returns results in the same order as `items`, or [Err] with the first encountered error.
+fn process_in_parallel<T, R, F>(items: &[T], threads_count: usize, process: F) -> Result<Vec<R>, String>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T, &mut SearchTables) -> Result<R, String> + Sync,
+{
+    if items.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let chunk_size = items.len().div_ceil(threads_count);
+    let process = &process;
+
+    thread::scope(|scope| {
+        let mut threads = Vec::new();
+        for chunk in items.chunks(chunk_size) {
+            threads.push(scope.spawn(move || {
+                let mut tables = SearchTables::new();
+                chunk.iter().map(|item| process(item, &mut tables)).collect::<Result<Vec<R>, String>>()
+            }));
+        }
+
+        let mut results = Vec::new();
+        for thread in threads {
+            results.extend(thread.join().unwrap()?);
+        }
+
+        Ok(results)
+    })
+}
+
+/// Gets a list of all positions in `pgn` which meet restrictions (`min_ply`, `max_score` and `max_diff`), together with the number of ignored ones.
+/// Positions without score in PGN are ignored too, unless they are going to be scored again by the search (`rescore`).
+fn get_viable_positions(
+    pgn: &ParsedPGN,
+    min_ply: usize,
+    max_score: i16,
+    max_diff: u16,
+    rescore: bool,
+    tables: &mut SearchTables,
+) -> Result<(Vec<DatasetPosition>, usize), String> {
+    let result = match RESULTS.iter().position(|result| *result == pgn.result) {
+        Some(value) => value,
+        None => return Ok((Vec::new(), 0)),
+    };
+
+    let board = match &pgn.fen {
+        Some(fen) => Board::new_from_fen(fen)?,
+        None => Board::new_initial_position(),
+    };
+
+    let mut context = tables.create_context(board);
+    let mut viable_positions = Vec::new();
+    let mut ignored_positions = 0;
+
+    for (index, data) in pgn.data.iter().enumerate() {
+        context.board.make_move(data.r#move);
+
+        if index < min_ply {
+            ignored_positions += 1;
+            continue;
+        }
+
+        if data.eval.is_none() && !rescore {
+            ignored_positions += 1;
+            continue;
+        }
+
+        if data.r#move.is_capture() || data.r#move.is_castling() || data.r#move.is_promotion() {
+            ignored_positions += 1;
+            continue;
+        }
+
+        if context.board.is_king_checked(context.board.stm) {
+            ignored_positions += 1;
+            continue;
+        }
+
+        let material_evaluation = material::evaluate(&context.board);
+        if material_evaluation.taper_score(context.board.game_phase).abs() > max_score {
+            ignored_positions += 1;
+            continue;
+        }
+
+        let score = context.board.evaluate_without_cache(context.board.stm);
+        let q_score = qsearch::run(&mut context, 0, MIN_ALPHA, MIN_BETA);
+
+        if score.abs_diff(q_score) > max_diff {
+            ignored_positions += 1;
+            continue;
+        }
+
+        viable_positions.push(DatasetPosition {
+            epd: context.board.to_epd(),
+            hash: context.board.state.hash,
+            eval: data.eval.unwrap_or(0.0),
+            result,
+            game_phase: (context.board.game_phase as f32) / (INITIAL_GAME_PHASE as f32),
+        });
+    }
+
+    Ok((viable_positions, ignored_positions))
+}
+
+/// Scores `position` again using the search with the fixed `depth`. Returns [None] if the position has checkmate score, as it's not useful for the tuner.
+fn rescore(position: &DatasetPosition, depth: i8, tables: &mut SearchTables) -> Result<Option<DatasetPosition>, String> {
+    let board = fen::epd_to_board(&position.epd)?.board;
+    let stm = board.stm;
+
+    let mut context = tables.create_context(board);
+    context.forced_depth = depth;
+
+    let mut score = None;
+    while context.next().is_some() {
+        score = Some(context.lines[0].score);
+    }
+
+    tables.clear();
+
+    match score {
+        Some(score) if !is_score_near_checkmate(score) => {
+            // Search score is relative to the side to move, but the dataset always uses white's point of view
+            let score = if stm == WHITE { score } else { -score };
+            Ok(Some(DatasetPosition { eval: (score as f32) / 100.0, ..position.clone() }))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Balances `positions` so their distribution matches `phase_targets` (weights of equal game phase ranges, from ending to opening) and
+/// `wdl_targets` (weights of white wins, draws and black wins, or a single weight if results are not balanced). Positions are randomly
+/// dropped from overrepresented buckets, while keeping as many of them as possible.
+pub fn balance(positions: Vec<DatasetPosition>, phase_targets: &[f32], wdl_targets: &[f32]) -> Vec<DatasetPosition> {
+    let phase_targets_sum = phase_targets.iter().sum::<f32>();
+    let wdl_targets_sum = wdl_targets.iter().sum::<f32>();
+
+    let mut buckets = vec![Vec::new(); phase_targets.len() * wdl_targets.len()];
+    for position in positions {
+        let phase_bucket = ((position.game_phase * phase_targets.len() as f32) as usize).min(phase_targets.len() - 1);
+        let wdl_bucket = if wdl_targets.len() > 1 { position.result } else { 0 };
+
+        buckets[phase_bucket * wdl_targets.len() + wdl_bucket].push(position);
+    }
+
+    let targets = phase_targets
+        .iter()
+        .flat_map(|phase_target| wdl_targets.iter().map(move |wdl_target| (phase_target / phase_targets_sum) * (wdl_target / wdl_targets_sum)))
+        .collect::<Vec<f32>>();
+
+    // The largest number of positions for which every bucket with non-zero target has enough of them
+    let total_count =
+        buckets.iter().zip(&targets).filter(|(_, target)| **target > 0.0).map(|(bucket, target)| bucket.len() as f32 / target).fold(f32::MAX, f32::min);
+
+    let mut output = Vec::new();
+    for (index, (mut bucket, target)) in buckets.into_iter().zip(targets).enumerate() {
+        let count = ((total_count * target) as usize).min(bucket.len());
+        let phase_bucket = index / wdl_targets.len();
+        let wdl_bucket = index % wdl_targets.len();
+
+        println!(
+            "Bucket phase={}, result={}: {} available, {} picked",
+            phase_bucket,
+            if wdl_targets.len() > 1 { RESULTS[wdl_bucket] } else { "*" },
+            bucket.len(),
+            count
+        );
+
+        while bucket.len() > count {
+            bucket.swap_remove(rand::usize(0..bucket.len()));
+        }
+
+        output.extend(bucket);
+    }
+
+    output
+}
+
+struct SearchTables {
+    ttable: Arc<TTable>,
+    ehtable: Arc<EHTable>,
+    phtable: Arc<PHTable>,
+    mhtable: Arc<MHTable>,
+    abort_flag: Arc<AtomicBool>,
+    ponder_flag: Arc<AtomicBool>,
+}
+
+impl SearchTables {
+    /// Constructs a new instance of [SearchTables] with the hashtables used by a single thread of the generator.
+    fn new() -> Self {
+        Self {
+            ttable: Arc::new(TTable::new(1 * 1024 * 1024)),
+            ehtable: Arc::new(EHTable::new(1 * 1024 * 1024)),
+            phtable: Arc::new(PHTable::new(1 * 1024 * 1024)),
+            mhtable: Arc::new(MHTable::new(256 * 1024)),
+            abort_flag: Arc::new(AtomicBool::new(false)),
+            ponder_flag: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Creates a new [SearchContext] for the `board`, using stored hashtables.
+    fn create_context(&self, board: Board) -> SearchContext {
+        SearchContext::new(
+            board,
+            self.ttable.clone(),
+            self.ehtable.clone(),
+            self.phtable.clone(),
+            self.mhtable.clone(),
+            self.abort_flag.clone(),
+            self.ponder_flag.clone(),
+        )
+    }
+
+    /// Clears the transposition table (so rescoring of every position is independent of the previous ones) and restores the abort flag.
+    fn clear(&self) {
+        self.ttable.clear();
+        self.abort_flag.store(false, Ordering::Relaxed);
+    }
+}
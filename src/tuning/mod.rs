@@ -3,3 +3,21 @@ pub mod tuner;
 
 #[cfg(feature = "dev")]
 pub mod dataset;
+
+#[cfg(feature = "dev")]
+pub mod packed;
+
+#[cfg(feature = "dev")]
+pub mod pgnsearch;
+
+#[cfg(feature = "dev")]
+pub mod pgnstats;
+
+#[cfg(feature = "dev")]
+pub mod polyglot;
+
+#[cfg(feature = "dev")]
+pub mod report;
+
+#[cfg(feature = "dev")]
+pub mod spsa;
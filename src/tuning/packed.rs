@@ -0,0 +1,307 @@
+use super::tuner;
+use super::tuner::TunerCoeff;
+use super::tuner::TunerPosition;
+use crate::state::representation::Board;
+use crate::state::representation::CastlingRights;
+use crate::state::*;
+use crate::utils::bithelpers::BitHelpers;
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::BufWriter;
+use std::io::Read;
+use std::io::Seek;
+use std::io::SeekFrom;
+use std::io::Write;
+use std::time::SystemTime;
+
+const MAGIC: [u8; 4] = *b"INPD";
+const VERSION: u16 = 3;
+const HEADER_SIZE: usize = 98;
+const RECORD_SIZE: usize = PACKED_BOARD_SIZE + 5;
+const COEFF_SIZE: usize = 4;
+const PACKED_BOARD_SIZE: usize = 26;
+const BUFFER_SIZE: usize = 1024 * 1024;
+const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+/// Board packed into 26 bytes: occupancy (8 bytes), pieces as nibbles in the occupancy order (16 bytes, 3 bits for the piece and 1 bit for the color),
+/// flags (side to move and castling rights) and en passant square (or [u8::MAX] if not available).
+#[derive(Clone, Copy)]
+pub struct PackedBoard {
+    pub data: [u8; PACKED_BOARD_SIZE],
+}
+
+pub struct PackedHeader {
+    pub version: u16,
+    pub weights_count: u32,
+    pub positions_count: u64,
+    pub coeffs_count: u64,
+    pub checksum: u64,
+    pub king_buckets: [usize; 64],
+}
+
+impl PackedBoard {
+    /// Constructs a new instance of [PackedBoard] from the `board`. Returns [Err] with a proper error message if there are more than 32 pieces.
+    pub fn new(board: &Board) -> Result<Self, String> {
+        let occupancy = board.occupancy[WHITE] | board.occupancy[BLACK];
+        if occupancy.bit_count() > 32 {
+            return Err(format!("Too many pieces to pack the board: fen={}", board));
+        }
+
+        let mut data = [0; PACKED_BOARD_SIZE];
+        data[0..8].copy_from_slice(&occupancy.to_le_bytes());
+
+        let mut occupancy_bb = occupancy;
+        let mut index = 0;
+
+        while occupancy_bb != 0 {
+            let square = occupancy_bb.bit_scan();
+            occupancy_bb = occupancy_bb.pop_lsb();
+
+            let nibble = (board.get_piece(square) as u8) | ((board.get_piece_color(square) as u8) << 3);
+            data[8 + index / 2] |= nibble << ((index % 2) * 4);
+            index += 1;
+        }
+
+        data[24] = (board.stm as u8) | (board.state.castling_rights << 1);
+        data[25] = if board.state.en_passant != 0 { board.state.en_passant.bit_scan() as u8 } else { u8::MAX };
+
+        Ok(Self { data })
+    }
+
+    /// Unpacks the board, with recalculated hashes and incremental values. Returns [Err] with a proper error message if the data is invalid.
+    pub fn to_board(&self) -> Result<Board, String> {
+        let mut board = Board::default();
+        let mut occupancy_bb = u64::from_le_bytes(self.data[0..8].try_into().unwrap());
+        let mut index = 0;
+
+        while occupancy_bb != 0 {
+            let square = occupancy_bb.bit_scan();
+            occupancy_bb = occupancy_bb.pop_lsb();
+
+            let nibble = (self.data[8 + index / 2] >> ((index % 2) * 4)) & 0xf;
+            let (piece, color) = ((nibble & 7) as usize, (nibble >> 3) as usize);
+
+            if piece > KING {
+                return Err(format!("Invalid packed piece: nibble={}", nibble));
+            }
+
+            board.add_piece::<true>(color, piece, square);
+            index += 1;
+        }
+
+        board.stm = (self.data[24] & 1) as usize;
+        board.state.castling_rights = (self.data[24] >> 1) & CastlingRights::ALL;
+        board.state.en_passant = if self.data[25] < 64 { 1u64 << self.data[25] } else { 0 };
+
+        board.recalculate_hashes();
+        board.recalculate_incremental_values();
+        board.recalculate_pawn_attacks(WHITE);
+        board.recalculate_pawn_attacks(BLACK);
+
+        Ok(board)
+    }
+}
+
+impl PackedHeader {
+    /// Serializes header into bytes, all numbers are stored in little-endian order.
+    pub fn to_bytes(&self) -> [u8; HEADER_SIZE] {
+        let mut data = [0; HEADER_SIZE];
+        data[0..4].copy_from_slice(&MAGIC);
+        data[4..6].copy_from_slice(&self.version.to_le_bytes());
+        data[6..10].copy_from_slice(&self.weights_count.to_le_bytes());
+        data[10..18].copy_from_slice(&self.positions_count.to_le_bytes());
+        data[18..26].copy_from_slice(&self.coeffs_count.to_le_bytes());
+        data[26..34].copy_from_slice(&self.checksum.to_le_bytes());
+
+        for (index, bucket) in self.king_buckets.iter().enumerate() {
+            data[34 + index] = *bucket as u8;
+        }
+
+        data
+    }
+
+    /// Deserializes header from `data`. Returns [Err] with a proper error message if the magic number doesn't match.
+    pub fn from_bytes(data: &[u8; HEADER_SIZE]) -> Result<Self, String> {
+        if data[0..4] != MAGIC {
+            return Err("Invalid packed dataset: magic number not found".to_string());
+        }
+
+        Ok(Self {
+            version: u16::from_le_bytes(data[4..6].try_into().unwrap()),
+            weights_count: u32::from_le_bytes(data[6..10].try_into().unwrap()),
+            positions_count: u64::from_le_bytes(data[10..18].try_into().unwrap()),
+            coeffs_count: u64::from_le_bytes(data[18..26].try_into().unwrap()),
+            checksum: u64::from_le_bytes(data[26..34].try_into().unwrap()),
+            king_buckets: std::array::from_fn(|index| data[34 + index] as usize),
+        })
+    }
+}
+
+/// Converts `epd_filename` file (with the same format as the one used by the tuner) into the packed dataset saved as `output_filename`. Every position is stored
+/// together with its precomputed coefficients (using `king_buckets` layout), so the tuner doesn't need to parse and evaluate it again. Returns the number of converted
+/// positions, or [Err] with a proper error message if the input file couldn't be parsed or the output couldn't be written.
+pub fn convert(epd_filename: &str, output_filename: &str, king_buckets: &[usize; 64]) -> Result<u64, String> {
+    let start_time = SystemTime::now();
+    let input = File::open(epd_filename).map_err(|error| format!("Invalid EPD file: {}", error))?;
+    let output = File::create(output_filename).map_err(|error| format!("Invalid output file: {}", error))?;
+
+    let mut writer = BufWriter::with_capacity(BUFFER_SIZE, output);
+    let mut header = PackedHeader {
+        version: VERSION,
+        weights_count: tuner::get_weights_count() as u32,
+        positions_count: 0,
+        coeffs_count: 0,
+        checksum: FNV_OFFSET,
+        king_buckets: *king_buckets,
+    };
+    let mut coeffs = Vec::new();
+    let mut indices = Vec::new();
+    let mut record = Vec::new();
+
+    writer.write_all(&header.to_bytes()).map_err(|error| format!("Invalid output file: {}", error))?;
+
+    for (line_index, line) in BufReader::new(input).lines().enumerate() {
+        let line = line.map_err(|error| format!("Invalid EPD file: {}", error))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let (board, eval, result) = tuner::parse_position(&line).map_err(|error| format!("Invalid position at line {}: {}", line_index + 1, error))?;
+
+        coeffs.clear();
+        indices.clear();
+        tuner::get_coeffs(&board, king_buckets, &mut coeffs, &mut indices);
+
+        if coeffs.len() > u8::MAX as usize {
+            return Err(format!("Too many coefficients at line {}: coeffs_count={}", line_index + 1, coeffs.len()));
+        }
+
+        record.clear();
+        record.extend_from_slice(&PackedBoard::new(&board)?.data);
+        record.extend_from_slice(&eval.to_le_bytes());
+        record.push(result);
+        record.push(board.game_phase);
+        record.push(coeffs.len() as u8);
+
+        for (coeff, index) in coeffs.iter().zip(&indices) {
+            record.extend_from_slice(&index.to_le_bytes());
+            record.extend_from_slice(&coeff.data.to_le_bytes());
+        }
+
+        header.checksum = update_checksum(header.checksum, &record);
+        header.positions_count += 1;
+        header.coeffs_count += coeffs.len() as u64;
+
+        writer.write_all(&record).map_err(|error| format!("Invalid output file: {}", error))?;
+    }
+
+    let mut output = writer.into_inner().map_err(|error| format!("Invalid output file: {}", error))?;
+    output.seek(SeekFrom::Start(0)).map_err(|error| format!("Invalid output file: {}", error))?;
+    output.write_all(&header.to_bytes()).map_err(|error| format!("Invalid output file: {}", error))?;
+
+    println!(
+        "Converted {} positions ({} coefficients) in {} seconds",
+        header.positions_count,
+        header.coeffs_count,
+        (start_time.elapsed().unwrap().as_millis() as f32) / 1000.0
+    );
+
+    Ok(header.positions_count)
+}
+
+/// Checks if `filename` starts with the magic number of the packed dataset.
+pub fn is_packed(filename: &str) -> bool {
+    let mut magic = [0; 4];
+    match File::open(filename) {
+        Ok(mut file) => file.read_exact(&mut magic).is_ok() && magic == MAGIC,
+        Err(_) => false,
+    }
+}
+
+/// Loads positions from the packed dataset `filename` into a list of [TunerPosition], while filling `coeffs`, `indices` and `weights_indices`. The file is streamed
+/// using a fixed-size buffer, so only the parsed data is kept in memory. Returns [Err] with a proper error message if the version, number of weights (which changes
+/// when evaluation is modified and the dataset has to be converted again), `king_buckets` layout or checksum doesn't match.
+pub fn load(
+    filename: &str,
+    king_buckets: &[usize; 64],
+    coeffs: &mut Vec<TunerCoeff>,
+    indices: &mut Vec<u16>,
+    weights_indices: &mut HashSet<u16>,
+) -> Result<Vec<TunerPosition>, String> {
+    let file = File::open(filename).map_err(|error| format!("Invalid packed dataset: {}", error))?;
+    let mut reader = BufReader::with_capacity(BUFFER_SIZE, file);
+
+    let mut header_data = [0; HEADER_SIZE];
+    reader.read_exact(&mut header_data).map_err(|error| format!("Invalid packed dataset: {}", error))?;
+    let header = PackedHeader::from_bytes(&header_data)?;
+
+    if header.version != VERSION {
+        return Err(format!("Invalid packed dataset version: version={}, expected={}", header.version, VERSION));
+    }
+
+    let weights_count = tuner::get_weights_count();
+    if header.weights_count as usize != weights_count {
+        return Err(format!("Packed dataset generated for a different evaluation: weights_count={}, expected={}", header.weights_count, weights_count));
+    }
+
+    if header.king_buckets != *king_buckets {
+        return Err("Packed dataset generated for a different king buckets layout".to_string());
+    }
+
+    let mut positions = Vec::with_capacity(header.positions_count as usize);
+    let mut checksum = FNV_OFFSET;
+    let mut record = [0; RECORD_SIZE];
+    let mut coeffs_data = [0; u8::MAX as usize * COEFF_SIZE];
+
+    coeffs.reserve(header.coeffs_count as usize);
+    indices.reserve(header.coeffs_count as usize);
+
+    for _ in 0..header.positions_count {
+        reader.read_exact(&mut record).map_err(|error| format!("Invalid packed dataset: {}", error))?;
+
+        let eval = i16::from_le_bytes([record[PACKED_BOARD_SIZE], record[PACKED_BOARD_SIZE + 1]]);
+        let (result, phase, coeffs_count) = (record[PACKED_BOARD_SIZE + 2], record[PACKED_BOARD_SIZE + 3], record[PACKED_BOARD_SIZE + 4]);
+        let coeffs_data = &mut coeffs_data[0..coeffs_count as usize * COEFF_SIZE];
+
+        reader.read_exact(coeffs_data).map_err(|error| format!("Invalid packed dataset: {}", error))?;
+        checksum = update_checksum(checksum, &record);
+        checksum = update_checksum(checksum, coeffs_data);
+
+        let base_index = coeffs.len();
+        for coeff in coeffs_data.chunks_exact(COEFF_SIZE) {
+            let index = u16::from_le_bytes([coeff[0], coeff[1]]);
+            if index as usize >= weights_count {
+                return Err(format!("Invalid packed dataset: index={}", index));
+            }
+
+            coeffs.push(TunerCoeff { data: u16::from_le_bytes([coeff[2], coeff[3]]) });
+            indices.push(index);
+            weights_indices.insert(index);
+        }
+
+        positions.push(TunerPosition::new(eval, result, phase, base_index as u32, coeffs_count));
+    }
+
+    if reader.read(&mut [0]).map_err(|error| format!("Invalid packed dataset: {}", error))? != 0 {
+        return Err("Invalid packed dataset: unexpected data after the last position".to_string());
+    }
+
+    if checksum != header.checksum {
+        return Err(format!("Invalid packed dataset checksum: checksum={:x}, expected={:x}", checksum, header.checksum));
+    }
+
+    Ok(positions)
+}
+
+/// Updates FNV-1a `checksum` with `data`.
+fn update_checksum(mut checksum: u64, data: &[u8]) -> u64 {
+    for byte in data {
+        checksum ^= *byte as u64;
+        checksum = checksum.wrapping_mul(FNV_PRIME);
+    }
+
+    checksum
+}
@@ -0,0 +1,212 @@
+use crate::state::representation::Board;
+use crate::state::text;
+use crate::state::text::pgn::PGNLoader;
+use crate::state::text::pgn::ParsedPGN;
+use crate::state::*;
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::BufReader;
+use std::io::BufWriter;
+use std::io::Write;
+use std::time::SystemTime;
+
+const PROGRESS_INTERVAL: usize = 100000;
+const RESULTS: [&str; 3] = ["1-0", "1/2-1/2", "0-1"];
+
+pub enum SearchQuery {
+    Position(u64),
+    Material([[u32; 6]; 2]),
+    PawnStructure(u64),
+}
+
+impl SearchQuery {
+    /// Constructs a new instance of [SearchQuery] based on `query_type` and `value`:
+    ///  - `fen` - position in the FEN format (compared using Zobrist hash)
+    ///  - `material` - material signature like `KRPvKR` (white pieces first, kings are optional)
+    ///  - `pawns` - position in the FEN format, which pawn structure is compared using pawn hash
+    ///
+    /// Returns [Err] with a proper error message if `query_type` is unknown or `value` is invalid.
+    pub fn new(query_type: &str, value: &str) -> Result<SearchQuery, String> {
+        match query_type {
+            "fen" => Ok(SearchQuery::Position(Board::new_from_fen(value)?.state.hash)),
+            "material" => Ok(SearchQuery::Material(parse_material_signature(value)?)),
+            "pawns" => Ok(SearchQuery::PawnStructure(Board::new_from_fen(value)?.state.pawn_hash)),
+            _ => Err(format!("Invalid query type: query_type={}", query_type)),
+        }
+    }
+
+    /// Checks if the position on the `board` matches the query.
+    pub fn matches(&self, board: &Board) -> bool {
+        match self {
+            SearchQuery::Position(hash) => board.state.hash == *hash,
+            SearchQuery::PawnStructure(pawn_hash) => board.state.pawn_hash == *pawn_hash,
+            SearchQuery::Material(pieces) => {
+                for color in ALL_COLORS {
+                    for piece in ALL_PIECES {
+                        if piece != KING && board.pieces[color][piece].count_ones() != pieces[color][piece] {
+                            return false;
+                        }
+                    }
+                }
+
+                true
+            }
+        }
+    }
+}
+
+/// Runs search of the positions matching `query` in `pgn_filename`. If `output_filename` has `.epd` extension, every matching position (without
+/// duplicates) is saved there together with the score from PGN (or 0.00 if not present) and the result of the game, in the same format as the
+/// dataset generator uses (so unfinished games are skipped). Otherwise, every game which reached at least one matching position is saved as PGN.
+pub fn run(pgn_filename: &str, output_filename: &str, query: SearchQuery) {
+    println!("Loading PGN file...");
+
+    let start_time = SystemTime::now();
+    let file = match File::open(pgn_filename) {
+        Ok(value) => value,
+        Err(error) => {
+            println!("Invalid PGN file: {}", error);
+            return;
+        }
+    };
+
+    let output_file = match File::create(output_filename) {
+        Ok(value) => value,
+        Err(error) => {
+            println!("Invalid output file: {}", error);
+            return;
+        }
+    };
+
+    let epd_output = output_filename.ends_with(".epd");
+    let mut writer = BufWriter::new(output_file);
+    let mut output_hashes = HashSet::new();
+
+    let mut parsed_pgns = 0;
+    let mut skipped_pgns = 0;
+    let mut matching_games = 0;
+    let mut matching_positions = 0;
+    let mut saved_count = 0;
+
+    for pgn in PGNLoader::new(BufReader::new(file)) {
+        let pgn = match pgn {
+            Ok(value) => value,
+            Err(error) => {
+                println!("{}, skipping", error);
+                skipped_pgns += 1;
+                continue;
+            }
+        };
+
+        parsed_pgns += 1;
+        if parsed_pgns % PROGRESS_INTERVAL == 0 {
+            println!("Parsed PGNs: {} ({} skipped, {} matching games, {} matching positions)", parsed_pgns, skipped_pgns, matching_games, matching_positions);
+        }
+
+        // Unfinished games have no result which could be used by the tuner
+        if epd_output && !RESULTS.contains(&pgn.result.as_str()) {
+            continue;
+        }
+
+        let positions = match get_matching_positions(&pgn, &query) {
+            Ok(value) => value,
+            Err(error) => {
+                println!("{}, skipping", error);
+                skipped_pgns += 1;
+                continue;
+            }
+        };
+
+        if positions.is_empty() {
+            continue;
+        }
+
+        matching_games += 1;
+        matching_positions += positions.len();
+
+        let output = if epd_output {
+            let mut output = String::new();
+            for (board, eval) in positions {
+                if output_hashes.insert(board.state.hash) {
+                    saved_count += 1;
+                    output.push_str(&format!("{} c9 \"{:.2}|{}\";\n", board.to_epd(), eval.unwrap_or(0.0), pgn.result));
+                }
+            }
+
+            output
+        } else {
+            match pgn.to_pgn() {
+                Ok(value) => {
+                    saved_count += 1;
+                    value
+                }
+                Err(error) => {
+                    println!("Invalid PGN: {}", error);
+                    continue;
+                }
+            }
+        };
+
+        if let Err(error) = writer.write_all(output.as_bytes()) {
+            println!("Error while saving output: {}", error);
+            return;
+        }
+    }
+
+    if let Err(error) = writer.flush() {
+        println!("Error while saving output: {}", error);
+        return;
+    }
+
+    println!("-----------------------------------------------------------------------------");
+    println!(
+        "PGN search done in {:.2} s, {} games parsed ({} skipped), {} matching games, {} matching positions, {} saved",
+        (start_time.elapsed().unwrap().as_millis() as f32) / 1000.0,
+        parsed_pgns,
+        skipped_pgns,
+        matching_games,
+        matching_positions,
+        saved_count
+    );
+}
+
+/// Replays `pgn` and gets a list of all positions (including the initial one) matching `query`, together with the scores of the moves
+/// which led to them. Returns [Err] with a proper error message if the initial FEN position is invalid.
+fn get_matching_positions(pgn: &ParsedPGN, query: &SearchQuery) -> Result<Vec<(Board, Option<f32>)>, String> {
+    let mut board = match &pgn.fen {
+        Some(fen) => Board::new_from_fen(fen)?,
+        None => Board::new_initial_position(),
+    };
+
+    let mut positions = Vec::new();
+    if query.matches(&board) {
+        positions.push((board.clone(), None));
+    }
+
+    for data in &pgn.data {
+        board.make_move(data.r#move);
+        if query.matches(&board) {
+            positions.push((board.clone(), data.eval));
+        }
+    }
+
+    Ok(positions)
+}
+
+/// Parses material signature stored in `value` (like `KRPvKR`, white pieces first) into the number of pieces for every color and type.
+/// Returns [Err] with a proper error message if the signature is invalid.
+fn parse_material_signature(value: &str) -> Result<[[u32; 6]; 2], String> {
+    let (white, black) = match value.split_once(['v', 'V']) {
+        Some(value) => value,
+        None => return Err(format!("Invalid material signature: value={}", value)),
+    };
+
+    let mut pieces = [[0; 6]; 2];
+    for (color, signature) in [(WHITE, white), (BLACK, black)] {
+        for symbol in signature.chars() {
+            pieces[color][text::symbol_to_piece(symbol)?] += 1;
+        }
+    }
+
+    Ok(pieces)
+}
@@ -0,0 +1,279 @@
+use super::polyglot;
+use crate::state::movescan::Move;
+use crate::state::movescan::MoveFlags;
+use crate::state::representation::Board;
+use crate::state::text::pgn::PGNLoader;
+use crate::state::*;
+use crate::utils::percent;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::io::BufWriter;
+use std::io::Write;
+use std::time::SystemTime;
+
+const PROGRESS_INTERVAL: usize = 100000;
+const RESULTS: [&str; 3] = ["1-0", "1/2-1/2", "0-1"];
+
+pub struct PGNStatsOptions {
+    pub min_games: u64,
+    pub book_filename: Option<String>,
+}
+
+pub struct OpeningMove {
+    pub r#move: Move,
+    pub games: u64,
+    pub points: f32,
+}
+
+impl PGNStatsOptions {
+    /// Sets statistics option `name` to `value`. Supported options:
+    ///  - `min` - minimal number of games in which the move was played, to include it in the opening tree and the book
+    ///  - `book` - path of the Polyglot book file, where the opening tree is exported
+    ///
+    /// Returns [Err] with a proper error message if the option is unknown or its value is invalid.
+    pub fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+        match name {
+            "min" => self.min_games = value.parse().map_err(|error| format!("Invalid minimal games count: {}", error))?,
+            "book" => self.book_filename = Some(value.to_string()),
+            _ => return Err(format!("Invalid statistics option: name={}", name)),
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for PGNStatsOptions {
+    /// Constructs a default instance of [PGNStatsOptions] with all moves included in the opening tree and no book export.
+    fn default() -> Self {
+        Self { min_games: 1, book_filename: None }
+    }
+}
+
+impl OpeningMove {
+    /// Constructs a new instance of [OpeningMove] with stored `r#move` and no games.
+    pub fn new(r#move: Move) -> Self {
+        Self { r#move, games: 0, points: 0.0 }
+    }
+}
+
+/// Runs statistics of `pgn_filename`: number of games, distribution of results, average length and coverage of the moves with scores. Then
+/// prints the opening tree built from games starting at the initial position, up to `max_ply`. Every move has the number of games in which it was
+/// played and the score of the side which made it. Positions are identified by their Zobrist hashes, so transpositions share the same statistics.
+pub fn run(pgn_filename: &str, max_ply: usize, options: PGNStatsOptions) {
+    println!("Loading PGN file...");
+
+    let start_time = SystemTime::now();
+    let file = match File::open(pgn_filename) {
+        Ok(value) => value,
+        Err(error) => {
+            println!("Invalid PGN file: {}", error);
+            return;
+        }
+    };
+
+    let mut tree: HashMap<u64, Vec<OpeningMove>> = HashMap::new();
+    let mut polyglot_keys: HashMap<u64, u64> = HashMap::new();
+
+    let mut parsed_pgns = 0;
+    let mut skipped_pgns = 0;
+    let mut custom_positions = 0;
+    let mut results_count = [0; RESULTS.len() + 1];
+    let mut total_plies = 0;
+    let mut min_plies = usize::MAX;
+    let mut max_plies = 0;
+    let mut evaluated_plies = 0;
+    let mut evaluated_games = 0;
+
+    for pgn in PGNLoader::new(BufReader::new(file)) {
+        let pgn = match pgn {
+            Ok(value) => value,
+            Err(error) => {
+                println!("{}, skipping", error);
+                skipped_pgns += 1;
+                continue;
+            }
+        };
+
+        parsed_pgns += 1;
+        if parsed_pgns % PROGRESS_INTERVAL == 0 {
+            println!("Parsed PGNs: {} ({} skipped)", parsed_pgns, skipped_pgns);
+        }
+
+        let result = RESULTS.iter().position(|result| *result == pgn.result);
+        results_count[result.unwrap_or(RESULTS.len())] += 1;
+
+        total_plies += pgn.data.len();
+        min_plies = min_plies.min(pgn.data.len());
+        max_plies = max_plies.max(pgn.data.len());
+
+        let evaluated = pgn.data.iter().filter(|data| data.eval.is_some()).count();
+        evaluated_plies += evaluated;
+        if evaluated > 0 {
+            evaluated_games += 1;
+        }
+
+        if pgn.fen.is_some() {
+            custom_positions += 1;
+            continue;
+        }
+
+        // Games without the known result can't be scored, so they aren't a part of the opening tree
+        let result = match result {
+            Some(value) => value,
+            None => continue,
+        };
+
+        let mut board = Board::new_initial_position();
+        for data in pgn.data.iter().take(max_ply) {
+            if options.book_filename.is_some() {
+                polyglot_keys.entry(board.state.hash).or_insert_with(|| polyglot::get_key(&board));
+            }
+
+            let moves = tree.entry(board.state.hash).or_default();
+            let index = match moves.iter().position(|opening_move| opening_move.r#move == data.r#move) {
+                Some(value) => value,
+                None => {
+                    moves.push(OpeningMove::new(data.r#move));
+                    moves.len() - 1
+                }
+            };
+
+            // Result is 0 for white wins and 2 for black wins, so it has to be reversed for white
+            let points = (result as f32) / 2.0;
+            moves[index].games += 1;
+            moves[index].points += if board.stm == WHITE { 1.0 - points } else { points };
+
+            board.make_move(data.r#move);
+        }
+    }
+
+    let games_count = parsed_pgns.max(1);
+
+    println!("-----------------------------------------------------------------------------");
+    println!("Games: {} ({} skipped, {} with custom initial position)", parsed_pgns, skipped_pgns, custom_positions);
+    println!(
+        "Results: white wins {} ({:.2}%), draws {} ({:.2}%), black wins {} ({:.2}%), unknown {} ({:.2}%)",
+        results_count[0],
+        percent!(results_count[0], games_count),
+        results_count[1],
+        percent!(results_count[1], games_count),
+        results_count[2],
+        percent!(results_count[2], games_count),
+        results_count[3],
+        percent!(results_count[3], games_count)
+    );
+    println!(
+        "Length: average {:.2} plies (min {}, max {})",
+        (total_plies as f32) / (games_count as f32),
+        if parsed_pgns > 0 { min_plies } else { 0 },
+        max_plies
+    );
+    println!(
+        "Eval coverage: {} of {} plies ({:.2}%), {} games with at least one score ({:.2}%)",
+        evaluated_plies,
+        total_plies,
+        percent!(evaluated_plies, total_plies.max(1)),
+        evaluated_games,
+        percent!(evaluated_games, games_count)
+    );
+
+    println!("-----------------------------------------------------------------------------");
+    println!("Opening tree (max ply {}, min games {}):", max_ply, options.min_games);
+
+    let mut board = Board::new_initial_position();
+    print_tree(&tree, &mut board, 0, max_ply, options.min_games);
+
+    if let Some(book_filename) = options.book_filename {
+        println!("-----------------------------------------------------------------------------");
+        println!("Saving book...");
+
+        match write_book(&tree, &polyglot_keys, &book_filename, options.min_games) {
+            Ok(entries_count) => println!("Book saved, {} entries", entries_count),
+            Err(error) => {
+                println!("Error while saving book: {}", error);
+                return;
+            }
+        }
+    }
+
+    println!("-----------------------------------------------------------------------------");
+    println!("Statistics done in {:.2} s", (start_time.elapsed().unwrap().as_millis() as f32) / 1000.0);
+}
+
+/// Prints moves stored in `tree` for the position on the `board`, which is `ply` plies from the initial one. Moves are sorted by the number of games,
+/// and these played less than `min_games` times are skipped (with their subtrees). Recursion stops at `max_ply`.
+fn print_tree(tree: &HashMap<u64, Vec<OpeningMove>>, board: &mut Board, ply: usize, max_ply: usize, min_games: u64) {
+    if ply >= max_ply {
+        return;
+    }
+
+    let mut moves = match tree.get(&board.state.hash) {
+        Some(value) => value.iter().filter(|opening_move| opening_move.games >= min_games).collect::<Vec<_>>(),
+        None => return,
+    };
+    moves.sort_by_key(|opening_move| std::cmp::Reverse(opening_move.games));
+
+    for opening_move in moves {
+        let move_number = if board.stm == WHITE { format!("{}.", ply / 2 + 1) } else { format!("{}...", ply / 2 + 1) };
+        println!(
+            "{}{} {} ({} games, {:.2}%)",
+            " ".repeat(ply * 2),
+            move_number,
+            opening_move.r#move.to_short_notation(board),
+            opening_move.games,
+            percent!(opening_move.points, opening_move.games as f32)
+        );
+
+        board.make_move(opening_move.r#move);
+        print_tree(tree, board, ply + 1, max_ply, min_games);
+        board.undo_move(opening_move.r#move);
+    }
+}
+
+/// Writes moves stored in `tree` (played at least `min_games` times) into the `book_filename` in Polyglot format: big-endian key, move, weight (number
+/// of games, scaled to fit 16 bits) and learn value (always 0), sorted by the key. Positions are identified by Polyglot keys taken from `polyglot_keys`
+/// (indexed by Zobrist hashes used in `tree`). Returns the number of saved entries, or [Err] with a proper error message if the file couldn't be saved.
+fn write_book(tree: &HashMap<u64, Vec<OpeningMove>>, polyglot_keys: &HashMap<u64, u64>, book_filename: &str, min_games: u64) -> Result<usize, String> {
+    let mut entries = Vec::new();
+    for (hash, moves) in tree {
+        let key = polyglot_keys[hash];
+        let max_games = moves.iter().map(|opening_move| opening_move.games).max().unwrap_or(0).max(u16::MAX as u64);
+        for opening_move in moves.iter().filter(|opening_move| opening_move.games >= min_games) {
+            let weight = ((opening_move.games * u16::MAX as u64) / max_games).max(1) as u16;
+            entries.push((key, get_polyglot_move(opening_move.r#move), weight));
+        }
+    }
+
+    entries.sort_by(|a, b| a.0.cmp(&b.0).then(b.2.cmp(&a.2)));
+
+    let file = File::create(book_filename).map_err(|error| format!("Invalid book file: {}", error))?;
+    let mut writer = BufWriter::new(file);
+
+    for (key, r#move, weight) in &entries {
+        writer.write_all(&key.to_be_bytes()).map_err(|error| error.to_string())?;
+        writer.write_all(&r#move.to_be_bytes()).map_err(|error| error.to_string())?;
+        writer.write_all(&weight.to_be_bytes()).map_err(|error| error.to_string())?;
+        writer.write_all(&0u32.to_be_bytes()).map_err(|error| error.to_string())?;
+    }
+
+    writer.flush().map_err(|error| error.to_string())?;
+    Ok(entries.len())
+}
+
+/// Converts `r#move` into the Polyglot encoding: destination file and row, source file and row, and promotion piece (3 bits each).
+/// Castling is encoded as the king capturing its own rook.
+fn get_polyglot_move(r#move: Move) -> u16 {
+    let from = r#move.get_from();
+    let to = match r#move.get_flags() {
+        MoveFlags::SHORT_CASTLING => from - 3,
+        MoveFlags::LONG_CASTLING => from + 4,
+        _ => r#move.get_to(),
+    };
+
+    let promotion = if r#move.is_promotion() { r#move.get_promotion_piece() } else { 0 };
+    let to_bits = ((to / 8) << 3) | (7 - to % 8);
+    let from_bits = ((from / 8) << 3) | (7 - from % 8);
+
+    ((promotion << 12) | (from_bits << 6) | to_bits) as u16
+}
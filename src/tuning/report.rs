@@ -0,0 +1,242 @@
+use crate::evaluation::params;
+use crate::evaluation::pst::KING_BUCKETS_COUNT;
+use crate::state::*;
+use crate::utils::panic_fast;
+use std::fs;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+const LARGEST_CHANGES_COUNT: usize = 10;
+
+#[rustfmt::skip]
+const PARAMETERS: [(&str, &[usize]); 37] = [
+    ("TAPERED_PIECE_VALUES", &[6]),
+    ("BISHOP_PAIR", &[]), ("PAWNS_ATTACKING_PIECES", &[]), ("ROOK_OPEN_FILE", &[]), ("ROOK_SEMI_OPEN_FILE", &[]),
+    ("MOBILITY_INNER", &[6]), ("MOBILITY_OUTER", &[6]),
+    ("KNIGHT_OUTPOST", &[]), ("BISHOP_OUTPOST", &[]), ("BAD_BISHOP", &[]), ("ROOK_ON_SEVENTH", &[]), ("QUEEN_ON_SEVENTH", &[]), ("CONNECTED_ROOKS", &[]),
+    ("DOUBLED_PAWN", &[8]), ("ISOLATED_PAWN", &[8]), ("CHAINED_PAWN", &[8]), ("PASSED_PAWN", &[8]),
+    ("BACKWARD_PAWN_OPEN_FILE", &[8]), ("BACKWARD_PAWN_CLOSED_FILE", &[8]), ("PAWN_SHIELD", &[8]), ("PAWN_SHIELD_OPEN_FILE", &[8]),
+    ("KING_AREA_THREATS", &[8]), ("KNIGHT_SAFE_CHECKS", &[8]), ("BISHOP_SAFE_CHECKS", &[8]), ("ROOK_SAFE_CHECKS", &[8]), ("QUEEN_SAFE_CHECKS", &[8]),
+    ("MINORS_ATTACKING_MAJORS", &[8]), ("HANGING_PIECES", &[8]), ("SAFE_PAWN_PUSH_THREATS", &[8]), ("QUEEN_ATTACKS", &[8]),
+    ("PASSED_PAWN_OWN_KING_DISTANCE", &[8]), ("PASSED_PAWN_ENEMY_KING_DISTANCE", &[8]),
+    ("PASSED_PAWN_BLOCKED", &[]), ("PASSED_PAWN_FREE_PATH", &[]), ("PASSED_PAWN_UNSTOPPABLE", &[]),
+    ("IMBALANCE_OURS", &[15]), ("IMBALANCE_THEIRS", &[10]),
+];
+
+const PST_PARAMETERS: [&str; 6] = ["PAWN_PST_PATTERN", "KNIGHT_PST_PATTERN", "BISHOP_PST_PATTERN", "ROOK_PST_PATTERN", "QUEEN_PST_PATTERN", "KING_PST_PATTERN"];
+
+pub struct WeightInfo {
+    pub group: &'static str,
+    pub name: String,
+    pub piece_value: Option<usize>,
+}
+
+pub struct WeightChange<'a> {
+    pub index: usize,
+    pub info: &'a WeightInfo,
+    pub old_value: i16,
+    pub new_value: i16,
+    pub gradient: f32,
+    pub used: bool,
+}
+
+impl WeightInfo {
+    /// Constructs a new instance of [WeightInfo] with stored `group`, `name` and `piece_value` (index of the weight added to this one when it's
+    /// saved in the Rust source).
+    pub fn new(group: &'static str, name: String, piece_value: Option<usize>) -> Self {
+        Self { group, name, piece_value }
+    }
+}
+
+impl<'a> WeightChange<'a> {
+    /// Gets the difference between new and old value.
+    pub fn get_delta(&self) -> i16 {
+        self.new_value - self.old_value
+    }
+}
+
+/// Gets a list of [WeightInfo] for every tuner weight, in the same order as the tuner uses them: all evaluation parameters starting with
+/// tapered piece values (opening and ending score for every [crate::evaluation::PackedEval]), and then piece-square tables.
+pub fn get_weights_info() -> Vec<WeightInfo> {
+    let mut info = Vec::new();
+    for (group, dimensions) in PARAMETERS {
+        push_packed_weights(&mut info, group, group, dimensions, None);
+    }
+
+    for (piece, group) in PST_PARAMETERS.iter().enumerate() {
+        // Piece-square tables for our point of view are tuned without piece values (the first weights)
+        for pov in ALL_POVS {
+            for bucket in 0..KING_BUCKETS_COUNT {
+                let name = format!("{}[{}][{}]", group, pov, bucket);
+                push_packed_weights(&mut info, group, &name, &[64], if pov == US { Some(piece * 2) } else { None });
+            }
+        }
+    }
+
+    info
+}
+
+/// Appends opening and ending weights of the parameter `name` (belonging to `group`) with `dimensions` (empty for a single value) into `info`,
+/// with the specified index of opening `piece_value` (ending one is right after it).
+fn push_packed_weights(info: &mut Vec<WeightInfo>, group: &'static str, name: &str, dimensions: &[usize], piece_value: Option<usize>) {
+    let combinations = dimensions.iter().product::<usize>();
+    for combination in 0..combinations {
+        let mut suffix = String::new();
+        let mut divider = combinations;
+
+        for dimension in dimensions {
+            divider /= dimension;
+            suffix.push_str(&format!("[{}]", (combination / divider) % dimension));
+        }
+
+        info.push(WeightInfo::new(group, format!("{}{} (opening)", name, suffix), piece_value));
+        info.push(WeightInfo::new(group, format!("{}{} (ending)", name, suffix), piece_value.map(|index| index + 1)));
+    }
+}
+
+/// Saves reports of the tuning session into the `output_directory`:
+///  - `params.csv` with index, name, old value, new value, difference, gradient magnitude and usage flag of every weight
+///  - `params.json` with new values in the format accepted by [crate::evaluation::runtime::EvalParams::load]
+///  - `report.txt` with a human-readable summary of changes for every parameter group, including the largest ones, and `coverage` of king buckets
+///
+/// Values are compared with `old_weights` (both saved the same way as in the generated Rust sources), `gradients` are the last calculated gradient
+/// magnitudes, and `error`, scaling constant `k`, `wdl_ratio` are included as metadata. The `king_buckets` layout is saved together with the values.
+pub fn write(
+    weights: &[f32],
+    old_weights: &[f32],
+    gradients: &[f32],
+    output_directory: &str,
+    error: f32,
+    k: f32,
+    wdl_ratio: f32,
+    king_buckets: &[usize; 64],
+    coverage: &[[usize; KING_BUCKETS_COUNT]; 2],
+) {
+    let info = get_weights_info();
+    if info.len() != weights.len() || old_weights.len() != weights.len() || gradients.len() != weights.len() {
+        panic_fast!("Invalid weights count: info.len()={}, weights.len()={}", info.len(), weights.len());
+    }
+
+    let changes = weights
+        .iter()
+        .zip(old_weights)
+        .zip(gradients)
+        .zip(&info)
+        .enumerate()
+        .map(|(index, (((weight, old_weight), gradient), info))| WeightChange {
+            index,
+            info,
+            old_value: get_source_value(old_weights, *old_weight, info),
+            new_value: get_source_value(weights, *weight, info),
+            gradient: *gradient,
+            used: *weight != f32::MIN,
+        })
+        .collect::<Vec<_>>();
+
+    let path = Path::new(output_directory);
+    fs::create_dir_all(path).unwrap();
+
+    write!(&mut File::create(path.join("params.csv")).unwrap(), "{}", get_csv(&changes)).unwrap();
+    write!(&mut File::create(path.join("params.json")).unwrap(), "{}", get_json(&changes, king_buckets)).unwrap();
+    write!(&mut File::create(path.join("report.txt")).unwrap(), "{}", get_report(&changes, error, k, wdl_ratio, king_buckets, coverage)).unwrap();
+}
+
+/// Gets a value of the `weight` the same way as it's saved in the Rust source (rounded `weight` with added piece value from `weights` if
+/// specified in `info`, or 0 if the weight is not used).
+fn get_source_value(weights: &[f32], weight: f32, info: &WeightInfo) -> i16 {
+    let piece_value = match info.piece_value {
+        Some(index) if weights[index] != f32::MIN => weights[index],
+        _ => 0.0,
+    };
+
+    if weight == f32::MIN {
+        0
+    } else {
+        (weight + piece_value).round() as i16
+    }
+}
+
+/// Gets a CSV representation of all `changes`.
+fn get_csv(changes: &[WeightChange]) -> String {
+    let mut output = String::new();
+    output.push_str("index,name,old,new,delta,gradient,used\n");
+
+    for change in changes {
+        output.push_str(&format!(
+            "{},{},{},{},{},{:.8},{}\n",
+            change.index,
+            change.info.name,
+            change.old_value,
+            change.new_value,
+            change.get_delta(),
+            change.gradient,
+            change.used
+        ));
+    }
+
+    output
+}
+
+/// Gets a JSON representation of new values in `changes`, with tempo, `king_buckets` layout and the values in the tuner layout.
+fn get_json(changes: &[WeightChange], king_buckets: &[usize; 64]) -> String {
+    let values = changes
+        .iter()
+        .map(|change| match change.info.piece_value {
+            Some(index) => (change.new_value - changes[index].new_value).to_string(),
+            None => change.new_value.to_string(),
+        })
+        .collect::<Vec<String>>();
+    let buckets = king_buckets.iter().map(|bucket| bucket.to_string()).collect::<Vec<String>>();
+    format!("{{\n    \"tempo\": {},\n    \"buckets\": [{}],\n    \"values\": [{}]\n}}\n", params::TEMPO, buckets.join(", "), values.join(", "))
+}
+
+/// Gets a human-readable report with the summary of `changes` for every parameter group, metadata (`error`, scaling constant `k`, `wdl_ratio`)
+/// and `coverage` of buckets used by `king_buckets` layout.
+fn get_report(changes: &[WeightChange], error: f32, k: f32, wdl_ratio: f32, king_buckets: &[usize; 64], coverage: &[[usize; KING_BUCKETS_COUNT]; 2]) -> String {
+    let mut output = String::new();
+    let changed = changes.iter().filter(|change| change.used && change.get_delta() != 0).count();
+
+    output.push_str(&format!("Tuning report (e = {:.6}, k = {:.4}, r = {:.2})\n", error, k, wdl_ratio));
+    output.push_str(&format!("Changed weights: {} of {}\n", changed, changes.len()));
+
+    output.push('\n');
+    output.push_str("King buckets coverage (positions for our king / enemy king):\n");
+    for bucket in 0..KING_BUCKETS_COUNT {
+        if king_buckets.contains(&bucket) {
+            let squares = king_buckets.iter().filter(|value| **value == bucket).count();
+            output.push_str(&format!("    bucket {}: {} squares, {} / {}\n", bucket, squares, coverage[US][bucket], coverage[THEM][bucket]));
+        }
+    }
+
+    for group in changes.chunk_by(|a, b| a.info.group == b.info.group) {
+        let used = group.iter().filter(|change| change.used).collect::<Vec<_>>();
+        let mut largest = used.iter().filter(|change| change.get_delta() != 0).collect::<Vec<_>>();
+        let sum_of_changes = used.iter().map(|change| change.get_delta().unsigned_abs() as u32).sum::<u32>();
+        let mean_change = if !used.is_empty() { sum_of_changes as f32 / used.len() as f32 } else { 0.0 };
+
+        output.push('\n');
+        output.push_str(&format!(
+            "{}: {} weights, {} changed, {} unused, mean absolute change {:.2}\n",
+            group[0].info.group,
+            group.len(),
+            largest.len(),
+            group.len() - used.len(),
+            mean_change
+        ));
+
+        largest.sort_by_key(|change| std::cmp::Reverse(change.get_delta().abs()));
+        for change in largest.iter().take(LARGEST_CHANGES_COUNT) {
+            output.push_str(&format!(
+                "    {}: {} -> {} ({:+}), gradient {:.8}\n",
+                change.info.name,
+                change.old_value,
+                change.new_value,
+                change.get_delta(),
+                change.gradient
+            ));
+        }
+    }
+
+    output
+}
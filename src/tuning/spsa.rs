@@ -0,0 +1,370 @@
+use crate::cache::eval::EHTable;
+use crate::cache::material::MHTable;
+use crate::cache::pawns::PHTable;
+use crate::cache::search::TTable;
+use crate::engine::context::SearchContext;
+use crate::engine::params::SearchParams;
+use crate::state::representation::Board;
+use crate::state::text::fen;
+use crate::state::*;
+use crate::utils::rand;
+use common::time::DateTime;
+use std::fs;
+use std::fs::File;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Write;
+use std::path::Path;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::thread;
+use std::time::SystemTime;
+
+const GAMMA: f64 = 0.101;
+const ALPHA: f64 = 0.602;
+const A_RATIO: f64 = 0.1;
+const R_END: f64 = 0.002;
+const OUTPUT_INTERVAL: u32 = 10;
+const MAX_GAME_LENGTH: usize = 400;
+const ADJUDICATION_SCORE: i16 = 1000;
+const ADJUDICATION_PLIES: usize = 8;
+
+pub struct SpsaParameter {
+    pub name: String,
+    pub value: f64,
+    pub min: f64,
+    pub max: f64,
+    pub step: f64,
+}
+
+pub struct SpsaSample {
+    pub deltas: Vec<f64>,
+    pub plus: SearchParams,
+    pub minus: SearchParams,
+    pub opening: usize,
+}
+
+impl SpsaParameter {
+    /// Constructs a new instance of [SpsaParameter] with stored `name`, initial `value`, `min`, `max` and `step` (perturbation size at the end of tuning).
+    pub fn new(name: String, value: f64, min: f64, max: f64, step: f64) -> Self {
+        Self { name, value, min, max, step }
+    }
+
+    /// Moves the value toward the better version of the sample perturbed by `delta`, using `result` of its game pair (wins minus losses of the plus version)
+    /// and gains of the `iteration` (out of `iterations`). The new value is clamped to the parameter range.
+    pub fn update(&mut self, delta: f64, result: f64, iteration: u32, iterations: f64) {
+        let a = A_RATIO * iterations;
+        let c = self.step * iterations.powf(GAMMA);
+        let a_end = R_END * self.step.powi(2);
+        let a_k = a_end * (a + iterations).powf(ALPHA) / (a + iteration as f64).powf(ALPHA);
+        let c_k = c / (iteration as f64).powf(GAMMA);
+        let r_k = a_k / c_k.powi(2);
+
+        self.value = (self.value + r_k * c_k * result * delta).clamp(self.min, self.max);
+    }
+}
+
+/// Runs SPSA tuner of search parameters. The list of tuned parameters is loaded from `config_filename`, where every line contains name of the search parameter,
+/// minimal value, maximal value and step (perturbation size at the end of tuning), separated by spaces (lines starting with `#` are ignored). Initial values
+/// are taken from [SearchParams::default].
+///
+/// Every iteration, `threads_count` samples are processed in parallel: parameters are perturbed in a random direction (plus and minus version), and both versions
+/// play a game pair (with swapped colors) starting from a random position from `openings_filename`, with `nodes` limit per move. Parameters are then moved toward
+/// the better version, using gains which decrease during `iterations_count` iterations. The current parameters are saved every [OUTPUT_INTERVAL] iterations into
+/// the `output_directory` as `params.rs` with the constants block, together with `spsa.csv` log containing game results and parameter values of every iteration.
+pub fn run(config_filename: &str, openings_filename: &str, output_directory: &str, iterations_count: u32, nodes: u64, threads_count: usize) {
+    let mut parameters = match load_parameters(config_filename) {
+        Ok(value) => value,
+        Err(error) => {
+            println!("Invalid configuration file: {}", error);
+            return;
+        }
+    };
+
+    let openings = match load_openings(openings_filename) {
+        Ok(value) => value,
+        Err(error) => {
+            println!("Invalid openings file: {}", error);
+            return;
+        }
+    };
+
+    if let Err(error) = fs::create_dir_all(output_directory) {
+        println!("Invalid output directory: {}", error);
+        return;
+    }
+
+    let mut log = match File::create(Path::new(output_directory).join("spsa.csv")) {
+        Ok(value) => value,
+        Err(error) => {
+            println!("Invalid log file: {}", error);
+            return;
+        }
+    };
+
+    let names = parameters.iter().map(|parameter| parameter.name.as_str()).collect::<Vec<&str>>();
+    writeln!(&mut log, "iteration,wins,draws,losses,{}", names.join(",")).unwrap();
+
+    println!("Loaded {} parameters and {} openings, starting SPSA tuner", parameters.len(), openings.len());
+
+    let iterations = iterations_count as f64;
+    let (mut wins, mut draws, mut losses) = (0, 0, 0);
+    let mut start_time = SystemTime::now();
+
+    for iteration in 1..=iterations_count {
+        let samples = (0..threads_count).map(|_| get_sample(&parameters, iteration, iterations, openings.len())).collect::<Vec<SpsaSample>>();
+        let results = thread::scope(|scope| {
+            let mut threads = Vec::new();
+            for sample in &samples {
+                let board = &openings[sample.opening];
+                threads.push(scope.spawn(move || play_game_pair(board, &sample.plus, &sample.minus, nodes)));
+            }
+
+            threads.into_iter().map(|thread| thread.join().unwrap()).collect::<Vec<(u32, u32, u32)>>()
+        });
+
+        let (mut iteration_wins, mut iteration_draws, mut iteration_losses) = (0, 0, 0);
+        for (sample, (sample_wins, sample_draws, sample_losses)) in samples.iter().zip(results) {
+            let result = sample_wins as f64 - sample_losses as f64;
+            for (parameter, delta) in parameters.iter_mut().zip(&sample.deltas) {
+                parameter.update(*delta, result, iteration, iterations);
+            }
+
+            iteration_wins += sample_wins;
+            iteration_draws += sample_draws;
+            iteration_losses += sample_losses;
+        }
+
+        wins += iteration_wins;
+        draws += iteration_draws;
+        losses += iteration_losses;
+
+        let values = parameters.iter().map(|parameter| format!("{:.4}", parameter.value)).collect::<Vec<String>>();
+        writeln!(&mut log, "{},{},{},{},{}", iteration, iteration_wins, iteration_draws, iteration_losses, values.join(",")).unwrap();
+
+        if iteration % OUTPUT_INTERVAL == 0 || iteration == iterations_count {
+            write_parameters(&parameters, output_directory);
+            log.flush().unwrap();
+
+            println!(
+                "Iteration {} done in {} seconds, plus vs minus: {} wins, {} draws, {} losses",
+                iteration,
+                (start_time.elapsed().unwrap().as_millis() as f32) / 1000.0,
+                wins,
+                draws,
+                losses
+            );
+
+            for parameter in &parameters {
+                println!("  {} = {:.2}", parameter.name, parameter.value);
+            }
+
+            start_time = SystemTime::now();
+        }
+    }
+}
+
+/// Loads a list of [SpsaParameter] from `config_filename`. Returns [Err] with a proper error message if the file couldn't be parsed, or contains
+/// parameters which don't exist in [SearchParams] or whose range doesn't fit into their type (see [SearchParams::FIELDS]).
+pub fn load_parameters(config_filename: &str) -> Result<Vec<SpsaParameter>, String> {
+    let file = File::open(config_filename).map_err(|error| format!("Invalid file: {}", error))?;
+    let default = SearchParams::default();
+    let mut parameters = Vec::new();
+
+    for line in BufReader::new(file).lines() {
+        let line = line.map_err(|error| format!("Invalid file: {}", error))?;
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let tokens = line.split_whitespace().collect::<Vec<&str>>();
+        if tokens.len() != 4 {
+            return Err(format!("Invalid parameter definition: line={}", line));
+        }
+
+        let value = match default.get(tokens[0]) {
+            Some(value) => value as f64,
+            None => return Err(format!("Invalid search parameter: name={}", tokens[0])),
+        };
+
+        let mut values = [0.0f64; 3];
+        for (index, token) in tokens[1..].iter().enumerate() {
+            values[index] = token.parse().map_err(|error| format!("Invalid parameter definition: line={}, error={}", line, error))?;
+        }
+
+        let [min, max, step] = values;
+        if min > max || step <= 0.0 {
+            return Err(format!("Invalid parameter range: line={}", line));
+        }
+
+        // Perturbed values are always clamped to the range, so it's enough to check if its bounds can be stored in the parameter
+        let mut search_params = SearchParams::default();
+        if search_params.set(tokens[0], min.round() as i64).is_err() || search_params.set(tokens[0], max.round() as i64).is_err() {
+            return Err(format!("Invalid parameter range for its type: line={}", line));
+        }
+
+        parameters.push(SpsaParameter::new(tokens[0].to_string(), value.clamp(min, max), min, max, step));
+    }
+
+    if parameters.is_empty() {
+        return Err("No parameters to tune".to_string());
+    }
+
+    Ok(parameters)
+}
+
+/// Loads a list of opening positions from `openings_filename` (one FEN or EPD per line). Returns [Err] with a proper error message if the file couldn't be parsed.
+fn load_openings(openings_filename: &str) -> Result<Vec<Board>, String> {
+    let file = File::open(openings_filename).map_err(|error| format!("Invalid file: {}", error))?;
+    let mut openings = Vec::new();
+
+    for line in BufReader::new(file).lines() {
+        let line = line.map_err(|error| format!("Invalid file: {}", error))?;
+        if !line.trim().is_empty() {
+            openings.push(fen::epd_to_board(&line)?.board);
+        }
+    }
+
+    if openings.is_empty() {
+        return Err("No openings found".to_string());
+    }
+
+    Ok(openings)
+}
+
+/// Gets a new [SpsaSample] with parameters perturbed in a random direction, for the `iteration` of `iterations` and a random opening (one of `openings_count`).
+pub fn get_sample(parameters: &[SpsaParameter], iteration: u32, iterations: f64, openings_count: usize) -> SpsaSample {
+    let mut plus = SearchParams::default();
+    let mut minus = SearchParams::default();
+    let mut deltas = Vec::new();
+
+    for parameter in parameters {
+        let delta = if rand::u8(0..=1) == 0 { -1.0 } else { 1.0 };
+        let c_k = parameter.step * iterations.powf(GAMMA) / (iteration as f64).powf(GAMMA);
+
+        let plus_value = (parameter.value + c_k * delta).clamp(parameter.min, parameter.max).round() as i64;
+        let minus_value = (parameter.value - c_k * delta).clamp(parameter.min, parameter.max).round() as i64;
+
+        plus.set(&parameter.name, plus_value).unwrap();
+        minus.set(&parameter.name, minus_value).unwrap();
+        deltas.push(delta);
+    }
+
+    SpsaSample { deltas, plus, minus, opening: rand::usize(0..openings_count) }
+}
+
+/// Plays a game pair between `plus` and `minus` parameters starting from `board`, with swapped colors in the second game. Returns a tuple with the number of
+/// wins, draws and losses from the `plus` perspective.
+fn play_game_pair(board: &Board, plus: &SearchParams, minus: &SearchParams, nodes: u64) -> (u32, u32, u32) {
+    let first = play_game(board.clone(), plus, minus, nodes);
+    let second = -play_game(board.clone(), minus, plus, nodes);
+    let results = [first, second];
+
+    let wins = results.iter().filter(|&&result| result > 0).count() as u32;
+    let losses = results.iter().filter(|&&result| result < 0).count() as u32;
+    (wins, 2 - wins - losses, losses)
+}
+
+/// Plays a single game starting from `board` between `white` and `black` parameters, with `nodes` limit per move. Returns 1 if white won, -1 if black won,
+/// or 0 if the game was drawn (also when it was longer than [MAX_GAME_LENGTH] plies, or the search couldn't return a move). The game is adjudicated when both sides agree that the score is
+/// bigger than [ADJUDICATION_SCORE] for [ADJUDICATION_PLIES] plies.
+fn play_game(mut board: Board, white: &SearchParams, black: &SearchParams, nodes: u64) -> i8 {
+    let tables = [create_tables(), create_tables()];
+    let abort_flag = Arc::new(AtomicBool::new(false));
+    let ponder_flag = Arc::new(AtomicBool::new(false));
+    let mut adjudication_plies = 0;
+    let mut last_winner = 0;
+
+    for _ in 0..MAX_GAME_LENGTH {
+        if board.get_legal_moves_count(&[]) == 0 {
+            let color_sign = -((board.stm as i8) * 2 - 1);
+            return if board.is_king_checked(board.stm) { -color_sign } else { 0 };
+        }
+
+        if board.is_repetition_draw(3) || board.is_fifty_move_rule_draw() || board.is_insufficient_material_draw() {
+            return 0;
+        }
+
+        let (ttable, ehtable, phtable, mhtable) = &tables[board.stm];
+        let mut context =
+            SearchContext::new(board.clone(), ttable.clone(), ehtable.clone(), phtable.clone(), mhtable.clone(), abort_flag.clone(), ponder_flag.clone());
+
+        context.params = if board.stm == WHITE { white.clone() } else { black.clone() };
+        context.max_nodes_count = nodes;
+
+        let mut best_move = None;
+        while context.next().is_some() {
+            best_move = Some((context.lines[0].pv_line[0], context.lines[0].score));
+        }
+
+        // Search is aborted when the nodes limit is reached, so the flag has to be restored before the next move
+        abort_flag.store(false, Ordering::Relaxed);
+
+        let (r#move, score) = match best_move {
+            Some(value) => value,
+            None => return 0,
+        };
+
+        let score = score * -((board.stm as i16) * 2 - 1);
+        let winner = if score >= ADJUDICATION_SCORE {
+            1
+        } else if score <= -ADJUDICATION_SCORE {
+            -1
+        } else {
+            0
+        };
+
+        adjudication_plies = if winner != 0 && winner == last_winner { adjudication_plies + 1 } else { 1 };
+        last_winner = winner;
+
+        if winner != 0 && adjudication_plies >= ADJUDICATION_PLIES {
+            return winner;
+        }
+
+        board.make_move(r#move);
+    }
+
+    0
+}
+
+/// Creates a set of hashtables used by one side during the game.
+fn create_tables() -> (Arc<TTable>, Arc<EHTable>, Arc<PHTable>, Arc<MHTable>) {
+    (Arc::new(TTable::new(4 * 1024 * 1024)), Arc::new(EHTable::new(1024 * 1024)), Arc::new(PHTable::new(1024 * 1024)), Arc::new(MHTable::new(256 * 1024)))
+}
+
+/// Generates `params.rs` file with the constants block containing all search parameters (with `parameters` values rounded, and the rest unchanged),
+/// then saves it into the `output_directory`.
+fn write_parameters(parameters: &[SpsaParameter], output_directory: &str) {
+    let mut search_params = SearchParams::default();
+    for parameter in parameters {
+        search_params.set(&parameter.name, parameter.value.round() as i64).unwrap();
+    }
+
+    let datetime = DateTime::now();
+    let datetime =
+        format!("{:0>2}-{:0>2}-{} {:0>2}:{:0>2}:{:0>2}", datetime.day, datetime.month, datetime.year, datetime.hour, datetime.minute, datetime.second);
+    let mut output = String::new();
+
+    output.push_str(&format!("// Generated by SPSA tuner at {} UTC\n", datetime));
+    output.push_str("#[allow(non_upper_case_globals)]\n");
+    output.push_str("impl SearchParams {\n");
+
+    let mut last_group = "";
+    for (name, r#type) in SearchParams::FIELDS {
+        let group = name.split('_').next().unwrap_or_default();
+        if !last_group.is_empty() && group != last_group {
+            output.push('\n');
+        }
+
+        output.push_str(&format!("    pub const {}: {} = {};\n", name, r#type, search_params.get(name).unwrap()));
+        last_group = group;
+    }
+
+    output.push_str("}\n");
+
+    let path = Path::new(output_directory).join("params.rs");
+    write!(&mut File::create(path).unwrap(), "{}", output).unwrap();
+}
@@ -1,11 +1,16 @@
+use super::packed;
+use super::report;
 use crate::evaluation::material;
 use crate::evaluation::mobility;
 use crate::evaluation::mobility::EvalAux;
+use crate::evaluation::passers;
 use crate::evaluation::pawns;
 use crate::evaluation::pst;
 use crate::evaluation::pst::*;
 use crate::evaluation::safety;
+use crate::evaluation::threats;
 use crate::evaluation::*;
+use crate::state::representation::Board;
 use crate::state::text::fen;
 use crate::state::*;
 use crate::utils::panic_fast;
@@ -22,11 +27,14 @@ use std::sync::Arc;
 use std::thread;
 use std::time::SystemTime;
 
-const LEARNING_RATE: f32 = 0.1;
 const K_STEP: f32 = 0.0001;
 const B1: f32 = 0.9;
 const B2: f32 = 0.999;
-const OUTPUT_INTERVAL: i32 = 100;
+const EPSILON: f32 = 0.00000001;
+const OUTPUT_INTERVAL: u32 = 100;
+const VALIDATION_SEED: u64 = 0x5eed_1234_abcd_9876;
+const PST_BLOCK_SIZE: usize = 64 * 2;
+const MATERIAL_WEIGHTS_COUNT: usize = 6 * 2;
 
 pub struct TunerPosition {
     eval: i16,
@@ -46,7 +54,37 @@ pub struct TunerParameter {
 
 #[derive(Clone)]
 pub struct TunerCoeff {
-    pub data: u8,
+    pub data: u16,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TunerOptimizer {
+    Adam,
+    AdaGrad,
+    GradientDescent,
+    GaussNewton,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LearningRateSchedule {
+    Constant,
+    Step(u32, f32),
+    Exponential(f32),
+    Cosine(u32),
+}
+
+pub struct TunerOptions {
+    pub optimizer: TunerOptimizer,
+    pub learning_rate: Option<f32>,
+    pub schedule: LearningRateSchedule,
+    pub l2: f32,
+    pub validation_ratio: f32,
+    pub patience: u32,
+    pub max_iterations: Option<u32>,
+    pub log_filename: Option<String>,
+    pub king_buckets: [usize; 64],
+    pub tie_threshold: usize,
+    pub material: bool,
 }
 
 impl TunerPosition {
@@ -76,51 +114,217 @@ impl TunerParameter {
 impl TunerCoeff {
     /// Constructs a new instance of [TunerCoeff] with stored `value` and `phase`.
     pub fn new(value: i8, phase: usize) -> Self {
-        Self { data: (((value + 32) as u8) << 1) | (phase as u8) }
+        Self { data: ((value as u8 as u16) << 1) | (phase as u16) }
     }
 
     /// Gets value and game phase as a tuple.
     pub fn get_data(&self) -> (i8, usize) {
-        ((self.data as i8 >> 1) - 32, (self.data & 1) as usize)
+        ((self.data >> 1) as u8 as i8, (self.data & 1) as usize)
+    }
+}
+
+impl TunerOptimizer {
+    /// Gets a default learning rate of the optimizer, used when none is specified in [TunerOptions].
+    pub fn get_default_learning_rate(&self) -> f32 {
+        match self {
+            TunerOptimizer::Adam => 0.1,
+            TunerOptimizer::AdaGrad => 1.0,
+            TunerOptimizer::GradientDescent => 100.0,
+            TunerOptimizer::GaussNewton => 0.5,
+        }
+    }
+
+    /// Parses optimizer name (`adam`, `adagrad`, `gd` or `gn`). Returns [Err] with a proper error message if the name is not recognized.
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "adam" => Ok(TunerOptimizer::Adam),
+            "adagrad" => Ok(TunerOptimizer::AdaGrad),
+            "gd" => Ok(TunerOptimizer::GradientDescent),
+            "gn" => Ok(TunerOptimizer::GaussNewton),
+            _ => Err(format!("Invalid optimizer: value={}", value)),
+        }
+    }
+}
+
+impl LearningRateSchedule {
+    /// Gets a learning rate for the `iteration`, calculated from `base_learning_rate`:
+    ///  - constant: `base_learning_rate` is used for all iterations
+    ///  - step: `base_learning_rate` is multiplied by `gamma` every `interval` iterations
+    ///  - exponential: `base_learning_rate` is multiplied by `gamma` every iteration
+    ///  - cosine: `base_learning_rate` is annealed to zero using cosine curve, and restarted every `period` iterations
+    pub fn get_learning_rate(&self, base_learning_rate: f32, iteration: u32) -> f32 {
+        match *self {
+            LearningRateSchedule::Constant => base_learning_rate,
+            LearningRateSchedule::Step(interval, gamma) => base_learning_rate * gamma.powi((iteration / interval) as i32),
+            LearningRateSchedule::Exponential(gamma) => base_learning_rate * gamma.powf(iteration as f32),
+            LearningRateSchedule::Cosine(period) => {
+                let progress = (iteration % period) as f32 / period as f32;
+                base_learning_rate * 0.5 * (1.0 + (std::f32::consts::PI * progress).cos())
+            }
+        }
+    }
+
+    /// Parses learning rate schedule (`constant`, `step:[interval]:[gamma]`, `exp:[gamma]` or `cosine:[period]`). Returns [Err] with a proper error message
+    /// if the schedule is not recognized or has invalid parameters.
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let tokens = value.split(':').collect::<Vec<&str>>();
+        let schedule = match tokens.as_slice() {
+            ["constant"] => LearningRateSchedule::Constant,
+            ["step", interval, gamma] => LearningRateSchedule::Step(
+                interval.parse().map_err(|error| format!("Invalid step interval: {}", error))?,
+                gamma.parse().map_err(|error| format!("Invalid step gamma: {}", error))?,
+            ),
+            ["exp", gamma] => LearningRateSchedule::Exponential(gamma.parse().map_err(|error| format!("Invalid exponential gamma: {}", error))?),
+            ["cosine", period] => LearningRateSchedule::Cosine(period.parse().map_err(|error| format!("Invalid cosine period: {}", error))?),
+            _ => return Err(format!("Invalid learning rate schedule: value={}", value)),
+        };
+
+        match schedule {
+            LearningRateSchedule::Step(0, _) | LearningRateSchedule::Cosine(0) => Err(format!("Invalid learning rate schedule: value={}", value)),
+            _ => Ok(schedule),
+        }
+    }
+}
+
+impl TunerOptions {
+    /// Sets tuner option `name` to `value`. Supported options:
+    ///  - `optimizer` - `adam`, `adagrad`, `gd` (plain gradient descent) or `gn` (Gauss-Newton with diagonal approximation)
+    ///  - `lr` - base learning rate (the default one depends on the optimizer)
+    ///  - `schedule` - learning rate schedule, see [LearningRateSchedule]
+    ///  - `l2` - strength of L2 regularization pulling weights toward the values currently set in the engine (also when starting from random values)
+    ///  - `validation` - ratio of positions held out for validation
+    ///  - `patience` - number of iterations without validation error improvement after which the tuner stops (0 = disabled)
+    ///  - `iterations` - maximal number of iterations
+    ///  - `log` - path of CSV file where train and validation errors are logged every iteration
+    ///  - `buckets` - path of the file with king buckets layout (see [pst::parse_king_buckets])
+    ///  - `tie` - minimal number of positions in the king bucket, below which its piece-square tables are tied with the nearest well-sampled bucket
+    ///  - `material` - `true` if tapered piece values should be tuned too (they are excluded by default)
+    ///
+    /// Returns [Err] with a proper error message if the option is unknown or its value is invalid.
+    pub fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+        match name {
+            "optimizer" => self.optimizer = TunerOptimizer::parse(value)?,
+            "lr" => self.learning_rate = Some(value.parse().map_err(|error| format!("Invalid learning rate: {}", error))?),
+            "schedule" => self.schedule = LearningRateSchedule::parse(value)?,
+            "l2" => self.l2 = value.parse().map_err(|error| format!("Invalid L2 regularization: {}", error))?,
+            "validation" => {
+                self.validation_ratio = value.parse().map_err(|error| format!("Invalid validation ratio: {}", error))?;
+                if !(0.0..1.0).contains(&self.validation_ratio) {
+                    return Err(format!("Invalid validation ratio: value={}", value));
+                }
+            }
+            "patience" => self.patience = value.parse().map_err(|error| format!("Invalid patience: {}", error))?,
+            "iterations" => self.max_iterations = Some(value.parse().map_err(|error| format!("Invalid iterations count: {}", error))?),
+            "log" => self.log_filename = Some(value.to_string()),
+            "buckets" => {
+                let content = fs::read_to_string(value).map_err(|error| format!("Invalid king buckets file: {}", error))?;
+                self.king_buckets = pst::parse_king_buckets(&content)?;
+            }
+            "tie" => self.tie_threshold = value.parse().map_err(|error| format!("Invalid tie threshold: {}", error))?,
+            "material" => self.material = value.parse().map_err(|error| format!("Invalid material flag: {}", error))?,
+            _ => return Err(format!("Invalid tuner option: name={}", name)),
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for TunerOptions {
+    /// Constructs a default instance of [TunerOptions] with Adam optimizer, constant learning rate, no regularization, no validation split
+    /// king buckets layout compiled into the engine (without tying) and fixed piece values.
+    fn default() -> Self {
+        Self {
+            optimizer: TunerOptimizer::Adam,
+            learning_rate: None,
+            schedule: LearningRateSchedule::Constant,
+            l2: 0.0,
+            validation_ratio: 0.0,
+            patience: 0,
+            max_iterations: None,
+            log_filename: None,
+            king_buckets: KING_BUCKETS,
+            tie_threshold: 0,
+            material: false,
+        }
     }
 }
 
-/// Runs tuner of evaluation parameters. The input file is specified by `epd_filename` with a list of positions and their expected results, and the `output_directory`
+/// Runs tuner of evaluation parameters. The input file is specified by `epd_filename` with a list of positions and their expected results (either EPD or
+/// packed dataset generated by [packed::convert]), and the `output_directory`
 /// directory is used to store generated Rust sources with the optimized values. Use `random_values` to initialize evaluation parameters with random values, `k` to
-/// set scaling constant (might be None) and `wdl_ratio` to set the ratio between WDL and eval. Multithreading is supported by `threads_count`. The tuner is implemented
-/// using gradient descent and Adam optimizer. The result (Rust sources with the calculated values) are saved every iteration, and can be put directly into the code.
-pub fn run(epd_filename: &str, output_directory: &str, random_values: bool, k: Option<f32>, wdl_ratio: f32, threads_count: usize) {
+/// set scaling constant (might be None) and `wdl_ratio` to set the ratio between WDL and eval. Multithreading is supported by `threads_count`.
+///
+/// The tuner is implemented using gradient descent with the optimizer, learning rate schedule and L2 regularization (pulling weights toward their initial values)
+/// specified in `options`. If the validation ratio is set, a part of positions is held out and used to calculate validation error every iteration - the best weights
+/// (with the lowest validation error) are then saved, and the tuner stops early if there was no improvement for `patience` iterations. The result (Rust sources with
+/// the calculated values) are saved every [OUTPUT_INTERVAL] iterations, and can be put directly into the code. Train and validation errors can be logged every
+/// iteration into a CSV file.
+pub fn run(epd_filename: &str, output_directory: &str, random_values: bool, k: Option<f32>, wdl_ratio: f32, threads_count: usize, options: TunerOptions) {
     println!("Loading EPD file...");
 
     let start_time = SystemTime::now();
     let mut weights_indices = HashSet::new();
     let mut weights = Vec::new();
-    let mut gradients = Vec::new();
     let mut coeffs = Vec::new();
     let mut indices = Vec::new();
 
-    let mut positions = match load_positions(epd_filename, &mut coeffs, &mut indices, &mut weights_indices) {
+    let positions = if packed::is_packed(epd_filename) {
+        packed::load(epd_filename, &options.king_buckets, &mut coeffs, &mut indices, &mut weights_indices)
+    } else {
+        load_positions(epd_filename, &options.king_buckets, &mut coeffs, &mut indices, &mut weights_indices)
+    };
+
+    let mut positions = match positions {
         Ok(value) => value,
         Err(error) => {
-            println!("Invalid EPD file: {}", error);
+            println!("Invalid input file: {}", error);
             return;
         }
     };
 
+    let validation_positions = split_positions(&mut positions, options.validation_ratio);
+    if positions.is_empty() {
+        println!("No positions left for training");
+        return;
+    }
+
     positions.shrink_to_fit();
     coeffs.shrink_to_fit();
     indices.shrink_to_fit();
 
     let coeffs = Arc::new(coeffs);
     let indices = Arc::new(indices);
-    println!("Loaded {} positions in {} seconds, starting tuner", positions.len(), (start_time.elapsed().unwrap().as_millis() as f32) / 1000.0);
+    println!(
+        "Loaded {} positions ({} for training, {} for validation) in {} seconds, starting tuner",
+        positions.len() + validation_positions.len(),
+        positions.len(),
+        validation_positions.len(),
+        (start_time.elapsed().unwrap().as_millis() as f32) / 1000.0
+    );
+
+    let mut log = match &options.log_filename {
+        Some(filename) => match File::create(filename) {
+            Ok(mut file) => {
+                writeln!(&mut file, "iteration,learning_rate,train_error,validation_error").unwrap();
+                Some(file)
+            }
+            Err(error) => {
+                println!("Invalid log file: {}", error);
+                return;
+            }
+        },
+        None => None,
+    };
 
-    let mut tuner_params = load_values(random_values);
+    let tuner_params = load_values(random_values);
 
     for parameter in &tuner_params {
         weights.push(parameter.value as f32);
     }
-    gradients.resize(weights.len(), 0.0);
+
+    let current_weights = load_values(false).iter().map(|parameter| parameter.value as f32).collect::<Vec<f32>>();
+    let mut gradient_magnitudes = vec![0.0; weights.len()];
+    let base_learning_rate = options.learning_rate.unwrap_or(options.optimizer.get_default_learning_rate());
 
     let mut m = Vec::new();
     m.resize(weights.len(), 0.0);
@@ -132,110 +336,162 @@ pub fn run(epd_filename: &str, output_directory: &str, random_values: bool, k: O
     weights_enabled.resize(weights.len(), false);
 
     for i in 0..weights_enabled.len() {
-        weights_enabled[i] = i == 5 || weights_indices.contains(&(i as u16));
+        // Piece values are always enabled, as they are added to piece-square tables even if there are no coefficients for them
+        weights_enabled[i] = i < MATERIAL_WEIGHTS_COUNT || weights_indices.contains(&(i as u16));
     }
 
     drop(weights_indices);
 
+    let coverage = get_buckets_coverage(&positions, &indices, weights.len());
+    print_buckets_coverage(&coverage, &options.king_buckets);
+
+    let ties = get_bucket_ties(&coverage, &options.king_buckets, options.tie_threshold, weights.len());
+    let mut weights_tied = vec![false; weights.len()];
+
+    for &(tied, target) in &ties {
+        weights_enabled[target] |= weights_enabled[tied];
+        weights_tied[tied] = true;
+    }
+
+    for &(tied, target) in &ties {
+        weights_enabled[tied] = weights_enabled[target];
+        weights[tied] = weights[target];
+    }
+
     let k = k.unwrap_or_else(|| calculate_k(&positions, &coeffs, &indices, &weights, wdl_ratio, threads_count));
     let mut last_error = calculate_error(&positions, &coeffs, &indices, &weights, k, wdl_ratio, threads_count);
+    let mut best_weights = weights.clone();
+    let mut best_validation_error = f32::MAX;
+    let mut best_iteration = 0;
     let mut iterations_count = 0;
 
     println!("Scaling constant: {}", k);
+    println!("Optimizer: {:?}, learning rate: {}, schedule: {:?}, L2 regularization: {}", options.optimizer, base_learning_rate, options.schedule, options.l2);
 
     let mut start_time = SystemTime::now();
     loop {
-        gradients.fill(0.0);
-
-        // Calculate gradients for all coefficients
-        thread::scope(|scope| {
-            let mut threads = Vec::new();
-            let weights = Arc::new(weights.clone());
-
-            for chunk in positions.chunks_exact(positions.len() / threads_count) {
-                let weights = weights.clone();
-                let coeffs = coeffs.clone();
-                let indices = indices.clone();
-
-                threads.push(scope.spawn(move || {
-                    let mut gradients = vec![0.0; weights.len()];
-                    for position in chunk {
-                        let position_result = position.get_result() as f32 / 2.0;
-                        let position_phase = position.get_phase() as f32 / INITIAL_GAME_PHASE as f32;
-                        let eval = evaluate_position(position, &coeffs, &indices, &weights);
-
-                        let sig = sigmoid(eval, k);
-                        let a = ((1.0 - wdl_ratio) * sigmoid(position.eval as f32, k) + wdl_ratio * position_result) - sig;
-                        let b = sig * (1.0 - sig);
-
-                        for i in 0..position.coeffs_count {
-                            let index = position.base_index as usize + i as usize;
-
-                            // Skip material weights
-                            if indices[index] < 6 {
-                                continue;
-                            }
-
-                            let (value, phase) = coeffs[index].get_data();
-                            let phase = if phase == OPENING { position_phase } else { 1.0 - position_phase };
-                            let c = phase * value as f32;
-
-                            gradients[indices[index] as usize] += a * b * c;
-                        }
-                    }
+        let gauss_newton = options.optimizer == TunerOptimizer::GaussNewton;
+        let (mut gradients, mut curvatures, train_error) =
+            calculate_gradients(&positions, &coeffs, &indices, &weights, k, wdl_ratio, threads_count, gauss_newton);
+        let learning_rate = options.schedule.get_learning_rate(base_learning_rate, iterations_count);
+
+        let validation_error = if !validation_positions.is_empty() {
+            let error = calculate_error(&validation_positions, &coeffs, &indices, &weights, k, wdl_ratio, threads_count);
+            if error < best_validation_error {
+                best_validation_error = error;
+                best_weights.copy_from_slice(&weights);
+                best_iteration = iterations_count;
+            }
 
-                    gradients
-                }));
+            Some(error)
+        } else {
+            None
+        };
+
+        if let Some(log) = &mut log {
+            let validation_error = validation_error.map(|error| format!("{:.8}", error)).unwrap_or_default();
+            writeln!(log, "{},{},{:.8},{}", iterations_count, learning_rate, train_error, validation_error).unwrap();
+        }
+
+        let stop_early = validation_error.is_some() && options.patience > 0 && iterations_count - best_iteration >= options.patience;
+        let stop_limit = options.max_iterations.map_or(false, |max_iterations| iterations_count >= max_iterations);
+
+        if stop_early || stop_limit {
+            let (output_weights, error) = if validation_error.is_some() { (&best_weights, best_validation_error) } else { (&weights, train_error) };
+            write_output(output_weights, &current_weights, &gradient_magnitudes, output_directory, error, k, wdl_ratio, &options.king_buckets, &coverage);
+
+            match validation_error {
+                Some(_) => println!(
+                    "Tuner stopped after {} iterations, best validation error {:.6} at iteration {}",
+                    iterations_count, best_validation_error, best_iteration
+                ),
+                None => println!("Tuner stopped after {} iterations, error {:.6}", iterations_count, train_error),
             }
 
-            for thread in threads {
-                for (i, gradient) in thread.join().unwrap().iter().enumerate() {
-                    gradients[i] += gradient;
-                }
+            break;
+        }
+
+        // Tied weights share the same value, so their gradients are accumulated in the target weights
+        for &(tied, target) in &ties {
+            gradients[target] += gradients[tied];
+            if gauss_newton {
+                curvatures[target] += curvatures[tied];
+            }
+        }
+
+        // Apply gradients and calculate new weights, material ones are excluded unless they are tuned too
+        let first_weight = if options.material { 0 } else { MATERIAL_WEIGHTS_COUNT };
+        for i in first_weight..weights.len() {
+            if weights_tied[i] {
+                continue;
             }
-        });
 
-        // Apply gradients and calculate new weights but exclude material ones
-        for i in 6..weights.len() {
             if weights_enabled[i] {
-                let gradient = -2.0 * gradients[i] / positions.len() as f32;
-                m[i] = B1 * m[i] + (1.0 - B1) * gradient;
-                v[i] = B2 * v[i] + (1.0 - B2) * gradient.powi(2);
+                let gradient = -2.0 * gradients[i] / positions.len() as f32 + 2.0 * options.l2 * (weights[i] - current_weights[i]);
+                let step = match options.optimizer {
+                    TunerOptimizer::Adam => {
+                        m[i] = B1 * m[i] + (1.0 - B1) * gradient;
+                        v[i] = B2 * v[i] + (1.0 - B2) * gradient.powi(2);
+                        m[i] / (v[i] + EPSILON).sqrt()
+                    }
+                    TunerOptimizer::AdaGrad => {
+                        v[i] += gradient.powi(2);
+                        gradient / (v[i] + EPSILON).sqrt()
+                    }
+                    TunerOptimizer::GradientDescent => gradient,
+                    TunerOptimizer::GaussNewton => {
+                        let curvature = 2.0 * k * curvatures[i] / positions.len() as f32 + 2.0 * options.l2;
+                        gradient / (curvature + EPSILON)
+                    }
+                };
 
-                weights[i] -= LEARNING_RATE * m[i] / (v[i] + 0.00000001).sqrt();
+                gradient_magnitudes[i] = gradient.abs();
+                weights[i] -= learning_rate * step;
                 weights[i] = weights[i].clamp(tuner_params[i].min as f32, tuner_params[i].max as f32);
             } else {
                 weights[i] = f32::MIN;
             }
         }
 
-        if iterations_count % OUTPUT_INTERVAL == 0 {
-            for i in 0..tuner_params.len() {
-                tuner_params[i].value = weights[i].round() as i16;
-            }
+        for &(tied, target) in &ties {
+            weights[tied] = weights[target];
+            gradient_magnitudes[tied] = gradient_magnitudes[target];
+        }
 
-            let mut weights_iter = weights.iter().skip(6);
+        if iterations_count % OUTPUT_INTERVAL == 0 {
             let error = calculate_error(&positions, &coeffs, &indices, &weights, k, wdl_ratio, threads_count);
 
-            write_evaluation_parameters(&mut weights_iter, output_directory, error, k, wdl_ratio);
-            write_piece_square_table(&mut weights_iter, output_directory, error, k, wdl_ratio, "PAWN", PIECE_VALUES[PAWN]);
-            write_piece_square_table(&mut weights_iter, output_directory, error, k, wdl_ratio, "KNIGHT", PIECE_VALUES[KNIGHT]);
-            write_piece_square_table(&mut weights_iter, output_directory, error, k, wdl_ratio, "BISHOP", PIECE_VALUES[BISHOP]);
-            write_piece_square_table(&mut weights_iter, output_directory, error, k, wdl_ratio, "ROOK", PIECE_VALUES[ROOK]);
-            write_piece_square_table(&mut weights_iter, output_directory, error, k, wdl_ratio, "QUEEN", PIECE_VALUES[QUEEN]);
-            write_piece_square_table(&mut weights_iter, output_directory, error, k, wdl_ratio, "KING", 0);
+            match validation_error {
+                Some(_) => write_output(
+                    &best_weights,
+                    &current_weights,
+                    &gradient_magnitudes,
+                    output_directory,
+                    best_validation_error,
+                    k,
+                    wdl_ratio,
+                    &options.king_buckets,
+                    &coverage,
+                ),
+                None => write_output(&weights, &current_weights, &gradient_magnitudes, output_directory, error, k, wdl_ratio, &options.king_buckets, &coverage),
+            }
 
-            if weights_iter.next().is_some() {
-                panic_fast!("Weights iterator has not ended properly");
+            if let Some(log) = &mut log {
+                log.flush().unwrap();
             }
 
             println!(
-                "Iteration {} done in {} seconds, error reduced from {:.6} to {:.6} ({:.6})",
+                "Iteration {} done in {} seconds, error reduced from {:.6} to {:.6} ({:.6}){}",
                 iterations_count,
                 (start_time.elapsed().unwrap().as_millis() as f32) / 1000.0,
                 last_error,
                 error,
-                last_error - error
+                last_error - error,
+                match validation_error {
+                    Some(validation_error) =>
+                        format!(", validation error {:.6} (best {:.6} at iteration {})", validation_error, best_validation_error, best_iteration),
+                    None => String::new(),
+                }
             );
 
             last_error = error;
@@ -274,7 +530,7 @@ fn calculate_error(positions: &[TunerPosition], coeffs: &[TunerCoeff], indices:
         let mut threads = Vec::new();
         let weights = Arc::new(weights);
 
-        for chunk in positions.chunks_exact(positions_count / threads_count) {
+        for chunk in positions.chunks(get_chunk_size(positions_count, threads_count)) {
             let weights = weights.clone();
             threads.push(scope.spawn(move || {
                 let mut error = 0.0;
@@ -297,6 +553,235 @@ fn calculate_error(positions: &[TunerPosition], coeffs: &[TunerCoeff], indices:
     sum_of_errors / (positions_count as f32)
 }
 
+/// Calculates gradients (without the constant factors) for `positions`, `coeffs`, `indices`, `weights`, scaling constant `k` and `wdl_ratio`. If `curvature` is
+/// set, sums of squared derivatives of evaluation (diagonal of Gauss-Newton matrix) are calculated too. Returns a tuple with gradients, curvatures (empty if not
+/// requested) and an error for the current `weights`. Multithreading is supported by `threads_count`.
+fn calculate_gradients(
+    positions: &[TunerPosition],
+    coeffs: &Arc<Vec<TunerCoeff>>,
+    indices: &Arc<Vec<u16>>,
+    weights: &[f32],
+    k: f32,
+    wdl_ratio: f32,
+    threads_count: usize,
+    curvature: bool,
+) -> (Vec<f32>, Vec<f32>, f32) {
+    let mut gradients = vec![0.0; weights.len()];
+    let mut curvatures = if curvature { vec![0.0; weights.len()] } else { Vec::new() };
+    let mut sum_of_errors = 0.0;
+
+    thread::scope(|scope| {
+        let mut threads = Vec::new();
+        let weights = Arc::new(weights);
+
+        for chunk in positions.chunks(get_chunk_size(positions.len(), threads_count)) {
+            let weights = weights.clone();
+            let coeffs = coeffs.clone();
+            let indices = indices.clone();
+
+            threads.push(scope.spawn(move || {
+                let mut gradients = vec![0.0; weights.len()];
+                let mut curvatures = if curvature { vec![0.0; weights.len()] } else { Vec::new() };
+                let mut error = 0.0;
+
+                for position in chunk {
+                    let position_result = position.get_result() as f32 / 2.0;
+                    let position_phase = position.get_phase() as f32 / INITIAL_GAME_PHASE as f32;
+                    let eval = evaluate_position(position, &coeffs, &indices, &weights);
+
+                    let sig = sigmoid(eval, k);
+                    let a = ((1.0 - wdl_ratio) * sigmoid(position.eval as f32, k) + wdl_ratio * position_result) - sig;
+                    let b = sig * (1.0 - sig);
+                    error += a.powi(2);
+
+                    for i in 0..position.coeffs_count {
+                        let index = position.base_index as usize + i as usize;
+                        let (value, phase) = coeffs[index].get_data();
+                        let phase = if phase == OPENING { position_phase } else { 1.0 - position_phase };
+                        let c = phase * value as f32;
+
+                        gradients[indices[index] as usize] += a * b * c;
+                        if curvature {
+                            curvatures[indices[index] as usize] += (b * c).powi(2);
+                        }
+                    }
+                }
+
+                (gradients, curvatures, error)
+            }));
+        }
+
+        for thread in threads {
+            let (thread_gradients, thread_curvatures, thread_error) = thread.join().unwrap();
+            for (i, gradient) in thread_gradients.iter().enumerate() {
+                gradients[i] += gradient;
+            }
+
+            for (i, curvature) in thread_curvatures.iter().enumerate() {
+                curvatures[i] += curvature;
+            }
+
+            sum_of_errors += thread_error;
+        }
+    });
+
+    (gradients, curvatures, sum_of_errors / positions.len() as f32)
+}
+
+/// Shuffles `positions` (using constant seed, so the split is reproducible) and moves `validation_ratio` part of them into a separate list, which is returned.
+fn split_positions(positions: &mut Vec<TunerPosition>, validation_ratio: f32) -> Vec<TunerPosition> {
+    if validation_ratio <= 0.0 {
+        return Vec::new();
+    }
+
+    rand::seed(VALIDATION_SEED);
+    for i in (1..positions.len()).rev() {
+        positions.swap(i, rand::usize(0..=i));
+    }
+
+    let validation_count = (positions.len() as f32 * validation_ratio).round() as usize;
+    positions.split_off(positions.len() - validation_count)
+}
+
+/// Gets a size of the chunk, so `positions_count` positions are evenly distributed between `threads_count` threads.
+fn get_chunk_size(positions_count: usize, threads_count: usize) -> usize {
+    positions_count.div_ceil(threads_count.max(1)).max(1)
+}
+
+/// Saves Rust sources with `weights` (evaluation parameters, piece-square tables and `king_buckets` layout) and metadata (`error`, scaling constant `k`,
+/// `wdl_ratio`) into the `output_directory`, together with reports comparing them with `current_weights` and including `gradients` magnitudes and
+/// king buckets `coverage` (see [report::write]).
+fn write_output(
+    weights: &[f32],
+    current_weights: &[f32],
+    gradients: &[f32],
+    output_directory: &str,
+    error: f32,
+    k: f32,
+    wdl_ratio: f32,
+    king_buckets: &[usize; 64],
+    coverage: &[[usize; KING_BUCKETS_COUNT]; 2],
+) {
+    let mut weights_iter = weights.iter();
+    let buckets_count = king_buckets.iter().max().unwrap() + 1;
+
+    write_evaluation_parameters(&mut weights_iter, output_directory, error, k, wdl_ratio);
+    write_piece_square_table(&mut weights_iter, output_directory, error, k, wdl_ratio, "PAWN", get_piece_value(weights, PAWN), buckets_count);
+    write_piece_square_table(&mut weights_iter, output_directory, error, k, wdl_ratio, "KNIGHT", get_piece_value(weights, KNIGHT), buckets_count);
+    write_piece_square_table(&mut weights_iter, output_directory, error, k, wdl_ratio, "BISHOP", get_piece_value(weights, BISHOP), buckets_count);
+    write_piece_square_table(&mut weights_iter, output_directory, error, k, wdl_ratio, "ROOK", get_piece_value(weights, ROOK), buckets_count);
+    write_piece_square_table(&mut weights_iter, output_directory, error, k, wdl_ratio, "QUEEN", get_piece_value(weights, QUEEN), buckets_count);
+    write_piece_square_table(&mut weights_iter, output_directory, error, k, wdl_ratio, "KING", get_piece_value(weights, KING), buckets_count);
+
+    if weights_iter.next().is_some() {
+        panic_fast!("Weights iterator has not ended properly");
+    }
+
+    write_king_buckets(king_buckets, output_directory, error, k, wdl_ratio);
+    report::write(weights, current_weights, gradients, output_directory, error, k, wdl_ratio, king_buckets, coverage);
+}
+
+/// Gets opening and ending value of the `piece` stored in `weights` (or zeros if the piece value is not used), which is added to piece-square tables
+/// for our point of view when they are saved.
+fn get_piece_value(weights: &[f32], piece: usize) -> (f32, f32) {
+    let opening = weights[piece * 2];
+    let ending = weights[piece * 2 + 1];
+
+    (if opening != f32::MIN { opening } else { 0.0 }, if ending != f32::MIN { ending } else { 0.0 })
+}
+
+/// Gets the number of `positions` with at least one piece-square table coefficient (looked up in `indices`, where the last piece-square tables
+/// weights end at `weights_count`) in every king bucket, separately for both points of view.
+fn get_buckets_coverage(positions: &[TunerPosition], indices: &[u16], weights_count: usize) -> [[usize; KING_BUCKETS_COUNT]; 2] {
+    let pst_offset = weights_count - PIECE_VALUES.len() * 2 * KING_BUCKETS_COUNT * PST_BLOCK_SIZE;
+    let mut coverage = [[0; KING_BUCKETS_COUNT]; 2];
+
+    for position in positions {
+        let mut hits = [[false; KING_BUCKETS_COUNT]; 2];
+        for i in 0..position.coeffs_count {
+            let index = indices[position.base_index as usize + i as usize] as usize;
+            if index >= pst_offset {
+                let block = (index - pst_offset) / PST_BLOCK_SIZE;
+                hits[(block / KING_BUCKETS_COUNT) % 2][block % KING_BUCKETS_COUNT] = true;
+            }
+        }
+
+        for pov in ALL_POVS {
+            for bucket in 0..KING_BUCKETS_COUNT {
+                coverage[pov][bucket] += hits[pov][bucket] as usize;
+            }
+        }
+    }
+
+    coverage
+}
+
+/// Prints `coverage` of all king buckets used by `king_buckets` layout.
+fn print_buckets_coverage(coverage: &[[usize; KING_BUCKETS_COUNT]; 2], king_buckets: &[usize; 64]) {
+    println!("King buckets coverage (positions for our king / enemy king):");
+    for bucket in 0..KING_BUCKETS_COUNT {
+        if king_buckets.contains(&bucket) {
+            println!(" - bucket {}: {} / {}", bucket, coverage[US][bucket], coverage[THEM][bucket]);
+        }
+    }
+}
+
+/// Gets a list of `(tied, target)` weight pairs for piece-square tables in king buckets with less than `threshold` positions in `coverage`. Every such
+/// bucket is tied with the nearest (by the distance between king squares in `king_buckets` layout) bucket with enough positions, so both of them share the
+/// same values during optimization. The last piece-square tables weights end at `weights_count`.
+fn get_bucket_ties(coverage: &[[usize; KING_BUCKETS_COUNT]; 2], king_buckets: &[usize; 64], threshold: usize, weights_count: usize) -> Vec<(usize, usize)> {
+    let pst_offset = weights_count - PIECE_VALUES.len() * 2 * KING_BUCKETS_COUNT * PST_BLOCK_SIZE;
+    let mut ties = Vec::new();
+
+    for pov in ALL_POVS {
+        for bucket in 0..KING_BUCKETS_COUNT {
+            if coverage[pov][bucket] >= threshold || !king_buckets.contains(&bucket) {
+                continue;
+            }
+
+            let target = (0..KING_BUCKETS_COUNT)
+                .filter(|target| coverage[pov][*target] >= threshold && king_buckets.contains(target))
+                .min_by_key(|target| (get_buckets_distance(king_buckets, bucket, *target), std::cmp::Reverse(coverage[pov][*target])));
+
+            let pov_name = if pov == US { "our king" } else { "enemy king" };
+            let target = match target {
+                Some(value) => value,
+                None => {
+                    println!("King bucket {} ({}) has {} positions, but there's no bucket to tie with", bucket, pov_name, coverage[pov][bucket]);
+                    continue;
+                }
+            };
+
+            println!("King bucket {} ({}) has {} positions, tied with bucket {}", bucket, pov_name, coverage[pov][bucket], target);
+
+            for piece in ALL_PIECES {
+                let tied_offset = pst_offset + ((piece * 2 + pov) * KING_BUCKETS_COUNT + bucket) * PST_BLOCK_SIZE;
+                let target_offset = pst_offset + ((piece * 2 + pov) * KING_BUCKETS_COUNT + target) * PST_BLOCK_SIZE;
+
+                for i in 0..PST_BLOCK_SIZE {
+                    ties.push((tied_offset + i, target_offset + i));
+                }
+            }
+        }
+    }
+
+    ties
+}
+
+/// Gets the smallest distance between king squares of buckets `a` and `b` in `king_buckets` layout.
+fn get_buckets_distance(king_buckets: &[usize; 64], a: usize, b: usize) -> u32 {
+    let mut distance = u32::MAX;
+    for (a_square, _) in king_buckets.iter().enumerate().filter(|(_, bucket)| **bucket == a) {
+        for (b_square, _) in king_buckets.iter().enumerate().filter(|(_, bucket)| **bucket == b) {
+            let rank_distance = (a_square / 8).abs_diff(b_square / 8);
+            let file_distance = (a_square % 8).abs_diff(b_square % 8);
+            distance = distance.min(rank_distance.max(file_distance) as u32);
+        }
+    }
+
+    distance
+}
+
 /// Evaluates a single `position` based on `coeffs`, `indices`, `weights`, and returns a score (casted to float).
 fn evaluate_position(position: &TunerPosition, coeffs: &[TunerCoeff], indices: &[u16], weights: &[f32]) -> f32 {
     let mut opening_score = 0.0;
@@ -308,15 +793,10 @@ fn evaluate_position(position: &TunerPosition, coeffs: &[TunerCoeff], indices: &
         let (value, phase) = coeffs[index].get_data();
         let value = weights[indices[index] as usize] * value as f32;
 
-        if indices[index] < 6 {
+        if phase == OPENING {
             opening_score += value;
-            ending_score += value;
         } else {
-            if phase == OPENING {
-                opening_score += value;
-            } else {
-                ending_score += value;
-            }
+            ending_score += value;
         }
     }
 
@@ -330,7 +810,13 @@ fn sigmoid(e: f32, k: f32) -> f32 {
 
 /// Loads positions from the `epd` and parses them into a list of [TunerPosition], while filling `coeffs`, `indices` and `weights_indices`.
 /// Returns [Err] with a proper error message if the file couldn't be parsed.
-fn load_positions(epd: &str, coeffs: &mut Vec<TunerCoeff>, indices: &mut Vec<u16>, weights_indices: &mut HashSet<u16>) -> Result<Vec<TunerPosition>, String> {
+fn load_positions(
+    epd: &str,
+    king_buckets: &[usize; 64],
+    coeffs: &mut Vec<TunerCoeff>,
+    indices: &mut Vec<u16>,
+    weights_indices: &mut HashSet<u16>,
+) -> Result<Vec<TunerPosition>, String> {
     let mut positions = Vec::new();
     let file = match File::open(epd) {
         Ok(value) => value,
@@ -339,68 +825,104 @@ fn load_positions(epd: &str, coeffs: &mut Vec<TunerCoeff>, indices: &mut Vec<u16
 
     for line in BufReader::new(file).lines() {
         let position = line.unwrap();
-        let parsed_epd = fen::epd_to_board(position.as_str())?;
-
-        if parsed_epd.comment.is_none() {
-            return Err("Game result not found".to_string());
-        }
-
-        let comment = parsed_epd.comment.unwrap();
-        let comment_tokens = comment.split('|').collect::<Vec<&str>>();
-        let eval = (comment_tokens[0].parse::<f32>().unwrap() * 100.0) as i16;
-        let result = match comment_tokens[1] {
-            "0-1" => 0,
-            "1/2-1/2" => 1,
-            "1-0" => 2,
-            _ => return Err(format!("Invalid game result: comment_tokens[1]={}", comment)),
-        };
-
-        let mut index = 0;
+        let (board, eval, result) = parse_position(&position)?;
         let base_index = coeffs.len();
-        let mut white_aux = EvalAux::default();
-        let mut black_aux = EvalAux::default();
-
-        material::get_coeffs(&parsed_epd.board, &mut index, coeffs, indices);
-        mobility::get_coeffs(&parsed_epd.board, &mut white_aux, &mut black_aux, &mut index, coeffs, indices);
-        pawns::get_coeffs(&parsed_epd.board, &mut index, coeffs, indices);
-        safety::get_coeffs(&parsed_epd.board, &white_aux, &black_aux, &mut index, coeffs, indices);
-        pst::get_coeffs(&parsed_epd.board, PAWN, &mut index, coeffs, indices);
-        pst::get_coeffs(&parsed_epd.board, KNIGHT, &mut index, coeffs, indices);
-        pst::get_coeffs(&parsed_epd.board, BISHOP, &mut index, coeffs, indices);
-        pst::get_coeffs(&parsed_epd.board, ROOK, &mut index, coeffs, indices);
-        pst::get_coeffs(&parsed_epd.board, QUEEN, &mut index, coeffs, indices);
-        pst::get_coeffs(&parsed_epd.board, KING, &mut index, coeffs, indices);
+
+        get_coeffs(&board, king_buckets, coeffs, indices);
 
         for i in base_index..coeffs.len() {
             weights_indices.insert(indices[i]);
         }
 
-        positions.push(TunerPosition::new(eval, result, parsed_epd.board.game_phase, base_index as u32, (coeffs.len() - base_index) as u8));
+        positions.push(TunerPosition::new(eval, result, board.game_phase, base_index as u32, (coeffs.len() - base_index) as u8));
     }
 
     Ok(positions)
 }
 
+/// Parses a single EPD `line` with the position and `eval|result` comment (stored as `c0` or `c9`). Returns a tuple with the board, eval and
+/// result (0 = black won, 1 = draw, 2 = white won), or [Err] with a proper error message if the line couldn't be parsed.
+pub fn parse_position(line: &str) -> Result<(Board, i16, u8), String> {
+    let mut parsed_epd = fen::epd_to_board(line)?;
+    let comment = match parsed_epd.comments[0].take().or(parsed_epd.comments[9].take()) {
+        Some(value) => value,
+        None => return Err("Game result not found".to_string()),
+    };
+
+    let comment_tokens = comment.split('|').collect::<Vec<&str>>();
+    if comment_tokens.len() != 2 {
+        return Err(format!("Invalid comment: comment={}", comment));
+    }
+
+    let eval = match comment_tokens[0].parse::<f32>() {
+        Ok(value) => (value * 100.0) as i16,
+        Err(error) => return Err(format!("Invalid eval: {}", error)),
+    };
+
+    let result = match comment_tokens[1] {
+        "0-1" => 0,
+        "1/2-1/2" => 1,
+        "1-0" => 2,
+        _ => return Err(format!("Invalid game result: comment_tokens[1]={}", comment)),
+    };
+
+    Ok((parsed_epd.board, eval, result))
+}
+
+/// Calculates coefficients of all evaluation parameters for the `board` (with piece-square tables selected by `king_buckets` layout), and appends
+/// them to `coeffs` and `indices`.
+pub fn get_coeffs(board: &Board, king_buckets: &[usize; 64], coeffs: &mut Vec<TunerCoeff>, indices: &mut Vec<u16>) {
+    let mut index = 0;
+    let mut white_aux = EvalAux::default();
+    let mut black_aux = EvalAux::default();
+
+    material::get_coeffs(board, &mut index, coeffs, indices);
+    mobility::get_coeffs(board, &mut white_aux, &mut black_aux, &mut index, coeffs, indices);
+    pawns::get_coeffs(board, &mut index, coeffs, indices);
+    safety::get_coeffs(board, &white_aux, &black_aux, &mut index, coeffs, indices);
+    threats::get_coeffs(board, &white_aux, &black_aux, &mut index, coeffs, indices);
+    passers::get_coeffs(board, &white_aux, &black_aux, &mut index, coeffs, indices);
+    material::get_imbalance_coeffs(board, &mut index, coeffs, indices);
+    pst::get_coeffs(board, PAWN, king_buckets, &mut index, coeffs, indices);
+    pst::get_coeffs(board, KNIGHT, king_buckets, &mut index, coeffs, indices);
+    pst::get_coeffs(board, BISHOP, king_buckets, &mut index, coeffs, indices);
+    pst::get_coeffs(board, ROOK, king_buckets, &mut index, coeffs, indices);
+    pst::get_coeffs(board, QUEEN, king_buckets, &mut index, coeffs, indices);
+    pst::get_coeffs(board, KING, king_buckets, &mut index, coeffs, indices);
+}
+
+/// Gets the number of all tuner weights, which depends on the current evaluation layout.
+pub fn get_weights_count() -> usize {
+    load_values(false).len()
+}
+
 /// Transforms the current evaluation values into a list of [TunerParameter]. Use  `random_values` if the parameters should have
 /// random values (useful when initializing tuner).
 fn load_values(random_values: bool) -> Vec<TunerParameter> {
-    let mut params = vec![
-        TunerParameter::new(PIECE_VALUES[PAWN], PIECE_VALUES[PAWN], PIECE_VALUES[PAWN], PIECE_VALUES[PAWN], PIECE_VALUES[PAWN]),
-        TunerParameter::new(PIECE_VALUES[KNIGHT], PIECE_VALUES[KNIGHT], PIECE_VALUES[KNIGHT], PIECE_VALUES[KNIGHT], PIECE_VALUES[KNIGHT]),
-        TunerParameter::new(PIECE_VALUES[BISHOP], PIECE_VALUES[BISHOP], PIECE_VALUES[BISHOP], PIECE_VALUES[BISHOP], PIECE_VALUES[BISHOP]),
-        TunerParameter::new(PIECE_VALUES[ROOK], PIECE_VALUES[ROOK], PIECE_VALUES[ROOK], PIECE_VALUES[ROOK], PIECE_VALUES[ROOK]),
-        TunerParameter::new(PIECE_VALUES[QUEEN], PIECE_VALUES[QUEEN], PIECE_VALUES[QUEEN], PIECE_VALUES[QUEEN], PIECE_VALUES[QUEEN]),
-        TunerParameter::new(PIECE_VALUES[KING], PIECE_VALUES[KING], PIECE_VALUES[KING], PIECE_VALUES[KING], PIECE_VALUES[KING]),
+    // Piece values are not randomized, as piece-square tables for our point of view are initialized without them
+    let mut params = Vec::new();
+    for value in params::TAPERED_PIECE_VALUES {
+        params.push(TunerParameter::new(value.get_opening(), 0, value.get_opening(), value.get_opening(), 9999));
+        params.push(TunerParameter::new(value.get_ending(), 0, value.get_ending(), value.get_ending(), 9999));
+    }
+
+    params.append(&mut vec![
         TunerParameter::new(params::BISHOP_PAIR.get_opening(), -99, 10, 40, 99),
         TunerParameter::new(params::BISHOP_PAIR.get_ending(), -99, 10, 40, 99),
         TunerParameter::new(params::PAWNS_ATTACKING_PIECES.get_opening(), -99, 10, 40, 99),
         TunerParameter::new(params::PAWNS_ATTACKING_PIECES.get_ending(), -99, 10, 40, 99),
-    ];
+    ]);
 
     params.append(&mut params::ROOK_OPEN_FILE.to_tuner_params(-999, 10, 40, 999, 0).to_vec());
     params.append(&mut params::ROOK_SEMI_OPEN_FILE.to_tuner_params(-999, 10, 40, 999, 0).to_vec());
     params.append(&mut params::MOBILITY_INNER.iter().flat_map(|v| v.to_tuner_params(0, 2, 6, 99, 0)).collect());
     params.append(&mut params::MOBILITY_OUTER.iter().flat_map(|v| v.to_tuner_params(0, 2, 6, 99, 0)).collect());
+    params.append(&mut params::KNIGHT_OUTPOST.to_tuner_params(-999, 10, 40, 999, 0).to_vec());
+    params.append(&mut params::BISHOP_OUTPOST.to_tuner_params(-999, 10, 40, 999, 0).to_vec());
+    params.append(&mut params::BAD_BISHOP.to_tuner_params(-999, -40, -10, 999, 0).to_vec());
+    params.append(&mut params::ROOK_ON_SEVENTH.to_tuner_params(-999, 10, 40, 999, 0).to_vec());
+    params.append(&mut params::QUEEN_ON_SEVENTH.to_tuner_params(-999, 10, 40, 999, 0).to_vec());
+    params.append(&mut params::CONNECTED_ROOKS.to_tuner_params(-999, 10, 40, 999, 0).to_vec());
     params.append(&mut params::DOUBLED_PAWN.iter().flat_map(|v| v.to_tuner_params(-999, -40, -10, 999, 0)).collect());
     params.append(&mut params::ISOLATED_PAWN.iter().flat_map(|v| v.to_tuner_params(-999, -40, -10, 999, 0)).collect());
     params.append(&mut params::CHAINED_PAWN.iter().flat_map(|v| v.to_tuner_params(-999, 10, 40, 999, 0)).collect());
@@ -414,37 +936,50 @@ fn load_values(random_values: bool) -> Vec<TunerParameter> {
     params.append(&mut params::BISHOP_SAFE_CHECKS.iter().flat_map(|v| v.to_tuner_params(-999, -40, 40, 999, 0)).collect());
     params.append(&mut params::ROOK_SAFE_CHECKS.iter().flat_map(|v| v.to_tuner_params(-999, -40, 40, 999, 0)).collect());
     params.append(&mut params::QUEEN_SAFE_CHECKS.iter().flat_map(|v| v.to_tuner_params(-999, -40, 40, 999, 0)).collect());
-
+    params.append(&mut params::MINORS_ATTACKING_MAJORS.iter().flat_map(|v| v.to_tuner_params(-999, 10, 40, 999, 0)).collect());
+    params.append(&mut params::HANGING_PIECES.iter().flat_map(|v| v.to_tuner_params(-999, 10, 40, 999, 0)).collect());
+    params.append(&mut params::SAFE_PAWN_PUSH_THREATS.iter().flat_map(|v| v.to_tuner_params(-999, 10, 40, 999, 0)).collect());
+    params.append(&mut params::QUEEN_ATTACKS.iter().flat_map(|v| v.to_tuner_params(-999, 10, 40, 999, 0)).collect());
+    params.append(&mut params::PASSED_PAWN_OWN_KING_DISTANCE.iter().flat_map(|v| v.to_tuner_params(-999, -40, 40, 999, 0)).collect());
+    params.append(&mut params::PASSED_PAWN_ENEMY_KING_DISTANCE.iter().flat_map(|v| v.to_tuner_params(-999, -40, 40, 999, 0)).collect());
+    params.append(&mut params::PASSED_PAWN_BLOCKED.to_tuner_params(-999, -40, -10, 999, 0).to_vec());
+    params.append(&mut params::PASSED_PAWN_FREE_PATH.to_tuner_params(-999, 10, 40, 999, 0).to_vec());
+    params.append(&mut params::PASSED_PAWN_UNSTOPPABLE.to_tuner_params(-999, 10, 40, 999, 0).to_vec());
+    params.append(&mut params::IMBALANCE_OURS.iter().flat_map(|v| v.to_tuner_params(-99, -5, 5, 99, 0)).collect());
+    params.append(&mut params::IMBALANCE_THEIRS.iter().flat_map(|v| v.to_tuner_params(-99, -5, 5, 99, 0)).collect());
+
+    // Piece-square tables for our point of view are tuned without piece values, so they are subtracted here and added back when saving the output
     for pov in ALL_POVS {
+        let offset = if pov == US { params::TAPERED_PIECE_VALUES[PAWN] } else { PackedEval::default() };
         for bucket in &pst::PAWN_PST_PATTERN[pov] {
-            params.append(&mut bucket.iter().flat_map(|v| v.to_tuner_params(-9999, 50, 150, 9999, if pov == US { -PIECE_VALUES[PAWN] } else { 0 })).collect());
+            params.append(&mut bucket.iter().flat_map(|v| (*v - offset).to_tuner_params(-9999, 50, 150, 9999, 0)).collect());
         }
     }
 
     for pov in ALL_POVS {
-        let offset = if pov == US { -PIECE_VALUES[KNIGHT] } else { 0 };
+        let offset = if pov == US { params::TAPERED_PIECE_VALUES[KNIGHT] } else { PackedEval::default() };
         for bucket in &pst::KNIGHT_PST_PATTERN[pov] {
-            params.append(&mut bucket.iter().flat_map(|v| v.to_tuner_params(-9999, 300, 500, 9999, offset)).collect());
+            params.append(&mut bucket.iter().flat_map(|v| (*v - offset).to_tuner_params(-9999, 300, 500, 9999, 0)).collect());
         }
     }
 
     for pov in ALL_POVS {
-        let offset = if pov == US { -PIECE_VALUES[BISHOP] } else { 0 };
+        let offset = if pov == US { params::TAPERED_PIECE_VALUES[BISHOP] } else { PackedEval::default() };
         for bucket in &pst::BISHOP_PST_PATTERN[pov] {
-            params.append(&mut bucket.iter().flat_map(|v| v.to_tuner_params(-9999, 300, 500, 9999, offset)).collect());
+            params.append(&mut bucket.iter().flat_map(|v| (*v - offset).to_tuner_params(-9999, 300, 500, 9999, 0)).collect());
         }
     }
     for pov in ALL_POVS {
-        let offset = if pov == US { -PIECE_VALUES[ROOK] } else { 0 };
+        let offset = if pov == US { params::TAPERED_PIECE_VALUES[ROOK] } else { PackedEval::default() };
         for bucket in &pst::ROOK_PST_PATTERN[pov] {
-            params.append(&mut bucket.iter().flat_map(|v| v.to_tuner_params(-9999, 400, 600, 9999, offset)).collect());
+            params.append(&mut bucket.iter().flat_map(|v| (*v - offset).to_tuner_params(-9999, 400, 600, 9999, 0)).collect());
         }
     }
 
     for pov in ALL_POVS {
-        let offset = if pov == US { -PIECE_VALUES[QUEEN] } else { 0 };
+        let offset = if pov == US { params::TAPERED_PIECE_VALUES[QUEEN] } else { PackedEval::default() };
         for bucket in &pst::QUEEN_PST_PATTERN[pov] {
-            params.append(&mut bucket.iter().flat_map(|v| v.to_tuner_params(-9999, 800, 1400, 9999, offset)).collect());
+            params.append(&mut bucket.iter().flat_map(|v| (*v - offset).to_tuner_params(-9999, 800, 1400, 9999, 0)).collect());
         }
     }
 
@@ -480,12 +1015,19 @@ where
     output.push_str("use super::*;\n");
     output.push('\n');
     output.push_str(get_constant("TEMPO", params::TEMPO).as_str());
+    output.push_str(get_array("TAPERED_PIECE_VALUES", weights, 6).as_str());
     output.push_str(get_parameter("BISHOP_PAIR", weights).as_str());
     output.push_str(get_parameter("PAWNS_ATTACKING_PIECES", weights).as_str());
     output.push_str(get_parameter("ROOK_OPEN_FILE", weights).as_str());
     output.push_str(get_parameter("ROOK_SEMI_OPEN_FILE", weights).as_str());
     output.push_str(get_array("MOBILITY_INNER", weights, 6).as_str());
     output.push_str(get_array("MOBILITY_OUTER", weights, 6).as_str());
+    output.push_str(get_parameter("KNIGHT_OUTPOST", weights).as_str());
+    output.push_str(get_parameter("BISHOP_OUTPOST", weights).as_str());
+    output.push_str(get_parameter("BAD_BISHOP", weights).as_str());
+    output.push_str(get_parameter("ROOK_ON_SEVENTH", weights).as_str());
+    output.push_str(get_parameter("QUEEN_ON_SEVENTH", weights).as_str());
+    output.push_str(get_parameter("CONNECTED_ROOKS", weights).as_str());
     output.push_str(get_array("DOUBLED_PAWN", weights, 8).as_str());
     output.push_str(get_array("ISOLATED_PAWN", weights, 8).as_str());
     output.push_str(get_array("CHAINED_PAWN", weights, 8).as_str());
@@ -499,6 +1041,25 @@ where
     output.push_str(get_array("BISHOP_SAFE_CHECKS", weights, 8).as_str());
     output.push_str(get_array("ROOK_SAFE_CHECKS", weights, 8).as_str());
     output.push_str(get_array("QUEEN_SAFE_CHECKS", weights, 8).as_str());
+    output.push_str(get_array("MINORS_ATTACKING_MAJORS", weights, 8).as_str());
+    output.push_str(get_array("HANGING_PIECES", weights, 8).as_str());
+    output.push_str(get_array("SAFE_PAWN_PUSH_THREATS", weights, 8).as_str());
+    output.push_str(get_array("QUEEN_ATTACKS", weights, 8).as_str());
+    output.push_str(get_array("PASSED_PAWN_OWN_KING_DISTANCE", weights, 8).as_str());
+    output.push_str(get_array("PASSED_PAWN_ENEMY_KING_DISTANCE", weights, 8).as_str());
+    output.push_str(get_parameter("PASSED_PAWN_BLOCKED", weights).as_str());
+    output.push_str(get_parameter("PASSED_PAWN_FREE_PATH", weights).as_str());
+    output.push_str(get_parameter("PASSED_PAWN_UNSTOPPABLE", weights).as_str());
+    output.push_str(get_triangle("IMBALANCE_OURS", weights, 5, true).as_str());
+    output.push_str(get_triangle("IMBALANCE_THEIRS", weights, 5, false).as_str());
+
+    // Specialised endgame evaluators are not tuned, so their constants are saved unchanged
+    output.push_str(get_constant("KPK_PAWN_RANK_BONUS", params::KPK_PAWN_RANK_BONUS).as_str());
+    output.push_str(get_constant("KBNK_CORNER_DISTANCE_BONUS", params::KBNK_CORNER_DISTANCE_BONUS).as_str());
+    output.push_str(get_constant("KBNK_KINGS_DISTANCE_BONUS", params::KBNK_KINGS_DISTANCE_BONUS).as_str());
+    output.push_str(get_constant("KBNK_CENTER_DISTANCE_BONUS", params::KBNK_CENTER_DISTANCE_BONUS).as_str());
+    output.push_str(get_constant("KXK_CENTER_DISTANCE_BONUS", params::KXK_CENTER_DISTANCE_BONUS).as_str());
+    output.push_str(get_constant("KXK_KINGS_DISTANCE_BONUS", params::KXK_KINGS_DISTANCE_BONUS).as_str());
 
     let path = Path::new(output_directory);
     fs::create_dir_all(path).unwrap();
@@ -507,10 +1068,19 @@ where
     write!(&mut File::create(path).unwrap(), "{}", output).unwrap();
 }
 
-/// Generates piece-square tables as Rust source file `name`.rs with current `weights` and metadata (`best_error`, scaling constant `k`, `wdl_ratio`),
-/// then saves it into the `output_directory`.
-fn write_piece_square_table<'a, W>(weights: &mut W, output_directory: &str, best_error: f32, k: f32, wdl_ratio: f32, name: &str, piece_value: i16)
-where
+/// Generates piece-square tables as Rust source file `name`.rs with current `weights` (our point of view adjusted by opening and ending `piece_value`)
+/// and metadata (`best_error`, scaling constant `k`, `wdl_ratio`), then saves it into the `output_directory`. Only the first `buckets_count` buckets
+/// (the ones used by the king buckets layout) are saved, so the tables match `KING_BUCKETS_COUNT` emitted together with the layout.
+fn write_piece_square_table<'a, W>(
+    weights: &mut W,
+    output_directory: &str,
+    best_error: f32,
+    k: f32,
+    wdl_ratio: f32,
+    name: &str,
+    piece_value: (f32, f32),
+    buckets_count: usize,
+) where
     W: Iterator<Item = &'a f32>,
 {
     let mut output = String::new();
@@ -526,10 +1096,13 @@ where
     for pov in ALL_POVS {
         output.push_str("    [\n");
 
-        for _ in 0..KING_BUCKETS_COUNT {
-            output.push_str("        [\n");
-            output.push_str(get_piece_square_table(weights, if pov == US { piece_value } else { 0 }).as_str());
-            output.push_str("        ],\n");
+        for bucket in 0..KING_BUCKETS_COUNT {
+            let table = get_piece_square_table(weights, if pov == US { piece_value } else { (0.0, 0.0) });
+            if bucket < buckets_count {
+                output.push_str("        [\n");
+                output.push_str(table.as_str());
+                output.push_str("        ],\n");
+            }
         }
 
         output.push_str("    ],\n");
@@ -544,6 +1117,30 @@ where
     write!(&mut File::create(path).unwrap(), "{}", output).unwrap();
 }
 
+/// Generates `buckets.rs` file with `king_buckets` layout, the number of buckets used by it and metadata (`best_error`, scaling constant `k`, `wdl_ratio`),
+/// then saves it into the `pst` subdirectory of `output_directory`. The file has the same format as `pst/buckets.rs` compiled into the engine.
+fn write_king_buckets(king_buckets: &[usize; 64], output_directory: &str, best_error: f32, k: f32, wdl_ratio: f32) {
+    let mut output = String::new();
+
+    output.push_str(get_header(best_error, k, wdl_ratio).as_str());
+    output.push('\n');
+    output.push_str(&format!("pub const KING_BUCKETS_COUNT: usize = {};\n", king_buckets.iter().max().unwrap() + 1));
+    output.push('\n');
+    output.push_str("#[rustfmt::skip]\n");
+    output.push_str("pub const KING_BUCKETS: [usize; 64] = [\n");
+
+    for rank in king_buckets.chunks(8) {
+        let values = rank.iter().map(|bucket| format!("{:<4}", format!("{},", bucket))).collect::<Vec<String>>().concat();
+        output.push_str(&format!("    {}\n", values.trim_end()));
+    }
+
+    output.push_str("];\n");
+
+    let path = Path::new(output_directory).join("pst");
+    fs::create_dir_all(&path).unwrap();
+    write!(&mut File::create(path.join("buckets.rs")).unwrap(), "{}", output).unwrap();
+}
+
 /// Gets a generated Rust source file header with timestamp, `best_error`, scaling constant `k` and `wdl_ratio`.
 fn get_header(best_error: f32, k: f32, wdl_ratio: f32) -> String {
     let mut output = String::new();
@@ -580,6 +1177,41 @@ where
     output
 }
 
+/// Gets a Rust representation of `weights` as a lower triangular matrix `name` with `size` rows (flattened into a single array, one row per line),
+/// including the `diagonal` if specified.
+fn get_triangle<'a, W>(name: &str, weights: &mut W, size: usize, diagonal: bool) -> String
+where
+    W: Iterator<Item = &'a f32>,
+{
+    let first_row = if diagonal { 0 } else { 1 };
+    let length = (first_row..size).map(|row| row + 1 - first_row).sum::<usize>();
+
+    let mut output = String::new();
+    output.push_str("#[rustfmt::skip]\n");
+    output.push_str(&format!("pub const {}: [PackedEval; {}] = [\n", name, length));
+
+    for row in first_row..size {
+        output.push_str("    ");
+        for column in 0..(row + 1 - first_row) {
+            if column > 0 {
+                output += " ";
+            }
+
+            let opening_score = *weights.next().unwrap();
+            let opening_score = if opening_score != f32::MIN { opening_score.round() } else { 0.0 };
+
+            let ending_score = *weights.next().unwrap();
+            let ending_score = if ending_score != f32::MIN { ending_score.round() } else { 0.0 };
+
+            output += &format!("s!({}, {}),", opening_score, ending_score);
+        }
+        output.push('\n');
+    }
+
+    output.push_str("];\n");
+    output
+}
+
 /// Gets a Rust representation of the constant value.
 fn get_constant(name: &str, value: i16) -> String {
     format!("pub const {}: i16 = {};\n", name, value)
@@ -599,8 +1231,8 @@ where
     format!("pub const {}: PackedEval = s!({}, {});\n", name, opening_score, ending_score)
 }
 
-/// Gets a Rust representation of the piece-square tables with `weights` adjusted by `piece_value`.
-fn get_piece_square_table<'a, W>(weights: &mut W, piece_value: i16) -> String
+/// Gets a Rust representation of the piece-square tables with `weights` adjusted by opening and ending `piece_value`.
+fn get_piece_square_table<'a, W>(weights: &mut W, piece_value: (f32, f32)) -> String
 where
     W: Iterator<Item = &'a f32>,
 {
@@ -609,10 +1241,10 @@ where
 
     for index in ALL_SQUARES {
         let opening_score = *weights.next().unwrap();
-        let opening_score = if opening_score != f32::MIN { opening_score + piece_value as f32 } else { 0.0 };
+        let opening_score = if opening_score != f32::MIN { opening_score + piece_value.0 } else { 0.0 };
 
         let ending_score = *weights.next().unwrap();
-        let ending_score = if ending_score != f32::MIN { ending_score + piece_value as f32 } else { 0.0 };
+        let ending_score = if ending_score != f32::MIN { ending_score + piece_value.1 } else { 0.0 };
 
         output.push_str(format!("s!({:4}, {:4})", opening_score.round(), ending_score.round()).as_str());
         if index % 8 == 7 {
@@ -27,6 +27,20 @@ macro_rules! param {
     };
 }
 
+#[cfg(not(feature = "dev"))]
+macro_rules! eval_param {
+    ($name: ident) => {
+        crate::evaluation::params::$name
+    };
+}
+
+#[cfg(feature = "dev")]
+macro_rules! eval_param {
+    ($name: ident) => {
+        crate::evaluation::runtime::get().$name
+    };
+}
+
 macro_rules! panic_fast {
     ($fmt:expr) => ({
         if cfg!(feature = "dev") {
@@ -59,6 +73,7 @@ macro_rules! assert_fast {
 
 pub(crate) use assert_fast;
 pub(crate) use dev;
+pub(crate) use eval_param;
 pub(crate) use panic_fast;
 pub(crate) use param;
 pub(crate) use percent;
@@ -0,0 +1,129 @@
+#[cfg(test)]
+mod analyze_tests {
+    use inanis::cache::eval::EHTable;
+    use inanis::cache::material::MHTable;
+    use inanis::cache::pawns::PHTable;
+    use inanis::cache::search::TTable;
+    use inanis::engine::see;
+    use inanis::engine::CHECKMATE_SCORE;
+    use inanis::state::movescan::Move;
+    use inanis::state::representation::Board;
+    use inanis::state::text::pgn::PGNLoader;
+    use inanis::state::*;
+    use inanis::testing::analyze;
+    use inanis::testing::analyze::AnalysisLimit;
+    use inanis::testing::analyze::AnalysisOptions;
+    use inanis::testing::analyze::PositionAnalysis;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::Arc;
+    use std::sync::Once;
+
+    static INIT: Once = Once::new();
+
+    macro_rules! annotate_tests {
+        ($($name:ident: $pgn:expr, $scores:expr, $best_moves:expr, $expected_evals:expr, $expected_mates:expr, $expected_nags:expr, $expected_counts:expr,)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    INIT.call_once(|| {
+                        see::init();
+                        movegen::init();
+                    });
+
+                    let mut pgn = PGNLoader::new($pgn.as_bytes()).next().unwrap().unwrap();
+                    let mut board = match &pgn.fen {
+                        Some(fen) => Board::new_from_fen(fen).unwrap(),
+                        None => Board::new_initial_position(),
+                    };
+
+                    let mut positions = Vec::new();
+                    for (index, score) in $scores.iter().enumerate() {
+                        let best_move = $best_moves.get(index).map(|r#move| Move::from_long_notation(r#move, &board).unwrap());
+                        positions.push(PositionAnalysis::new(*score, best_move.into_iter().collect()));
+
+                        if let Some(data) = pgn.data.get(index) {
+                            board.make_move(data.r#move);
+                        }
+                    }
+
+                    let counts = analyze::annotate(&mut pgn, &positions, &AnalysisOptions::default());
+                    let evals = pgn.data.iter().map(|data| data.eval.unwrap()).collect::<Vec<_>>();
+                    let mates = pgn.data.iter().map(|data| data.mate).collect::<Vec<_>>();
+                    let nags = pgn.data.iter().map(|data| data.nag.map(|nag| format!("${}", nag))).collect::<Vec<_>>();
+                    let variations = pgn.data.iter().map(|data| data.variation.len()).collect::<Vec<_>>();
+                    let expected_nags = $expected_nags.map(|nag: Option<&str>| nag.map(|nag| nag.to_string()));
+                    let expected_variations = expected_nags.iter().map(|nag| if nag.is_some() { 1 } else { 0 }).collect::<Vec<_>>();
+
+                    assert_eq!($expected_evals, evals[..]);
+                    assert_eq!($expected_mates, mates[..]);
+                    assert_eq!(expected_nags, nags[..]);
+                    assert_eq!(expected_variations, variations);
+                    assert_eq!($expected_counts, counts);
+                }
+            )*
+        }
+    }
+
+    annotate_tests! {
+        annotate_no_drops: "[Result \"*\"]\n\n1. e4 e5 2. Nf3 Nc6 *\n",
+            [20, -20, 30, -30, 30], ["d2d4", "c7c5", "f1c4", "d7d6"],
+            [0.2, 0.3, 0.3, 0.3], [None; 4], [None, None, None, None], [0, 0, 0],
+        annotate_all_nags: "[Result \"*\"]\n\n1. e4 e5 2. Nf3 Nc6 *\n",
+            [20, 40, 60, 400, 50], ["d2d4", "c7c5", "f1c4", "d7d6"],
+            [-0.4, 0.6, -4.0, 0.5], [None; 4], [Some("$6"), Some("$2"), Some("$4"), Some("$4")], [1, 1, 2],
+        annotate_thresholds: "[Result \"*\"]\n\n1. e4 e5 2. Nf3 Nc6 *\n",
+            [20, 29, 21, 79, 220], ["d2d4", "c7c5", "f1c4", "d7d6"],
+            [-0.29, 0.21, -0.79, 2.2], [None; 4], [None, Some("$6"), Some("$2"), Some("$2")], [1, 2, 0],
+        annotate_best_move_played: "[Result \"*\"]\n\n1. e4 e5 2. Nf3 Nc6 *\n",
+            [20, 500, 20, 500, 20], ["e2e4", "e7e5", "g1f3", "b8c6"],
+            [-5.0, 0.2, -5.0, 0.2], [None; 4], [None, None, None, None], [0, 0, 0],
+        annotate_slower_mate: "[Result \"*\"]\n\n1. e4 e5 2. Nf3 Nc6 *\n",
+            [CHECKMATE_SCORE - 5, -CHECKMATE_SCORE + 7, CHECKMATE_SCORE - 9, -CHECKMATE_SCORE + 11, CHECKMATE_SCORE - 13], ["d2d4", "c7c5", "f1c4", "d7d6"],
+            [100.0, 100.0, 100.0, 100.0], [Some(3), Some(4), Some(5), Some(6)], [None, None, None, None], [0, 0, 0],
+        annotate_missed_mate: "[Result \"*\"]\n\n1. e4 e5 2. Nf3 Nc6 *\n",
+            [CHECKMATE_SCORE - 5, 0, 0, CHECKMATE_SCORE - 3, -CHECKMATE_SCORE + 2], ["d2d4", "c7c5", "f1c4", "d7d6"],
+            [0.0, 0.0, -100.0, -100.0], [None, None, Some(-1), Some(-1)], [Some("$4"), None, Some("$4"), None], [0, 0, 2],
+        annotate_black_to_move: "[FEN \"rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1\"]\n[Result \"*\"]\n\n1... e5 2. Nf3 Nc6 *\n",
+            [-20, 200, -200, 100], ["c7c5", "f1c4", "d7d6"],
+            [2.0, 2.0, 1.0], [None; 3], [Some("$2"), None, None], [0, 1, 0],
+    }
+
+    macro_rules! analyze_position_tests {
+        ($($name:ident: $fen:expr, $limit:expr, $expected_move:expr, $expected_score_range:expr,)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    INIT.call_once(|| {
+                        see::init();
+                        movegen::init();
+                    });
+
+                    let board = Board::new_from_fen($fen).unwrap();
+                    let result = analyze::analyze_position(
+                        &board,
+                        $limit,
+                        &AnalysisOptions::default(),
+                        &Arc::new(TTable::new(1 * 1024 * 1024)),
+                        &Arc::new(EHTable::new(1 * 1024 * 1024)),
+                        &Arc::new(PHTable::new(1 * 1024 * 1024)),
+                        &Arc::new(MHTable::new(256 * 1024)),
+                        &Arc::new(AtomicBool::new(false)),
+                        &Arc::new(AtomicBool::new(false)),
+                    );
+
+                    let expected_move: Option<&str> = $expected_move;
+                    assert_eq!(expected_move.map(|r#move| r#move.to_string()), result.pv_line.first().map(|r#move| r#move.to_long_notation()));
+                    assert!($expected_score_range.contains(&result.score), "score={}", result.score);
+                }
+            )*
+        }
+    }
+
+    analyze_position_tests! {
+        analyze_position_single_move_depth: "7k/8/8/8/8/8/6PP/r5K1 w - - 0 1", AnalysisLimit::Depth(3), Some("g1f2"), -2000..=-300,
+        analyze_position_single_move_move_time: "7k/8/8/8/8/8/6PP/r5K1 w - - 0 1", AnalysisLimit::MoveTime(50), Some("g1f2"), -2000..=-300,
+        analyze_position_single_move_nodes: "7k/8/8/8/8/8/6PP/r5K1 w - - 0 1", AnalysisLimit::Nodes(10000), Some("g1f2"), -2000..=-300,
+        analyze_position_checkmate: "7k/8/8/8/8/8/5PPP/r5K1 w - - 0 1", AnalysisLimit::MoveTime(50), None, -CHECKMATE_SCORE..=-CHECKMATE_SCORE,
+        analyze_position_stalemate: "7k/5Q2/6K1/8/8/8/8/8 b - - 0 1", AnalysisLimit::Nodes(10000), None, 0..=0,
+    }
+}
@@ -0,0 +1,60 @@
+#[cfg(test)]
+mod bitbase_tests {
+    use inanis::engine::see;
+    use inanis::state::representation::Board;
+    use inanis::state::*;
+    use inanis::tablebases::bitbase;
+    use inanis::tablebases::WdlResult;
+    use std::sync::Once;
+
+    static INIT: Once = Once::new();
+
+    macro_rules! wdl_tests {
+        ($($name:ident: $fen:expr, $expected_result:expr, )*) => {
+            $(
+                #[test]
+                fn $name() {
+                    INIT.call_once(|| {
+                        see::init();
+                        movegen::init();
+                        bitbase::init();
+                        bitbase::init_kbnk();
+                    });
+
+                    let board = Board::new_from_fen($fen).unwrap();
+                    assert_eq!($expected_result, bitbase::get_wdl(&board));
+                }
+            )*
+        }
+    }
+
+    wdl_tests! {
+        kqk_white_to_move: "4k3/8/8/8/8/8/8/4KQ2 w - - 0 1", Some(WdlResult::Win),
+        kqk_black_to_move: "4k3/8/8/8/8/8/8/4KQ2 b - - 0 1", Some(WdlResult::Loss),
+        kqk_queen_capture: "4k3/4Q3/8/8/8/8/8/K7 b - - 0 1", Some(WdlResult::Draw),
+        kqk_stalemate: "k7/2Q5/1K6/8/8/8/8/8 b - - 0 1", Some(WdlResult::Draw),
+        kqk_black_queen: "4kq2/8/8/8/8/8/8/4K3 w - - 0 1", Some(WdlResult::Loss),
+        krk_white_to_move: "4k3/8/8/8/8/8/8/4KR2 w - - 0 1", Some(WdlResult::Win),
+        krk_black_to_move: "4k3/8/8/8/8/8/8/4KR2 b - - 0 1", Some(WdlResult::Loss),
+        krk_mate: "R3k3/8/4K3/8/8/8/8/8 b - - 0 1", Some(WdlResult::Loss),
+        krk_rook_capture: "8/8/8/8/8/8/3kR3/7K b - - 0 1", Some(WdlResult::Draw),
+        krk_black_rook: "r3k3/8/8/8/8/8/8/4K3 b - - 0 1", Some(WdlResult::Win),
+        kpk_king_on_sixth_white_to_move: "4k3/8/4K3/4P3/8/8/8/8 w - - 0 1", Some(WdlResult::Win),
+        kpk_king_on_sixth_black_to_move: "4k3/8/4K3/4P3/8/8/8/8 b - - 0 1", Some(WdlResult::Loss),
+        kpk_outside_square: "8/8/8/8/8/k7/7P/7K w - - 0 1", Some(WdlResult::Win),
+        kpk_rook_pawn: "k7/8/8/8/8/8/P7/7K w - - 0 1", Some(WdlResult::Draw),
+        kpk_pawn_capture: "8/8/8/8/8/8/3kP3/7K b - - 0 1", Some(WdlResult::Draw),
+        kpk_black_pawn: "8/8/8/8/4p3/4k3/8/4K3 w - - 0 1", Some(WdlResult::Loss),
+        kbnk_white_to_move: "4k3/8/8/8/8/8/8/3BKN2 w - - 0 1", Some(WdlResult::Win),
+        kbnk_black_to_move: "4k3/8/8/8/8/8/8/3BKN2 b - - 0 1", Some(WdlResult::Loss),
+        kbnk_mate: "k7/3N4/1K6/8/4B3/8/8/8 b - - 0 1", Some(WdlResult::Loss),
+        kbnk_bishop_capture: "8/8/8/8/8/8/3kB3/5N1K b - - 0 1", Some(WdlResult::Draw),
+        kbnk_knight_capture: "8/8/8/8/8/7B/3kN3/7K b - - 0 1", Some(WdlResult::Draw),
+        kbnk_black_pieces: "3bkn2/8/8/8/8/8/8/4K3 b - - 0 1", Some(WdlResult::Win),
+        kk: "4k3/8/8/8/8/8/8/4K3 w - - 0 1", None,
+        kbk: "4k3/8/8/8/8/8/8/4KB2 w - - 0 1", None,
+        kbbk: "4k3/8/8/8/8/8/8/2B1KB2 w - - 0 1", None,
+        krkr: "r3k3/8/8/8/8/8/8/4KR2 w - - 0 1", None,
+        too_many_pieces: "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", None,
+    }
+}
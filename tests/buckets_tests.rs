@@ -0,0 +1,44 @@
+#[cfg(all(test, feature = "dev"))]
+mod buckets_tests {
+    use inanis::evaluation::pst;
+    use inanis::evaluation::pst::KING_BUCKETS;
+
+    const COMPILED_LAYOUT: &str = include_str!("../src/evaluation/pst/buckets.rs");
+    const HALVES_LAYOUT: &str = "// Generated\n\npub const KING_BUCKETS_COUNT: usize = 2;\n\n#[rustfmt::skip]\npub const KING_BUCKETS: [usize; 64] = [\n    1, 1, 1, 1, 1, 1, 1, 1,\n    1, 1, 1, 1, 1, 1, 1, 1,\n    1, 1, 1, 1, 1, 1, 1, 1,\n    1, 1, 1, 1, 1, 1, 1, 1,\n    0, 0, 0, 0, 0, 0, 0, 0,\n    0, 0, 0, 0, 0, 0, 0, 0,\n    0, 0, 0, 0, 0, 0, 0, 0,\n    0, 0, 0, 0, 0, 0, 0, 0,\n];\n";
+
+    #[test]
+    fn parse_compiled_layout() {
+        assert_eq!(KING_BUCKETS, pst::parse_king_buckets(COMPILED_LAYOUT).unwrap());
+    }
+
+    #[test]
+    fn parse_halves_layout() {
+        let king_buckets = pst::parse_king_buckets(HALVES_LAYOUT).unwrap();
+
+        assert!(king_buckets[..32].iter().all(|bucket| *bucket == 1));
+        assert!(king_buckets[32..].iter().all(|bucket| *bucket == 0));
+    }
+
+    macro_rules! parse_invalid_tests {
+        ($($name:ident: $from:expr, $to:expr,)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    assert!(pst::parse_king_buckets(&HALVES_LAYOUT.replacen($from, $to, 1)).is_err());
+                }
+            )*
+        }
+    }
+
+    parse_invalid_tests! {
+        parse_invalid_missing_count: "pub const KING_BUCKETS_COUNT: usize = 2;", "",
+        parse_invalid_count_mismatch: "KING_BUCKETS_COUNT: usize = 2;", "KING_BUCKETS_COUNT: usize = 3;",
+        parse_invalid_count_too_large: "1, 1, 1, 1, 1, 1, 1, 1,", "16, 1, 1, 1, 1, 1, 1, 1,",
+        parse_invalid_missing_array: "pub const KING_BUCKETS: [usize; 64] = [", "pub const BUCKETS: [usize; 64] = [",
+        parse_invalid_unterminated_array: "];", "",
+        parse_invalid_content_after_array: "];\n", "];\npub const TEMPO: i16 = 15;\n",
+        parse_invalid_too_few_values: "0, 0, 0, 0, 0, 0, 0, 0,\n];", "0, 0, 0, 0, 0, 0, 0,\n];",
+        parse_invalid_too_many_values: "0, 0, 0, 0, 0, 0, 0, 0,\n];", "0, 0, 0, 0, 0, 0, 0, 0, 0,\n];",
+        parse_invalid_value: "1, 1, 1, 1, 1, 1, 1, 1,", "1, x, 1, 1, 1, 1, 1, 1,",
+    }
+}
@@ -0,0 +1,53 @@
+#[cfg(all(test, feature = "dev"))]
+mod dataset_tests {
+    use inanis::tuning::dataset;
+    use inanis::tuning::dataset::DatasetPosition;
+
+    macro_rules! balance_tests {
+        ($($name:ident: $phase_targets:expr, $wdl_targets:expr, $counts:expr, $expected_counts:expr,)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let phase_targets: &[f32] = &$phase_targets;
+                    let wdl_targets: &[f32] = &$wdl_targets;
+                    let counts: &[usize] = &$counts;
+
+                    // Counts are stored in the same order as balance buckets: every phase bucket is split by results (if they are balanced)
+                    let mut positions = Vec::new();
+                    for (index, count) in counts.iter().enumerate() {
+                        let phase_bucket = index / wdl_targets.len();
+                        let result = index % wdl_targets.len();
+                        let game_phase = (phase_bucket as f32 + 0.5) / phase_targets.len() as f32;
+
+                        for _ in 0..*count {
+                            positions.push(DatasetPosition { epd: String::new(), hash: 0, eval: 0.0, result, game_phase });
+                        }
+                    }
+
+                    let output = dataset::balance(positions, phase_targets, wdl_targets);
+                    let mut output_counts = vec![0; counts.len()];
+
+                    for position in &output {
+                        let phase_bucket = ((position.game_phase * phase_targets.len() as f32) as usize).min(phase_targets.len() - 1);
+                        let wdl_bucket = if wdl_targets.len() > 1 { position.result } else { 0 };
+
+                        output_counts[phase_bucket * wdl_targets.len() + wdl_bucket] += 1;
+                    }
+
+                    assert_eq!($expected_counts, output_counts[..]);
+                }
+            )*
+        }
+    }
+
+    balance_tests! {
+        balance_uniform_phases: [1.0, 1.0, 1.0, 1.0], [1.0], [100, 50, 30, 200], [30, 30, 30, 30],
+        balance_weighted_phases: [1.0, 1.0, 2.0, 4.0], [1.0], [100, 50, 80, 200], [40, 40, 80, 160],
+        balance_zero_weight_phase: [1.0, 0.0, 1.0, 2.0], [1.0], [100, 50, 30, 200], [30, 0, 30, 60],
+        balance_empty_zero_weight_phase: [1.0, 0.0, 1.0, 2.0], [1.0], [100, 0, 30, 200], [30, 0, 30, 60],
+        balance_results: [1.0], [2.0, 1.0, 1.0], [80, 30, 50], [60, 30, 30],
+        balance_zero_weight_result: [1.0], [1.0, 0.0, 1.0], [80, 30, 50], [50, 0, 50],
+        balance_phases_and_results: [1.0, 1.0], [1.0, 1.0, 1.0], [10, 20, 30, 40, 50, 60], [10, 10, 10, 10, 10, 10],
+        balance_already_balanced: [1.0, 1.0], [1.0], [40, 40], [40, 40],
+    }
+}
@@ -0,0 +1,99 @@
+#[cfg(test)]
+mod endgame_tests {
+    use inanis::engine::see;
+    use inanis::evaluation::endgame;
+    use inanis::evaluation::trace::EvalTrace;
+    use inanis::state::representation::Board;
+    use inanis::state::*;
+    use inanis::tablebases::bitbase;
+    use std::sync::Once;
+
+    static INIT: Once = Once::new();
+
+    macro_rules! scale_tests {
+        ($($name:ident: $fen:expr, $expected_scale:expr, )*) => {
+            $(
+                #[test]
+                fn $name() {
+                    init();
+
+                    let board = Board::new_from_fen($fen).unwrap();
+                    assert_eq!($expected_scale, EvalTrace::new(&board).scale);
+                }
+            )*
+        }
+    }
+
+    macro_rules! evaluate_tests {
+        ($($name:ident: $fen:expr, $expected_min:expr, $expected_max:expr, )*) => {
+            $(
+                #[test]
+                fn $name() {
+                    init();
+
+                    let board = Board::new_from_fen($fen).unwrap();
+                    let score = endgame::evaluate(&board).unwrap();
+
+                    assert!(($expected_min..=$expected_max).contains(&score), "score={}", score);
+                }
+            )*
+        }
+    }
+
+    scale_tests! {
+        scale_initial_position: "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", endgame::SCALE_NORMAL,
+        scale_opposite_bishops: "8/4kb2/8/3p4/3P4/8/3BK3/8 w - - 0 1", endgame::SCALE_OPPOSITE_BISHOPS,
+        scale_opposite_bishops_with_rooks: "r7/4kb2/8/3p4/3P4/8/3BK3/R7 w - - 0 1", endgame::SCALE_OPPOSITE_BISHOPS_WITH_PIECES,
+        scale_same_colored_bishops: "8/4k1b1/8/3p4/3P4/8/3BK3/8 w - - 0 1", endgame::SCALE_NORMAL,
+        scale_rook_vs_bishop: "4k3/8/8/8/8/8/2b5/4K2R w - - 0 1", endgame::SCALE_ROOK_VS_MINOR,
+        scale_rook_vs_knight: "4k3/8/8/8/8/8/2n5/4K2R w - - 0 1", endgame::SCALE_ROOK_VS_MINOR,
+        scale_knight_vs_pawn: "4k3/p7/8/8/8/8/8/3NK3 w - - 0 1", endgame::SCALE_MINOR_ADVANTAGE_WITHOUT_PAWNS,
+        scale_wrong_bishop: "k7/8/P7/8/8/8/8/2B1K3 w - - 0 1", endgame::SCALE_DRAW,
+        scale_right_bishop: "k7/8/P7/8/8/8/8/1B2K3 w - - 0 1", endgame::SCALE_NORMAL,
+        scale_wrong_bishop_black: "2b1k3/8/8/8/8/p7/8/K7 b - - 0 1", endgame::SCALE_DRAW,
+    }
+
+    evaluate_tests! {
+        evaluate_kqk_white: "8/8/8/3k4/8/8/8/4KQ2 w - - 0 1", endgame::KNOWN_WIN_SCORE, i16::MAX,
+        evaluate_kqk_black: "8/8/8/3K4/8/8/8/4kq2 w - - 0 1", i16::MIN, -endgame::KNOWN_WIN_SCORE,
+        evaluate_kqk_hanging_queen: "8/8/8/8/8/3k4/4Q3/K7 b - - 0 1", 0, 0,
+        evaluate_krrk: "8/8/8/3k4/8/8/8/R3K2R w - - 0 1", endgame::KNOWN_WIN_SCORE, i16::MAX,
+        evaluate_kbbk: "8/8/8/3k4/8/8/8/2B1KB2 w - - 0 1", endgame::KNOWN_WIN_SCORE, i16::MAX,
+        evaluate_kpk_win: "4k3/8/4K3/4P3/8/8/8/8 b - - 0 1", endgame::KNOWN_WIN_SCORE, i16::MAX,
+        evaluate_kpk_draw: "k7/8/8/8/8/8/P7/7K w - - 0 1", 0, 0,
+    }
+
+    #[test]
+    fn evaluate_kxk_edge() {
+        init();
+
+        let center = Board::new_from_fen("8/8/8/3k4/8/8/8/4KR2 w - - 0 1").unwrap();
+        let edge = Board::new_from_fen("3k4/8/8/8/8/8/8/4KR2 w - - 0 1").unwrap();
+
+        assert!(endgame::evaluate(&edge).unwrap() > endgame::evaluate(&center).unwrap());
+    }
+
+    #[test]
+    fn evaluate_same_colored_bishops() {
+        init();
+
+        let board = Board::new_from_fen("8/8/8/3k4/8/8/8/1B2KB2 w - - 0 1").unwrap();
+        assert_eq!(None, endgame::evaluate(&board));
+    }
+
+    #[test]
+    fn evaluate_two_knights() {
+        init();
+
+        let board = Board::new_from_fen("8/8/8/3k4/8/8/8/1N2KN2 w - - 0 1").unwrap();
+        assert_eq!(None, endgame::evaluate(&board));
+    }
+
+    fn init() {
+        INIT.call_once(|| {
+            see::init();
+            movegen::init();
+            bitbase::init();
+        });
+    }
+}
@@ -0,0 +1,239 @@
+#[cfg(test)]
+mod evaluation_tests {
+    use inanis::cache::eval::EHTable;
+    use inanis::cache::material::MHTable;
+    use inanis::cache::pawns::PHTable;
+    use inanis::engine::see;
+    use inanis::engine::stats::SearchStats;
+    use inanis::evaluation::mobility::EvalAux;
+    use inanis::evaluation::trace::EvalTrace;
+    use inanis::evaluation::*;
+    use inanis::state::representation::Board;
+    use inanis::state::*;
+    use std::sync::Once;
+
+    static INIT: Once = Once::new();
+
+    macro_rules! trace_tests {
+        ($($name:ident: $fen:expr, )*) => {
+            $(
+                #[test]
+                fn $name() {
+                    INIT.call_once(|| {
+                        see::init();
+                        movegen::init();
+                    });
+
+                    let board = Board::new_from_fen($fen).unwrap();
+                    let trace = EvalTrace::new(&board);
+                    let sign = -((board.stm as i16) * 2 - 1);
+
+                    let mut white_aux = EvalAux::default();
+                    let mut black_aux = EvalAux::default();
+                    let mobility_eval = mobility::evaluate(&board, &mut white_aux, &mut black_aux);
+                    let safety_eval = safety::evaluate(&board, &white_aux, &black_aux);
+                    let threats_eval = threats::evaluate(&board, &white_aux, &black_aux);
+                    let passers_eval = passers::evaluate(&board, &white_aux, &black_aux);
+
+                    assert_eq!(material::evaluate(&board).data, trace.get_eval(Some("Material")).data);
+                    assert_eq!(material::evaluate_imbalance_without_cache(&board).data, trace.get_eval(Some("Imbalance")).data);
+                    assert_eq!(pst::evaluate(&board).data, trace.get_eval(Some("Piece-square tables")).data);
+                    assert_eq!(mobility_eval.data, trace.get_eval(Some("Mobility")).data);
+                    assert_eq!(mobility::evaluate_placement(&board).data, trace.get_eval(Some("Placement")).data);
+                    assert_eq!(safety_eval.data, trace.get_eval(Some("Safety")).data);
+                    assert_eq!(pawns::evaluate_without_cache(&board).data, trace.get_eval(Some("Pawns")).data);
+                    assert_eq!(threats_eval.data, trace.get_eval(Some("Threats")).data);
+                    assert_eq!(passers_eval.data, trace.get_eval(Some("Passed pawns")).data);
+                    assert_eq!(sign * board.evaluate_without_cache(board.stm), trace.get_score());
+                    assert_eq!(sign * board.evaluate(board.stm, &EHTable::new(1024 * 1024), &PHTable::new(1024 * 1024), &MHTable::new(64 * 1024), &mut SearchStats::default()), trace.get_score());
+                }
+            )*
+        }
+    }
+
+    macro_rules! threats_tests {
+        ($($name:ident: $fen:expr, $color:expr, $minors_attacking_majors:expr, $hanging_pieces:expr, $safe_pawn_push_threats:expr, $queen_attacks:expr, )*) => {
+            $(
+                #[test]
+                fn $name() {
+                    INIT.call_once(|| {
+                        see::init();
+                        movegen::init();
+                    });
+
+                    let board = Board::new_from_fen($fen).unwrap();
+                    let mut white_aux = EvalAux::default();
+                    let mut black_aux = EvalAux::default();
+                    mobility::evaluate(&board, &mut white_aux, &mut black_aux);
+
+                    let data = match $color {
+                        WHITE => threats::get_threats_data(&board, WHITE, &white_aux, &black_aux),
+                        _ => threats::get_threats_data(&board, BLACK, &black_aux, &white_aux),
+                    };
+
+                    assert_eq!($minors_attacking_majors, data.minors_attacking_majors);
+                    assert_eq!($hanging_pieces, data.hanging_pieces);
+                    assert_eq!($safe_pawn_push_threats, data.safe_pawn_push_threats);
+                    assert_eq!($queen_attacks, data.queen_attacks);
+                }
+            )*
+        }
+    }
+
+    macro_rules! features_tests {
+        ($($name:ident: $fen:expr, $feature:expr, $expected_white_count:expr, $expected_black_count:expr, )*) => {
+            $(
+                #[test]
+                fn $name() {
+                    INIT.call_once(|| {
+                        see::init();
+                        movegen::init();
+                    });
+
+                    let board = Board::new_from_fen($fen).unwrap();
+                    let trace = EvalTrace::new(&board);
+                    let entry = trace.entries.iter().find(|entry| entry.name == $feature);
+
+                    assert_eq!($expected_white_count, entry.map(|entry| entry.white_count).unwrap_or(0));
+                    assert_eq!($expected_black_count, entry.map(|entry| entry.black_count).unwrap_or(0));
+                }
+            )*
+        }
+    }
+
+    macro_rules! imbalance_tests {
+        ($($name:ident: $fen:expr, )*) => {
+            $(
+                #[test]
+                fn $name() {
+                    INIT.call_once(|| {
+                        see::init();
+                        movegen::init();
+                    });
+
+                    let board = Board::new_from_fen($fen).unwrap();
+                    let mhtable = MHTable::new(64 * 1024);
+                    let mut stats = SearchStats::default();
+                    let expected = material::evaluate_imbalance_without_cache(&board);
+
+                    assert_eq!(expected.data, material::evaluate_imbalance(&board, &mhtable, &mut stats).data);
+                    assert_eq!(expected.data, material::evaluate_imbalance(&board, &mhtable, &mut stats).data);
+
+                    #[cfg(feature = "dev")]
+                    {
+                        let count = |color: usize, piece: usize| board.pieces[color][piece].count_ones() as i8;
+                        let mut expected_coeffs = Vec::new();
+                        let mut index = 0;
+                        let mut coeffs = Vec::new();
+                        let mut indices = Vec::new();
+
+                        for first in 0..5 {
+                            for second in 0..=first {
+                                expected_coeffs.push(count(WHITE, first) * count(WHITE, second) - count(BLACK, first) * count(BLACK, second));
+                            }
+                        }
+
+                        for first in 0..5 {
+                            for second in 0..first {
+                                expected_coeffs.push(count(WHITE, first) * count(BLACK, second) - count(BLACK, first) * count(WHITE, second));
+                            }
+                        }
+
+                        material::get_imbalance_coeffs(&board, &mut index, &mut coeffs, &mut indices);
+                        assert_eq!(expected_coeffs.len() * 2, index as usize);
+
+                        let mut values = vec![0; expected_coeffs.len()];
+                        for (coeff, index) in coeffs.iter().zip(indices) {
+                            values[index as usize / 2] = coeff.get_data().0;
+                        }
+
+                        assert_eq!(expected_coeffs, values);
+                    }
+                }
+            )*
+        }
+    }
+
+    macro_rules! eval_hash_tests {
+        ($($name:ident: $fen:expr, )*) => {
+            $(
+                #[test]
+                fn $name() {
+                    INIT.call_once(|| {
+                        see::init();
+                        movegen::init();
+                    });
+
+                    let board = Board::new_from_fen($fen).unwrap();
+                    let ehtable = EHTable::new(1024 * 1024);
+                    let phtable = PHTable::new(1024 * 1024);
+                    let mhtable = MHTable::new(64 * 1024);
+                    let mut stats = SearchStats::default();
+
+                    let expected = board.evaluate(board.stm, &EHTable::new(1024 * 1024), &phtable, &mhtable, &mut stats);
+                    assert_eq!(expected, board.evaluate(board.stm, &ehtable, &phtable, &mhtable, &mut stats));
+                    assert_eq!(expected, board.evaluate(board.stm, &ehtable, &phtable, &mhtable, &mut stats));
+                    assert_eq!(expected, board.evaluate_fast(board.stm, &ehtable, &phtable, &mhtable, &mut stats));
+                    assert_eq!(-expected, board.evaluate(board.stm ^ 1, &ehtable, &phtable, &mhtable, &mut stats) - 2 * params::TEMPO);
+                }
+            )*
+        }
+    }
+
+    trace_tests! {
+        trace_initial_position: "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        trace_italian_game: "r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4",
+        trace_middlegame_black_to_move: "r2q1rk1/pp2bppp/2n1pn2/3p4/2PP4/2N1PN2/PP2BPPP/R2Q1RK1 b - - 0 10",
+        trace_passed_pawns: "8/5pk1/1P4p1/P7/8/6P1/5PK1/8 w - - 0 1",
+        trace_kings_attack: "r1b2rk1/pp3ppp/2n5/3qN3/3P4/3B4/PP3PPP/R2Q1RK1 w - - 0 14",
+    }
+
+    threats_tests! {
+        threats_initial_position: "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", WHITE, 0, 0, 0, 0,
+        threats_knight_fork: "4k3/8/2r1q3/8/3N4/8/8/4K3 w - - 0 1", WHITE, 2, 0, 0, 0,
+        threats_hanging_bishop: "4k3/8/8/3b4/8/8/8/3RK3 w - - 0 1", WHITE, 0, 1, 0, 0,
+        threats_defended_bishop: "4k3/8/4p3/3b4/8/8/8/3RK3 w - - 0 1", WHITE, 0, 0, 0, 0,
+        threats_pawn_push: "4k3/8/8/2n5/8/3P4/8/4K3 w - - 0 1", WHITE, 0, 0, 1, 0,
+        threats_black_pawn_push: "4k3/8/3p4/8/2N5/8/8/4K3 b - - 0 1", BLACK, 0, 0, 1, 0,
+        threats_queen_attacks: "4k3/8/8/4q3/8/8/8/R3K3 w - - 0 1", WHITE, 0, 0, 0, 1,
+    }
+
+    features_tests! {
+        features_knight_outpost: "4k3/8/8/3N4/4P3/8/8/4K3 w - - 0 1", "KNIGHT_OUTPOST", 1, 0,
+        features_knight_outpost_attackable: "4k3/2p5/8/3N4/4P3/8/8/4K3 w - - 0 1", "KNIGHT_OUTPOST", 0, 0,
+        features_knight_outpost_unsupported: "4k3/8/8/3N4/8/8/8/4K3 w - - 0 1", "KNIGHT_OUTPOST", 0, 0,
+        features_bishop_outpost_black: "4k3/8/4p3/3b4/8/8/8/4K3 b - - 0 1", "BISHOP_OUTPOST", 0, 1,
+        features_bad_bishop: "4k3/8/8/8/4p3/4P3/8/2B1K3 w - - 0 1", "BAD_BISHOP", 1, 0,
+        features_good_bishop: "4k3/8/8/8/4p3/4P3/8/3BK3 w - - 0 1", "BAD_BISHOP", 0, 0,
+        features_rook_on_seventh: "4k3/R7/8/8/8/8/8/4K3 w - - 0 1", "ROOK_ON_SEVENTH", 1, 0,
+        features_rook_on_seventh_inactive: "8/R7/4k3/8/8/8/8/4K3 w - - 0 1", "ROOK_ON_SEVENTH", 0, 0,
+        features_queen_on_seventh_black: "4k3/8/8/8/8/8/1q3P2/6K1 b - - 0 1", "QUEEN_ON_SEVENTH", 0, 1,
+        features_connected_rooks: "r3k2r/8/8/8/8/8/8/R4RK1 w - - 0 1", "CONNECTED_ROOKS", 1, 0,
+        features_connected_rooks_black: "r4rk1/8/8/8/8/8/8/R3K2R b - - 0 1", "CONNECTED_ROOKS", 0, 1,
+        features_imbalance_knight_pawns: "4k3/pp6/8/8/8/8/PPP5/1N2K3 w - - 0 1", "IMBALANCE_OURS[KNIGHT][PAWN]", 3, 0,
+        features_imbalance_queen_rooks: "r3k2r/8/8/8/8/8/8/3QK3 w - - 0 1", "IMBALANCE_THEIRS[QUEEN][ROOK]", 2, 0,
+        features_passed_pawn_unstoppable: "7k/7p/8/P7/8/8/8/K7 w - - 0 1", "PASSED_PAWN_UNSTOPPABLE", 1, 1,
+        features_passed_pawn_inside_square: "8/1k6/8/P7/8/8/8/7K w - - 0 1", "PASSED_PAWN_UNSTOPPABLE", 0, 0,
+        features_passed_pawn_not_pawn_ending: "7k/2n5/8/P7/8/8/8/K7 w - - 0 1", "PASSED_PAWN_UNSTOPPABLE", 0, 0,
+        features_passed_pawn_blocked: "4k3/8/8/n7/P7/8/8/4K3 w - - 0 1", "PASSED_PAWN_BLOCKED", 1, 0,
+        features_passed_pawn_free_path: "4k3/8/8/P7/8/8/8/4K3 w - - 0 1", "PASSED_PAWN_FREE_PATH", 1, 0,
+        features_passed_pawn_attacked_path: "1r2k3/8/8/P7/8/8/8/4K3 w - - 0 1", "PASSED_PAWN_FREE_PATH", 0, 0,
+        features_passed_pawn_own_king_distance: "4k3/8/8/P7/8/8/8/4K3 w - - 0 1", "PASSED_PAWN_OWN_KING_DISTANCE[5]", 1, 0,
+        features_passed_pawn_enemy_king_distance: "8/8/8/4k3/8/2p5/8/K7 w - - 0 1", "PASSED_PAWN_ENEMY_KING_DISTANCE[2]", 0, 1,
+    }
+
+    imbalance_tests! {
+        imbalance_initial_position: "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        imbalance_queen_against_rooks: "r3k2r/pppppppp/8/8/8/8/PPPPPPPP/3QK3 w - - 0 1",
+        imbalance_knights_against_bishops: "2b1kb2/pppppppp/8/8/8/8/PPPPPPPP/1N2K1N1 w - - 0 1",
+        imbalance_pawns_majority: "2b1kb2/ppppp3/8/8/8/8/PPPPPPPP/1N2K1N1 w - - 0 1",
+        imbalance_promoted_queens: "4k3/8/8/8/8/8/QQQQQQ2/QQQQK3 w - - 0 1",
+    }
+
+    eval_hash_tests! {
+        eval_hash_initial_position: "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        eval_hash_italian_game: "r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4",
+        eval_hash_middlegame_black_to_move: "r2q1rk1/pp2bppp/2n1pn2/3p4/2PP4/2N1PN2/PP2BPPP/R2Q1RK1 b - - 0 10",
+        eval_hash_passed_pawns: "8/5pk1/1P4p1/P7/8/6P1/5PK1/8 w - - 0 1",
+    }
+}
@@ -2,6 +2,7 @@
 mod fen_tests {
     use inanis::engine::see;
     use inanis::state::representation::Board;
+    use inanis::state::text::fen;
     use inanis::state::*;
     use std::sync::Once;
 
@@ -35,4 +36,38 @@ mod fen_tests {
         fen_end_game2: "8/8/4nPk1/8/6pK/8/1R3P1P/2B3r1 b - - 1 54",
         fen_end_game3: "8/7q/5K2/2q5/6k1/8/8/8 b - - 5 60",
     }
+
+    macro_rules! epd_tests {
+        ($($name:ident: $epd:expr, $fen:expr, $id:expr, $best_move:expr, $avoid_move:expr, $direct_mate:expr, $c0:expr,)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    INIT.call_once(|| {
+                        see::init();
+                        movegen::init();
+                    });
+
+                    let parsed_epd = fen::epd_to_board($epd).unwrap();
+
+                    assert_eq!($fen, parsed_epd.board.to_fen());
+                    assert_eq!($id, parsed_epd.id.as_deref());
+                    assert_eq!($best_move, parsed_epd.best_move.as_deref());
+                    assert_eq!($avoid_move, parsed_epd.avoid_move.as_deref());
+                    assert_eq!($direct_mate, parsed_epd.direct_mate.as_deref());
+                    assert_eq!($c0, parsed_epd.comments[0].as_deref());
+                }
+            )*
+        }
+    }
+
+    epd_tests! {
+        epd_best_move: "1k1r4/pp1b1R2/3q2pp/4p3/2B5/4Q3/PPP2B2/2K5 b - - bm Qd1+; id \"BK.01\";",
+            "1k1r4/pp1b1R2/3q2pp/4p3/2B5/4Q3/PPP2B2/2K5 b - - 0 1", Some("BK.01"), Some("Qd1+"), None, None, None,
+        epd_avoid_move: "r1bqk1r1/1p1p1n2/p1n2pN1/2p1b2Q/2P1Pp2/1PN5/PB4PP/R4RK1 w q - am Rxf4; id \"WAC.299\"; c0 \"first; second\";",
+            "r1bqk1r1/1p1p1n2/p1n2pN1/2p1b2Q/2P1Pp2/1PN5/PB4PP/R4RK1 w q - 0 1", Some("WAC.299"), None, Some("Rxf4"), None, Some("first; second"),
+        epd_direct_mate: "6k1/5ppp/8/8/8/8/8/R5K1 w - - 3 40 dm 1; id \"mate\";",
+            "6k1/5ppp/8/8/8/8/8/R5K1 w - - 3 40", Some("mate"), None, None, Some("1"), None,
+        epd_multiple_moves: "8/8/8/8/8/8/8/K1k5 w - - bm Ka2 Kb1; pv Ka2;",
+            "8/8/8/8/8/8/8/K1k5 w - - 0 1", None, Some("Ka2 Kb1"), None, None, None,
+    }
 }
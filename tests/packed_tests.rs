@@ -0,0 +1,168 @@
+#[cfg(all(test, feature = "dev"))]
+mod packed_tests {
+    use inanis::engine::see;
+    use inanis::evaluation::pst::KING_BUCKETS;
+    use inanis::state::representation::Board;
+    use inanis::state::*;
+    use inanis::tuning::packed;
+    use inanis::tuning::packed::PackedBoard;
+    use inanis::tuning::tuner;
+    use std::collections::HashSet;
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::sync::Once;
+
+    static INIT: Once = Once::new();
+
+    const EPD: &str = concat!(
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - c9 \"0.35|1-0\";\n",
+        "r3k2r/8/8/3pP3/8/8/8/R3K2R w Kq d6 c9 \"-1.20|1/2-1/2\";\n",
+        "\n",
+        "8/8/8/3k4/8/8/8/4KQ2 b - - c9 \"5.00|0-1\";\n",
+    );
+
+    macro_rules! packed_board_tests {
+        ($($name:ident: $fen:expr,)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    init();
+
+                    let board = Board::new_from_fen($fen).unwrap();
+                    let unpacked = PackedBoard::new(&board).unwrap().to_board().unwrap();
+
+                    assert_eq!(board.to_epd(), unpacked.to_epd());
+                    assert_eq!(board.state.hash, unpacked.state.hash);
+                    assert_eq!(board.state.pawn_hash, unpacked.state.pawn_hash);
+                    assert_eq!(board.game_phase, unpacked.game_phase);
+                }
+            )*
+        }
+    }
+
+    packed_board_tests! {
+        packed_board_initial_position: "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        packed_board_castling_rights: "r3k2r/8/8/8/8/8/8/R3K2R b Kq - 0 1",
+        packed_board_en_passant: "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+        packed_board_promoted_pieces: "QQQQQQQQ/QQQQQQQQ/8/8/8/8/qqqqqqqq/K1k4q w - - 0 1",
+    }
+
+    #[test]
+    fn packed_round_trip() {
+        init();
+
+        let (epd_path, packed_path) = get_paths("round_trip");
+        fs::write(&epd_path, EPD).unwrap();
+
+        assert_eq!(3, packed::convert(epd_path.to_str().unwrap(), packed_path.to_str().unwrap(), &KING_BUCKETS).unwrap());
+        assert!(packed::is_packed(packed_path.to_str().unwrap()));
+        assert!(!packed::is_packed(epd_path.to_str().unwrap()));
+
+        let mut coeffs = Vec::new();
+        let mut indices = Vec::new();
+        let mut weights_indices = HashSet::new();
+        let positions = packed::load(packed_path.to_str().unwrap(), &KING_BUCKETS, &mut coeffs, &mut indices, &mut weights_indices).unwrap();
+
+        let mut expected_coeffs = Vec::new();
+        let mut expected_indices = Vec::new();
+        let mut expected_positions = Vec::new();
+
+        for line in EPD.lines().filter(|line| !line.is_empty()) {
+            let (board, _, result) = tuner::parse_position(line).unwrap();
+            tuner::get_coeffs(&board, &KING_BUCKETS, &mut expected_coeffs, &mut expected_indices);
+            expected_positions.push((result, board.game_phase));
+        }
+
+        assert_eq!(expected_positions, positions.iter().map(|position| (position.get_result(), position.get_phase())).collect::<Vec<_>>());
+        assert_eq!(expected_coeffs.iter().map(|coeff| coeff.data).collect::<Vec<_>>(), coeffs.iter().map(|coeff| coeff.data).collect::<Vec<_>>());
+        assert_eq!(expected_indices, indices);
+        assert_eq!(expected_indices.iter().copied().collect::<HashSet<_>>(), weights_indices);
+
+        fs::remove_file(epd_path).unwrap();
+        fs::remove_file(packed_path).unwrap();
+    }
+
+    macro_rules! packed_corrupted_tests {
+        ($($name:ident: $offset:expr,)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    init();
+
+                    let (epd_path, packed_path) = get_paths(stringify!($name));
+                    fs::write(&epd_path, EPD).unwrap();
+                    packed::convert(epd_path.to_str().unwrap(), packed_path.to_str().unwrap(), &KING_BUCKETS).unwrap();
+
+                    let mut data = fs::read(&packed_path).unwrap();
+                    let get_offset: fn(usize) -> usize = $offset;
+                    let offset = get_offset(data.len());
+                    data[offset] ^= 0x01;
+                    fs::write(&packed_path, data).unwrap();
+
+                    let result = packed::load(packed_path.to_str().unwrap(), &KING_BUCKETS, &mut Vec::new(), &mut Vec::new(), &mut HashSet::new());
+                    assert!(result.err().unwrap().contains("checksum"));
+
+                    fs::remove_file(epd_path).unwrap();
+                    fs::remove_file(packed_path).unwrap();
+                }
+            )*
+        }
+    }
+
+    packed_corrupted_tests! {
+        packed_corrupted_coeff_value: |length: usize| length - 1,
+        packed_corrupted_header_checksum: |_| 26,
+    }
+
+    #[test]
+    fn packed_different_king_buckets() {
+        init();
+
+        let (epd_path, packed_path) = get_paths("different_king_buckets");
+        fs::write(&epd_path, EPD).unwrap();
+        packed::convert(epd_path.to_str().unwrap(), packed_path.to_str().unwrap(), &KING_BUCKETS).unwrap();
+
+        let mut king_buckets = KING_BUCKETS;
+        king_buckets[0] = king_buckets[63];
+
+        let result = packed::load(packed_path.to_str().unwrap(), &king_buckets, &mut Vec::new(), &mut Vec::new(), &mut HashSet::new());
+        assert!(result.err().unwrap().contains("king buckets"));
+
+        fs::remove_file(epd_path).unwrap();
+        fs::remove_file(packed_path).unwrap();
+    }
+
+    #[test]
+    fn packed_truncated() {
+        init();
+
+        let (epd_path, packed_path) = get_paths("truncated");
+        fs::write(&epd_path, EPD).unwrap();
+        packed::convert(epd_path.to_str().unwrap(), packed_path.to_str().unwrap(), &KING_BUCKETS).unwrap();
+
+        let data = fs::read(&packed_path).unwrap();
+        fs::write(&packed_path, &data[..data.len() - 1]).unwrap();
+
+        let result = packed::load(packed_path.to_str().unwrap(), &KING_BUCKETS, &mut Vec::new(), &mut Vec::new(), &mut HashSet::new());
+        assert!(result.is_err());
+
+        fs::remove_file(epd_path).unwrap();
+        fs::remove_file(packed_path).unwrap();
+    }
+
+    /// Gets paths of the EPD and packed files used by the test `name`, unique for the test process.
+    fn get_paths(name: &str) -> (PathBuf, PathBuf) {
+        let directory = env::temp_dir().join(format!("inanis_packed_tests_{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+
+        (directory.join(format!("{}.epd", name)), directory.join(format!("{}.bin", name)))
+    }
+
+    fn init() {
+        INIT.call_once(|| {
+            see::init();
+            movegen::init();
+        });
+    }
+}
@@ -0,0 +1,125 @@
+#[cfg(test)]
+mod pgn_tests {
+    use inanis::engine::see;
+    use inanis::state::movescan::Move;
+    use inanis::state::representation::Board;
+    use inanis::state::text::pgn::PGNLoader;
+    use inanis::state::*;
+    use std::sync::Once;
+
+    static INIT: Once = Once::new();
+
+    macro_rules! pgn_tests {
+        ($($name:ident: $pgn:expr, $expected_moves:expr, $expected_evals:expr,)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    INIT.call_once(|| {
+                        see::init();
+                        movegen::init();
+                    });
+
+                    let loader = PGNLoader::new($pgn.as_bytes());
+                    let games = loader.map(|pgn| pgn.ok()).collect::<Vec<_>>();
+
+                    let moves = games.iter().map(|pgn| pgn.as_ref().map(|pgn| pgn.data.len())).collect::<Vec<_>>();
+                    let evals = games.iter().flatten().flat_map(|pgn| pgn.data.iter().map(|data| data.eval)).collect::<Vec<_>>();
+
+                    assert_eq!($expected_moves, moves[..]);
+                    assert_eq!($expected_evals, evals[..]);
+                }
+            )*
+        }
+    }
+
+    pgn_tests! {
+        pgn_simple: "[Event \"?\"]\n[Result \"1-0\"]\n\n1. e4 e5 2. Nf3 Nc6 1-0\n", [Some(4)], [None; 4],
+        pgn_move_numbers: "[Result \"*\"]\n\n1.e4 e5 2.Nf3 2...Nc6 *\n", [Some(4)], [None; 4],
+        pgn_castling: "[Result \"*\"]\n\n1. e4 e5 2. Nf3 Nc6 3. Bc4 Bc5 4. O-O Nf6 5. d3 0-0 *\n", [Some(10)], [None; 10],
+        pgn_cutechess_evals: "[Result \"0-1\"]\n\n1. e4 {+0.30/10 0.1s} e5 {+0.20/11 0.1s} 2. Qh5 {-M3/12 0.1s} 0-1\n", [Some(3)], [Some(0.3), Some(-0.2), Some(-100.0)],
+        pgn_lichess_evals: "[Result \"1-0\"]\n\n1. e4 { [%eval 0.17] [%clk 0:03:00] } 1... e5 { [%eval #-2] } 2. Nf3 { [%clk 0:02:58] } 1-0\n", [Some(3)], [Some(0.17), Some(-100.0), None],
+        pgn_book_comments: "[Result \"1-0\"]\n\n1. e4 {book} e5 {book} 2. Nf3 {+0.50/9 0.2s} 1-0\n", [Some(3)], [None, None, Some(0.5)],
+        pgn_multiline_comments: "[Result \"1-0\"]\n\n1. e4 {a comment\nspanning [%eval 0.25]\nlines} e5 2. Nf3 1-0\n", [Some(3)], [Some(0.25), None, None],
+        pgn_semicolon_comments: "[Result \"1-0\"]\n\n1. e4 ; comment { with a brace\ne5 2. Nf3 1-0\n", [Some(3)], [None; 3],
+        pgn_variations: "[Result \"1-0\"]\n\n1. e4 (1. d4 d5 (1... Nf6 2. c4 {+0.10/5 0.1s}) 2. c4) e5 {+0.20/6 0.1s} 2. Nf3 1-0\n", [Some(3)], [None, Some(-0.2), None],
+        pgn_en_passant: "[Result \"*\"]\n\n1. e4 d5 2. e5 f5 3. exf6 *\n", [Some(5)], [None; 5],
+        pgn_nags: "[Result \"1-0\"]\n\n1. e4! $1 e5?! $6 2. Nf3 $14 Nc6?? 1-0\n", [Some(4)], [None; 4],
+        pgn_escape_lines: "% comment\n[Result \"1-0\"]\n% another comment\n\n1. e4 e5 1-0\n", [Some(2)], [None; 2],
+        pgn_byte_order_mark: "\u{feff}[Event \"?\"]\n[Result \"1-0\"]\n\n1. e4 e5 1-0\n", [Some(2)], [None; 2],
+        pgn_escaped_properties: "[White \"A \\\"B\\\" C]\"]\n[Result \"1/2-1/2\"]\n\n1. e4 e5 1/2-1/2\n", [Some(2)], [None; 2],
+        pgn_no_result_property: "[Event \"?\"]\n\n1. e4 e5 0-1\n", [Some(2)], [None; 2],
+        pgn_fen: "[Result \"1-0\"]\n[FEN \"4k3/8/8/8/8/8/4P3/4K3 w - - 0 1\"]\n\n1. e4 Kd7 1-0\n", [Some(2)], [None; 2],
+        pgn_multiple_games: "[Event \"1\"]\n[Result \"1-0\"]\n\n1. e4 1-0\n\n[Event \"2\"]\n[Result \"0-1\"]\n\n1. d4 d5\n2. c4 0-1\n", [Some(1), Some(3)], [None; 4],
+        pgn_invalid_game_skipped: "[Result \"1-0\"]\n\n1. e4 e5 1-0\n\n[Result \"1-0\"]\n\n1. e5 1-0\n\n[Result \"0-1\"]\n\n1. d4 d5 0-1\n", [Some(2), None, Some(2)], [None; 4],
+        pgn_unterminated_comment: "[Result \"1-0\"]\n\n1. e4 {comment 1-0\n\n[Result \"0-1\"]\n\n1. d4 0-1\n", [None, Some(1)], [None; 1],
+        pgn_unbalanced_variation: "[Result \"1-0\"]\n\n1. e4 e5) 1-0\n\n[Result \"0-1\"]\n\n1. d4 0-1\n", [None, Some(1)], [None; 1],
+    }
+
+    macro_rules! short_notation_tests {
+        ($($name:ident: $fen:expr, $long_notation:expr, $short_notation:expr,)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    INIT.call_once(|| {
+                        see::init();
+                        movegen::init();
+                    });
+
+                    let mut board = Board::new_from_fen($fen).unwrap();
+                    let r#move = Move::from_long_notation($long_notation, &board).unwrap();
+
+                    assert_eq!($short_notation, r#move.to_short_notation(&mut board));
+                    assert_eq!($fen, board.to_fen());
+                    assert!(r#move == Move::from_short_notation($short_notation, &mut board).unwrap());
+                }
+            )*
+        }
+    }
+
+    short_notation_tests! {
+        short_notation_pawn_push: "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", "e2e4", "e4",
+        short_notation_pawn_capture: "rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2", "e4d5", "exd5",
+        short_notation_en_passant: "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3", "e5f6", "exf6",
+        short_notation_piece: "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", "g1f3", "Nf3",
+        short_notation_file_ambiguity: "4k3/8/8/8/8/8/8/R4RK1 w - - 0 1", "a1d1", "Rad1",
+        short_notation_rank_ambiguity: "4k3/8/8/8/R7/8/8/R3K3 w - - 0 1", "a1a2", "R1a2",
+        short_notation_square_ambiguity: "4k3/8/8/8/8/Q1Q5/8/Q3K3 w - - 0 1", "a3b2", "Qa3b2",
+        short_notation_pinned_ambiguity: "4k3/4r3/8/8/8/8/4N3/2N1K3 w - - 0 1", "c1d3", "Nd3",
+        short_notation_promotion: "8/4P3/8/8/8/8/k7/4K3 w - - 0 1", "e7e8q", "e8=Q",
+        short_notation_promotion_capture: "3r4/4P3/8/8/8/8/k7/4K3 w - - 0 1", "e7d8n", "exd8=N",
+        short_notation_short_castling: "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", "e1g1", "O-O",
+        short_notation_long_castling: "r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1", "e8c8", "O-O-O",
+        short_notation_check: "4k3/8/8/8/8/8/8/R3K3 w - - 0 1", "a1a8", "Ra8+",
+        short_notation_checkmate: "6k1/5ppp/8/8/8/8/8/R3K3 w - - 0 1", "a1a8", "Ra8#",
+    }
+
+    macro_rules! to_pgn_tests {
+        ($($name:ident: $pgn:expr, $expected_pgn:expr,)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    INIT.call_once(|| {
+                        see::init();
+                        movegen::init();
+                    });
+
+                    let pgn = PGNLoader::new($pgn.as_bytes()).next().unwrap().unwrap();
+                    assert_eq!($expected_pgn, pgn.to_pgn().unwrap());
+                }
+            )*
+        }
+    }
+
+    to_pgn_tests! {
+        to_pgn_simple: "[Event \"?\"]\n[Result \"1-0\"]\n\n1. e4 {+0.30/10 0.1s} e5 (1... c5) 2. Qh5 Nc6 3. Bc4 Nf6 4. Qxf7# 1-0\n",
+            "[Event \"?\"]\n[Result \"1-0\"]\n\n1. e4 {[%eval 0.30]} 1... e5 2. Qh5 Nc6 3. Bc4 Nf6 4. Qxf7# 1-0\n\n",
+        to_pgn_black_to_move: "[FEN \"4k3/8/8/8/8/8/4P3/4K3 b - - 0 10\"]\n\n10... Kd7 11. e4 { [%eval -0.0] } *\n",
+            "[FEN \"4k3/8/8/8/8/8/4P3/4K3 b - - 0 10\"]\n[Result \"*\"]\n\n10... Kd7 11. e4 {[%eval 0.00]} *\n\n",
+        to_pgn_mates: "[Result \"0-1\"]\n\n1. e4 { [%eval #-4] } e5 {+M3/12 0.1s} 2. Qh5 {-M2/12 0.1s} 0-1\n",
+            "[Result \"0-1\"]\n\n1. e4 {[%eval #-4]} 1... e5 {[%eval #-3]} 2. Qh5 {[%eval #-2]} 0-1\n\n",
+        to_pgn_annotations: "[Result \"*\"]\n\n1. e4! e5 $2 2. Nf3 $14 Nc6?! 3. Bb5!? *\n",
+            "[Result \"*\"]\n\n1. e4! e5? 2. Nf3 $14 Nc6?! 3. Bb5!? *\n\n",
+        to_pgn_escaped_properties: "[White \"A \\\"B\\\" C\"]\n[Result \"*\"]\n\n1. e4 *\n",
+            "[White \"A \\\"B\\\" C\"]\n[Result \"*\"]\n\n1. e4 *\n\n",
+    }
+}
@@ -0,0 +1,30 @@
+#[cfg(all(test, feature = "dev"))]
+mod polyglot_tests {
+    use inanis::state::representation::Board;
+    use inanis::tuning::polyglot;
+
+    macro_rules! polyglot_tests {
+        ($($name:ident: $fen:expr, $expected_key:expr, )*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let board = Board::new_from_fen($fen).unwrap();
+                    assert_eq!($expected_key, polyglot::get_key(&board));
+                }
+            )*
+        }
+    }
+
+    // Reference keys from the Polyglot book format specification
+    polyglot_tests! {
+        polyglot_initial_position: "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", 0x463b96181691fc9c,
+        polyglot_e4: "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1", 0x823c9b50fd114196,
+        polyglot_e4_d5: "rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2", 0x0756b94461c50fb0,
+        polyglot_e4_d5_e5: "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR b KQkq - 0 2", 0x662fafb965db29d4,
+        polyglot_e4_d5_e5_f5: "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3", 0x22a48b5a8e47ff78,
+        polyglot_e4_d5_e5_f5_ke2: "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPPKPPP/RNBQ1BNR b kq - 0 3", 0x652a607ca3f242c1,
+        polyglot_e4_d5_e5_f5_ke2_kf7: "rnbq1bnr/ppp1pkpp/8/3pPp2/8/8/PPPPKPPP/RNBQ1BNR w - - 0 4", 0x00fdd303c946bdd9,
+        polyglot_a4_b5_h4_b4_c4: "rnbqkbnr/p1pppppp/8/8/PpP4P/8/1P1PPPP1/RNBQKBNR b KQkq c3 0 3", 0x3c8123ea7b067637,
+        polyglot_a4_b5_h4_b4_c4_bxc3_ra3: "rnbqkbnr/p1pppppp/8/8/P6P/R1p5/1P1PPPP1/1NBQKBNR b Kkq - 0 4", 0x5c3f9b829b279560,
+    }
+}
@@ -0,0 +1,19 @@
+#[cfg(all(test, feature = "dev"))]
+mod report_tests {
+    use inanis::tuning::report;
+    use inanis::tuning::tuner;
+    use std::collections::HashSet;
+
+    #[test]
+    fn weights_info_count() {
+        assert_eq!(tuner::get_weights_count(), report::get_weights_info().len());
+    }
+
+    #[test]
+    fn weights_info_unique_names() {
+        let info = report::get_weights_info();
+        let names = info.iter().map(|p| &p.name).collect::<HashSet<_>>();
+
+        assert_eq!(info.len(), names.len());
+    }
+}
@@ -0,0 +1,123 @@
+#[cfg(all(test, feature = "dev"))]
+mod spsa_tests {
+    use inanis::engine::params::SearchParams;
+    use inanis::tuning::spsa;
+    use inanis::tuning::spsa::SpsaParameter;
+    use inanis::utils::rand;
+    use std::env;
+    use std::fs;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::atomic::Ordering;
+
+    static CONFIGS_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+    #[test]
+    fn load_parameters_type_bounds() {
+        for (name, r#type) in SearchParams::FIELDS {
+            let (min, max) = match *r#type {
+                "i8" => (i8::MIN as i64, i8::MAX as i64),
+                "i16" => (i16::MIN as i64, i16::MAX as i64),
+                "usize" => (0, i16::MAX as i64),
+                _ => panic!("Unexpected type: {}", r#type),
+            };
+
+            assert!(load_parameters(&format!("{} {} {} 1", name, min, max)).is_ok(), "name={}", name);
+            assert!(load_parameters(&format!("{} {} {} 1", name, min - 1, max)).is_err(), "name={}", name);
+
+            if *r#type != "usize" {
+                assert!(load_parameters(&format!("{} {} {} 1", name, min, max + 1)).is_err(), "name={}", name);
+            }
+        }
+    }
+
+    #[test]
+    fn load_parameters_clamped_value() {
+        let parameters = load_parameters("# comment\n\naspwin_delta 20 50 2\nlmr_max_reduction 0 2 1\n").unwrap();
+
+        assert_eq!(2, parameters.len());
+        assert_eq!(20.0, parameters[0].value);
+        assert_eq!(2.0, parameters[1].value);
+    }
+
+    #[test]
+    fn sample_within_bounds() {
+        let parameters = [
+            SpsaParameter::new("aspwin_delta".to_string(), 15.0, 5.0, 50.0, 2.0),
+            SpsaParameter::new("lmr_max_reduction".to_string(), 3.0, 0.0, 3.0, 1.0),
+            SpsaParameter::new("lmp_move_index_margin_base".to_string(), 0.0, 0.0, 10.0, 1.0),
+        ];
+
+        rand::seed(42);
+        let first = (1..=100).map(|iteration| spsa::get_sample(&parameters, iteration, 100.0, 10)).collect::<Vec<_>>();
+
+        rand::seed(42);
+        let second = (1..=100).map(|iteration| spsa::get_sample(&parameters, iteration, 100.0, 10)).collect::<Vec<_>>();
+
+        for (first, second) in first.iter().zip(&second) {
+            assert_eq!(first.deltas, second.deltas);
+            assert_eq!(first.opening, second.opening);
+            assert!(first.opening < 10);
+
+            for (parameter, delta) in parameters.iter().zip(&first.deltas) {
+                let plus = first.plus.get(&parameter.name).unwrap() as f64;
+                let minus = first.minus.get(&parameter.name).unwrap() as f64;
+
+                assert!(*delta == 1.0 || *delta == -1.0);
+                assert!((parameter.min..=parameter.max).contains(&plus));
+                assert!((parameter.min..=parameter.max).contains(&minus));
+                assert!((plus - parameter.value) * delta >= 0.0 && (minus - parameter.value) * delta <= 0.0);
+            }
+        }
+    }
+
+    #[test]
+    fn update_direction() {
+        let mut plus_won = SpsaParameter::new("aspwin_delta".to_string(), 15.0, 5.0, 50.0, 2.0);
+        let mut minus_won = SpsaParameter::new("aspwin_delta".to_string(), 15.0, 5.0, 50.0, 2.0);
+        let mut draw = SpsaParameter::new("aspwin_delta".to_string(), 15.0, 5.0, 50.0, 2.0);
+
+        plus_won.update(1.0, 2.0, 1, 100.0);
+        minus_won.update(1.0, -2.0, 1, 100.0);
+        draw.update(-1.0, 0.0, 1, 100.0);
+
+        assert!(plus_won.value > 15.0);
+        assert!(minus_won.value < 15.0);
+        assert!((plus_won.value - 15.0 - (15.0 - minus_won.value)).abs() < 1e-9);
+        assert_eq!(15.0, draw.value);
+    }
+
+    #[test]
+    fn update_decreasing_gain() {
+        let mut first = SpsaParameter::new("aspwin_delta".to_string(), 15.0, 5.0, 50.0, 2.0);
+        let mut last = SpsaParameter::new("aspwin_delta".to_string(), 15.0, 5.0, 50.0, 2.0);
+
+        first.update(1.0, 1.0, 1, 100.0);
+        last.update(1.0, 1.0, 100, 100.0);
+
+        assert!(first.value - 15.0 > last.value - 15.0);
+        assert!(last.value > 15.0);
+    }
+
+    #[test]
+    fn update_clamped() {
+        let mut max = SpsaParameter::new("lmr_max_reduction".to_string(), 2.0, 0.0, 3.0, 1.0);
+        let mut min = SpsaParameter::new("lmr_max_reduction".to_string(), 2.0, 0.0, 3.0, 1.0);
+
+        max.update(1.0, 1000.0, 1, 100.0);
+        min.update(-1.0, 1000.0, 1, 100.0);
+
+        assert_eq!(3.0, max.value);
+        assert_eq!(0.0, min.value);
+    }
+
+    /// Saves `config` into a temporary file and loads SPSA parameters from it.
+    fn load_parameters(config: &str) -> Result<Vec<SpsaParameter>, String> {
+        let path = env::temp_dir().join(format!("inanis_spsa_tests_{}_{}.txt", std::process::id(), CONFIGS_COUNT.fetch_add(1, Ordering::Relaxed)));
+        fs::write(&path, config).unwrap();
+
+        let result = spsa::load_parameters(path.to_str().unwrap());
+        fs::remove_file(path).unwrap();
+
+        result
+    }
+}
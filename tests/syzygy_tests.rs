@@ -0,0 +1,193 @@
+#[cfg(test)]
+mod syzygy_tests {
+    use inanis::engine::see;
+    use inanis::state::representation::Board;
+    use inanis::state::*;
+    use inanis::tablebases::syzygy::probe;
+    use inanis::tablebases::WdlResult;
+    use std::env;
+    use std::fs;
+    use std::path::Path;
+    use std::sync::Once;
+
+    static INIT: Once = Once::new();
+
+    macro_rules! wdl_tests {
+        ($($name:ident: $fen:expr, $expected_result:expr, )*) => {
+            $(
+                #[test]
+                fn $name() {
+                    init();
+
+                    let board = Board::new_from_fen($fen).unwrap();
+                    assert_eq!($expected_result, probe::get_wdl(&board));
+                }
+            )*
+        }
+    }
+
+    macro_rules! root_tests {
+        ($($name:ident: $fen:expr, $expected_wdl:expr, $expected_dtz:expr, $expected_move:expr, )*) => {
+            $(
+                #[test]
+                fn $name() {
+                    init();
+
+                    let board = Board::new_from_fen($fen).unwrap();
+                    let result = probe::get_root_wdl_dtz(&board).unwrap();
+
+                    assert_eq!($expected_wdl, result.wdl);
+                    assert_eq!($expected_dtz, result.dtz);
+                    assert_eq!($expected_move, result.r#move.to_long_notation());
+                }
+            )*
+        }
+    }
+
+    wdl_tests! {
+        wdl_kqvk_white_to_move: "4k3/8/8/8/8/8/8/4KQ2 w - - 0 1", Some(WdlResult::Win),
+        wdl_kqvk_black_to_move: "4k3/8/8/8/8/8/8/4KQ2 b - - 0 1", Some(WdlResult::Loss),
+        wdl_kqvk_queen_capture: "4k3/4Q3/8/8/8/8/8/K7 b - - 0 1", Some(WdlResult::Draw),
+        wdl_kvkq_white_to_move: "4kq2/8/8/8/8/8/8/4K3 w - - 0 1", Some(WdlResult::Loss),
+        wdl_kvkq_black_to_move: "4kq2/8/8/8/8/8/8/4K3 b - - 0 1", Some(WdlResult::Win),
+        wdl_kvkq_queen_capture: "4K3/4q3/8/8/8/8/8/k7 w - - 0 1", Some(WdlResult::Draw),
+        wdl_kvk: "4k3/8/8/8/8/8/8/4K3 w - - 0 1", Some(WdlResult::Draw),
+        wdl_krvk_white_to_move: "4k3/8/8/8/8/8/8/4KR2 w - - 0 1", Some(WdlResult::Win),
+        wdl_krvk_black_to_move: "4k3/8/8/8/8/8/8/4KR2 b - - 0 1", Some(WdlResult::Loss),
+        wdl_krvk_rook_capture: "8/8/8/8/8/8/3kR3/7K b - - 0 1", Some(WdlResult::Draw),
+        wdl_missing_table: "4k3/8/8/8/8/8/8/4KB2 w - - 0 1", None,
+        wdl_too_many_pieces: "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", None,
+    }
+
+    root_tests! {
+        root_kqvk_mate_in_one: "k7/8/1K6/8/8/8/7Q/8 w - - 0 1", WdlResult::Win, 1, "h2h8",
+        root_kvkq_mate_in_one: "K7/8/1k6/8/8/8/7q/8 b - - 0 1", WdlResult::Win, 1, "h2h8",
+        root_kqvk_queen_capture: "8/8/8/8/8/3k4/4Q3/K7 b - - 0 1", WdlResult::Draw, 0, "d3e2",
+        root_krvk_dtz_map: "k7/8/1K6/8/8/8/8/7R w - - 0 1", WdlResult::Win, 6, "h1h8",
+        root_krvk_cursed_win: "k7/8/1K6/8/8/8/8/7R w - - 95 100", WdlResult::CursedWin, 6, "h1h8",
+        root_krvk_no_cursed_win: "k7/8/1K6/8/8/8/8/7R w - - 94 100", WdlResult::Win, 6, "h1h8",
+    }
+
+    #[test]
+    fn max_pieces_count() {
+        init();
+        assert_eq!(3, probe::get_max_pieces_count());
+    }
+
+    #[test]
+    fn root_moves_krvk_black_to_move() {
+        init();
+
+        // DTZ is stored only for white to move, so it's obtained by probing all positions after black moves
+        for (fen, expected_result) in [("8/8/8/8/8/2k5/8/R3K3 b - - 0 1", WdlResult::Loss), ("8/8/8/8/8/2k5/8/R3K3 b - - 94 100", WdlResult::BlessedLoss)] {
+            let board = Board::new_from_fen(fen).unwrap();
+            let root_moves = probe::get_root_moves(&board).unwrap();
+
+            assert_eq!(7, root_moves.len());
+            assert!(root_moves.iter().all(|result| result.wdl == expected_result && result.dtz == 7));
+        }
+    }
+
+    #[test]
+    fn root_moves_hanging_queen() {
+        init();
+
+        let board = Board::new_from_fen("k7/8/1K6/8/8/8/7Q/8 w - - 0 1").unwrap();
+        let root_moves = probe::get_root_moves(&board).unwrap();
+        let hanging_move = root_moves.iter().find(|result| result.r#move.to_long_notation() == "h2b8").unwrap();
+        let mating_move = root_moves.iter().find(|result| result.r#move.to_long_notation() == "h2h8").unwrap();
+
+        assert_eq!(WdlResult::Draw, hanging_move.wdl);
+        assert_eq!(WdlResult::Win, mating_move.wdl);
+        assert_eq!(1, mating_move.dtz);
+    }
+
+    fn init() {
+        INIT.call_once(|| {
+            see::init();
+            movegen::init();
+
+            let directory = env::temp_dir().join(format!("inanis_syzygy_tests_{}", std::process::id()));
+            fs::create_dir_all(&directory).unwrap();
+
+            generate_tables(&directory);
+            probe::init(directory.to_str().unwrap());
+        });
+    }
+
+    /// Generates minimal table sets with known values, which allow to test the file format, material keys, color flipping, capture resolution
+    /// and DTZ decoding without real tables:
+    ///  - KQvK with a win for the side with the queen to move (stored as a constant) and a loss for the other side (compressed), and a constant DTZ
+    ///  - KRvK with the same WDL values, and compressed DTZ of every position with the rook side to move equal to 5 (stored through a DTZ map)
+    fn generate_tables(directory: &Path) {
+        generate_table_set(directory, "KQvK", 5, None);
+        generate_table_set(directory, "KRvK", 4, Some(5));
+    }
+
+    /// Generates WDL and DTZ tables for `name` material, where `piece` is the type (1 for pawn up to 6 for king) of the third piece. Compressed
+    /// DTZ table with `dtz` value of every position is generated if specified, otherwise DTZ is stored as a constant.
+    fn generate_table_set(directory: &Path, name: &str, piece: u8, dtz: Option<u8>) {
+        // Encoding info (order and pieces: white king, black king, white piece) with the same values in lower and upper nibbles
+        let header = [0x00, 0x66, 0xee, piece | (piece << 4)];
+
+        let mut wdl = Vec::new();
+        wdl.extend_from_slice(&0x5d23e871u32.to_le_bytes());
+        wdl.push(0x01);
+        wdl.extend_from_slice(&header);
+        wdl.push(0x00);
+        wdl.extend_from_slice(&[0x80, 4]);
+
+        // Black to move values are compressed using two symbols (a draw encoded as 0 and a loss encoded as 1) with all bits set
+        write_pairs(&mut wdl, 0x00, [2, 0]);
+        write_data(&mut wdl);
+
+        let mut dtz_table = Vec::new();
+        dtz_table.extend_from_slice(&0xa50c66d7u32.to_le_bytes());
+        dtz_table.push(0x00);
+        dtz_table.extend_from_slice(&header);
+        dtz_table.push(0x00);
+
+        match dtz {
+            Some(dtz) => {
+                // Flags: white to move stored, DTZ maps used, win values not doubled. The value 1 is mapped to `dtz` for wins, other maps are empty
+                write_pairs(&mut dtz_table, 0x06, [0, 1]);
+                dtz_table.extend_from_slice(&[2, 0, dtz, 0, 0, 0]);
+                write_data(&mut dtz_table);
+            }
+            None => {
+                dtz_table.extend_from_slice(&[0x80, 0]);
+                dtz_table.resize(16, 0);
+            }
+        }
+
+        fs::write(directory.join(format!("{}.rtbw", name)), wdl).unwrap();
+        fs::write(directory.join(format!("{}.rtbz", name)), dtz_table).unwrap();
+    }
+
+    /// Writes a header of the compressed pairs into `data`, with `flags` as the first byte, and two symbols expanding to `values`.
+    fn write_pairs(data: &mut Vec<u8>, flags: u8, values: [u8; 2]) {
+        // Block size (4096 bytes), index bits (64 entries per index), one block, code lengths (1 bit), offsets and two symbols
+        data.extend_from_slice(&[flags, 12, 6, 0]);
+        data.extend_from_slice(&1u32.to_le_bytes());
+        data.extend_from_slice(&[1, 1, 0, 0, 2, 0]);
+        data.extend_from_slice(&[values[0], 0xf0, 0xff, values[1], 0xf0, 0xff]);
+    }
+
+    /// Writes index table, size table and a single block of compressed data (with all bits set, so every entry is the second symbol) into `data`,
+    /// then pads the file to the valid size.
+    fn write_data(data: &mut Vec<u8>) {
+        let entries_count = 31332;
+        let indices_count = (entries_count + 63) / 64;
+
+        data.resize(data.len() + (data.len() & 1), 0);
+        for index in 0..indices_count {
+            data.extend_from_slice(&0u32.to_le_bytes());
+            data.extend_from_slice(&(32 + 64 * index as u16).to_le_bytes());
+        }
+
+        data.extend_from_slice(&(entries_count as u16 - 1).to_le_bytes());
+        data.resize((data.len() + 63) / 64 * 64, 0);
+        data.resize(data.len() + 4096, 0xff);
+        data.resize(data.len() + 16, 0);
+    }
+}
@@ -0,0 +1,55 @@
+#[cfg(all(test, feature = "dev"))]
+mod testset_tests {
+    use inanis::engine::see;
+    use inanis::engine::CHECKMATE_SCORE;
+    use inanis::state::movescan::Move;
+    use inanis::state::representation::Board;
+    use inanis::state::*;
+    use inanis::testing::testset::TestPosition;
+    use std::sync::Once;
+
+    static INIT: Once = Once::new();
+
+    macro_rules! is_solved_tests {
+        ($($name:ident: $fen:expr, $best_moves:expr, $avoid_moves:expr, $direct_mate:expr, $move:expr, $score:expr, $expected_result:expr,)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    INIT.call_once(|| {
+                        see::init();
+                        movegen::init();
+                    });
+
+                    let board = Board::new_from_fen($fen).unwrap();
+                    let best_moves: &[&str] = &$best_moves;
+                    let avoid_moves: &[&str] = &$avoid_moves;
+
+                    let best_moves = best_moves.iter().map(|r#move| Move::from_long_notation(r#move, &board).unwrap()).collect();
+                    let avoid_moves = avoid_moves.iter().map(|r#move| Move::from_long_notation(r#move, &board).unwrap()).collect();
+                    let r#move = Move::from_long_notation($move, &board).unwrap();
+                    let position = TestPosition::new(0, String::new(), board, best_moves, avoid_moves, $direct_mate);
+
+                    assert_eq!($expected_result, position.is_solved(r#move, $score));
+                }
+            )*
+        }
+    }
+
+    is_solved_tests! {
+        is_solved_am_hit: "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", [], ["f2f3"], None, "f2f3", 0, false,
+        is_solved_am_miss: "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", [], ["f2f3"], None, "e2e4", 0, true,
+        is_solved_am_several_moves: "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", [], ["f2f3", "g2g4"], None, "g2g4", 0, false,
+        is_solved_bm_hit: "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", ["e2e4"], [], None, "e2e4", 0, true,
+        is_solved_bm_miss: "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", ["e2e4"], [], None, "d2d4", 0, false,
+        is_solved_bm_several_moves_first: "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", ["e2e4", "d2d4", "c2c4"], [], None, "e2e4", 0, true,
+        is_solved_bm_several_moves_last: "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", ["e2e4", "d2d4", "c2c4"], [], None, "c2c4", 0, true,
+        is_solved_bm_several_moves_miss: "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", ["e2e4", "d2d4", "c2c4"], [], None, "g1f3", 0, false,
+        is_solved_bm_and_am: "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", ["e2e4"], ["d2d4"], None, "e2e4", 0, true,
+        is_solved_dm_exact: "6k1/8/6K1/8/8/8/8/R7 w - - 0 1", [], [], Some(2), "a1a7", CHECKMATE_SCORE - 4, true,
+        is_solved_dm_faster: "6k1/8/6K1/8/8/8/8/R7 w - - 0 1", [], [], Some(2), "a1a8", CHECKMATE_SCORE - 2, true,
+        is_solved_dm_slower: "6k1/8/6K1/8/8/8/8/R7 w - - 0 1", [], [], Some(2), "a1a7", CHECKMATE_SCORE - 6, false,
+        is_solved_dm_mated: "6k1/8/6K1/8/8/8/8/R7 w - - 0 1", [], [], Some(2), "a1a7", -CHECKMATE_SCORE + 3, false,
+        is_solved_dm_no_mate: "6k1/8/6K1/8/8/8/8/R7 w - - 0 1", [], [], Some(2), "a1a7", 500, false,
+        is_solved_dm_and_bm_miss: "6k1/8/6K1/8/8/8/8/R7 w - - 0 1", ["a1a8"], [], Some(2), "a1a7", CHECKMATE_SCORE - 4, false,
+    }
+}
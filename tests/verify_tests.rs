@@ -0,0 +1,77 @@
+#[cfg(test)]
+mod verify_tests {
+    use inanis::engine::see;
+    use inanis::state::representation::Board;
+    use inanis::state::*;
+    use inanis::testing::verify;
+    use std::sync::Once;
+
+    static INIT: Once = Once::new();
+
+    macro_rules! mirror_tests {
+        ($($name:ident: $fen:expr, $mirrored_fen:expr, $flipped_fen:expr,)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    INIT.call_once(|| {
+                        see::init();
+                        movegen::init();
+                    });
+
+                    let board = Board::new_from_fen($fen).unwrap();
+                    let mirrored = board.mirror();
+                    let flipped = board.flip();
+
+                    assert_eq!($mirrored_fen, mirrored.to_fen());
+                    assert_eq!($flipped_fen, flipped.to_fen());
+                    assert_eq!(board.state.hash, mirrored.mirror().state.hash);
+                    assert_eq!(board.state.pst_score.data, mirrored.mirror().state.pst_score.data);
+                    assert_eq!(board.game_phase, mirrored.game_phase);
+                }
+            )*
+        }
+    }
+
+    macro_rules! verify_tests {
+        ($($name:ident: $depth:expr, $fen:expr,)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    INIT.call_once(|| {
+                        see::init();
+                        movegen::init();
+                    });
+
+                    let result = verify::run(&mut Board::new_from_fen($fen).unwrap(), $depth);
+                    assert_eq!(None, result.error);
+                }
+            )*
+        }
+    }
+
+    mirror_tests! {
+        mirror_initial_position:
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1",
+            "rnbkqbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBKQBNR w - - 0 1",
+        mirror_en_passant:
+            "rnbqkbnr/pp1p1ppp/8/2pPp3/8/8/PPP1PPPP/RNBQKBNR w Kq e6 0 2",
+            "rnbqkbnr/ppp1pppp/8/8/2PpP3/8/PP1P1PPP/RNBQKBNR b Qk e3 0 2",
+            "rnbkqbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBKQBNR w - d6 0 2",
+        mirror_end_game:
+            "8/5pk1/1P4p1/P7/8/6P1/5PK1/8 w - - 0 1",
+            "8/5pk1/6p1/8/p7/1p4P1/5PK1/8 b - - 0 1",
+            "8/1kp5/1p4P1/7P/8/1P6/1KP5/8 w - - 0 1",
+    }
+
+    verify_tests! {
+        verify_initial_position: 3, "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        verify_mid_game: 2, "5rk1/2b1qp1p/1r2p1pB/1ppnn3/3pN3/1P1P2P1/2P1QPBP/R4RK1 b - - 7 22",
+        verify_castling: 2, "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        verify_en_passant: 2, "r3kb2/p4pp1/2q1p3/1pP1n1N1/3B2nr/1QP1P3/PP1N3P/R2R2K1 w q b6 0 2",
+        verify_promotions: 3, "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+        verify_end_game: 3, "8/5pk1/1P4p1/P7/8/6P1/5PK1/8 w - - 0 1",
+        verify_kbnk: 3, "8/8/8/4k3/8/8/8/KBN5 w - - 0 1",
+        verify_kpk: 4, "8/8/8/4k3/8/2P5/8/K7 w - - 0 1",
+    }
+}